@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use game_protocol::domain::AgentReqEvent;
+use game_protocol::{AbstractFAAgent, ObsMirror, respond};
+use tracing::{debug, error, info, warn};
+
+use crate::connection::AgentConnection;
+use crate::error::SdkError;
+
+/// Answers the server's requests with a local agent until the server closes the session.
+pub struct AgentClient {
+  connection: Box<dyn AgentConnection>,
+  heartbeat: Duration,
+  max_reconnects: u32,
+  backoff: Duration,
+}
+
+impl AgentClient {
+  pub fn new(connection: Box<dyn AgentConnection>) -> Self {
+    Self {
+      connection,
+      heartbeat: Duration::from_secs(15),
+      max_reconnects: 5,
+      backoff: Duration::from_secs(1),
+    }
+  }
+
+  /// Checks the connection after `heartbeat` without requests.
+  pub fn with_heartbeat(mut self, heartbeat: Duration) -> Self {
+    self.heartbeat = heartbeat;
+    self
+  }
+
+  /// Reconnects up to `max_reconnects` times in a row, waiting `backoff` longer before each attempt.
+  pub fn with_reconnects(mut self, max_reconnects: u32, backoff: Duration) -> Self {
+    self.max_reconnects = max_reconnects;
+    self.backoff = backoff;
+    self
+  }
+
+  /// Returns `Ok` once the server closes the session, e.g. at the end of the game.
+  pub async fn run(&mut self, agent: &mut dyn AbstractFAAgent) -> Result<(), SdkError> {
+    // The mirror survives reconnects; a request lost in between shows up as a gap and is resynced.
+    let mut mirror = ObsMirror::new();
+    loop {
+      let result = match self.connection.recv(self.heartbeat).await {
+        Ok(Some(event)) => self.handle(agent, &mut mirror, event).await,
+        Ok(None) => self.connection.heartbeat().await,
+        Err(e) => Err(e),
+      };
+      match result {
+        Ok(()) => {},
+        Err(SdkError::Closed) => {
+          info!(connection = self.connection.name(), "closed by server");
+          return Ok(());
+        },
+        Err(e @ SdkError::BadFrame { .. }) => error!(connection = self.connection.name(), "{}", e),
+        Err(SdkError::Disconnected(reason)) => {
+          warn!(connection = self.connection.name(), "disconnected: {}", reason);
+          self.reconnect(reason).await?;
+        },
+        Err(e) => return Err(e),
+      }
+    }
+  }
+
+  async fn handle(
+    &mut self, agent: &mut dyn AbstractFAAgent, mirror: &mut ObsMirror, event: AgentReqEvent,
+  ) -> Result<(), SdkError> {
+    let id = event.id();
+    let kind: &'static str = (&event).into();
+    info!(connection = self.connection.name(), id, kind, "request");
+    debug!(connection = self.connection.name(), "< {:?}", event);
+    match &event {
+      AgentReqEvent::Reject { reason, .. } => return Err(SdkError::Rejected(reason.clone())),
+      AgentReqEvent::Hello {
+        resume_token: Some(token),
+        ..
+      } => self.connection.set_resume_token(*token),
+      _ => {},
+    }
+    // Resumed needs no answer; the outstanding request follows it
+    let Some(resp) = respond(agent, mirror, event).await else {
+      return Ok(());
+    };
+    debug!(connection = self.connection.name(), "> {:?}", resp);
+    self.connection.send(&resp).await
+  }
+
+  async fn reconnect(&mut self, reason: String) -> Result<(), SdkError> {
+    let mut last = SdkError::Disconnected(reason);
+    for attempt in 1..=self.max_reconnects {
+      tokio::time::sleep(self.backoff * attempt).await;
+      match self.connection.reconnect().await {
+        Ok(()) => {
+          info!(connection = self.connection.name(), attempt, "reconnected");
+          return Ok(());
+        },
+        Err(e @ SdkError::CannotReconnect(_)) => return Err(e),
+        Err(e) => {
+          warn!(connection = self.connection.name(), attempt, "reconnect failed: {}", e);
+          last = e;
+        },
+      }
+    }
+    Err(last)
+  }
+}
@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use game_protocol::WireFormat;
+use game_protocol::domain::{AgentReqEvent, AgentRespEvent};
+use uuid::Uuid;
+
+use crate::client::AgentClient;
+use crate::connections::{RedisConnection, StdioConnection, WsConnection};
+use crate::error::SdkError;
+
+/// One agent's link to the game server, the mirror image of the server's `AgentTransport`.
+#[async_trait]
+pub trait AgentConnection: Send {
+  fn name(&self) -> &str;
+
+  /// Waits up to `idle` for the next request; `Ok(None)` means nothing arrived in time.
+  async fn recv(&mut self, idle: Duration) -> Result<Option<AgentReqEvent>, SdkError>;
+
+  async fn send(&mut self, resp: &AgentRespEvent) -> Result<(), SdkError>;
+
+  /// Called after an idle `recv`; fails when the server no longer answers.
+  async fn heartbeat(&mut self) -> Result<(), SdkError> {
+    Ok(())
+  }
+
+  async fn reconnect(&mut self) -> Result<(), SdkError> {
+    Err(SdkError::CannotReconnect(self.name().to_string()))
+  }
+
+  /// Remembers the token from the server's `Hello` so that `reconnect` resumes the same seat.
+  fn set_resume_token(&mut self, _token: Uuid) {}
+}
+
+pub enum Endpoint {
+  /// A `WsDispatcher` endpoint such as `ws://host:port/<game>/<seat>` or `ws://host:port/<uuid>`.
+  Ws { url: String, format: WireFormat },
+  /// The request and response queues of `RedisTransport` for `agent_uuid`.
+  Redis {
+    url: String,
+    agent_uuid: Uuid,
+    format: WireFormat,
+  },
+  /// JSON lines on stdin/stdout, for agents spawned by an `exec:` seat.
+  Stdio,
+}
+
+pub async fn connect(endpoint: Endpoint) -> Result<AgentClient, SdkError> {
+  let connection: Box<dyn AgentConnection> = match endpoint {
+    Endpoint::Ws { url, format } => Box::new(WsConnection::connect(&url, format).await?),
+    Endpoint::Redis {
+      url,
+      agent_uuid,
+      format,
+    } => Box::new(RedisConnection::connect(&url, agent_uuid, format).await?),
+    Endpoint::Stdio => Box::new(StdioConnection::stdio()),
+  };
+  Ok(AgentClient::new(connection))
+}
@@ -0,0 +1,7 @@
+mod lines_connection;
+mod redis_connection;
+mod ws_connection;
+
+pub use lines_connection::{LinesConnection, StdioConnection};
+pub use redis_connection::RedisConnection;
+pub use ws_connection::WsConnection;
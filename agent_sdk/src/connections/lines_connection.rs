@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use game_protocol::domain::{AgentReqEvent, AgentRespEvent};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, Lines, Stdin, Stdout};
+
+use crate::connection::AgentConnection;
+use crate::error::SdkError;
+
+/// One JSON object per line; end of input means the server is done with this agent.
+pub struct LinesConnection<R, W> {
+  name: &'static str,
+  lines: Lines<BufReader<R>>,
+  writer: W,
+}
+
+impl<R, W> LinesConnection<R, W>
+where
+  R: AsyncRead + Unpin + Send,
+  W: AsyncWrite + Unpin + Send,
+{
+  pub fn new(name: &'static str, reader: R, writer: W) -> Self {
+    Self {
+      name,
+      lines: BufReader::new(reader).lines(),
+      writer,
+    }
+  }
+}
+
+/// Stdout carries the protocol, so logs must go to stderr or a file.
+pub type StdioConnection = LinesConnection<Stdin, Stdout>;
+
+impl StdioConnection {
+  pub fn stdio() -> Self {
+    Self::new("StdioAgent", tokio::io::stdin(), tokio::io::stdout())
+  }
+}
+
+#[async_trait]
+impl<R, W> AgentConnection for LinesConnection<R, W>
+where
+  R: AsyncRead + Unpin + Send,
+  W: AsyncWrite + Unpin + Send,
+{
+  fn name(&self) -> &str {
+    self.name
+  }
+
+  async fn recv(&mut self, idle: Duration) -> Result<Option<AgentReqEvent>, SdkError> {
+    match tokio::time::timeout(idle, self.lines.next_line()).await {
+      Err(_) => Ok(None),
+      Ok(Ok(Some(line))) => serde_json::from_str(&line)
+        .map(Some)
+        .map_err(|e| SdkError::bad_frame(line, e)),
+      Ok(Ok(None)) => Err(SdkError::Closed),
+      Ok(Err(e)) => Err(SdkError::Disconnected(e.to_string())),
+    }
+  }
+
+  async fn send(&mut self, resp: &AgentRespEvent) -> Result<(), SdkError> {
+    let mut json = serde_json::to_string(resp).unwrap();
+    json.push('\n');
+    self
+      .writer
+      .write_all(json.as_bytes())
+      .await
+      .map_err(|e| SdkError::Disconnected(e.to_string()))?;
+    self
+      .writer
+      .flush()
+      .await
+      .map_err(|e| SdkError::Disconnected(e.to_string()))
+  }
+}
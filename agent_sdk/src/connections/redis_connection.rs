@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use game_protocol::WireFormat;
+use game_protocol::domain::{AgentReqEvent, AgentRespEvent};
+use redis::AsyncCommands;
+use redis::aio::MultiplexedConnection;
+use uuid::Uuid;
+
+use crate::connection::AgentConnection;
+use crate::error::SdkError;
+
+/// Pops requests from and pushes responses to the queues of a server-side `RedisTransport`.
+pub struct RedisConnection {
+  client: redis::Client,
+  con: Option<MultiplexedConnection>,
+  req_redis_key: String,
+  resp_redis_key: String,
+  /// Must match the server's `redis_format`.
+  format: WireFormat,
+}
+
+impl RedisConnection {
+  pub async fn connect(url: &str, agent_uuid: Uuid, format: WireFormat) -> Result<Self, SdkError> {
+    let client = redis::Client::open(url).map_err(|e| SdkError::Connect {
+      endpoint: url.to_string(),
+      reason: e.to_string(),
+    })?;
+    let (req_redis_key, resp_redis_key) = game_protocol::redis_keys(agent_uuid);
+    let mut connection = Self {
+      client,
+      con: None,
+      req_redis_key,
+      resp_redis_key,
+      format,
+    };
+    connection.reconnect().await?;
+    Ok(connection)
+  }
+
+  fn con(&self) -> Result<MultiplexedConnection, SdkError> {
+    self
+      .con
+      .clone()
+      .ok_or_else(|| SdkError::Disconnected("not connected".to_string()))
+  }
+
+  fn disconnected(&mut self, e: redis::RedisError) -> SdkError {
+    self.con = None;
+    SdkError::Disconnected(e.to_string())
+  }
+}
+
+#[async_trait]
+impl AgentConnection for RedisConnection {
+  fn name(&self) -> &str {
+    "RedisAgent"
+  }
+
+  async fn recv(&mut self, idle: Duration) -> Result<Option<AgentReqEvent>, SdkError> {
+    let popped = self
+      .con()?
+      .brpop::<_, Option<(String, Vec<u8>)>>(&self.req_redis_key, idle.as_secs_f64())
+      .await;
+    match popped {
+      Ok(Some((_, frame))) => self
+        .format
+        .decode(&frame)
+        .map(Some)
+        .map_err(|e| SdkError::bad_frame(String::from_utf8_lossy(&frame), e)),
+      Ok(None) => Ok(None),
+      Err(e) => Err(self.disconnected(e)),
+    }
+  }
+
+  async fn send(&mut self, resp: &AgentRespEvent) -> Result<(), SdkError> {
+    let frame = self.format.encode(resp).unwrap();
+    let pushed = self.con()?.lpush::<_, _, usize>(&self.resp_redis_key, frame).await;
+    pushed.map(|_| ()).map_err(|e| self.disconnected(e))
+  }
+
+  async fn heartbeat(&mut self) -> Result<(), SdkError> {
+    let pong = redis::cmd("PING").query_async::<String>(&mut self.con()?).await;
+    pong.map(|_| ()).map_err(|e| self.disconnected(e))
+  }
+
+  async fn reconnect(&mut self) -> Result<(), SdkError> {
+    let con = self
+      .client
+      .get_multiplexed_async_connection()
+      .await
+      .map_err(|e| SdkError::Connect {
+        endpoint: self.client.get_connection_info().addr.to_string(),
+        reason: e.to_string(),
+      })?;
+    self.con = Some(con);
+    Ok(())
+  }
+}
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures_util::{SinkExt, StreamExt};
+use game_protocol::WireFormat;
+use game_protocol::domain::{AgentReqEvent, AgentRespEvent};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
+use uuid::Uuid;
+
+use crate::connection::AgentConnection;
+use crate::error::SdkError;
+
+/// A `WsDispatcher` endpoint. Text frames are always JSON; binary frames use `format`.
+pub struct WsConnection {
+  url: String,
+  format: WireFormat,
+  stream: Option<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+  resume_token: Option<Uuid>,
+  /// A ping went out and nothing has been received since.
+  awaiting_pong: bool,
+}
+
+impl WsConnection {
+  pub async fn connect(url: &str, format: WireFormat) -> Result<Self, SdkError> {
+    let mut connection = Self {
+      url: url.to_string(),
+      format,
+      stream: None,
+      resume_token: None,
+      awaiting_pong: false,
+    };
+    connection.reconnect().await?;
+    Ok(connection)
+  }
+
+  fn url(&self) -> String {
+    let mut url = format!("{}{}", self.url, self.format.query());
+    if let Some(token) = self.resume_token {
+      url.push(if url.contains('?') { '&' } else { '?' });
+      url.push_str(&format!("resume={}", token));
+    }
+    url
+  }
+
+  fn stream(&mut self) -> Result<&mut WebSocketStream<MaybeTlsStream<TcpStream>>, SdkError> {
+    self
+      .stream
+      .as_mut()
+      .ok_or_else(|| SdkError::Disconnected("not connected".to_string()))
+  }
+
+  fn disconnected(&mut self, reason: impl ToString) -> SdkError {
+    self.stream = None;
+    SdkError::Disconnected(reason.to_string())
+  }
+}
+
+#[async_trait]
+impl AgentConnection for WsConnection {
+  fn name(&self) -> &str {
+    "WsAgent"
+  }
+
+  async fn recv(&mut self, idle: Duration) -> Result<Option<AgentReqEvent>, SdkError> {
+    loop {
+      let msg = match tokio::time::timeout(idle, self.stream()?.next()).await {
+        Err(_) => return Ok(None),
+        Ok(None) => return Err(self.disconnected("stream ended")),
+        Ok(Some(Err(e))) => return Err(self.disconnected(e)),
+        Ok(Some(Ok(msg))) => msg,
+      };
+      self.awaiting_pong = false;
+      return match msg {
+        Message::Text(text) => serde_json::from_str(&text)
+          .map(Some)
+          .map_err(|e| SdkError::bad_frame(text.as_str(), e)),
+        Message::Binary(frame) => self
+          .format
+          .decode(&frame)
+          .map(Some)
+          .map_err(|e| SdkError::bad_frame(String::from_utf8_lossy(&frame), e)),
+        Message::Close(_) => {
+          self.stream = None;
+          Err(SdkError::Closed)
+        },
+        Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => continue,
+      };
+    }
+  }
+
+  async fn send(&mut self, resp: &AgentRespEvent) -> Result<(), SdkError> {
+    let msg = match self.format {
+      WireFormat::Json => Message::Text(serde_json::to_string(resp).unwrap().into()),
+      WireFormat::MessagePack => Message::Binary(self.format.encode(resp).unwrap().into()),
+    };
+    let sent = self.stream()?.send(msg).await;
+    sent.map_err(|e| self.disconnected(e))
+  }
+
+  async fn heartbeat(&mut self) -> Result<(), SdkError> {
+    if self.awaiting_pong {
+      return Err(self.disconnected("no pong since the last heartbeat"));
+    }
+    let sent = self.stream()?.send(Message::Ping(Vec::new().into())).await;
+    sent.map_err(|e| self.disconnected(e))?;
+    self.awaiting_pong = true;
+    Ok(())
+  }
+
+  async fn reconnect(&mut self) -> Result<(), SdkError> {
+    let url = self.url();
+    let (stream, _) = connect_async(&url).await.map_err(|e| SdkError::Connect {
+      endpoint: url,
+      reason: e.to_string(),
+    })?;
+    self.stream = Some(stream);
+    self.awaiting_pong = false;
+    Ok(())
+  }
+
+  fn set_resume_token(&mut self, token: Uuid) {
+    self.resume_token = Some(token);
+  }
+}
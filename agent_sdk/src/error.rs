@@ -0,0 +1,34 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SdkError {
+  #[error("could not connect to {endpoint}: {reason}")]
+  Connect { endpoint: String, reason: String },
+
+  /// The connection dropped; [`AgentClient`](crate::AgentClient) reconnects.
+  #[error("disconnected: {0}")]
+  Disconnected(String),
+
+  /// The server ended the session, e.g. because the game is over.
+  #[error("closed by server")]
+  Closed,
+
+  #[error("rejected by server: {0}")]
+  Rejected(String),
+
+  /// A request that could not be decoded; it is skipped.
+  #[error("bad frame {frame:?}: {reason}")]
+  BadFrame { frame: String, reason: String },
+
+  #[error("{0} cannot reconnect")]
+  CannotReconnect(String),
+}
+
+impl SdkError {
+  pub(crate) fn bad_frame(frame: impl Into<String>, reason: impl ToString) -> Self {
+    SdkError::BadFrame {
+      frame: frame.into(),
+      reason: reason.to_string(),
+    }
+  }
+}
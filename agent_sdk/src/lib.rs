@@ -0,0 +1,30 @@
+//! Client side of the remote agent protocol for bot authors.
+//!
+//! [`connect`] opens a WebSocket, Redis or stdio connection to the game server and
+//! [`AgentClient::run`] answers every request with an [`AbstractFAAgent`], handling the
+//! handshake, observation deltas, heartbeats and reconnection.
+//!
+//! The protocol types come from `game_protocol`; the SDK does not depend on the game server.
+//!
+//! ```no_run
+//! use agent_sdk::{AbstractFAAgent, Endpoint, connect};
+//! use game_protocol::WireFormat;
+//!
+//! # async fn play(agent: &mut impl AbstractFAAgent) -> Result<(), agent_sdk::SdkError> {
+//! let endpoint = Endpoint::Ws {
+//!   url: "ws://127.0.0.1:7001/917c7861-185d-496c-82a1-51692a294a2e".to_string(),
+//!   format: WireFormat::Json,
+//! };
+//! connect(endpoint).await?.run(agent).await
+//! # }
+//! ```
+
+mod client;
+mod connection;
+pub mod connections;
+mod error;
+
+pub use client::AgentClient;
+pub use connection::{AgentConnection, Endpoint, connect};
+pub use error::SdkError;
+pub use game_protocol::AbstractFAAgent;
@@ -1,7 +1,9 @@
 use async_trait::async_trait;
 
-use crate::domain::{Card, DestroyTarget, MagicianSkill, Oper, Role, RoleSet};
+use crate::agent_protocol::AgentProtocol;
+use crate::domain::{Card, DestroyTarget, MagicianSkill, Oper, Rationale, Role, RoleSet};
 use crate::obs::Obs;
+use crate::time_control::DecisionTimeout;
 
 #[async_trait]
 pub trait AbstractFAAgent: Send + Sync {
@@ -28,4 +30,19 @@ pub trait AbstractFAAgent: Send + Sync {
   async fn choose_from_2(&mut self, obs: &Obs, c0: Card, c1: Card) -> Card;
 
   async fn choose_from_3(&mut self, obs: &Obs, c0: Card, c1: Card, c2: Card) -> Card;
+
+  // 取走上一次决策的解释 (可选), 调用方在每个 choose_* 之后立即调用
+  fn take_rationale(&mut self) -> Option<Rationale> {
+    None
+  }
+
+  // 取走上一次决策的超时记录, 超时后的决策由 fallback 做出. 调用方在 take_rationale 之后调用
+  fn take_timeout(&mut self) -> Option<DecisionTimeout> {
+    None
+  }
+
+  // 远程 agent 握手协商的协议, 本地 agent 为 None
+  fn protocol(&self) -> Option<AgentProtocol> {
+    None
+  }
 }
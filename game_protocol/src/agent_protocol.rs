@@ -0,0 +1,146 @@
+// 远程 agent 协议的版本协商. 连上后服务端先发 Hello: 协议版本, 规则集, 角色和卡牌表;
+// agent 回复 Hello: 自己的协议版本, 名字和支持的决策类型. 版本太旧或者缺少决策类型的 agent 会收到 Reject,
+// 不能入座. 协商的结果在开局时写进历史记录 (AgentProtocol).
+//
+// 版本历史:
+// 1: 加入 Hello/Reject 握手
+// 2: 可选的 obs 增量模式 (Hello 的 obs_deltas), 决策请求的 obs 为 ObsSync, agent 可以回复 Resync
+// 3: websocket 断线重连 (Hello 的 resume_token), 重连后先收到 Resumed, 再收到没有回复的请求
+
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use thiserror::Error;
+
+use uuid::Uuid;
+
+use crate::domain::{Card, Color, Role};
+
+pub const PROTOCOL_VERSION: u32 = 3;
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+pub const RULESET: &str = "citadels_team"; // doc/rule.md
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum DecisionKind {
+  InitCard,
+  ChooseRole,
+  Kill,
+  Steal,
+  Magic,
+  Destroy,
+  Tomb,
+  Oper,
+  ChooseFrom2,
+  ChooseFrom3,
+}
+
+impl DecisionKind {
+  pub fn all() -> [DecisionKind; 10] {
+    [
+      DecisionKind::InitCard,
+      DecisionKind::ChooseRole,
+      DecisionKind::Kill,
+      DecisionKind::Steal,
+      DecisionKind::Magic,
+      DecisionKind::Destroy,
+      DecisionKind::Tomb,
+      DecisionKind::Oper,
+      DecisionKind::ChooseFrom2,
+      DecisionKind::ChooseFrom3,
+    ]
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CardInfo {
+  pub card: Card,
+  pub color: Color,
+  pub fee: u32,
+  pub number: u32, // 牌库中的张数
+  pub score: u32,
+}
+
+pub fn card_catalog() -> Vec<CardInfo> {
+  Card::iter()
+    .map(|card| CardInfo {
+      card,
+      color: card.color(),
+      fee: card.fee(),
+      number: card.number(),
+      score: card.score(),
+    })
+    .collect()
+}
+
+pub fn roles() -> Vec<Role> {
+  Role::population().to_vec()
+}
+
+// 协商的结果
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AgentProtocol {
+  pub version: u32,
+  pub name: String,
+  pub decisions: Vec<DecisionKind>,
+  #[serde(default)]
+  pub obs_deltas: bool,
+}
+
+#[derive(Error, Debug)]
+pub enum TransportError {
+  #[error("timed out")]
+  Timeout,
+  // 断线超过宽限期, 还可能重连回来, 这一次由 fallback 决策
+  #[error("away")]
+  Away,
+  #[error("disconnected: {0}")]
+  Disconnected(String),
+  // 收到了无法解析的回复, 跳过这一条继续等
+  #[error("bad frame {frame:?}: {reason}")]
+  BadFrame { frame: String, reason: String },
+}
+
+#[derive(Error, Debug)]
+pub enum HandshakeError {
+  #[error("transport: {0}")]
+  Transport(#[from] TransportError),
+  #[error("unexpected reply to Hello: {0}")]
+  Unexpected(String),
+  #[error("incompatible agent: {0}")]
+  Incompatible(String),
+}
+
+// 取双方都支持的最高版本. agent 必须支持服务端会发出的所有决策类型
+// 增量模式要双方都在协议 2 以上
+pub fn negotiate(
+  version: u32, name: String, decisions: Vec<DecisionKind>, obs_deltas: bool,
+) -> Result<AgentProtocol, HandshakeError> {
+  if version < MIN_PROTOCOL_VERSION {
+    return Err(HandshakeError::Incompatible(format!(
+      "protocol version {} not supported, server supports {}..={}",
+      version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+    )));
+  }
+  let missing = DecisionKind::all()
+    .into_iter()
+    .filter(|kind| !decisions.contains(kind))
+    .collect::<Vec<_>>();
+  if !missing.is_empty() {
+    return Err(HandshakeError::Incompatible(format!("missing decisions {:?}", missing)));
+  }
+  let version = version.min(PROTOCOL_VERSION);
+  Ok(AgentProtocol {
+    version,
+    name,
+    decisions,
+    obs_deltas: obs_deltas && version >= 2,
+  })
+}
+
+// Redis 上 (请求, 回复) 队列的 key, 服务端的 RedisTransport 和 agent 一侧用同样的 key
+pub fn redis_keys(agent_uuid: Uuid) -> (String, String) {
+  let room_uuid = "";
+  (
+    format!("room{room_uuid}:agent{agent_uuid}"),
+    format!("agent{agent_uuid}_to_room{room_uuid}"),
+  )
+}
@@ -11,6 +11,7 @@ mod option_role;
 mod player_index;
 mod player_offset;
 mod player_offset_set;
+mod rationale;
 mod role;
 mod role_offset_pair;
 mod roleset;
@@ -28,6 +29,7 @@ pub use option_role::OptionRole;
 pub use player_index::PlayerIndex;
 pub use player_offset::PlayerOffset;
 pub use player_offset_set::PlayerOffsetSet;
+pub use rationale::{Rationale, ScoredAlternative};
 pub use role::Role;
 pub use role_offset_pair::OptionRoleOffsetPair;
 pub use roleset::RoleSet;
@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+use strum::IntoStaticStr;
+use uuid::Uuid;
+
+use super::{Card, DestroyTarget, Oper, Role, RoleSet};
+use crate::agent_protocol::CardInfo;
+use crate::obs::ObsMessage;
+
+// 决策请求的 obs 在增量模式下是 ObsSync, 见 obs_delta
+#[derive(Serialize, Deserialize, Debug, Clone, IntoStaticStr)]
+pub enum AgentReqEvent {
+  WaitForReady {
+    id: u32,
+  },
+  // 握手, 见 agent_protocol
+  Hello {
+    id: u32,
+    protocol_version: u32,
+    ruleset: String,
+    roles: Vec<Role>,
+    cards: Vec<CardInfo>,
+    // 断线后凭它重连, 协议 3 起. 由 WsDispatcher 填上, 其他传输没有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    resume_token: Option<Uuid>,
+  },
+  // 握手失败, 不需要回复, 之后也不会再有请求
+  Reject {
+    id: u32,
+    reason: String,
+  },
+  // 重连成功, 不需要回复. obs 为当前的 obs (还没有决策过时为 None), 之后会重发没有回复的请求
+  Resumed {
+    id: u32,
+    obs: Option<ObsMessage>,
+  },
+  ChooseInitCard {
+    id: u32,
+    obs: ObsMessage,
+    c0: Card,
+    c1: Card,
+  },
+  ChooseRole {
+    id: u32,
+    obs: ObsMessage,
+    roles: RoleSet,
+  },
+  ChooseKillTarget {
+    id: u32,
+    obs: ObsMessage,
+    choices: RoleSet,
+  },
+  ChooseStealTarget {
+    id: u32,
+    obs: ObsMessage,
+    choices: RoleSet,
+  },
+  ChooseMagicTarget {
+    id: u32,
+    obs: ObsMessage,
+  },
+  ChooseDestoryTarget {
+    id: u32,
+    obs: ObsMessage,
+    choices: Vec<DestroyTarget>,
+  },
+  ChooseTomb {
+    id: u32,
+    obs: ObsMessage,
+    c: Card,
+  },
+  ChooseOper {
+    id: u32,
+    obs: ObsMessage,
+    choices: Vec<Oper>,
+  },
+  ChooseFrom2 {
+    id: u32,
+    obs: ObsMessage,
+    c0: Card,
+    c1: Card,
+  },
+  ChooseFrom3 {
+    id: u32,
+    obs: ObsMessage,
+    c0: Card,
+    c1: Card,
+    c2: Card,
+  },
+}
+
+impl AgentReqEvent {
+  pub fn id(&self) -> u32 {
+    match self {
+      AgentReqEvent::WaitForReady { id }
+      | AgentReqEvent::Hello { id, .. }
+      | AgentReqEvent::Reject { id, .. }
+      | AgentReqEvent::Resumed { id, .. }
+      | AgentReqEvent::ChooseInitCard { id, .. }
+      | AgentReqEvent::ChooseRole { id, .. }
+      | AgentReqEvent::ChooseKillTarget { id, .. }
+      | AgentReqEvent::ChooseStealTarget { id, .. }
+      | AgentReqEvent::ChooseMagicTarget { id, .. }
+      | AgentReqEvent::ChooseDestoryTarget { id, .. }
+      | AgentReqEvent::ChooseTomb { id, .. }
+      | AgentReqEvent::ChooseOper { id, .. }
+      | AgentReqEvent::ChooseFrom2 { id, .. }
+      | AgentReqEvent::ChooseFrom3 { id, .. } => *id,
+    }
+  }
+
+  // 决策请求的 obs
+  pub fn obs(&self) -> Option<&ObsMessage> {
+    match self {
+      AgentReqEvent::WaitForReady { .. }
+      | AgentReqEvent::Hello { .. }
+      | AgentReqEvent::Reject { .. }
+      | AgentReqEvent::Resumed { .. } => None,
+      AgentReqEvent::ChooseInitCard { obs, .. }
+      | AgentReqEvent::ChooseRole { obs, .. }
+      | AgentReqEvent::ChooseKillTarget { obs, .. }
+      | AgentReqEvent::ChooseStealTarget { obs, .. }
+      | AgentReqEvent::ChooseMagicTarget { obs, .. }
+      | AgentReqEvent::ChooseDestoryTarget { obs, .. }
+      | AgentReqEvent::ChooseTomb { obs, .. }
+      | AgentReqEvent::ChooseOper { obs, .. }
+      | AgentReqEvent::ChooseFrom2 { obs, .. }
+      | AgentReqEvent::ChooseFrom3 { obs, .. } => Some(obs),
+    }
+  }
+
+  // 重连时换成完整的 obs
+  pub fn obs_mut(&mut self) -> Option<&mut ObsMessage> {
+    match self {
+      AgentReqEvent::WaitForReady { .. }
+      | AgentReqEvent::Hello { .. }
+      | AgentReqEvent::Reject { .. }
+      | AgentReqEvent::Resumed { .. } => None,
+      AgentReqEvent::ChooseInitCard { obs, .. }
+      | AgentReqEvent::ChooseRole { obs, .. }
+      | AgentReqEvent::ChooseKillTarget { obs, .. }
+      | AgentReqEvent::ChooseStealTarget { obs, .. }
+      | AgentReqEvent::ChooseMagicTarget { obs, .. }
+      | AgentReqEvent::ChooseDestoryTarget { obs, .. }
+      | AgentReqEvent::ChooseTomb { obs, .. }
+      | AgentReqEvent::ChooseOper { obs, .. }
+      | AgentReqEvent::ChooseFrom2 { obs, .. }
+      | AgentReqEvent::ChooseFrom3 { obs, .. } => Some(obs),
+    }
+  }
+}
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Card, DestroyTarget, MagicianSkill, Oper, Rationale, Role};
+use crate::agent_protocol::DecisionKind;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub enum AgentRespEvent {
+  WaitForReady {
+    id: u32,
+  },
+  Hello {
+    id: u32,
+    protocol_version: u32,
+    name: String,
+    decisions: Vec<DecisionKind>,
+    // 要不要增量模式的 obs, 协议 2 起
+    #[serde(default)]
+    obs_deltas: bool,
+  },
+  // obs 的副本对不上 (序号有缺口), 要求服务端重发这个请求, 带完整的 obs
+  Resync {
+    id: u32,
+  },
+  InitCard {
+    id: u32,
+    chosen: Card,
+    // 决策的解释, 可选, 会记进对局历史
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+  Role {
+    id: u32,
+    chosen: Role,
+    // 决策的解释, 可选, 会记进对局历史
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+  KillTarget {
+    id: u32,
+    chosen: Role,
+    // 决策的解释, 可选, 会记进对局历史
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+  StealTarget {
+    id: u32,
+    chosen: Role,
+    // 决策的解释, 可选, 会记进对局历史
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+  MagicTarget {
+    id: u32,
+    chosen: MagicianSkill,
+    // 决策的解释, 可选, 会记进对局历史
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+  DestoryTarget {
+    id: u32,
+    chosen: Option<DestroyTarget>,
+    // 决策的解释, 可选, 会记进对局历史
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+  Tomb {
+    id: u32,
+    chosen: bool,
+    // 决策的解释, 可选, 会记进对局历史
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+  Oper {
+    id: u32,
+    chosen: Oper,
+    // 决策的解释, 可选, 会记进对局历史
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+  From2 {
+    id: u32,
+    chosen: Card,
+    // 决策的解释, 可选, 会记进对局历史
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+  From3 {
+    id: u32,
+    chosen: Card,
+    // 决策的解释, 可选, 会记进对局历史
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+}
+
+impl AgentRespEvent {
+  pub fn id(&self) -> u32 {
+    match self {
+      AgentRespEvent::WaitForReady { id }
+      | AgentRespEvent::Hello { id, .. }
+      | AgentRespEvent::Resync { id }
+      | AgentRespEvent::InitCard { id, .. }
+      | AgentRespEvent::Role { id, .. }
+      | AgentRespEvent::KillTarget { id, .. }
+      | AgentRespEvent::StealTarget { id, .. }
+      | AgentRespEvent::MagicTarget { id, .. }
+      | AgentRespEvent::DestoryTarget { id, .. }
+      | AgentRespEvent::Tomb { id, .. }
+      | AgentRespEvent::Oper { id, .. }
+      | AgentRespEvent::From2 { id, .. }
+      | AgentRespEvent::From3 { id, .. } => *id,
+    }
+  }
+
+  // 决策回复带的解释, 其他回复为 None
+  pub fn rationale(&self) -> Option<&Rationale> {
+    match self {
+      AgentRespEvent::WaitForReady { .. } | AgentRespEvent::Hello { .. } | AgentRespEvent::Resync { .. } => None,
+      AgentRespEvent::InitCard { rationale, .. }
+      | AgentRespEvent::Role { rationale, .. }
+      | AgentRespEvent::KillTarget { rationale, .. }
+      | AgentRespEvent::StealTarget { rationale, .. }
+      | AgentRespEvent::MagicTarget { rationale, .. }
+      | AgentRespEvent::DestoryTarget { rationale, .. }
+      | AgentRespEvent::Tomb { rationale, .. }
+      | AgentRespEvent::Oper { rationale, .. }
+      | AgentRespEvent::From2 { rationale, .. }
+      | AgentRespEvent::From3 { rationale, .. } => rationale.as_ref(),
+    }
+  }
+}
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use strum::EnumIter;
+use strum::{EnumCount, EnumIter};
 use valuable::{Valuable, Value};
 
 use crate::domain::Color;
@@ -25,7 +25,7 @@ use crate::domain::Color;
 // 金矿 = 44
 // 公园 = 45
 
-#[derive(Copy, Clone, EnumIter, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Copy, Clone, EnumCount, EnumIter, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Card {
   // 绿色
   酒馆 = 2,
@@ -63,7 +63,7 @@ pub enum Card {
 }
 
 impl Card {
-  fn name(&self) -> &'static str {
+  pub fn name(&self) -> &'static str {
     match self {
       Card::酒馆 => "酒馆",
       Card::贸易站 => "贸易站",
@@ -3,7 +3,7 @@ use valuable::Valuable;
 
 use crate::domain::Card;
 
-#[derive(Copy, Clone, Valuable, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Valuable, Serialize, Deserialize, Debug, PartialEq)]
 pub enum Oper {
   EndRound,
   Card3Choose1,
@@ -3,7 +3,7 @@ use valuable::Valuable;
 
 use crate::domain::PlayerOffset;
 
-#[derive(Clone, Debug, Valuable)]
+#[derive(Clone, Debug, PartialEq, Eq, Valuable)]
 pub struct OptionOffset {
   value: usize,
 }
@@ -22,6 +22,14 @@ impl OptionOffset {
       value: Self::NONE_VALUE,
     }
   }
+
+  pub fn to_option(&self) -> Option<PlayerOffset> {
+    if self.value == Self::NONE_VALUE {
+      None
+    } else {
+      Some(PlayerOffset::from_usize(self.value))
+    }
+  }
 }
 
 impl Serialize for OptionOffset {
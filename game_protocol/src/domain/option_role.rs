@@ -3,7 +3,7 @@ use valuable::{Valuable, Value};
 
 use crate::domain::Role;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum OptionRole {
   None = 0,
   刺客 = (Role::刺客 as isize),
@@ -37,6 +37,22 @@ impl From<Role> for OptionRole {
   }
 }
 
+impl OptionRole {
+  pub fn to_option(self) -> Option<Role> {
+    match self {
+      OptionRole::None => None,
+      OptionRole::刺客 => Some(Role::刺客),
+      OptionRole::小偷 => Some(Role::小偷),
+      OptionRole::魔术师 => Some(Role::魔术师),
+      OptionRole::国王 => Some(Role::国王),
+      OptionRole::主教 => Some(Role::主教),
+      OptionRole::商人 => Some(Role::商人),
+      OptionRole::建筑师 => Some(Role::建筑师),
+      OptionRole::军阀 => Some(Role::军阀),
+    }
+  }
+}
+
 impl Valuable for OptionRole {
   fn as_value(&self) -> Value<'_> {
     match self {
@@ -6,7 +6,7 @@ use crate::domain::PlayerIndex;
 // 0: 自己
 // 1: 下家
 // ...
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct PlayerOffset {
   value: usize,
 }
@@ -5,7 +5,7 @@ use valuable::Valuable;
 
 use crate::domain::PlayerOffset;
 
-#[derive(Clone, Debug, Valuable)]
+#[derive(Clone, Debug, PartialEq, Eq, Valuable)]
 pub struct PlayerOffsetSet {
   value: u32,
 }
@@ -30,6 +30,18 @@ impl PlayerOffsetSet {
   pub fn empty() -> Self {
     Self { value: 0 }
   }
+
+  pub fn len(&self) -> usize {
+    self.value.count_ones() as usize
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.value == 0
+  }
+
+  pub fn contains(&self, offset: PlayerOffset) -> bool {
+    self.value & (1 << offset.value()) != 0
+  }
 }
 
 impl Serialize for PlayerOffsetSet {
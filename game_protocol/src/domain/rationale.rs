@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use valuable::Valuable;
+
+// 智能体对一次决策的解释: 简短说明 + 各候选项的打分
+#[derive(Clone, Valuable, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Rationale {
+  pub text: String,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub alternatives: Vec<ScoredAlternative>,
+}
+
+#[derive(Clone, Valuable, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ScoredAlternative {
+  pub choice: String,
+  pub score: f64,
+}
+
+impl Rationale {
+  pub fn new(text: impl Into<String>) -> Self {
+    Rationale {
+      text: text.into(),
+      alternatives: Vec::new(),
+    }
+  }
+
+  pub fn with_alternative(mut self, choice: impl Into<String>, score: f64) -> Self {
+    self.alternatives.push(ScoredAlternative {
+      choice: choice.into(),
+      score,
+    });
+    self
+  }
+
+  // 单行展示, 用于复盘和日志
+  pub fn summary(&self) -> String {
+    if self.alternatives.is_empty() {
+      return self.text.clone();
+    }
+    let scores: Vec<String> = self
+      .alternatives
+      .iter()
+      .map(|a| format!("{}={:.3}", a.choice, a.score))
+      .collect();
+    format!("{} [{}]", self.text, scores.join(", "))
+  }
+}
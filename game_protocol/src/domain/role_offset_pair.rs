@@ -3,7 +3,7 @@ use valuable::Valuable;
 
 use crate::domain::{OptionOffset, OptionRole, PlayerOffset, Role};
 
-#[derive(Serialize, Deserialize, Clone, Debug, Valuable)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Valuable)]
 pub struct OptionRoleOffsetPair {
   offset: OptionOffset,
   role: OptionRole,
@@ -24,4 +24,12 @@ impl OptionRoleOffsetPair {
   pub fn set_role(&mut self, role: Role) {
     self.role = OptionRole::from(role);
   }
+
+  pub fn offset(&self) -> Option<PlayerOffset> {
+    self.offset.to_option()
+  }
+
+  pub fn role(&self) -> Option<Role> {
+    self.role.to_option()
+  }
 }
@@ -8,7 +8,7 @@ use valuable::Valuable;
 use crate::bit;
 use crate::domain::{OptionRole, Role};
 
-#[derive(Clone, Copy, Valuable, Debug)]
+#[derive(Clone, Copy, Valuable, Debug, PartialEq, Eq, Hash)]
 pub struct RoleSet {
   value: isize,
 }
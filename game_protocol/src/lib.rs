@@ -0,0 +1,23 @@
+// 服务端和 agent 共用的类型: 决策请求和回复, Obs 及其增量, 握手, 连接上的编码和 AbstractFAAgent.
+// 不依赖游戏引擎, 写 agent 时只需要这个 crate (和 agent_sdk)
+
+pub mod abstract_fa_agent;
+pub mod agent_protocol;
+pub mod bit;
+pub mod domain;
+pub mod obs;
+pub mod player_indexed_vec;
+mod respond;
+pub mod time_control;
+pub mod wire_format;
+
+pub use abstract_fa_agent::AbstractFAAgent;
+pub use agent_protocol::{
+  AgentProtocol, CardInfo, DecisionKind, HandshakeError, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, RULESET,
+  TransportError, card_catalog, redis_keys,
+};
+pub use obs::{DeckState, Obs, ObsEncoder, ObsMessage, ObsMirror, ObsPatch, ObsSync, PlayerState};
+pub use player_indexed_vec::PlayerIndexedVec;
+pub use respond::respond;
+pub use time_control::{Clock, ClockInfo, DecisionTimeout, TimeControl};
+pub use wire_format::WireFormat;
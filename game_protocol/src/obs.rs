@@ -0,0 +1,372 @@
+mod building_extra_score;
+mod building_info;
+mod common_player_info;
+mod feature_writer;
+mod hero_info;
+mod obs_delta;
+mod round_info;
+mod villain_info;
+
+pub use building_extra_score::BuildingExtraScore;
+pub use building_info::BuildingInfo;
+pub use feature_writer::FeatureWriter;
+pub use hero_info::HeroInfo;
+pub use obs_delta::{ObsEncoder, ObsMessage, ObsMirror, ObsPatch, ObsSync};
+pub use round_info::RoundInfo;
+use serde::{Deserialize, Serialize};
+use valuable::Valuable;
+pub use villain_info::VillainInfo;
+
+use crate::domain::{Camp, Card, PlayerIndex, PlayerOffset, PlayerOffsetSet, Role, RoleSet};
+use crate::player_indexed_vec::PlayerIndexedVec;
+use crate::time_control::ClockInfo;
+
+// 特征向量按 6 人局定长, 人数不足时 villain 补 0
+pub const MAX_PLAYERS: usize = 6;
+
+// 生成 obs 要读的玩家状态, 由 server 的 Player 实现
+pub trait PlayerState {
+  fn camp(&self) -> Camp;
+  fn gold(&self) -> u32;
+  fn cards(&self) -> &[Card];
+  fn current_role(&self) -> Option<Role>;
+  fn score(&self) -> u32;
+  fn buildings_len(&self) -> usize;
+  fn iter_buildings(&self) -> impl Iterator<Item = Card>;
+  fn building_destroy_fee_as(&self, c: Card, role: Option<Role>) -> Option<u32>;
+  fn has_all_colors(&self) -> bool;
+  fn is_first_8_buildings(&self) -> bool;
+}
+
+// 生成 obs 要读的牌堆状态, 由 server 的 Deck 实现
+pub trait DeckState {
+  fn peek_deck(&self) -> &[Card];
+  fn peek_drop(&self) -> &[Card];
+}
+
+#[derive(Clone, PartialEq, Valuable, Serialize, Deserialize, Debug)]
+pub struct Obs {
+  num_players: usize,
+  round_info: RoundInfo,
+  actor_info: HeroInfo,
+  villain_infos: Vec<VillainInfo>, // 从自己的下家开始，逆时针的其他玩家，也就是 player_offset 为 1, 2, ... 的玩家
+  deck_cnt: usize,
+  drop_cnt: usize,
+  total_score: [u32; 2],
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  clock: Option<ClockInfo>, // 只有有时限的远程 agent 收到的 obs 才有
+}
+
+impl Obs {
+  pub fn new(
+    num_players: usize, round: u32, crown: PlayerOffset, actor_info: HeroInfo, villain_infos: Vec<VillainInfo>,
+    deck: &impl DeckState,
+  ) -> Self {
+    Self {
+      num_players,
+      round_info: RoundInfo::new(round, crown),
+      actor_info,
+      villain_infos,
+      deck_cnt: deck.peek_deck().len(),
+      drop_cnt: deck.peek_drop().len(),
+      total_score: [0, 0],
+      clock: None,
+    }
+  }
+
+  pub fn round(&self) -> u32 {
+    self.round_info.round()
+  }
+
+  pub fn clock(&self) -> Option<ClockInfo> {
+    self.clock
+  }
+
+  pub fn set_clock(&mut self, clock: Option<ClockInfo>) {
+    self.clock = clock;
+  }
+
+  pub fn set_crown(&mut self, crown: PlayerOffset) {
+    self.round_info.set_crown(crown);
+  }
+
+  pub fn set_killed(&mut self, killed: PlayerOffset) {
+    self.round_info.set_killed_offset(killed);
+  }
+
+  pub fn set_stolen(&mut self, stolen: PlayerOffset) {
+    self.round_info.set_stolen_offset(stolen);
+  }
+
+  pub fn set_round(&mut self, round: u32) {
+    self.round_info.set_round(round);
+  }
+
+  pub fn set_roles_public_dropped(&mut self, roles: RoleSet) {
+    self.round_info.set_roles_public_dropped(roles);
+  }
+
+  pub fn set_players_choose_role_before(&mut self, offsets: PlayerOffsetSet) {
+    self.round_info.set_players_choose_role_before(offsets);
+  }
+
+  pub fn set_players_choose_role_after(&mut self, offsets: PlayerOffsetSet) {
+    self.round_info.set_players_choose_role_after(offsets);
+  }
+
+  pub fn set_roles_chosen_before(&mut self, roles: RoleSet) {
+    self.round_info.set_roles_chosen_before(roles);
+  }
+
+  pub fn set_roles_chosen_after(&mut self, roles: RoleSet) {
+    self.round_info.set_roles_chosen_after(roles);
+  }
+
+  pub fn set_villain_role(&mut self, offset: PlayerOffset, role: Role) {
+    self.villain_infos[offset.value() - 1].set_role(role);
+  }
+
+  pub fn set_killed_role(&mut self, role: Role) {
+    self.round_info.set_killed_role(role);
+  }
+
+  pub fn set_stolen_role(&mut self, role: Role) {
+    self.round_info.set_stolen_role(role);
+  }
+
+  pub fn reset(&mut self) {
+    self.actor_info.unset_role();
+    for villain in self.villain_infos.iter_mut() {
+      villain.unset_role();
+    }
+    self.round_info.reset();
+  }
+
+  pub fn set_actor_role(&mut self, role: Role) {
+    self.actor_info.set_role(role);
+  }
+
+  pub fn update_infos<P: PlayerState>(
+    &mut self, deck: &impl DeckState, players: &PlayerIndexedVec<P>, actor: PlayerIndex,
+  ) {
+    self.deck_cnt = deck.peek_deck().len();
+    self.drop_cnt = deck.peek_drop().len();
+
+    let mut villains = Vec::new();
+    for i in actor.value() + 1..players.len() {
+      villains.push(&players[PlayerIndex::from_usize(i)]);
+    }
+    for i in 0..actor.value() {
+      villains.push(&players[PlayerIndex::from_usize(i)]);
+    }
+
+    let actor = &players[actor];
+    self.actor_info.update_info(actor);
+    self.villain_infos.iter_mut().enumerate().for_each(|(i, villain)| {
+      villain.update_info(villains[i]);
+    });
+
+    self.total_score = [0, 0];
+    self.total_score[actor.camp() as usize] += actor.score();
+    for villain in villains.iter() {
+      self.total_score[villain.camp() as usize] += villain.score();
+    }
+  }
+
+  // 用 deck 和 players 重新 update_infos, 返回和自己的第一处不同
+  pub fn refresh_diff<P: PlayerState>(
+    &self, deck: &impl DeckState, players: &PlayerIndexedVec<P>, actor: PlayerIndex,
+  ) -> Option<String> {
+    let mut fresh = self.clone();
+    fresh.update_infos(deck, players, actor);
+    if fresh.deck_cnt != self.deck_cnt || fresh.drop_cnt != self.drop_cnt {
+      return Some(format!(
+        "deck/drop count {}/{}, expected {}/{}",
+        self.deck_cnt, self.drop_cnt, fresh.deck_cnt, fresh.drop_cnt
+      ));
+    }
+    if fresh.total_score != self.total_score {
+      return Some(format!(
+        "total score {:?}, expected {:?}",
+        self.total_score, fresh.total_score
+      ));
+    }
+    if fresh.actor_info != self.actor_info {
+      return Some(format!("hero {:?}, expected {:?}", self.actor_info, fresh.actor_info));
+    }
+    for (i, (villain, fresh)) in self.villain_infos.iter().zip(fresh.villain_infos.iter()).enumerate() {
+      if villain != fresh {
+        return Some(format!("villain {} {:?}, expected {:?}", i + 1, villain, fresh));
+      }
+    }
+    None
+  }
+
+  // 与真实局面比对, 返回第一处不一致: 公开信息要和 update_infos 刷新后的一样, 已经知道的角色要正确
+  pub fn check_consistency<P: PlayerState>(
+    &self, deck: &impl DeckState, players: &PlayerIndexedVec<P>, actor: PlayerIndex,
+  ) -> Result<(), String> {
+    if let Some(diff) = self.refresh_diff(deck, players, actor) {
+      return Err(diff);
+    }
+
+    if self.actor_info.role() != players[actor].current_role() {
+      return Err(format!(
+        "hero role {:?}, actually {:?}",
+        self.actor_info.role(),
+        players[actor].current_role()
+      ));
+    }
+    for (i, villain) in self.villain_infos.iter().enumerate() {
+      let index = PlayerOffset::from_usize(i + 1).to_index(actor, self.num_players);
+      if let Some(role) = villain.role()
+        && Some(role) != players[index].current_role()
+      {
+        return Err(format!(
+          "villain {} role {:?}, actually {:?}",
+          i + 1,
+          role,
+          players[index].current_role()
+        ));
+      }
+    }
+    Ok(())
+  }
+
+  pub fn actor_num_cards(&self) -> usize {
+    self.actor_info.num_cards()
+  }
+
+  pub fn num_players(&self) -> usize {
+    self.num_players
+  }
+
+  pub fn hero_card_at(&self, index: usize) -> Card {
+    self.actor_info.card_at(index)
+  }
+
+  pub fn hero_camp(&self) -> Camp {
+    self.actor_info.camp()
+  }
+
+  pub fn hero_gold(&self) -> u32 {
+    self.actor_info.gold()
+  }
+
+  pub fn hero_cards(&self) -> &[Card] {
+    self.actor_info.cards()
+  }
+
+  // 本轮选角色时自己是第几个选的, 皇冠为 0
+  pub fn role_select_position(&self) -> usize {
+    self.round_info.num_players_choose_role_before()
+  }
+
+  // 自己这一轮的角色, 还没选时为 None
+  pub fn hero_role(&self) -> Option<Role> {
+    self.actor_info.role()
+  }
+
+  pub fn hero_buildings(&self) -> impl Iterator<Item = Card> {
+    self.actor_info.iter_buildings()
+  }
+
+  pub fn hero_num_buildings(&self) -> usize {
+    self.actor_info.iter_buildings().count()
+  }
+
+  pub fn hero_has_building(&self, card: Card) -> bool {
+    self.actor_info.iter_buildings().any(|c| c == card)
+  }
+
+  pub fn villain_has_building(&self, offset: PlayerOffset, card: Card) -> bool {
+    self.villain_infos[offset.value() - 1]
+      .iter_buildings()
+      .any(|c| c == card)
+  }
+
+  pub fn crown(&self) -> PlayerOffset {
+    self.round_info.crown()
+  }
+
+  pub fn villain_gold(&self, offset: PlayerOffset) -> u32 {
+    self.villain_infos[offset.value() - 1].gold()
+  }
+
+  pub fn villain_buildings(&self, offset: PlayerOffset) -> impl Iterator<Item = Card> {
+    self.villain_infos[offset.value() - 1].iter_buildings()
+  }
+
+  // 本轮公开弃掉的角色
+  pub fn roles_public_dropped(&self) -> RoleSet {
+    self.round_info.roles_public_dropped()
+  }
+
+  // 本轮在自己之前被选走的角色
+  pub fn roles_chosen_before(&self) -> RoleSet {
+    self.round_info.roles_chosen_before()
+  }
+
+  // 没公开时为 None
+  pub fn villain_role(&self, offset: PlayerOffset) -> Option<Role> {
+    self.villain_infos[offset.value() - 1].role()
+  }
+
+  pub fn villain_camp(&self, offset: PlayerOffset) -> Camp {
+    self.villain_infos[offset.value() - 1].camp()
+  }
+
+  pub fn villain_num_cards(&self, offset: PlayerOffset) -> usize {
+    self.villain_infos[offset.value() - 1].num_cards()
+  }
+
+  pub fn write_features(&self, w: &mut FeatureWriter) {
+    w.push("num_players", self.num_players as f32);
+    w.push("deck_cnt", self.deck_cnt as f32);
+    w.push("drop_cnt", self.drop_cnt as f32);
+    let hero_camp = self.hero_camp();
+    let other_camp = match hero_camp {
+      Camp::楚 => Camp::汉,
+      Camp::汉 => Camp::楚,
+    };
+    w.push("total_score.hero_camp", self.total_score[hero_camp as usize] as f32);
+    w.push("total_score.other_camp", self.total_score[other_camp as usize] as f32);
+    self.round_info.write_features(w);
+    self.actor_info.write_features(w);
+    for i in 1..MAX_PLAYERS {
+      VillainInfo::write_features(
+        self.villain_infos.get(i - 1),
+        format_args!("villain{}", i),
+        hero_camp,
+        w,
+      );
+    }
+  }
+
+  pub fn features(&self) -> Vec<f32> {
+    let mut w = FeatureWriter::new();
+    self.write_features(&mut w);
+    w.into_values()
+  }
+
+  // 特征向量按 MAX_PLAYERS 补齐, 长度与局面无关
+  pub fn num_features() -> usize {
+    let obs = Obs {
+      num_players: 1,
+      round_info: RoundInfo::new(0, PlayerOffset::from_usize(0)),
+      actor_info: HeroInfo::empty(Camp::汉),
+      villain_infos: Vec::new(),
+      deck_cnt: 0,
+      drop_cnt: 0,
+      total_score: [0, 0],
+      clock: None,
+    };
+    obs.features().len()
+  }
+
+  pub fn feature_names(&self) -> Vec<String> {
+    let mut w = FeatureWriter::with_names();
+    self.write_features(&mut w);
+    w.into_names()
+  }
+}
@@ -0,0 +1,38 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use valuable::Valuable;
+
+use super::feature_writer::FeatureWriter;
+use crate::obs::PlayerState;
+
+#[derive(Debug, Clone, Default, PartialEq, Valuable, Serialize, Deserialize)]
+pub struct BuildingExtraScore {
+  // TODO: rename to extra score
+  all_colors: u32,
+  eight_buildings: u32,
+  first_eight_buildings: u32,
+}
+
+impl BuildingExtraScore {
+  pub fn new(player: &impl PlayerState) -> Self {
+    Self {
+      all_colors: if player.has_all_colors() { 3 } else { 0 },
+      eight_buildings: if player.buildings_len() == 8 { 2 } else { 0 },
+      first_eight_buildings: if player.is_first_8_buildings() { 2 } else { 0 },
+    }
+  }
+
+  pub fn write_features(info: Option<&Self>, prefix: impl Display, w: &mut FeatureWriter) {
+    let value = |f: fn(&Self) -> u32| info.map_or(0.0, |info| f(info) as f32);
+    w.push(format_args!("{}.all_colors", prefix), value(|info| info.all_colors));
+    w.push(
+      format_args!("{}.eight_buildings", prefix),
+      value(|info| info.eight_buildings),
+    );
+    w.push(
+      format_args!("{}.first_eight_buildings", prefix),
+      value(|info| info.first_eight_buildings),
+    );
+  }
+}
@@ -3,7 +3,7 @@ use valuable::Valuable;
 
 use crate::domain::{Card, Color};
 
-#[derive(Debug, Clone, Valuable, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Valuable, Serialize, Deserialize)]
 pub struct BuildingInfo {
   card: Card,
   color: Color,
@@ -22,4 +22,8 @@ impl BuildingInfo {
       destroy_fee,
     }
   }
+
+  pub fn card(&self) -> Card {
+    self.card
+  }
 }
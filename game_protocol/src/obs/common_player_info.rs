@@ -0,0 +1,177 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use valuable::Valuable;
+
+use super::building_extra_score::BuildingExtraScore;
+use super::building_info::BuildingInfo;
+use super::feature_writer::FeatureWriter;
+use super::obs_delta::ObsPatch;
+use crate::domain::{Camp, Card, PlayerOffset, Role};
+use crate::obs::PlayerState;
+
+#[derive(Debug, Clone, PartialEq, Valuable, Serialize, Deserialize)]
+pub struct CommonPlayerInfo {
+  camp: Camp,
+  gold: u32,
+  buildings: Vec<BuildingInfo>,
+  building_extra_score: BuildingExtraScore,
+  role: Option<Role>,
+}
+
+impl<P: PlayerState> From<&P> for CommonPlayerInfo {
+  fn from(player: &P) -> Self {
+    // TODO: 和 update 重复了?
+    let mut buildings = Vec::new();
+    for b in player.iter_buildings() {
+      buildings.push(BuildingInfo::new(b, player.building_destroy_fee_as(b, None)));
+    }
+
+    let building_extra_score = BuildingExtraScore::new(player);
+
+    Self {
+      camp: player.camp(),
+      gold: player.gold(),
+      buildings,
+      building_extra_score,
+      role: None,
+    }
+  }
+}
+
+impl CommonPlayerInfo {
+  // 没有金币和建筑的玩家, 只用来算特征长度
+  pub(super) fn empty(camp: Camp) -> Self {
+    Self {
+      camp,
+      gold: 0,
+      buildings: Vec::new(),
+      building_extra_score: BuildingExtraScore::default(),
+      role: None,
+    }
+  }
+
+  pub fn set_role(&mut self, role: Role) {
+    assert!(self.role.is_none());
+    self.role = Some(role);
+  }
+
+  pub fn unset_role(&mut self) {
+    assert!(self.role.is_some());
+    self.role = None;
+  }
+
+  pub fn update_info(&mut self, player: &impl PlayerState) {
+    self.gold = player.gold();
+
+    let mut buildings = Vec::new();
+    for b in player.iter_buildings() {
+      buildings.push(BuildingInfo::new(b, player.building_destroy_fee_as(b, self.role)));
+    }
+    self.buildings = buildings;
+    self.building_extra_score = BuildingExtraScore::new(player);
+  }
+
+  pub fn camp(&self) -> Camp {
+    self.camp
+  }
+
+  pub fn role(&self) -> Option<Role> {
+    self.role
+  }
+
+  pub fn gold(&self) -> u32 {
+    self.gold
+  }
+
+  pub fn iter_buildings(&self) -> impl Iterator<Item = Card> {
+    self.buildings.iter().map(|b| b.card())
+  }
+
+  pub(super) fn diff(&self, new: &Self, offset: PlayerOffset, patches: &mut Vec<ObsPatch>) {
+    if self.gold != new.gold {
+      patches.push(ObsPatch::Gold {
+        offset,
+        from: self.gold,
+        to: new.gold,
+      });
+    }
+    if self.role != new.role {
+      patches.push(ObsPatch::Role { offset, role: new.role });
+    }
+    if self.buildings != new.buildings {
+      let (old, new) = (&self.buildings, &new.buildings);
+      if new.len() > old.len() && new.starts_with(old) {
+        for building in &new[old.len()..] {
+          patches.push(ObsPatch::Built {
+            offset,
+            building: building.clone(),
+          });
+        }
+      } else if let Some(i) =
+        (0..old.len()).find(|&i| new.len() + 1 == old.len() && old[..i] == new[..i] && old[i + 1..] == new[i..])
+      {
+        patches.push(ObsPatch::Destroyed {
+          offset,
+          card: old[i].card(),
+        });
+      } else {
+        patches.push(ObsPatch::Buildings {
+          offset,
+          buildings: new.clone(),
+        });
+      }
+    }
+    if self.building_extra_score != new.building_extra_score {
+      patches.push(ObsPatch::ExtraScore {
+        offset,
+        extra_score: new.building_extra_score.clone(),
+      });
+    }
+  }
+
+  pub(super) fn apply(&mut self, patch: &ObsPatch) -> Result<(), String> {
+    match patch {
+      ObsPatch::Gold { from, to, .. } => {
+        if self.gold != *from {
+          return Err(format!("gold {}, patch from {}", self.gold, from));
+        }
+        self.gold = *to;
+      },
+      ObsPatch::Role { role, .. } => self.role = *role,
+      ObsPatch::Built { building, .. } => self.buildings.push(building.clone()),
+      ObsPatch::Destroyed { card, .. } => {
+        let i = self
+          .buildings
+          .iter()
+          .position(|b| b.card() == *card)
+          .ok_or_else(|| format!("no building {:?} to destroy", card))?;
+        self.buildings.remove(i);
+      },
+      ObsPatch::Buildings { buildings, .. } => self.buildings = buildings.clone(),
+      ObsPatch::ExtraScore { extra_score, .. } => self.building_extra_score = extra_score.clone(),
+      _ => return Err(format!("{:?} is not a player patch", patch)),
+    }
+    Ok(())
+  }
+
+  // info 为 None 时输出全 0, 用于补齐不足 6 人的对局
+  pub fn write_features(info: Option<&Self>, prefix: impl Display + Copy, hero_camp: Camp, w: &mut FeatureWriter) {
+    w.push_bool(
+      format_args!("{}.same_camp", prefix),
+      info.is_some_and(|info| info.camp == hero_camp),
+    );
+    w.push(
+      format_args!("{}.gold", prefix),
+      info.map_or(0.0, |info| info.gold as f32),
+    );
+    w.push_role(format_args!("{}.role", prefix), info.and_then(|info| info.role));
+    let buildings = info.map_or(&[][..], |info| &info.buildings[..]);
+    w.push_card_counts(format_args!("{}.building", prefix), buildings.iter().map(|b| b.card()));
+    BuildingExtraScore::write_features(
+      info.map(|info| &info.building_extra_score),
+      format_args!("{}.extra_score", prefix),
+      w,
+    );
+  }
+}
@@ -0,0 +1,83 @@
+use std::fmt::Display;
+
+use strum::IntoEnumIterator;
+
+use crate::domain::{Card, PlayerOffset, Role, RoleSet};
+
+// Obs 转成定长特征向量时使用, 需要 schema 时同时记录每一维的名字
+pub struct FeatureWriter {
+  values: Vec<f32>,
+  names: Option<Vec<String>>,
+}
+
+impl FeatureWriter {
+  pub fn new() -> Self {
+    Self {
+      values: Vec::new(),
+      names: None,
+    }
+  }
+
+  pub fn with_names() -> Self {
+    Self {
+      values: Vec::new(),
+      names: Some(Vec::new()),
+    }
+  }
+
+  pub fn push(&mut self, name: impl Display, value: f32) {
+    self.values.push(value);
+    if let Some(names) = self.names.as_mut() {
+      names.push(name.to_string());
+    }
+  }
+
+  pub fn push_bool(&mut self, name: impl Display, value: bool) {
+    self.push(name, if value { 1.0 } else { 0.0 });
+  }
+
+  pub fn push_offset(&mut self, prefix: impl Display, offset: Option<PlayerOffset>) {
+    for i in 0..super::MAX_PLAYERS {
+      self.push_bool(
+        format_args!("{}.offset{}", prefix, i),
+        offset.is_some_and(|o| o.value() == i),
+      );
+    }
+  }
+
+  pub fn push_role(&mut self, prefix: impl Display, role: Option<Role>) {
+    for r in Role::population() {
+      self.push_bool(
+        format_args!("{}.{}", prefix, r.name()),
+        role.is_some_and(|role| role == r),
+      );
+    }
+  }
+
+  pub fn push_roles(&mut self, prefix: impl Display, roles: RoleSet) {
+    for r in Role::population() {
+      self.push_bool(format_args!("{}.{}", prefix, r.name()), roles.contains(r));
+    }
+  }
+
+  pub fn push_card_counts(&mut self, prefix: impl Display, cards: impl Iterator<Item = Card> + Clone) {
+    for c in Card::iter() {
+      let cnt = cards.clone().filter(|&card| card == c).count();
+      self.push(format_args!("{}.{}", prefix, c.name()), cnt as f32);
+    }
+  }
+
+  pub fn into_values(self) -> Vec<f32> {
+    self.values
+  }
+
+  pub fn into_names(self) -> Vec<String> {
+    self.names.unwrap_or_default()
+  }
+}
+
+impl Default for FeatureWriter {
+  fn default() -> Self {
+    Self::new()
+  }
+}
@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+use valuable::Valuable;
+
+use super::common_player_info::CommonPlayerInfo;
+use super::feature_writer::FeatureWriter;
+use super::obs_delta::ObsPatch;
+use crate::domain::{Camp, Card, PlayerOffset, Role};
+use crate::obs::PlayerState;
+
+#[derive(Debug, Clone, PartialEq, Valuable, Serialize, Deserialize)]
+pub struct HeroInfo {
+  #[serde(flatten)]
+  common: CommonPlayerInfo,
+
+  cards: Vec<Card>,
+}
+
+impl<P: PlayerState> From<&P> for HeroInfo {
+  fn from(player: &P) -> Self {
+    Self {
+      common: CommonPlayerInfo::from(player),
+      cards: player.cards().to_vec(),
+    }
+  }
+}
+
+impl HeroInfo {
+  pub(super) fn empty(camp: Camp) -> Self {
+    Self {
+      common: CommonPlayerInfo::empty(camp),
+      cards: Vec::new(),
+    }
+  }
+
+  pub fn set_role(&mut self, role: Role) {
+    self.common.set_role(role);
+  }
+
+  pub fn unset_role(&mut self) {
+    self.common.unset_role();
+  }
+
+  pub fn update_info(&mut self, actor: &impl PlayerState) {
+    self.common.update_info(actor);
+    self.cards = actor.cards().to_vec();
+  }
+
+  pub fn num_cards(&self) -> usize {
+    self.cards.len()
+  }
+
+  pub fn card_at(&self, index: usize) -> Card {
+    self.cards[index]
+  }
+
+  pub fn camp(&self) -> Camp {
+    self.common.camp()
+  }
+
+  pub fn role(&self) -> Option<Role> {
+    self.common.role()
+  }
+
+  pub fn gold(&self) -> u32 {
+    self.common.gold()
+  }
+
+  pub fn cards(&self) -> &[Card] {
+    &self.cards
+  }
+
+  pub fn iter_buildings(&self) -> impl Iterator<Item = Card> {
+    self.common.iter_buildings()
+  }
+
+  pub(super) fn diff(&self, new: &Self, patches: &mut Vec<ObsPatch>) {
+    self.common.diff(&new.common, PlayerOffset::ZERO, patches);
+    if self.cards != new.cards {
+      patches.push(ObsPatch::Cards(new.cards.clone()));
+    }
+  }
+
+  pub(super) fn apply(&mut self, patch: &ObsPatch) -> Result<(), String> {
+    match patch {
+      ObsPatch::Cards(cards) => self.cards = cards.clone(),
+      _ => self.common.apply(patch)?,
+    }
+    Ok(())
+  }
+
+  pub fn write_features(&self, w: &mut FeatureWriter) {
+    CommonPlayerInfo::write_features(Some(&self.common), "hero", self.camp(), w);
+    w.push("hero.num_cards", self.cards.len() as f32);
+    w.push_card_counts("hero.card", self.cards.iter().copied());
+  }
+}
@@ -0,0 +1,244 @@
+// obs 的增量模式: 每个远程 agent 一个序号递增的流, 先发一次完整的 obs, 之后只发和上一次相比的变化 (ObsPatch).
+// agent 一侧用 ObsMirror 维护副本, 序号对不上 (有缺口) 或者 patch 和副本对不上时回复 Resync, 服务端重发完整的 obs
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::{BuildingExtraScore, BuildingInfo, Obs, RoundInfo};
+use crate::domain::{Card, PlayerOffset, Role};
+use crate::time_control::ClockInfo;
+
+// offset 0 为 hero, 其余为 villain
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ObsPatch {
+  Round(RoundInfo),
+  Counts {
+    deck_cnt: usize,
+    drop_cnt: usize,
+  },
+  TotalScore([u32; 2]),
+  Clock(Option<ClockInfo>),
+  Gold {
+    offset: PlayerOffset,
+    from: u32,
+    to: u32,
+  },
+  Role {
+    offset: PlayerOffset,
+    role: Option<Role>,
+  },
+  Built {
+    offset: PlayerOffset,
+    building: BuildingInfo,
+  },
+  Destroyed {
+    offset: PlayerOffset,
+    card: Card,
+  },
+  // 不是建造或者拆除的变化 (比如拆除费用), 整个替换
+  Buildings {
+    offset: PlayerOffset,
+    buildings: Vec<BuildingInfo>,
+  },
+  ExtraScore {
+    offset: PlayerOffset,
+    extra_score: BuildingExtraScore,
+  },
+  // hero 的手牌
+  Cards(Vec<Card>),
+  // villain 的手牌数
+  NumCards {
+    offset: PlayerOffset,
+    from: u32,
+    to: u32,
+  },
+}
+
+impl ObsPatch {
+  // 针对某个玩家的 patch
+  pub fn offset(&self) -> Option<PlayerOffset> {
+    match self {
+      ObsPatch::Round(_) | ObsPatch::Counts { .. } | ObsPatch::TotalScore(_) | ObsPatch::Clock(_) => None,
+      ObsPatch::Cards(_) => Some(PlayerOffset::ZERO),
+      ObsPatch::Gold { offset, .. }
+      | ObsPatch::Role { offset, .. }
+      | ObsPatch::Built { offset, .. }
+      | ObsPatch::Destroyed { offset, .. }
+      | ObsPatch::Buildings { offset, .. }
+      | ObsPatch::ExtraScore { offset, .. }
+      | ObsPatch::NumCards { offset, .. } => Some(*offset),
+    }
+  }
+}
+
+// full 为 Some 时是完整的 obs (流的开头和 Resync 之后), 否则 patches 是相对于 seq - 1 的变化
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ObsSync {
+  pub seq: u64,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub full: Option<Obs>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub patches: Vec<ObsPatch>,
+}
+
+// 决策请求中的 obs. 没有协商增量模式的 agent 收到的都是 Full, 和协议 1 的格式一样
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ObsMessage {
+  Sync(ObsSync),
+  Full(Obs),
+}
+
+impl ObsMessage {
+  pub fn full(&self) -> Option<&Obs> {
+    match self {
+      ObsMessage::Full(obs) => Some(obs),
+      ObsMessage::Sync(sync) if sync.patches.is_empty() => sync.full.as_ref(),
+      ObsMessage::Sync(_) => None,
+    }
+  }
+}
+
+impl From<Obs> for ObsMessage {
+  fn from(obs: Obs) -> Self {
+    ObsMessage::Full(obs)
+  }
+}
+
+impl Obs {
+  // 从自己到 new 的变化
+  pub fn diff(&self, new: &Obs) -> Vec<ObsPatch> {
+    let mut patches = Vec::new();
+    if self.round_info != new.round_info {
+      patches.push(ObsPatch::Round(new.round_info.clone()));
+    }
+    if self.deck_cnt != new.deck_cnt || self.drop_cnt != new.drop_cnt {
+      patches.push(ObsPatch::Counts {
+        deck_cnt: new.deck_cnt,
+        drop_cnt: new.drop_cnt,
+      });
+    }
+    if self.total_score != new.total_score {
+      patches.push(ObsPatch::TotalScore(new.total_score));
+    }
+    if self.clock != new.clock {
+      patches.push(ObsPatch::Clock(new.clock));
+    }
+    self.actor_info.diff(&new.actor_info, &mut patches);
+    for (i, (villain, new)) in self.villain_infos.iter().zip(new.villain_infos.iter()).enumerate() {
+      villain.diff(new, PlayerOffset::from_usize(i + 1), &mut patches);
+    }
+    patches
+  }
+
+  // patch 和自己对不上时返回第一处不一致, 这时自己可能已经被改了一部分
+  pub fn apply(&mut self, patches: &[ObsPatch]) -> Result<(), String> {
+    for patch in patches {
+      match patch {
+        ObsPatch::Round(round_info) => self.round_info = round_info.clone(),
+        ObsPatch::Counts { deck_cnt, drop_cnt } => {
+          self.deck_cnt = *deck_cnt;
+          self.drop_cnt = *drop_cnt;
+        },
+        ObsPatch::TotalScore(total_score) => self.total_score = *total_score,
+        ObsPatch::Clock(clock) => self.clock = *clock,
+        _ => {
+          let offset = patch.offset().unwrap().value();
+          if offset == 0 {
+            self.actor_info.apply(patch)?;
+          } else {
+            self
+              .villain_infos
+              .get_mut(offset - 1)
+              .ok_or_else(|| format!("no villain at offset {}", offset))?
+              .apply(patch)?;
+          }
+        },
+      }
+    }
+    Ok(())
+  }
+}
+
+// 服务端一侧, 每个 agent 一个
+#[derive(Debug, Default)]
+pub struct ObsEncoder {
+  seq: u64,
+  last: Option<Obs>,
+}
+
+impl ObsEncoder {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn encode(&mut self, obs: &Obs) -> ObsMessage {
+    self.seq += 1;
+    let sync = match &self.last {
+      Some(last) => ObsSync {
+        seq: self.seq,
+        full: None,
+        patches: last.diff(obs),
+      },
+      None => ObsSync {
+        seq: self.seq,
+        full: Some(obs.clone()),
+        patches: Vec::new(),
+      },
+    };
+    self.last = Some(obs.clone());
+    ObsMessage::Sync(sync)
+  }
+
+  // 下一次发完整的 obs
+  pub fn resync(&mut self) {
+    self.last = None;
+  }
+}
+
+// agent 一侧的副本
+#[derive(Debug, Default)]
+pub struct ObsMirror {
+  seq: u64,
+  obs: Option<Obs>,
+}
+
+impl ObsMirror {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  // 返回 None 时要回复 Resync
+  pub fn receive(&mut self, message: &ObsMessage) -> Option<Obs> {
+    let sync = match message {
+      ObsMessage::Full(obs) => return Some(obs.clone()),
+      ObsMessage::Sync(sync) => sync,
+    };
+    let mut obs = match (&sync.full, self.obs.take()) {
+      (Some(full), _) => full.clone(),
+      (None, Some(obs)) if sync.seq == self.seq + 1 => obs,
+      (None, _) => {
+        warn!("obs gap: have {}, got {}", self.seq, sync.seq);
+        return None;
+      },
+    };
+    if let Err(e) = obs.apply(&sync.patches) {
+      warn!("obs patch {} mismatch: {}", sync.seq, e);
+      return None;
+    }
+    self.seq = sync.seq;
+    self.obs = Some(obs.clone());
+    Some(obs)
+  }
+
+  // 当前的副本, 作为完整的 obs, 收到的 agent 之后可以接着收增量
+  pub fn snapshot(&self) -> Option<ObsMessage> {
+    self.obs.as_ref().map(|obs| {
+      ObsMessage::Sync(ObsSync {
+        seq: self.seq,
+        full: Some(obs.clone()),
+        patches: Vec::new(),
+      })
+    })
+  }
+}
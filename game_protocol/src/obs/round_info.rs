@@ -1,9 +1,11 @@
 use serde::{Deserialize, Serialize};
 use valuable::Valuable;
 
+use super::MAX_PLAYERS;
+use super::feature_writer::FeatureWriter;
 use crate::domain::{OptionRoleOffsetPair, PlayerOffset, PlayerOffsetSet, Role, RoleSet};
 
-#[derive(Clone, Valuable, Serialize, Deserialize, Debug)]
+#[derive(Clone, PartialEq, Valuable, Serialize, Deserialize, Debug)]
 pub struct RoundInfo {
   round: u32,
   crown: PlayerOffset,
@@ -35,6 +37,22 @@ impl RoundInfo {
     self.round
   }
 
+  pub fn crown(&self) -> PlayerOffset {
+    self.crown
+  }
+
+  pub fn roles_public_dropped(&self) -> RoleSet {
+    self.roles_public_dropped
+  }
+
+  pub fn roles_chosen_before(&self) -> RoleSet {
+    self.roles_chosen_before
+  }
+
+  pub fn num_players_choose_role_before(&self) -> usize {
+    self.players_choose_role_before.len()
+  }
+
   pub fn set_crown(&mut self, crown: PlayerOffset) {
     self.crown = crown;
   }
@@ -79,6 +97,36 @@ impl RoundInfo {
     self.stolen.set_role(role);
   }
 
+  pub fn write_features(&self, w: &mut FeatureWriter) {
+    w.push("round", self.round as f32);
+    w.push_offset("crown", Some(self.crown));
+    w.push_roles("roles_public_dropped", self.roles_public_dropped);
+    for i in 0..MAX_PLAYERS {
+      let offset = PlayerOffset::from_usize(i);
+      w.push_bool(
+        format_args!("players_choose_role_before.offset{}", i),
+        self.players_choose_role_before.contains(offset),
+      );
+    }
+    for i in 0..MAX_PLAYERS {
+      let offset = PlayerOffset::from_usize(i);
+      w.push_bool(
+        format_args!("players_choose_role_after.offset{}", i),
+        self.players_choose_role_after.contains(offset),
+      );
+    }
+    w.push_roles("roles_chosen_before", self.roles_chosen_before);
+    w.push_bool("roles_chosen_after.known", self.roles_chosen_after.is_some());
+    w.push_roles(
+      "roles_chosen_after",
+      self.roles_chosen_after.unwrap_or(RoleSet::empty()),
+    );
+    w.push_offset("killed", self.killed.offset());
+    w.push_role("killed.role", self.killed.role());
+    w.push_offset("stolen", self.stolen.offset());
+    w.push_role("stolen.role", self.stolen.role());
+  }
+
   pub fn reset(&mut self) {
     self.roles_public_dropped = RoleSet::empty();
     self.players_choose_role_before = PlayerOffsetSet::empty();
@@ -0,0 +1,95 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use valuable::Valuable;
+
+use super::common_player_info::CommonPlayerInfo;
+use super::feature_writer::FeatureWriter;
+use super::obs_delta::ObsPatch;
+use crate::domain::{Camp, Card, PlayerOffset, Role};
+use crate::obs::PlayerState;
+
+#[derive(Debug, Clone, PartialEq, Valuable, Serialize, Deserialize)]
+pub struct VillainInfo {
+  #[serde(flatten)]
+  common: CommonPlayerInfo,
+
+  num_cards: u32,
+}
+
+impl<P: PlayerState> From<&P> for VillainInfo {
+  fn from(player: &P) -> Self {
+    Self {
+      common: CommonPlayerInfo::from(player),
+      num_cards: player.cards().len() as u32,
+    }
+  }
+}
+impl VillainInfo {
+  pub fn set_role(&mut self, role: Role) {
+    self.common.set_role(role);
+  }
+
+  pub fn unset_role(&mut self) {
+    self.common.unset_role();
+  }
+
+  pub fn update_info(&mut self, player: &impl PlayerState) {
+    self.common.update_info(player);
+    self.num_cards = player.cards().len() as u32;
+  }
+
+  pub fn camp(&self) -> Camp {
+    self.common.camp()
+  }
+
+  pub fn role(&self) -> Option<Role> {
+    self.common.role()
+  }
+
+  pub fn gold(&self) -> u32 {
+    self.common.gold()
+  }
+
+  pub fn iter_buildings(&self) -> impl Iterator<Item = Card> {
+    self.common.iter_buildings()
+  }
+
+  pub fn num_cards(&self) -> usize {
+    self.num_cards as usize
+  }
+
+  pub(super) fn diff(&self, new: &Self, offset: PlayerOffset, patches: &mut Vec<ObsPatch>) {
+    self.common.diff(&new.common, offset, patches);
+    if self.num_cards != new.num_cards {
+      patches.push(ObsPatch::NumCards {
+        offset,
+        from: self.num_cards,
+        to: new.num_cards,
+      });
+    }
+  }
+
+  pub(super) fn apply(&mut self, patch: &ObsPatch) -> Result<(), String> {
+    match patch {
+      ObsPatch::NumCards { from, to, .. } => {
+        if self.num_cards != *from {
+          return Err(format!("num cards {}, patch from {}", self.num_cards, from));
+        }
+        self.num_cards = *to;
+      },
+      ObsPatch::Cards(_) => return Err("villain cards are hidden".to_string()),
+      _ => self.common.apply(patch)?,
+    }
+    Ok(())
+  }
+
+  pub fn write_features(info: Option<&Self>, prefix: impl Display + Copy, hero_camp: Camp, w: &mut FeatureWriter) {
+    w.push_bool(format_args!("{}.present", prefix), info.is_some());
+    CommonPlayerInfo::write_features(info.map(|info| &info.common), prefix, hero_camp, w);
+    w.push(
+      format_args!("{}.num_cards", prefix),
+      info.map_or(0.0, |info| info.num_cards as f32),
+    );
+  }
+}
@@ -76,3 +76,20 @@ impl<T> IndexMut<PlayerIndex> for PlayerIndexedVec<T> {
     &mut self.values[index.value()]
   }
 }
+
+impl<T> IntoIterator for PlayerIndexedVec<T> {
+  type Item = T;
+  type IntoIter = std::vec::IntoIter<T>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.values.into_iter()
+  }
+}
+
+impl<T> FromIterator<T> for PlayerIndexedVec<T> {
+  fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+    Self {
+      values: iter.into_iter().collect(),
+    }
+  }
+}
@@ -0,0 +1,100 @@
+use tracing::warn;
+
+use crate::abstract_fa_agent::AbstractFAAgent;
+use crate::agent_protocol::{DecisionKind, PROTOCOL_VERSION};
+use crate::domain::{AgentReqEvent, AgentRespEvent};
+use crate::obs::ObsMirror;
+
+// agent 一侧: 用本地的 agent 回复一个请求, 带上 agent 给出的解释. Reject 和 Resumed 不需要回复. 会要求增量模式的 obs, mirror 是这个连接上的副本
+pub async fn respond(
+  agent: &mut dyn AbstractFAAgent, mirror: &mut ObsMirror, event: AgentReqEvent,
+) -> Option<AgentRespEvent> {
+  let resp = match event {
+    AgentReqEvent::WaitForReady { id } => {
+      agent.wait_for_ready().await;
+      AgentRespEvent::WaitForReady { id }
+    },
+    AgentReqEvent::Hello { id, .. } => AgentRespEvent::Hello {
+      id,
+      protocol_version: PROTOCOL_VERSION,
+      name: agent.name().to_string(),
+      decisions: DecisionKind::all().to_vec(),
+      obs_deltas: true,
+    },
+    AgentReqEvent::Reject { reason, .. } => {
+      warn!("rejected by server: {}", reason);
+      return None;
+    },
+    AgentReqEvent::Resumed { obs, .. } => {
+      if let Some(obs) = obs {
+        mirror.receive(&obs);
+      }
+      return None;
+    },
+    _ => decide(agent, mirror, event).await,
+  };
+  Some(resp)
+}
+
+async fn decide(agent: &mut dyn AbstractFAAgent, mirror: &mut ObsMirror, event: AgentReqEvent) -> AgentRespEvent {
+  let id = event.id();
+  let Some(obs) = event.obs().and_then(|obs| mirror.receive(obs)) else {
+    return AgentRespEvent::Resync { id };
+  };
+  match event {
+    AgentReqEvent::WaitForReady { .. }
+    | AgentReqEvent::Hello { .. }
+    | AgentReqEvent::Reject { .. }
+    | AgentReqEvent::Resumed { .. } => unreachable!(),
+    AgentReqEvent::ChooseInitCard { c0, c1, .. } => AgentRespEvent::InitCard {
+      id,
+      chosen: agent.choose_init_card(&obs, c0, c1).await,
+      rationale: agent.take_rationale(),
+    },
+    AgentReqEvent::ChooseRole { roles, .. } => AgentRespEvent::Role {
+      id,
+      chosen: agent.choose_role(&obs, roles).await,
+      rationale: agent.take_rationale(),
+    },
+    AgentReqEvent::ChooseKillTarget { choices, .. } => AgentRespEvent::KillTarget {
+      id,
+      chosen: agent.choose_kill_target(&obs, choices).await,
+      rationale: agent.take_rationale(),
+    },
+    AgentReqEvent::ChooseStealTarget { choices, .. } => AgentRespEvent::StealTarget {
+      id,
+      chosen: agent.choose_steal_target(&obs, choices).await,
+      rationale: agent.take_rationale(),
+    },
+    AgentReqEvent::ChooseMagicTarget { .. } => AgentRespEvent::MagicTarget {
+      id,
+      chosen: agent.choose_swap_target(&obs).await,
+      rationale: agent.take_rationale(),
+    },
+    AgentReqEvent::ChooseDestoryTarget { choices, .. } => AgentRespEvent::DestoryTarget {
+      id,
+      chosen: agent.choose_destory_target(&obs, &choices).await,
+      rationale: agent.take_rationale(),
+    },
+    AgentReqEvent::ChooseTomb { c, .. } => AgentRespEvent::Tomb {
+      id,
+      chosen: agent.choose_tomb(&obs, c).await,
+      rationale: agent.take_rationale(),
+    },
+    AgentReqEvent::ChooseOper { choices, .. } => AgentRespEvent::Oper {
+      id,
+      chosen: agent.choose_oper(&obs, &choices).await,
+      rationale: agent.take_rationale(),
+    },
+    AgentReqEvent::ChooseFrom2 { c0, c1, .. } => AgentRespEvent::From2 {
+      id,
+      chosen: agent.choose_from_2(&obs, c0, c1).await,
+      rationale: agent.take_rationale(),
+    },
+    AgentReqEvent::ChooseFrom3 { c0, c1, c2, .. } => AgentRespEvent::From3 {
+      id,
+      chosen: agent.choose_from_3(&obs, c0, c1, c2).await,
+      rationale: agent.take_rationale(),
+    },
+  }
+}
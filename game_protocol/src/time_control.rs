@@ -0,0 +1,129 @@
+// 远程 agent 的决策时限. 每次决策有上限 decision_ms, 另外可以给每个玩家一个 Fischer 式的时间池:
+// 开局有 bank_ms, 每次决策用掉的时间从池里扣, 决策后加 increment_ms. 一次决策可用的时间取两者中较小的.
+// 超时后由 fallback agent 代为决策, 并在历史记录中写一条 DecisionTimeout.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use valuable::Valuable;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeControl {
+  #[serde(default)]
+  pub decision_ms: u64, // 0 为不限
+  #[serde(default)]
+  pub bank_ms: u64, // 0 为不用时间池
+  #[serde(default)]
+  pub increment_ms: u64,
+}
+
+impl TimeControl {
+  pub fn new(decision_ms: u64, bank_ms: u64, increment_ms: u64) -> Self {
+    Self {
+      decision_ms,
+      bank_ms,
+      increment_ms,
+    }
+  }
+
+  pub fn is_unlimited(&self) -> bool {
+    self.decision_ms == 0 && self.bank_ms == 0
+  }
+}
+
+// 发给玩家的 obs 中的时钟, 都是这次决策开始时的值
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Valuable)]
+pub struct ClockInfo {
+  pub budget_ms: u64,       // 这次决策最多可用的时间
+  pub bank_ms: Option<u64>, // 时间池中剩余的时间
+  pub increment_ms: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecisionTimeout {
+  pub budget_ms: u64,
+  pub bank_ms: Option<u64>, // 超时后时间池中剩余的时间, 已加上 increment
+}
+
+// 一个玩家的时钟, 由远程 agent 持有
+#[derive(Debug, Clone)]
+pub struct Clock {
+  control: TimeControl,
+  bank_ms: Option<u64>,
+  started: Option<(Instant, u64)>,
+  timeout: Option<DecisionTimeout>,
+}
+
+impl Clock {
+  pub fn new(control: TimeControl) -> Self {
+    Self {
+      control,
+      bank_ms: (control.bank_ms > 0).then_some(control.bank_ms),
+      started: None,
+      timeout: None,
+    }
+  }
+
+  pub fn control(&self) -> TimeControl {
+    self.control
+  }
+
+  pub fn bank_ms(&self) -> Option<u64> {
+    self.bank_ms
+  }
+
+  // 下一次决策可用的时间, None 为不限
+  pub fn budget_ms(&self) -> Option<u64> {
+    let decision_ms = (self.control.decision_ms > 0).then_some(self.control.decision_ms);
+    match (decision_ms, self.bank_ms) {
+      (Some(decision_ms), Some(bank_ms)) => Some(decision_ms.min(bank_ms)),
+      (decision_ms, bank_ms) => decision_ms.or(bank_ms),
+    }
+  }
+
+  pub fn info(&self) -> Option<ClockInfo> {
+    self.budget_ms().map(|budget_ms| ClockInfo {
+      budget_ms,
+      bank_ms: self.bank_ms,
+      increment_ms: self.bank_ms.map(|_| self.control.increment_ms),
+    })
+  }
+
+  // 开始一次决策, 返回截止时间, None 为不限
+  pub fn start(&mut self) -> Option<Instant> {
+    let budget_ms = self.budget_ms()?;
+    let now = Instant::now();
+    self.started = Some((now, budget_ms));
+    Some(now + Duration::from_millis(budget_ms))
+  }
+
+  // 按时做出了决策
+  pub fn stop(&mut self) {
+    if let Some((started, budget_ms)) = self.started.take() {
+      let elapsed_ms = (started.elapsed().as_millis() as u64).min(budget_ms);
+      self.charge(elapsed_ms);
+    }
+  }
+
+  // 超时, 用掉全部可用时间
+  pub fn expire(&mut self) {
+    if let Some((_, budget_ms)) = self.started.take() {
+      self.charge(budget_ms);
+      self.timeout = Some(DecisionTimeout {
+        budget_ms,
+        bank_ms: self.bank_ms,
+      });
+    }
+  }
+
+  // 取走上一次决策的超时记录, 见 AbstractFAAgent::take_timeout
+  pub fn take_timeout(&mut self) -> Option<DecisionTimeout> {
+    self.timeout.take()
+  }
+
+  fn charge(&mut self, elapsed_ms: u64) {
+    if let Some(bank_ms) = self.bank_ms.as_mut() {
+      *bank_ms = bank_ms.saturating_sub(elapsed_ms) + self.control.increment_ms;
+    }
+  }
+}
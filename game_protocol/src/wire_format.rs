@@ -0,0 +1,77 @@
+// 连接上的编码, 默认 JSON. MessagePack 用 named 编码 (结构体为 map), 和 history_archive 一样.
+// 服务端内部的 channel 都是 JSON 文本, WsDispatcher 按连接在收发时转换; websocket 客户端在 URL 上加
+// ?format=msgpack 选 MessagePack, 之后服务端发 binary frame, 客户端也发 binary frame (text frame 仍按 JSON 收)
+
+use std::str::FromStr;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WireFormat {
+  #[default]
+  #[serde(rename = "json")]
+  Json,
+  #[serde(rename = "msgpack")]
+  MessagePack,
+}
+
+impl FromStr for WireFormat {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "json" => Ok(WireFormat::Json),
+      "msgpack" => Ok(WireFormat::MessagePack),
+      _ => Err(format!("unknown format {}, expected json or msgpack", s)),
+    }
+  }
+}
+
+impl WireFormat {
+  // websocket URL 的 query 中的 format=..., 没有时为 JSON
+  pub fn from_query(query: Option<&str>) -> Result<Self, String> {
+    query
+      .unwrap_or_default()
+      .split('&')
+      .find_map(|pair| pair.strip_prefix("format="))
+      .map_or(Ok(WireFormat::Json), str::parse)
+  }
+
+  pub fn query(self) -> &'static str {
+    match self {
+      WireFormat::Json => "",
+      WireFormat::MessagePack => "?format=msgpack",
+    }
+  }
+
+  pub fn encode<T: Serialize>(self, value: &T) -> anyhow::Result<Vec<u8>> {
+    Ok(match self {
+      WireFormat::Json => serde_json::to_vec(value)?,
+      WireFormat::MessagePack => rmp_serde::to_vec_named(value)?,
+    })
+  }
+
+  pub fn decode<T: DeserializeOwned>(self, frame: &[u8]) -> anyhow::Result<T> {
+    Ok(match self {
+      WireFormat::Json => serde_json::from_slice(frame)?,
+      WireFormat::MessagePack => rmp_serde::from_slice(frame)?,
+    })
+  }
+
+  // 内部的 JSON 文本转成这个编码
+  pub fn encode_json_text(self, json: &str) -> anyhow::Result<Vec<u8>> {
+    match self {
+      WireFormat::Json => Ok(json.as_bytes().to_vec()),
+      WireFormat::MessagePack => self.encode(&serde_json::from_str::<serde_json::Value>(json)?),
+    }
+  }
+
+  // 这个编码的一帧转成内部的 JSON 文本
+  pub fn decode_json_text(self, frame: &[u8]) -> anyhow::Result<String> {
+    match self {
+      WireFormat::Json => Ok(String::from_utf8(frame.to_vec())?),
+      WireFormat::MessagePack => Ok(serde_json::to_string(&self.decode::<serde_json::Value>(frame)?)?),
+    }
+  }
+}
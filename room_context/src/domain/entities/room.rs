@@ -1,7 +1,7 @@
 use sqlx::Row;
 use user_context::domain::valueobjects::UserId;
 
-use crate::domain::valueobjects::{MaxPlayers, RoomId, RoomName, RoomNumber};
+use crate::domain::valueobjects::{MaxPlayers, RoomId, RoomName, RoomNumber, TimeControl};
 
 /// Parameters for creating a Room with stand_by_limit
 #[derive(Debug)]
@@ -24,6 +24,10 @@ pub struct Room {
   creator: UserId,
   max_players: MaxPlayers,
   stand_by_limit: Option<usize>,
+  /// Whether seated players may ask the hint service for advice (ranked games turn it off)
+  hints_enabled: bool,
+  /// Time limits for remote players' decisions, unlimited by default
+  time_control: TimeControl,
   created_at: chrono::DateTime<chrono::Utc>,
   expires_at: chrono::DateTime<chrono::Utc>,
 }
@@ -41,6 +45,12 @@ impl<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow> for Room {
         .ok()
         .flatten()
         .map(|v| v as usize),
+      hints_enabled: row.try_get("hints_enabled")?,
+      time_control: TimeControl::new(
+        row.try_get::<i64, _>("decision_ms")? as u64,
+        row.try_get::<i64, _>("bank_ms")? as u64,
+        row.try_get::<i64, _>("increment_ms")? as u64,
+      ),
       created_at: row.try_get("created_at")?,
       expires_at: row.try_get("expires_at")?,
     })
@@ -59,6 +69,8 @@ impl Room {
       creator,
       max_players,
       stand_by_limit: None,
+      hints_enabled: true,
+      time_control: TimeControl::default(),
       created_at,
       expires_at,
     }
@@ -72,6 +84,8 @@ impl Room {
       creator: params.creator,
       max_players: params.max_players,
       stand_by_limit: params.stand_by_limit,
+      hints_enabled: true,
+      time_control: TimeControl::default(),
       created_at: params.created_at,
       expires_at: params.expires_at,
     }
@@ -101,6 +115,14 @@ impl Room {
     self.stand_by_limit
   }
 
+  pub fn hints_enabled(&self) -> bool {
+    self.hints_enabled
+  }
+
+  pub fn time_control(&self) -> TimeControl {
+    self.time_control
+  }
+
   pub fn created_at(&self) -> chrono::DateTime<chrono::Utc> {
     self.created_at
   }
@@ -62,6 +62,20 @@ impl RoomToUserMessage {
         "to": to.value() as u32
       })
       .to_string(),
+      RoomToUserMessageDetails::HintsEnabledUpdated { room_id, hints_enabled } => serde_json::json!({
+        "verb": verb,
+        "room_id": room_id.to_string(),
+        "hints_enabled": hints_enabled
+      })
+      .to_string(),
+      RoomToUserMessageDetails::TimeControlUpdated { room_id, time_control } => serde_json::json!({
+        "verb": verb,
+        "room_id": room_id.to_string(),
+        "decision_ms": time_control.decision_ms,
+        "bank_ms": time_control.bank_ms,
+        "increment_ms": time_control.increment_ms
+      })
+      .to_string(),
       RoomToUserMessageDetails::ForceStandUp {
         room_id,
         user_id,
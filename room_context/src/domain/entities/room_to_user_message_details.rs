@@ -1,6 +1,6 @@
 use user_context::domain::valueobjects::UserId;
 
-use crate::domain::valueobjects::{MaxPlayers, MessageTopic, RoomId, RoomState};
+use crate::domain::valueobjects::{MaxPlayers, MessageTopic, RoomId, RoomState, TimeControl};
 
 /// RoomToUserMessageDetails - enum representing different types of room to user messages
 #[derive(Debug, Clone)]
@@ -21,6 +21,10 @@ pub enum RoomToUserMessageDetails {
     from: MaxPlayers,
     to: MaxPlayers,
   },
+  /// Hints switched on or off
+  HintsEnabledUpdated { room_id: RoomId, hints_enabled: bool },
+  /// Time control changed
+  TimeControlUpdated { room_id: RoomId, time_control: TimeControl },
   /// Force stand up notification
   ForceStandUp {
     room_id: RoomId,
@@ -35,6 +39,8 @@ impl RoomToUserMessageDetails {
       RoomToUserMessageDetails::RoomStateUpdate { .. } => "room_state_update",
       RoomToUserMessageDetails::RoomDeleted { .. } => "delete_room",
       RoomToUserMessageDetails::MaxPlayersUpdated { .. } => "update_max_players",
+      RoomToUserMessageDetails::HintsEnabledUpdated { .. } => "update_hints_enabled",
+      RoomToUserMessageDetails::TimeControlUpdated { .. } => "update_time_control",
       RoomToUserMessageDetails::ForceStandUp { .. } => "force_stand_up",
     };
     MessageTopic::from(topic_str)
@@ -6,7 +6,7 @@ use crate::domain::managers::MessageManager;
 use common_context::domain::valueobjects::Pagination;
 
 use crate::domain::repositories::{RawMessageRepository, RoomRepository};
-use crate::domain::valueobjects::{MaxPlayers, RoomId, RoomName, Seat, SeatIndex};
+use crate::domain::valueobjects::{MaxPlayers, RoomId, RoomName, Seat, SeatIndex, TimeControl};
 use crate::errors::RoomError;
 
 /// Outcome of update_room_max_players operation
@@ -255,6 +255,54 @@ impl RoomManager {
     Ok(UpdateMaxPlayersOutcome::Changed)
   }
 
+  /// Switch hints on or off, notifying everyone in the room when the value changes
+  pub async fn update_room_hints_enabled(&self, id: RoomId, hints_enabled: bool) -> Result<(), RoomError> {
+    let room = self.room_repository.find_by_id(id).await?;
+    let room = room.ok_or(RoomError::NotFound)?;
+
+    if room.hints_enabled() == hints_enabled {
+      return Ok(());
+    }
+
+    let updated = self.room_repository.update_hints_enabled(id, hints_enabled).await?;
+    if !updated {
+      return Err(RoomError::NotFound);
+    }
+
+    let participants = self.get_room_participants(id).await?;
+    let details = RoomToUserMessageDetails::HintsEnabledUpdated {
+      room_id: id,
+      hints_enabled,
+    };
+    self.send_messages_to_participants(id, &participants, details).await;
+
+    Ok(())
+  }
+
+  /// Change the time control, notifying everyone in the room when the value changes
+  pub async fn update_room_time_control(&self, id: RoomId, time_control: TimeControl) -> Result<(), RoomError> {
+    let room = self.room_repository.find_by_id(id).await?;
+    let room = room.ok_or(RoomError::NotFound)?;
+
+    if room.time_control() == time_control {
+      return Ok(());
+    }
+
+    let updated = self.room_repository.update_time_control(id, time_control).await?;
+    if !updated {
+      return Err(RoomError::NotFound);
+    }
+
+    let participants = self.get_room_participants(id).await?;
+    let details = RoomToUserMessageDetails::TimeControlUpdated {
+      room_id: id,
+      time_control,
+    };
+    self.send_messages_to_participants(id, &participants, details).await;
+
+    Ok(())
+  }
+
   /// Delete room by ID
   pub async fn delete_room(&self, id: RoomId) -> Result<(), RoomError> {
     // Get participants before deletion to send messages
@@ -3,7 +3,7 @@ use common_context::domain::valueobjects::Pagination;
 use user_context::domain::valueobjects::UserId;
 
 use crate::domain::entities::{Room, RoomParticipant};
-use crate::domain::valueobjects::{MaxPlayers, RoomId, RoomName, RoomNumber, Seat};
+use crate::domain::valueobjects::{MaxPlayers, RoomId, RoomName, RoomNumber, Seat, TimeControl};
 use crate::errors::RoomError;
 
 /// RoomRepository trait - interface for room data access
@@ -16,6 +16,8 @@ pub trait RoomRepository: Send + Sync {
   async fn create(&self, creator: UserId, name: &RoomName, max_players: MaxPlayers) -> Result<Room, RoomError>;
   async fn update_name(&self, id: RoomId, new_name: &RoomName) -> Result<bool, RoomError>;
   async fn update_max_players(&self, id: RoomId, max_players: MaxPlayers) -> Result<bool, RoomError>;
+  async fn update_hints_enabled(&self, id: RoomId, hints_enabled: bool) -> Result<bool, RoomError>;
+  async fn update_time_control(&self, id: RoomId, time_control: TimeControl) -> Result<bool, RoomError>;
   async fn delete(&self, id: RoomId) -> Result<bool, RoomError>;
   async fn get_next_room_number(&self) -> Result<RoomNumber, RoomError>;
 
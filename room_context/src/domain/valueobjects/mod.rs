@@ -9,6 +9,7 @@ mod room_state;
 mod room_to_user_message_id;
 mod seat;
 mod seat_index;
+mod time_control;
 
 pub use max_players::MaxPlayers;
 pub use message_content::MessageContent;
@@ -21,3 +22,4 @@ pub use room_state::RoomState;
 pub use room_to_user_message_id::RoomToUserMessageId;
 pub use seat::Seat;
 pub use seat_index::SeatIndex;
+pub use time_control::TimeControl;
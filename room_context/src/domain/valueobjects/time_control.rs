@@ -0,0 +1,4 @@
+/// TimeControl - value object for per-decision time limits of remote players, all in milliseconds
+///
+/// The same type the game server's remote agents are clocked with, so a room's setting is passed to the game as is.
+pub use game_protocol::TimeControl;
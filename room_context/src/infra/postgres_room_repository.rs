@@ -5,7 +5,7 @@ use common_context::domain::valueobjects::Pagination;
 
 use crate::domain::entities::{Room, RoomParticipant};
 use crate::domain::repositories::RoomRepository;
-use crate::domain::valueobjects::{MaxPlayers, RoomId, RoomName, RoomNumber, Seat};
+use crate::domain::valueobjects::{MaxPlayers, RoomId, RoomName, RoomNumber, Seat, TimeControl};
 use crate::errors::RoomError;
 
 /// PostgreSQL implementation of RoomRepository
@@ -29,7 +29,7 @@ impl PostgresRoomRepository {
 impl RoomRepository for PostgresRoomRepository {
   async fn find_by_id(&self, id: RoomId) -> Result<Option<Room>, RoomError> {
     let room = sqlx::query_as::<_, Room>(
-      "SELECT id, room_number, room_name, creator, max_players, created_at, expires_at FROM room WHERE id = $1",
+      "SELECT id, room_number, room_name, creator, max_players, hints_enabled, decision_ms, bank_ms, increment_ms, created_at, expires_at FROM room WHERE id = $1",
     )
     .bind(id)
     .fetch_optional(&self.pool)
@@ -40,7 +40,7 @@ impl RoomRepository for PostgresRoomRepository {
 
   async fn find_by_name(&self, name: &RoomName) -> Result<Vec<Room>, RoomError> {
     let rooms = sqlx::query_as::<_, Room>(
-      "SELECT id, room_number, room_name, creator, max_players, created_at, expires_at FROM room WHERE room_name = $1",
+      "SELECT id, room_number, room_name, creator, max_players, hints_enabled, decision_ms, bank_ms, increment_ms, created_at, expires_at FROM room WHERE room_name = $1",
     )
     .bind(name.as_str())
     .fetch_all(&self.pool)
@@ -51,7 +51,7 @@ impl RoomRepository for PostgresRoomRepository {
 
   async fn find_all(&self, pagination: Pagination) -> Result<Vec<Room>, RoomError> {
     let rooms = sqlx::query_as::<_, Room>(
-      "SELECT id, room_number, room_name, creator, max_players, created_at, expires_at FROM room ORDER BY created_at DESC LIMIT $1 OFFSET $2",
+      "SELECT id, room_number, room_name, creator, max_players, hints_enabled, decision_ms, bank_ms, increment_ms, created_at, expires_at FROM room ORDER BY created_at DESC LIMIT $1 OFFSET $2",
     )
     .bind(pagination.limit as i64)
     .bind(pagination.offset as i64)
@@ -63,7 +63,7 @@ impl RoomRepository for PostgresRoomRepository {
 
   async fn find_active(&self, pagination: Pagination) -> Result<Vec<Room>, RoomError> {
     let rooms = sqlx::query_as::<_, Room>(
-      "SELECT id, room_number, room_name, creator, max_players, created_at, expires_at FROM room WHERE expires_at > NOW() ORDER BY created_at DESC LIMIT $1 OFFSET $2",
+      "SELECT id, room_number, room_name, creator, max_players, hints_enabled, decision_ms, bank_ms, increment_ms, created_at, expires_at FROM room WHERE expires_at > NOW() ORDER BY created_at DESC LIMIT $1 OFFSET $2",
     )
     .bind(pagination.limit as i64)
     .bind(pagination.offset as i64)
@@ -83,13 +83,17 @@ impl RoomRepository for PostgresRoomRepository {
     let room = Room::new(id, number, name.clone(), creator, max_players, created_at, expires_at);
 
     sqlx::query(
-      "INSERT INTO room (id, room_number, room_name, creator, max_players, created_at, expires_at) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+      "INSERT INTO room (id, room_number, room_name, creator, max_players, hints_enabled, decision_ms, bank_ms, increment_ms, created_at, expires_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)",
     )
     .bind(room.id())
     .bind(room.number())
     .bind(room.name().as_str())
     .bind(room.creator())
     .bind(room.max_players())
+    .bind(room.hints_enabled())
+    .bind(room.time_control().decision_ms as i64)
+    .bind(room.time_control().bank_ms as i64)
+    .bind(room.time_control().increment_ms as i64)
     .bind(room.created_at())
     .bind(room.expires_at())
     .execute(&self.pool)
@@ -121,6 +125,31 @@ impl RoomRepository for PostgresRoomRepository {
     Ok(rows_affected > 0)
   }
 
+  async fn update_hints_enabled(&self, id: RoomId, hints_enabled: bool) -> Result<bool, RoomError> {
+    let rows_affected = sqlx::query("UPDATE room SET hints_enabled = $1 WHERE id = $2")
+      .bind(hints_enabled)
+      .bind(id)
+      .execute(&self.pool)
+      .await?
+      .rows_affected();
+
+    Ok(rows_affected > 0)
+  }
+
+  async fn update_time_control(&self, id: RoomId, time_control: TimeControl) -> Result<bool, RoomError> {
+    let rows_affected =
+      sqlx::query("UPDATE room SET decision_ms = $1, bank_ms = $2, increment_ms = $3 WHERE id = $4")
+        .bind(time_control.decision_ms as i64)
+        .bind(time_control.bank_ms as i64)
+        .bind(time_control.increment_ms as i64)
+        .bind(id)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+    Ok(rows_affected > 0)
+  }
+
   async fn delete(&self, id: RoomId) -> Result<bool, RoomError> {
     // Note: emitting delete_room event to all users is handled by room_service
     let rows_affected = sqlx::query("DELETE FROM room WHERE id = $1")
@@ -28,6 +28,22 @@ pub async fn create_room_table(db: &DbConfig) -> Result<(), RoomError> {
   .map_err(RoomError::Database)?;
 
   if table_exists {
+    // Tables created before the hint switch existed lack the column
+    sqlx::query("ALTER TABLE room ADD COLUMN IF NOT EXISTS hints_enabled BOOLEAN NOT NULL DEFAULT TRUE")
+      .execute(&pool)
+      .await
+      .map_err(RoomError::Database)?;
+
+    // Same for the time control columns
+    for column in ["decision_ms", "bank_ms", "increment_ms"] {
+      sqlx::query(&format!(
+        "ALTER TABLE room ADD COLUMN IF NOT EXISTS {column} BIGINT NOT NULL DEFAULT 0"
+      ))
+      .execute(&pool)
+      .await
+      .map_err(RoomError::Database)?;
+    }
+
     println!("Table room already exists.");
     println!();
     return Ok(());
@@ -41,6 +57,10 @@ pub async fn create_room_table(db: &DbConfig) -> Result<(), RoomError> {
         room_name VARCHAR(255) UNIQUE NOT NULL,
         creator UUID NOT NULL,
         max_players INTEGER NOT NULL CHECK (max_players IN (4, 6)),
+        hints_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+        decision_ms BIGINT NOT NULL DEFAULT 0,
+        bank_ms BIGINT NOT NULL DEFAULT 0,
+        increment_ms BIGINT NOT NULL DEFAULT 0,
         created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
         expires_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW() + INTERVAL '1 hour'
     )
@@ -70,7 +90,9 @@ pub async fn create_room_table(db: &DbConfig) -> Result<(), RoomError> {
   println!("Room table created successfully!");
   println!("{}", "=".repeat(40));
   println!("Table: room");
-  println!("Columns: id, room_number, room_name, creator, max_players, created_at, expires_at");
+  println!(
+    "Columns: id, room_number, room_name, creator, max_players, hints_enabled, decision_ms, bank_ms, increment_ms, created_at, expires_at"
+  );
   println!();
 
   Ok(())
@@ -7,7 +7,7 @@ use common_context::domain::valueobjects::Pagination;
 use crate::domain::entities::{Room, RoomParticipant};
 use crate::domain::managers::RoomManager;
 use crate::domain::repositories::{RawMessageRepository, RoomRepository};
-use crate::domain::valueobjects::{MaxPlayers, RoomId, RoomName, Seat, SeatIndex};
+use crate::domain::valueobjects::{MaxPlayers, RoomId, RoomName, Seat, SeatIndex, TimeControl};
 use crate::errors::RoomError;
 
 /// Outcome of entering a room
@@ -40,6 +40,8 @@ pub struct RoomDetails {
   pub creator_name: String,
   pub max_players: usize,
   pub seated_players: usize,
+  pub hints_enabled: bool,
+  pub time_control: TimeControl,
   pub created_at: chrono::DateTime<chrono::Utc>,
   pub expires_at: chrono::DateTime<chrono::Utc>,
 }
@@ -151,6 +153,8 @@ impl RoomService {
           creator_name: room_info.creator_name,
           max_players: room_info.room.max_players().value(),
           seated_players: room_info.seated_players,
+          hints_enabled: room_info.room.hints_enabled(),
+          time_control: room_info.room.time_control(),
           created_at: room_info.room.created_at(),
           expires_at: room_info.room.expires_at(),
         })
@@ -192,6 +196,39 @@ impl RoomService {
     result.map(|_| ())
   }
 
+  /// Switch hints on or off for a room
+  #[instrument(skip(self), fields(id = id_str, hints_enabled = hints_enabled))]
+  pub async fn update_room_hints_enabled(&self, id_str: &str, hints_enabled: bool) -> Result<(), RoomError> {
+    // Parse string to RoomId
+    let room_id = id_str
+      .parse::<RoomId>()
+      .map_err(|e| RoomError::InvalidOperation(format!("Invalid room ID format: {} ({})", id_str, e)))?;
+    let result = self
+      .room_manager
+      .update_room_hints_enabled(room_id, hints_enabled)
+      .await;
+    match &result {
+      Ok(_) => info!("Successfully updated hints for ID: {}", id_str),
+      Err(e) => error!("Failed to update hints for ID {}: {:?}", id_str, e),
+    }
+    result
+  }
+
+  /// Change the time control of a room
+  #[instrument(skip(self), fields(id = id_str, time_control = ?time_control))]
+  pub async fn update_room_time_control(&self, id_str: &str, time_control: TimeControl) -> Result<(), RoomError> {
+    // Parse string to RoomId
+    let room_id = id_str
+      .parse::<RoomId>()
+      .map_err(|e| RoomError::InvalidOperation(format!("Invalid room ID format: {} ({})", id_str, e)))?;
+    let result = self.room_manager.update_room_time_control(room_id, time_control).await;
+    match &result {
+      Ok(_) => info!("Successfully updated time control for ID: {}", id_str),
+      Err(e) => error!("Failed to update time control for ID {}: {:?}", id_str, e),
+    }
+    result
+  }
+
   /// Delete room by ID
   #[instrument(skip(self), fields(id = id_str))]
   pub async fn delete_room(&self, id_str: &str) -> Result<(), RoomError> {
@@ -0,0 +1,92 @@
+// 命令行和配置文件里 agent 的写法, 所有工具共用一套:
+// random, v2, heuristic[:<weights.toml>], policy:<weights.json>[@temperature], exec:<command>.
+// exec 启动一个在 stdio 上收发 JSON lines 的子进程, 它的 fallback 是能坐任一阵营的 random.
+
+use std::sync::Arc;
+
+use anyhow::bail;
+
+use crate::abstract_fa_agent::AbstractFAAgent;
+use crate::domain::Camp;
+use crate::fa_agents::{
+  HeuristicFAAgent, HeuristicWeights, PolicyFAAgent, PolicyModel, RandomFAAgent, RemoteFAAgent, V2FAAgent,
+};
+
+#[derive(Clone)]
+pub enum AgentSpec {
+  Random,
+  V2,
+  Heuristic(Arc<HeuristicWeights>),
+  Policy(Arc<PolicyModel>, f32),
+  Exec(String),
+}
+
+impl AgentSpec {
+  // 权重文件在这里就加载, 出错时还没有开局
+  pub fn parse(spec: &str) -> anyhow::Result<Self> {
+    match spec {
+      "random" => Ok(AgentSpec::Random),
+      "v2" => Ok(AgentSpec::V2),
+      "heuristic" => Ok(AgentSpec::Heuristic(Arc::new(HeuristicWeights::default()))),
+      _ => {
+        if let Some(path) = spec.strip_prefix("heuristic:") {
+          return Ok(AgentSpec::Heuristic(Arc::new(HeuristicWeights::load(path)?)));
+        }
+        if let Some(spec) = spec.strip_prefix("policy:") {
+          let (path, temperature) = match spec.rsplit_once('@') {
+            Some((path, temperature)) => (path, temperature.parse()?),
+            None => (spec, 0.0),
+          };
+          return Ok(AgentSpec::Policy(Arc::new(PolicyModel::load(path)?), temperature));
+        }
+        if let Some(command) = spec.strip_prefix("exec:") {
+          return Ok(AgentSpec::Exec(command.to_string()));
+        }
+        bail!("unknown agent: {}", spec)
+      },
+    }
+  }
+
+  // v2 只会打汉
+  pub fn supports(&self, camp: Camp) -> bool {
+    !matches!(self, AgentSpec::V2) || camp == Camp::汉
+  }
+
+  // 在创建任何 agent (包括启动外部进程) 之前检查整个 lineup
+  pub fn check_seat(&self, seat: usize, camp: Camp) -> anyhow::Result<()> {
+    if !self.supports(camp) {
+      bail!("seat {}: v2 only supports 汉 seats", seat);
+    }
+    Ok(())
+  }
+
+  // 同样的局面总是做同样的决策
+  pub fn is_deterministic(&self) -> bool {
+    match self {
+      AgentSpec::Random | AgentSpec::V2 | AgentSpec::Exec(_) => false,
+      AgentSpec::Heuristic(_) => true,
+      AgentSpec::Policy(_, temperature) => *temperature <= 0.0,
+    }
+  }
+
+  // 进程内的 agent, exec 返回错误
+  pub fn local_agent(&self, seed: u64) -> anyhow::Result<Box<dyn AbstractFAAgent>> {
+    Ok(match self {
+      AgentSpec::Random => Box::new(RandomFAAgent::with_seed(seed)),
+      AgentSpec::V2 => Box::new(V2FAAgent::with_seed(seed)),
+      AgentSpec::Heuristic(weights) => Box::new(HeuristicFAAgent::new(weights.as_ref().clone())),
+      AgentSpec::Policy(model, temperature) => Box::new(PolicyFAAgent::with_seed(model.clone(), *temperature, seed)),
+      AgentSpec::Exec(command) => bail!("exec:{} is not an in-process agent", command),
+    })
+  }
+
+  // seed 用于 agent 自己的随机数; exec 的外部进程不受控制, 只有它的 fallback 用 seed
+  pub async fn agent(&self, seed: u64) -> anyhow::Result<Box<dyn AbstractFAAgent>> {
+    match self {
+      AgentSpec::Exec(command) => Ok(Box::new(
+        RemoteFAAgent::spawn(command, Box::new(RandomFAAgent::with_seed(seed))).await?,
+      )),
+      _ => self.local_agent(seed),
+    }
+  }
+}
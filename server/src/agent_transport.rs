@@ -0,0 +1,69 @@
+// 远程 agent 的传输层. 传输只负责把 AgentReqEvent 发出去, 再把收到的 AgentRespEvent 交回来,
+// 按 id 对应请求和回复, 超时和 fallback 都由 RemoteFAAgent 处理. 新的传输只要实现 send 和 recv
+
+use std::time::Instant;
+
+use async_trait::async_trait;
+use tracing::error;
+
+use crate::agent_protocol::TransportError;
+use crate::domain::{AgentReqEvent, AgentRespEvent};
+use crate::wire_format::WireFormat;
+
+#[async_trait]
+pub trait AgentTransport: Send + Sync {
+  fn name(&self) -> &str;
+
+  async fn send(&mut self, event: &AgentReqEvent) -> Result<(), TransportError>;
+
+  // 需要可以被取消: 超时后 recv 的 future 会被 drop
+  async fn recv(&mut self) -> Result<AgentRespEvent, TransportError>;
+
+  // 断开后重新连上, 返回 false 时不再重试, 之后的决策都由 fallback 做出
+  async fn reconnect(&mut self) -> bool {
+    false
+  }
+
+  // 发出请求, 等到 id 相同的回复为止. 超过 deadline 返回 Timeout, None 为不限
+  async fn request(
+    &mut self, event: &AgentReqEvent, deadline: Option<Instant>,
+  ) -> Result<AgentRespEvent, TransportError> {
+    self.send(event).await?;
+    let id = event.id();
+    let name = self.name().to_string();
+    let wait = async {
+      loop {
+        match self.recv().await {
+          Ok(resp) if resp.id() == id => return Ok(resp),
+          Ok(resp) => error!("{} wrong id: expected {}, got {}", name, id, resp.id()),
+          Err(TransportError::BadFrame { frame, reason }) => error!("{} bad resp {:?}: {}", name, frame, reason),
+          Err(e) => return Err(e),
+        }
+      }
+    };
+    match deadline {
+      Some(deadline) => tokio::time::timeout_at(tokio::time::Instant::from_std(deadline), wait)
+        .await
+        .unwrap_or(Err(TransportError::Timeout)),
+      None => wait.await,
+    }
+  }
+}
+
+pub(crate) fn encode_json(event: &AgentReqEvent) -> String {
+  serde_json::to_string(event).unwrap()
+}
+
+pub(crate) fn decode_json(frame: &str) -> Result<AgentRespEvent, TransportError> {
+  serde_json::from_str(frame).map_err(|e| TransportError::BadFrame {
+    frame: frame.to_string(),
+    reason: e.to_string(),
+  })
+}
+
+pub(crate) fn decode_frame(format: WireFormat, frame: &[u8]) -> Result<AgentRespEvent, TransportError> {
+  format.decode(frame).map_err(|e| TransportError::BadFrame {
+    frame: String::from_utf8_lossy(frame).to_string(),
+    reason: e.to_string(),
+  })
+}
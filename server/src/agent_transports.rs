@@ -0,0 +1,13 @@
+mod channel_transport;
+mod lines_transport;
+mod redis_transport;
+mod subprocess_transport;
+mod ws_transport;
+
+pub use channel_transport::ChannelTransport;
+#[cfg(unix)]
+pub use lines_transport::UnixTransport;
+pub use lines_transport::{LinesTransport, TcpTransport};
+pub use redis_transport::RedisTransport;
+pub use subprocess_transport::SubprocessTransport;
+pub use ws_transport::WsTransport;
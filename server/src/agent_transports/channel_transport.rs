@@ -0,0 +1,65 @@
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+
+use crate::abstract_fa_agent::AbstractFAAgent;
+use crate::agent_protocol::TransportError;
+use crate::agent_transport::AgentTransport;
+use crate::domain::{AgentReqEvent, AgentRespEvent};
+use crate::obs::ObsMirror;
+use crate::respond;
+
+// 同一进程内的 channel, 不经过序列化, 主要给测试用
+pub struct ChannelTransport {
+  req_sender: mpsc::Sender<AgentReqEvent>,
+  resp_receiver: mpsc::Receiver<AgentRespEvent>,
+}
+
+impl ChannelTransport {
+  pub fn new(req_sender: mpsc::Sender<AgentReqEvent>, resp_receiver: mpsc::Receiver<AgentRespEvent>) -> Self {
+    Self {
+      req_sender,
+      resp_receiver,
+    }
+  }
+
+  // 在后台 task 中用 agent 回复每个请求
+  pub fn spawn(mut agent: Box<dyn AbstractFAAgent>) -> Self {
+    let (req_sender, mut req_receiver) = mpsc::channel::<AgentReqEvent>(16);
+    let (resp_sender, resp_receiver) = mpsc::channel::<AgentRespEvent>(16);
+    tokio::spawn(async move {
+      let mut mirror = ObsMirror::new();
+      while let Some(event) = req_receiver.recv().await {
+        let Some(resp) = respond(agent.as_mut(), &mut mirror, event).await else {
+          break;
+        };
+        if resp_sender.send(resp).await.is_err() {
+          break;
+        }
+      }
+    });
+    Self::new(req_sender, resp_receiver)
+  }
+}
+
+#[async_trait]
+impl AgentTransport for ChannelTransport {
+  fn name(&self) -> &str {
+    "ChannelAgent"
+  }
+
+  async fn send(&mut self, event: &AgentReqEvent) -> Result<(), TransportError> {
+    self
+      .req_sender
+      .send(event.clone())
+      .await
+      .map_err(|_| TransportError::Disconnected("req channel closed".to_string()))
+  }
+
+  async fn recv(&mut self) -> Result<AgentRespEvent, TransportError> {
+    self
+      .resp_receiver
+      .recv()
+      .await
+      .ok_or_else(|| TransportError::Disconnected("resp channel closed".to_string()))
+  }
+}
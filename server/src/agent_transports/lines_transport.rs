@@ -0,0 +1,94 @@
+use async_trait::async_trait;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, Lines};
+use tokio::net::{TcpStream, ToSocketAddrs, tcp};
+
+use crate::agent_protocol::TransportError;
+use crate::agent_transport::{AgentTransport, decode_json, encode_json};
+use crate::domain::{AgentReqEvent, AgentRespEvent};
+
+// 一行一个 JSON 的字节流, 读写两端分开, 可以是 TCP, unix socket 或者子进程的 stdin/stdout
+pub struct LinesTransport<R, W> {
+  name: &'static str,
+  lines: Lines<BufReader<R>>,
+  writer: W,
+}
+
+impl<R, W> LinesTransport<R, W>
+where
+  R: AsyncRead + Unpin + Send + Sync,
+  W: AsyncWrite + Unpin + Send + Sync,
+{
+  pub fn new(name: &'static str, reader: R, writer: W) -> Self {
+    Self {
+      name,
+      lines: BufReader::new(reader).lines(),
+      writer,
+    }
+  }
+}
+
+pub type TcpTransport = LinesTransport<tcp::OwnedReadHalf, tcp::OwnedWriteHalf>;
+
+impl TcpTransport {
+  pub fn from_stream(stream: TcpStream) -> Self {
+    let (reader, writer) = stream.into_split();
+    Self::new("TcpAgent", reader, writer)
+  }
+
+  pub async fn connect(addr: impl ToSocketAddrs) -> std::io::Result<Self> {
+    Ok(Self::from_stream(TcpStream::connect(addr).await?))
+  }
+}
+
+#[cfg(unix)]
+pub type UnixTransport = LinesTransport<tokio::net::unix::OwnedReadHalf, tokio::net::unix::OwnedWriteHalf>;
+
+#[cfg(unix)]
+impl UnixTransport {
+  pub fn from_stream(stream: tokio::net::UnixStream) -> Self {
+    let (reader, writer) = stream.into_split();
+    Self::new("UnixAgent", reader, writer)
+  }
+
+  pub async fn connect(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+    Ok(Self::from_stream(tokio::net::UnixStream::connect(path).await?))
+  }
+}
+
+#[async_trait]
+impl<R, W> AgentTransport for LinesTransport<R, W>
+where
+  R: AsyncRead + Unpin + Send + Sync,
+  W: AsyncWrite + Unpin + Send + Sync,
+{
+  fn name(&self) -> &str {
+    self.name
+  }
+
+  async fn send(&mut self, event: &AgentReqEvent) -> Result<(), TransportError> {
+    let mut line = encode_json(event);
+    line.push('\n');
+    self
+      .writer
+      .write_all(line.as_bytes())
+      .await
+      .map_err(|e| TransportError::Disconnected(e.to_string()))?;
+    self
+      .writer
+      .flush()
+      .await
+      .map_err(|e| TransportError::Disconnected(e.to_string()))
+  }
+
+  // Lines::next_line 可以被取消, 不会丢掉读了一半的行
+  async fn recv(&mut self) -> Result<AgentRespEvent, TransportError> {
+    loop {
+      match self.lines.next_line().await {
+        Ok(Some(line)) if line.trim().is_empty() => continue,
+        Ok(Some(line)) => return decode_json(&line),
+        Ok(None) => return Err(TransportError::Disconnected("eof".to_string())),
+        Err(e) => return Err(TransportError::Disconnected(e.to_string())),
+      }
+    }
+  }
+}
@@ -0,0 +1,76 @@
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::agent_protocol::TransportError;
+use crate::agent_transport::{AgentTransport, decode_frame};
+use crate::domain::{AgentReqEvent, AgentRespEvent};
+use crate::wire_format::WireFormat;
+
+// 请求 lpush 到 req key, agent 把回复 lpush 到 resp key
+pub struct RedisTransport {
+  redis_conn: redis::aio::MultiplexedConnection,
+  req_redis_key: String,
+  resp_redis_key: String,
+  format: WireFormat,
+}
+
+impl RedisTransport {
+  pub fn new(agent_uuid: Uuid, redis_conn: redis::aio::MultiplexedConnection) -> Self {
+    let (req_redis_key, resp_redis_key) = Self::keys(agent_uuid);
+    Self {
+      redis_conn,
+      req_redis_key,
+      resp_redis_key,
+      format: WireFormat::Json,
+    }
+  }
+
+  // (请求, 回复) 的 key, agent 一侧用同样的 key
+  pub fn keys(agent_uuid: Uuid) -> (String, String) {
+    crate::agent_protocol::redis_keys(agent_uuid)
+  }
+
+  // 两个方向用同一种编码, agent 一侧要一致
+  pub fn with_format(mut self, format: WireFormat) -> Self {
+    self.format = format;
+    self
+  }
+}
+
+#[async_trait]
+impl AgentTransport for RedisTransport {
+  fn name(&self) -> &str {
+    "RedisProxyFAAgent"
+  }
+
+  async fn send(&mut self, event: &AgentReqEvent) -> Result<(), TransportError> {
+    self
+      .redis_conn
+      .lpush::<String, Vec<u8>, usize>(self.req_redis_key.clone(), self.format.encode(event).unwrap())
+      .await
+      .map(|_| ())
+      .map_err(|e| TransportError::Disconnected(e.to_string()))
+  }
+
+  async fn recv(&mut self) -> Result<AgentRespEvent, TransportError> {
+    const TIMEOUT: f64 = 1.0; // in seconds
+
+    loop {
+      match self
+        .redis_conn
+        .brpop::<String, Option<(String, Vec<u8>)>>(self.resp_redis_key.clone(), TIMEOUT)
+        .await
+      {
+        Ok(Some((_, frame))) => return decode_frame(self.format, &frame),
+        Ok(None) => info!("RedisProxyFAAgent block on resp timeout"),
+        Err(e) if e.is_connection_dropped() => return Err(TransportError::Disconnected(e.to_string())),
+        Err(e) => {
+          error!("RedisProxyFAAgent brpop error: {:?}", e);
+          tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        },
+      }
+    }
+  }
+}
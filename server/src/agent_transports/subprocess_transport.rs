@@ -0,0 +1,127 @@
+// 子进程 agent: 启动一个命令, 经 stdin/stdout 一行一个 JSON 对象交换 AgentReqEvent/AgentRespEvent,
+// stderr 的每一行都写进日志. 启动后先握手 (WaitForReady), 子进程退出后重启, 最多 max_restarts 次
+
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail};
+use async_trait::async_trait;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tracing::{error, info, warn};
+
+use super::LinesTransport;
+use crate::agent_protocol::TransportError;
+use crate::agent_transport::AgentTransport;
+use crate::domain::{AgentReqEvent, AgentRespEvent};
+
+// 握手用的 id, 不会和 RemoteFAAgent 的请求撞上
+const HANDSHAKE_ID: u32 = u32::MAX;
+
+pub struct SubprocessTransport {
+  program: String,
+  args: Vec<String>,
+  startup_timeout: Duration,
+  max_restarts: u32,
+  restarts: u32,
+  child: Child,
+  lines: LinesTransport<ChildStdout, ChildStdin>,
+}
+
+impl SubprocessTransport {
+  // command 按空白切分, 第一段是可执行文件, 不支持引号
+  pub async fn spawn(command: &str, startup_timeout: Duration) -> anyhow::Result<Self> {
+    let mut parts = command.split_whitespace().map(str::to_string);
+    let program = parts.next().ok_or_else(|| anyhow!("empty agent command"))?;
+    let args = parts.collect::<Vec<_>>();
+    let (child, lines) = launch(&program, &args, startup_timeout).await?;
+    Ok(Self {
+      program,
+      args,
+      startup_timeout,
+      max_restarts: 3,
+      restarts: 0,
+      child,
+      lines,
+    })
+  }
+
+  pub fn with_max_restarts(mut self, max_restarts: u32) -> Self {
+    self.max_restarts = max_restarts;
+    self
+  }
+
+  pub fn restarts(&self) -> u32 {
+    self.restarts
+  }
+}
+
+async fn launch(
+  program: &str, args: &[String], startup_timeout: Duration,
+) -> anyhow::Result<(Child, LinesTransport<ChildStdout, ChildStdin>)> {
+  let mut child = Command::new(program)
+    .args(args)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .kill_on_drop(true)
+    .spawn()?;
+  let pid = child.id().unwrap_or_default();
+  let stdin = child.stdin.take().unwrap();
+  let stdout = child.stdout.take().unwrap();
+  let stderr = child.stderr.take().unwrap();
+
+  let name = program.to_string();
+  tokio::spawn(async move {
+    let mut lines = BufReader::new(stderr).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+      info!(agent = name, pid, "stderr: {}", line);
+    }
+  });
+
+  let mut lines = LinesTransport::new("SubprocessAgent", stdout, stdin);
+  let handshake = AgentReqEvent::WaitForReady { id: HANDSHAKE_ID };
+  match lines.request(&handshake, Some(Instant::now() + startup_timeout)).await {
+    Ok(AgentRespEvent::WaitForReady { .. }) => Ok((child, lines)),
+    Ok(resp) => bail!("{}: unexpected handshake reply {:?}", program, resp),
+    Err(e) => bail!("{}: handshake failed: {}", program, e),
+  }
+}
+
+#[async_trait]
+impl AgentTransport for SubprocessTransport {
+  fn name(&self) -> &str {
+    self.lines.name()
+  }
+
+  async fn send(&mut self, event: &AgentReqEvent) -> Result<(), TransportError> {
+    self.lines.send(event).await
+  }
+
+  async fn recv(&mut self) -> Result<AgentRespEvent, TransportError> {
+    self.lines.recv().await
+  }
+
+  async fn reconnect(&mut self) -> bool {
+    if let Ok(Some(status)) = self.child.try_wait() {
+      warn!("agent {} exited: {}", self.program, status);
+    }
+    while self.restarts < self.max_restarts {
+      self.restarts += 1;
+      warn!(
+        "restart agent {} ({}/{})",
+        self.program, self.restarts, self.max_restarts
+      );
+      match launch(&self.program, &self.args, self.startup_timeout).await {
+        Ok((child, lines)) => {
+          // 旧的子进程在 drop 时被杀掉
+          self.child = child;
+          self.lines = lines;
+          return true;
+        },
+        Err(e) => error!("restart agent {} failed: {}", self.program, e),
+      }
+    }
+    false
+  }
+}
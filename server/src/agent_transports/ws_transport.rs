@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::select;
+use tokio::sync::{mpsc, watch};
+use tracing::info;
+
+use crate::agent_protocol::TransportError;
+use crate::agent_transport::{AgentTransport, decode_json, encode_json};
+use crate::domain::{AgentReqEvent, AgentRespEvent};
+use crate::ws_dispatcher::Presence;
+
+// 经 WsDispatcher 转发的 websocket 连接
+pub struct WsTransport {
+  req_bcast_sender: mpsc::Sender<String>,
+  resp_receiver: mpsc::Receiver<String>,
+  pending: Option<String>, // 最近一次请求, 客户端可能在请求之后才连上, 每秒重发一次
+  session: Option<WsSession>,
+}
+
+// agent endpoint 上的连接状态. 重连时由 WsDispatcher 补发请求, 不需要每秒重发
+struct WsSession {
+  presence: watch::Receiver<Presence>,
+  grace: Duration,
+}
+
+impl WsTransport {
+  pub fn new(req_bcast_sender: mpsc::Sender<String>, resp_receiver: mpsc::Receiver<String>) -> Self {
+    Self {
+      req_bcast_sender,
+      resp_receiver,
+      pending: None,
+      session: None,
+    }
+  }
+
+  pub(crate) fn with_session(mut self, presence: watch::Receiver<Presence>, grace: Duration) -> Self {
+    self.session = Some(WsSession { presence, grace });
+    self
+  }
+}
+
+impl WsSession {
+  // 等到连上. 还没连上过时一直等, 断线后最多等 grace, 之后返回 Away
+  async fn wait_connected(&mut self) -> Result<(), TransportError> {
+    loop {
+      let presence = *self.presence.borrow_and_update();
+      let changed = match presence {
+        Presence::Connected => return Ok(()),
+        Presence::Waiting => self.presence.changed().await,
+        Presence::Away(since) => {
+          let deadline = tokio::time::Instant::from_std(since + self.grace);
+          match tokio::time::timeout_at(deadline, self.presence.changed()).await {
+            Ok(changed) => changed,
+            Err(_) => return Err(TransportError::Away),
+          }
+        },
+      };
+      changed.map_err(|_| TransportError::Disconnected("end point removed".to_string()))?;
+    }
+  }
+}
+
+async fn disconnected(session: &mut Option<WsSession>) {
+  match session {
+    Some(session) => {
+      let removed = session
+        .presence
+        .wait_for(|presence| *presence != Presence::Connected)
+        .await
+        .is_err();
+      // endpoint 被移除, 之后由 resp channel 报告
+      if removed {
+        std::future::pending::<()>().await;
+      }
+    },
+    None => std::future::pending().await,
+  }
+}
+
+#[async_trait]
+impl AgentTransport for WsTransport {
+  fn name(&self) -> &str {
+    "WsAgent"
+  }
+
+  async fn send(&mut self, event: &AgentReqEvent) -> Result<(), TransportError> {
+    let json = encode_json(event);
+    self.pending = Some(json.clone());
+    self
+      .req_bcast_sender
+      .send(json)
+      .await
+      .map_err(|_| TransportError::Disconnected("req channel closed".to_string()))
+  }
+
+  async fn recv(&mut self) -> Result<AgentRespEvent, TransportError> {
+    loop {
+      if let Some(session) = &mut self.session {
+        session.wait_connected().await?;
+      }
+      select! {
+        resp = self.resp_receiver.recv() => {
+          let Some(resp) = resp else {
+            return Err(TransportError::Disconnected("resp channel closed".to_string()));
+          };
+          return decode_json(&resp);
+        }
+
+        _ = disconnected(&mut self.session) => {
+          info!("WsAgent disconnected");
+        }
+
+        _ = tokio::time::sleep(std::time::Duration::from_secs(1)), if self.session.is_none() => {
+          info!("WsAgent block on req timeout");
+          if let Some(json) = self.pending.clone() {
+            self
+              .req_bcast_sender
+              .send(json)
+              .await
+              .map_err(|_| TransportError::Disconnected("req channel closed".to_string()))?;
+          }
+        }
+      }
+    }
+  }
+
+  // 断线超过宽限期后, 等 agent 凭 resume token 连回来
+  async fn reconnect(&mut self) -> bool {
+    match &mut self.session {
+      Some(session) => session
+        .presence
+        .wait_for(|presence| *presence == Presence::Connected)
+        .await
+        .is_ok(),
+      None => false,
+    }
+  }
+}
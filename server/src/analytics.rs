@@ -0,0 +1,443 @@
+// 多局统计: 扫描大量历史记录, 按 4 人局和 6 人局分别汇总选角色, 角色组合, 刺杀/偷取目标, 轮数, 建筑和首个建满 8 个
+// 建筑的数据, 用于调整规则和发现 bot 的弱点. 每张表可以打印成对齐的文本, 也可以写成 CSV.
+//
+// 胜负按队伍计: 胜 1, 平 0.5, 负 0, 与 replay_outcome 相同.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+use strum::{EnumCount, IntoEnumIterator};
+
+use crate::dataset::{card_index, replay_outcome, replay_players};
+use crate::domain::{Camp, Card, Role, RoleSet};
+use crate::history::HistoryReqEvent;
+use crate::history_view::infer_camps;
+
+#[derive(Default, Clone, Copy)]
+struct Counter {
+  n: u64,
+  wins: f64,
+}
+
+impl Counter {
+  fn add(&mut self, result: f32) {
+    self.n += 1;
+    self.wins += result as f64;
+  }
+
+  fn win_rate(&self) -> f64 {
+    if self.n == 0 { 0.0 } else { self.wins / self.n as f64 }
+  }
+}
+
+#[derive(Default, Clone, Copy)]
+struct TargetStats {
+  chosen: u64,
+  hits: u64, // 本轮有人选了这个角色
+}
+
+#[derive(Default, Clone, Copy)]
+struct CardStats {
+  builds: u64,
+  kept: Counter,   // 终局时在城里
+  absent: Counter, // 终局时不在城里
+}
+
+#[derive(Default)]
+struct FirstEightStats {
+  games: u64,
+  team: Counter,
+  decisive: u64, // 去掉首个建满 8 个的 2 分后胜负会变
+}
+
+// 同一人数的对局的统计
+struct PlayerCountStats {
+  games: u64,
+  rounds: u64,
+  min_rounds: u32,
+  max_rounds: u32,
+  results: [f64; 2],
+  draws: u64,
+  role_picks: BTreeMap<usize, [Counter; 8]>, // 选角色的顺位 (皇冠为 0) -> 每个角色
+  role_combos: HashMap<RoleSet, Counter>,    // 一个队伍一轮中拿到的角色
+  kills: [TargetStats; 8],
+  steals: [TargetStats; 8],
+  cards: Vec<CardStats>,
+  first_eight: FirstEightStats,
+}
+
+impl Default for PlayerCountStats {
+  fn default() -> Self {
+    Self {
+      games: 0,
+      rounds: 0,
+      min_rounds: u32::MAX,
+      max_rounds: 0,
+      results: [0.0; 2],
+      draws: 0,
+      role_picks: BTreeMap::new(),
+      role_combos: HashMap::new(),
+      kills: [TargetStats::default(); 8],
+      steals: [TargetStats::default(); 8],
+      cards: vec![CardStats::default(); Card::COUNT],
+      first_eight: FirstEightStats::default(),
+    }
+  }
+}
+
+fn role_index(role: Role) -> usize {
+  (role as u32).trailing_zeros() as usize
+}
+
+fn combo_name(roles: RoleSet) -> String {
+  Role::population()
+    .into_iter()
+    .filter(|&role| roles.contains(role))
+    .map(|role| role.name())
+    .collect::<Vec<_>>()
+    .join("+")
+}
+
+fn rate(n: u64, total: u64) -> f64 {
+  if total == 0 { 0.0 } else { n as f64 / total as f64 }
+}
+
+fn fmt_rate(value: f64) -> String {
+  format!("{:.3}", value)
+}
+
+#[derive(Default)]
+pub struct Analytics {
+  by_players: BTreeMap<usize, PlayerCountStats>,
+  skipped: u64,
+}
+
+impl Analytics {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn num_games(&self) -> u64 {
+    self.by_players.values().map(|stats| stats.games).sum()
+  }
+
+  // 没有打完的对局
+  pub fn num_skipped(&self) -> u64 {
+    self.skipped
+  }
+
+  pub fn add_game(&mut self, events: &[HistoryReqEvent]) {
+    let camps = infer_camps(events);
+    let finished = matches!(events.last(), Some(HistoryReqEvent::FinishGame { .. }));
+    if camps.is_empty() || !finished {
+      self.skipped += 1;
+      return;
+    }
+    let num_players = camps.len();
+    let outcome = replay_outcome(events);
+    let stats = self.by_players.entry(num_players).or_default();
+    stats.games += 1;
+    stats.results[0] += outcome[0] as f64;
+    stats.results[1] += outcome[1] as f64;
+    if outcome[0] == outcome[1] {
+      stats.draws += 1;
+    }
+
+    let mut rounds = 0;
+    let mut round_roles = RoleSet::empty();
+    let mut team_roles = [RoleSet::empty(); 2];
+    let flush_round = |stats: &mut PlayerCountStats, team_roles: &mut [RoleSet; 2]| {
+      for camp in [Camp::楚, Camp::汉] {
+        let roles = std::mem::replace(&mut team_roles[camp as usize], RoleSet::empty());
+        if !roles.is_empty() {
+          stats.role_combos.entry(roles).or_default().add(outcome[camp as usize]);
+        }
+      }
+    };
+    for event in events {
+      match event {
+        HistoryReqEvent::StartRound { .. } => {
+          flush_round(stats, &mut team_roles);
+          round_roles = RoleSet::empty();
+          rounds += 1;
+        },
+        HistoryReqEvent::ChooseRoleResp { actor, obs, chosen, .. } => {
+          let camp = camps[actor.value()];
+          let picks = stats.role_picks.entry(obs.role_select_position()).or_default();
+          picks[role_index(*chosen)].add(outcome[camp as usize]);
+          round_roles |= *chosen;
+          team_roles[camp as usize] |= *chosen;
+        },
+        HistoryReqEvent::KillResp { chosen, .. } => {
+          let target = &mut stats.kills[role_index(*chosen)];
+          target.chosen += 1;
+          target.hits += round_roles.contains(*chosen) as u64;
+        },
+        HistoryReqEvent::StealResp { chosen, .. } => {
+          let target = &mut stats.steals[role_index(*chosen)];
+          target.chosen += 1;
+          target.hits += round_roles.contains(*chosen) as u64;
+        },
+        HistoryReqEvent::Build { card, .. } => stats.cards[card_index(*card)].builds += 1,
+        _ => {},
+      }
+    }
+    flush_round(stats, &mut team_roles);
+
+    stats.rounds += rounds as u64;
+    stats.min_rounds = stats.min_rounds.min(rounds);
+    stats.max_rounds = stats.max_rounds.max(rounds);
+
+    let players = replay_players(events, &camps);
+    for player in players.iter() {
+      let result = outcome[player.camp() as usize];
+      for card in Card::iter() {
+        let card_stats = &mut stats.cards[card_index(card)];
+        if player.has_building(card) {
+          card_stats.kept.add(result);
+        } else {
+          card_stats.absent.add(result);
+        }
+      }
+    }
+
+    if let Some(first) = players.iter().find(|player| player.is_first_8_buildings()) {
+      let camp = first.camp();
+      let mut team_scores = [0i64; 2];
+      for player in players.iter() {
+        team_scores[player.camp() as usize] += player.score() as i64;
+      }
+      let margin = team_scores[camp as usize] - team_scores[1 - camp as usize];
+      let first_eight = &mut stats.first_eight;
+      first_eight.games += 1;
+      first_eight.team.add(outcome[camp as usize]);
+      if (0..=2).contains(&margin) {
+        first_eight.decisive += 1;
+      }
+    }
+  }
+
+  pub fn tables(&self) -> Vec<Table> {
+    vec![
+      self.games_table(),
+      self.role_picks_table(),
+      self.role_combos_table(),
+      self.targets_table(),
+      self.cards_table(),
+      self.first_eight_table(),
+    ]
+  }
+
+  fn games_table(&self) -> Table {
+    let mut table = Table::new(
+      "games",
+      &[
+        "players",
+        "games",
+        "avg_rounds",
+        "min_rounds",
+        "max_rounds",
+        "楚_win_rate",
+        "汉_win_rate",
+        "draw_rate",
+      ],
+    );
+    for (&num_players, stats) in self.by_players.iter() {
+      let games = stats.games as f64;
+      table.push(vec![
+        num_players.to_string(),
+        stats.games.to_string(),
+        format!("{:.2}", stats.rounds as f64 / games),
+        stats.min_rounds.to_string(),
+        stats.max_rounds.to_string(),
+        fmt_rate(stats.results[Camp::楚 as usize] / games),
+        fmt_rate(stats.results[Camp::汉 as usize] / games),
+        fmt_rate(rate(stats.draws, stats.games)),
+      ]);
+    }
+    table
+  }
+
+  fn role_picks_table(&self) -> Table {
+    let mut table = Table::new(
+      "role_picks",
+      &["players", "position", "role", "picks", "pick_rate", "win_rate"],
+    );
+    for (&num_players, stats) in self.by_players.iter() {
+      for (&position, picks) in stats.role_picks.iter() {
+        let total = picks.iter().map(|p| p.n).sum::<u64>();
+        for role in Role::population() {
+          let pick = picks[role_index(role)];
+          if pick.n == 0 {
+            continue;
+          }
+          table.push(vec![
+            num_players.to_string(),
+            position.to_string(),
+            role.name().to_string(),
+            pick.n.to_string(),
+            fmt_rate(rate(pick.n, total)),
+            fmt_rate(pick.win_rate()),
+          ]);
+        }
+      }
+    }
+    table
+  }
+
+  fn role_combos_table(&self) -> Table {
+    let mut table = Table::new("role_combos", &["players", "roles", "team_rounds", "win_rate"]);
+    for (&num_players, stats) in self.by_players.iter() {
+      let mut combos = stats.role_combos.iter().collect::<Vec<_>>();
+      combos.sort_by(|a, b| b.1.n.cmp(&a.1.n).then_with(|| combo_name(*a.0).cmp(&combo_name(*b.0))));
+      for (&roles, combo) in combos {
+        table.push(vec![
+          num_players.to_string(),
+          combo_name(roles),
+          combo.n.to_string(),
+          fmt_rate(combo.win_rate()),
+        ]);
+      }
+    }
+    table
+  }
+
+  fn targets_table(&self) -> Table {
+    let mut table = Table::new("targets", &["players", "action", "role", "chosen", "share", "hit_rate"]);
+    for (&num_players, stats) in self.by_players.iter() {
+      for (action, targets) in [("kill", &stats.kills), ("steal", &stats.steals)] {
+        let total = targets.iter().map(|t| t.chosen).sum::<u64>();
+        for role in Role::population() {
+          let target = targets[role_index(role)];
+          if target.chosen == 0 {
+            continue;
+          }
+          table.push(vec![
+            num_players.to_string(),
+            action.to_string(),
+            role.name().to_string(),
+            target.chosen.to_string(),
+            fmt_rate(rate(target.chosen, total)),
+            fmt_rate(rate(target.hits, target.chosen)),
+          ]);
+        }
+      }
+    }
+    table
+  }
+
+  // win_delta 为终局城里有这张牌的玩家与没有的玩家的胜率之差
+  fn cards_table(&self) -> Table {
+    let mut table = Table::new(
+      "cards",
+      &[
+        "players",
+        "card",
+        "builds",
+        "builds_per_game",
+        "kept",
+        "kept_win_rate",
+        "absent_win_rate",
+        "win_delta",
+      ],
+    );
+    for (&num_players, stats) in self.by_players.iter() {
+      for card in Card::iter() {
+        let card_stats = stats.cards[card_index(card)];
+        table.push(vec![
+          num_players.to_string(),
+          card.name().to_string(),
+          card_stats.builds.to_string(),
+          format!("{:.3}", card_stats.builds as f64 / stats.games as f64),
+          card_stats.kept.n.to_string(),
+          fmt_rate(card_stats.kept.win_rate()),
+          fmt_rate(card_stats.absent.win_rate()),
+          format!("{:+.3}", card_stats.kept.win_rate() - card_stats.absent.win_rate()),
+        ]);
+      }
+    }
+    table
+  }
+
+  fn first_eight_table(&self) -> Table {
+    let mut table = Table::new(
+      "first_eight",
+      &["players", "games", "ended_by_eight", "team_win_rate", "decisive_rate"],
+    );
+    for (&num_players, stats) in self.by_players.iter() {
+      let first_eight = &stats.first_eight;
+      table.push(vec![
+        num_players.to_string(),
+        stats.games.to_string(),
+        fmt_rate(rate(first_eight.games, stats.games)),
+        fmt_rate(first_eight.team.win_rate()),
+        fmt_rate(rate(first_eight.decisive, first_eight.games)),
+      ]);
+    }
+    table
+  }
+}
+
+pub struct Table {
+  pub name: &'static str,
+  pub header: Vec<&'static str>,
+  pub rows: Vec<Vec<String>>,
+}
+
+// 终端里汉字占两格
+fn display_width(s: &str) -> usize {
+  s.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum()
+}
+
+impl Table {
+  fn new(name: &'static str, header: &[&'static str]) -> Self {
+    Self {
+      name,
+      header: header.to_vec(),
+      rows: Vec::new(),
+    }
+  }
+
+  fn push(&mut self, row: Vec<String>) {
+    self.rows.push(row);
+  }
+
+  // 第一列左对齐, 其余右对齐
+  pub fn render(&self) -> String {
+    let mut widths = self.header.iter().map(|h| display_width(h)).collect::<Vec<_>>();
+    for row in self.rows.iter() {
+      for (width, cell) in widths.iter_mut().zip(row.iter()) {
+        *width = (*width).max(display_width(cell));
+      }
+    }
+
+    let mut text = format!("== {} ==\n", self.name);
+    let header = self.header.iter().map(|h| h.to_string()).collect::<Vec<_>>();
+    for row in std::iter::once(&header).chain(self.rows.iter()) {
+      let mut line = String::new();
+      for (i, (cell, &width)) in row.iter().zip(widths.iter()).enumerate() {
+        let pad = " ".repeat(width - display_width(cell));
+        if i == 0 {
+          line.push_str(cell);
+          line.push_str(&pad);
+        } else {
+          line.push_str("  ");
+          line.push_str(&pad);
+          line.push_str(cell);
+        }
+      }
+      text.push_str(line.trim_end());
+      text.push('\n');
+    }
+    text
+  }
+
+  // 单元格里没有逗号, 引号和换行, 不需要转义
+  pub fn write_csv<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+    writeln!(writer, "{}", self.header.join(","))?;
+    for row in self.rows.iter() {
+      writeln!(writer, "{}", row.join(","))?;
+    }
+    writer.flush()
+  }
+}
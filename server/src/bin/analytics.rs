@@ -0,0 +1,75 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use indicatif::ProgressBar;
+use server::{Analytics, load_history};
+
+#[derive(Parser)]
+#[command(about = "Aggregate statistics over many recorded games, split by 4 and 6 players")]
+struct Cli {
+  /// History files or directories of them; JSONL if the file ends with `.jsonl`, otherwise the binary archive format
+  inputs: Vec<String>,
+  /// Also write every table as `<name>.csv` into this directory
+  #[arg(long)]
+  csv: Option<String>,
+}
+
+// 目录只展开一层, 按文件名排序
+fn collect_files(inputs: &[String]) -> anyhow::Result<Vec<PathBuf>> {
+  let mut files = Vec::new();
+  for input in inputs {
+    let path = Path::new(input);
+    if path.is_dir() {
+      let mut entries = std::fs::read_dir(path)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+      entries.retain(|p| p.is_file());
+      entries.sort();
+      files.extend(entries);
+    } else {
+      files.push(path.to_path_buf());
+    }
+  }
+  Ok(files)
+}
+
+fn main() -> anyhow::Result<()> {
+  let cli = Cli::parse();
+  let files = collect_files(&cli.inputs)?;
+
+  let mut analytics = Analytics::new();
+  let mut unreadable = 0;
+  let pb = ProgressBar::new(files.len() as u64);
+  for file in files.iter() {
+    match load_history(&file.to_string_lossy()) {
+      Ok(events) => analytics.add_game(&events),
+      Err(e) => {
+        pb.suspend(|| eprintln!("skip {}: {}", file.display(), e));
+        unreadable += 1;
+      },
+    }
+    pb.inc(1);
+  }
+  pb.finish_and_clear();
+
+  let tables = analytics.tables();
+  for table in tables.iter() {
+    println!("{}", table.render());
+  }
+  println!("games: {}", analytics.num_games());
+  println!("unfinished: {}", analytics.num_skipped());
+  println!("unreadable: {}", unreadable);
+
+  if let Some(dir) = &cli.csv {
+    std::fs::create_dir_all(dir)?;
+    for table in tables.iter() {
+      let path = Path::new(dir).join(format!("{}.csv", table.name));
+      table.write_csv(BufWriter::new(File::create(&path)?))?;
+    }
+    println!("csv written to {}", dir);
+  }
+
+  Ok(())
+}
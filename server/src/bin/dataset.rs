@@ -0,0 +1,117 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use server::{
+  AbstractFAAgent, AbstractFYIAgent, DatasetFormat, DatasetWriter, Game, History, HistoryReqEvent, NoopFYIAgent,
+  Player, PlayerIndexedVec, RandomFAAgent, V2FAAgent, load_history, replay_outcome,
+};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+#[derive(Parser)]
+#[command(about = "Export recorded or simulated games as supervised-learning samples")]
+struct Cli {
+  #[command(subcommand)]
+  source: Source,
+  /// Output directory for the shards and schema.json
+  #[arg(long, default_value = "dataset")]
+  out_dir: String,
+  #[arg(long, value_enum, default_value_t = Format::Npy)]
+  format: Format,
+  /// Number of samples per shard
+  #[arg(long, default_value_t = 100_000)]
+  shard_size: usize,
+}
+
+#[derive(Subcommand)]
+enum Source {
+  /// Recorded histories, JSONL if the file ends with `.jsonl`, otherwise the binary archive format
+  History { inputs: Vec<String> },
+  /// Simulate games between V2FAAgent and RandomFAAgent
+  Sim {
+    #[arg(long, default_value_t = 100)]
+    games: usize,
+  },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+  Csv,
+  Npy,
+}
+
+async fn simulate() -> (Vec<HistoryReqEvent>, [f32; 2]) {
+  let mut rng = StdRng::seed_from_u64(rand::random());
+
+  let (history_req_bcast_sender, mut history_req_bcast_receiver) = mpsc::channel::<String>(1024);
+  let (_, history_resp_receiver) = mpsc::channel::<String>(1024);
+  let history = History::new(history_req_bcast_sender, history_resp_receiver);
+
+  let collector = tokio::spawn(async move {
+    let mut events = Vec::new();
+    while let Some(event) = history_req_bcast_receiver.recv().await {
+      events.push(serde_json::from_str::<HistoryReqEvent>(&event).unwrap());
+    }
+    events
+  });
+
+  let num_players = if rng.random_range(0..2) == 0 { 4 } else { 6 };
+  let names = ["刘邦", "项羽", "张良", "范增", "韩信", "龙且"];
+  let mut players = PlayerIndexedVec::<Player>::new();
+  let mut agents = PlayerIndexedVec::<Box<dyn AbstractFAAgent>>::new();
+  let mut fyi_agents = PlayerIndexedVec::<Box<dyn AbstractFYIAgent>>::new();
+  for (i, name) in names.iter().take(num_players).enumerate() {
+    if i % 2 == 0 {
+      players.push(Player::new_汉(uuid::Uuid::new_v4(), name.to_string()));
+      agents.push(Box::new(V2FAAgent::new()));
+    } else {
+      players.push(Player::new_楚(uuid::Uuid::new_v4(), name.to_string()));
+      agents.push(Box::new(RandomFAAgent::new()));
+    }
+    fyi_agents.push(Box::new(NoopFYIAgent::new()));
+  }
+
+  let mut game = Game::new(num_players, players, agents, fyi_agents, rng, history).await;
+  let (chu, han) = game.run().await.unwrap().result;
+  drop(game);
+
+  (collector.await.unwrap(), [chu as f32, han as f32])
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+  let cli = Cli::parse();
+
+  let format = match cli.format {
+    Format::Csv => DatasetFormat::Csv,
+    Format::Npy => DatasetFormat::Npy,
+  };
+  let mut writer = DatasetWriter::new(&cli.out_dir, format, cli.shard_size)?;
+
+  match cli.source {
+    Source::History { inputs } => {
+      for (game, input) in inputs.iter().enumerate() {
+        let events = load_history(input)?;
+        writer.write_game(game, &events, replay_outcome(&events))?;
+      }
+    },
+    Source::Sim { games } => {
+      let mut join_set = JoinSet::new();
+      for _ in 0..games {
+        join_set.spawn(simulate());
+      }
+      let mut game = 0;
+      while let Some(result) = join_set.join_next().await {
+        let (events, outcome) = result?;
+        writer.write_game(game, &events, outcome)?;
+        game += 1;
+      }
+    },
+  }
+
+  let (num_samples, num_shards) = writer.finish()?;
+  println!("samples: {}", num_samples);
+  println!("shards: {}", num_shards);
+
+  Ok(())
+}
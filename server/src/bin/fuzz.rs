@@ -0,0 +1,312 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use clap::Parser;
+use indicatif::ProgressBar;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use server::domain::Camp;
+use server::{
+  AbstractFAAgent, AbstractFYIAgent, Game, History, NoopFYIAgent, Player, PlayerIndexedVec, RandomFAAgent, Scenario,
+};
+use tokio::sync::mpsc;
+use tokio::task::{JoinHandle, JoinSet};
+
+#[derive(Parser)]
+#[command(about = "Play games with random legal actions and check engine invariants after every state change")]
+struct Cli {
+  #[arg(long, default_value_t = 100000)]
+  games: u64,
+  /// Game i uses seed + i, which decides the number of players, the deck and every agent
+  #[arg(long, default_value_t = 0)]
+  seed: u64,
+  /// Games played concurrently
+  #[arg(long, default_value_t = 64)]
+  jobs: usize,
+  /// Directory for the minimized scenarios of failing games
+  #[arg(long, default_value = "fuzz-failures")]
+  out: String,
+  /// Stop after this many failing games
+  #[arg(long, default_value_t = 10)]
+  max_failures: usize,
+  /// Seeds tried when reproducing a failure from the start of its round
+  #[arg(long, default_value_t = 1000)]
+  repro_tries: u64,
+  /// Replay a scenario written by a previous run instead of fuzzing
+  #[arg(long)]
+  replay: Option<String>,
+  /// Record the history of the replayed game as JSONL
+  #[arg(long)]
+  history: Option<String>,
+}
+
+struct Failure {
+  message: String,
+  // 不变量的名字或出错的类别, 见 GameError::kind
+  kind: String,
+  // 出问题那一轮开始时的局面, 在发牌阶段出问题时为 None
+  snapshot: Option<Scenario>,
+}
+
+// 同一个种子下每个座位的 agent 用不同的随机数
+fn agent_seed(seed: u64, seat: usize) -> u64 {
+  seed ^ ((seat as u64 + 1) << 48)
+}
+
+fn agents(seed: u64, num_players: usize) -> PlayerIndexedVec<Box<dyn AbstractFAAgent>> {
+  let mut agents = PlayerIndexedVec::<Box<dyn AbstractFAAgent>>::new();
+  for i in 0..num_players {
+    agents.push(Box::new(RandomFAAgent::with_seed(agent_seed(seed, i))));
+  }
+  agents
+}
+
+fn fyi_agents(num_players: usize) -> PlayerIndexedVec<Box<dyn AbstractFYIAgent>> {
+  let mut fyi_agents = PlayerIndexedVec::<Box<dyn AbstractFYIAgent>>::new();
+  for _ in 0..num_players {
+    fyi_agents.push(Box::new(NoopFYIAgent::new()));
+  }
+  fyi_agents
+}
+
+// 只有 replay 时记录到文件, fuzz 时直接丢掉
+fn history(mut writer: Option<BufWriter<File>>) -> (History, JoinHandle<std::io::Result<()>>) {
+  let (history_req_bcast_sender, mut history_req_bcast_receiver) = mpsc::channel::<String>(1024);
+  let (_, history_resp_receiver) = mpsc::channel::<String>(1024);
+  let recorder = tokio::spawn(async move {
+    while let Some(event) = history_req_bcast_receiver.recv().await {
+      if let Some(writer) = writer.as_mut() {
+        writer.write_all(event.as_bytes())?;
+        writer.write_all(b"\n")?;
+      }
+    }
+    if let Some(mut writer) = writer {
+      writer.flush()?;
+    }
+    Ok(())
+  });
+  (History::new(history_req_bcast_sender, history_resp_receiver), recorder)
+}
+
+async fn run(mut game: Game) -> Result<(), Failure> {
+  game.enable_invariant_checks();
+  game.enable_leak_checks();
+  game.enable_strict_agents();
+  match game.run().await {
+    Ok(_) => Ok(()),
+    Err(error) => Err(Failure {
+      message: error.to_string(),
+      kind: error.kind().to_string(),
+      snapshot: game.round_snapshot().cloned(),
+    }),
+  }
+}
+
+async fn play_new(seed: u64) -> Result<(), Failure> {
+  let num_players = if seed.is_multiple_of(2) { 4 } else { 6 };
+  let mut players = PlayerIndexedVec::<Player>::new();
+  for i in 0..num_players {
+    let camp = if i % 2 == 0 { Camp::汉 } else { Camp::楚 };
+    players.push(Player::new(uuid::Uuid::new_v4(), format!("player{}", i), camp));
+  }
+  let rng = StdRng::seed_from_u64(seed);
+  let (history, _) = history(None);
+  let game = Game::new(
+    num_players,
+    players,
+    agents(seed, num_players),
+    fyi_agents(num_players),
+    rng,
+    history,
+  )
+  .await;
+  run(game).await
+}
+
+// 局面的 seed 同时决定对局和 agent 的随机数
+async fn play_scenario(scenario: &Scenario, history: History) -> Result<(), Failure> {
+  let seed = scenario.seed.unwrap_or(0);
+  let num_players = scenario.players.len();
+  let game = Game::from_scenario(
+    scenario,
+    agents(seed, num_players),
+    fyi_agents(num_players),
+    StdRng::seed_from_u64(seed),
+    history,
+  )
+  .unwrap();
+  run(game).await
+}
+
+async fn reproduces(scenario: &Scenario, kind: &str) -> bool {
+  if scenario.validate().is_err() {
+    return false;
+  }
+  match play_scenario(scenario, history(None).0).await {
+    Ok(()) => false,
+    Err(failure) => failure.kind == kind,
+  }
+}
+
+// 去掉一点东西的局面, 去掉的牌放进弃牌堆以保持总数不变
+fn shrink_candidates(scenario: &Scenario) -> Vec<Scenario> {
+  let mut candidates = Vec::new();
+  for (i, player) in scenario.players.iter().enumerate() {
+    for gold in [0, player.gold / 2] {
+      if gold < player.gold {
+        let mut candidate = scenario.clone();
+        candidate.players[i].gold = gold;
+        candidates.push(candidate);
+      }
+    }
+    for j in 0..player.hand.len() {
+      let mut candidate = scenario.clone();
+      let card = candidate.players[i].hand.remove(j);
+      candidate.drop.push(card);
+      candidates.push(candidate);
+    }
+    for j in 0..player.buildings.len() {
+      let mut candidate = scenario.clone();
+      let card = candidate.players[i].buildings.remove(j);
+      candidate.drop.push(card);
+      candidates.push(candidate);
+    }
+  }
+  candidates
+}
+
+// 从出问题那一轮开始只打一轮, 先找一个能复现同类问题的种子, 再贪心地去掉与问题无关的金币, 手牌和建筑
+async fn minimize(snapshot: &Scenario, kind: &str, repro_tries: u64) -> Option<Scenario> {
+  let mut scenario = snapshot.clone();
+  scenario.rounds = Some(1);
+
+  let mut found = false;
+  for seed in 0..repro_tries {
+    scenario.seed = Some(seed);
+    if reproduces(&scenario, kind).await {
+      found = true;
+      break;
+    }
+  }
+  if !found {
+    return None;
+  }
+
+  'shrink: loop {
+    for candidate in shrink_candidates(&scenario) {
+      if reproduces(&candidate, kind).await {
+        scenario = candidate;
+        continue 'shrink;
+      }
+    }
+    break;
+  }
+  Some(scenario)
+}
+
+fn write_scenario(path: &Path, scenario: &Scenario, header: &str) -> anyhow::Result<()> {
+  let mut text = String::new();
+  for line in header.lines() {
+    text.push_str("# ");
+    text.push_str(line);
+    text.push('\n');
+  }
+  text.push_str(&toml::to_string(scenario)?);
+  std::fs::write(path, text)?;
+  Ok(())
+}
+
+async fn report(cli: &Cli, seed: u64, failure: &Failure) -> anyhow::Result<()> {
+  println!("seed {}: {}", seed, failure.message);
+  let Some(snapshot) = &failure.snapshot else {
+    println!("  failed while dealing, rerun with --seed {} --games 1", seed);
+    return Ok(());
+  };
+
+  std::fs::create_dir_all(&cli.out)?;
+  let path = Path::new(&cli.out).join(format!("seed-{}.toml", seed));
+  match minimize(snapshot, &failure.kind, cli.repro_tries).await {
+    Some(scenario) => {
+      let header = format!(
+        "fuzz seed {}: {}\nreplay with: fuzz --replay {}",
+        seed,
+        failure.message,
+        path.display()
+      );
+      write_scenario(&path, &scenario, &header)?;
+      println!("  minimized scenario written to {}", path.display());
+    },
+    None => {
+      let header = format!(
+        "fuzz seed {}: {}\nstart of the failing round, not reproduced within {} seeds",
+        seed, failure.message, cli.repro_tries
+      );
+      write_scenario(&path, snapshot, &header)?;
+      println!(
+        "  not reproduced from the start of the round, snapshot written to {}",
+        path.display()
+      );
+    },
+  }
+  Ok(())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+  let cli = Cli::parse();
+  // 失败的对局在最后统一报告
+  std::panic::set_hook(Box::new(|_| {}));
+
+  if let Some(path) = &cli.replay {
+    let scenario = Scenario::load(path)?;
+    let writer = cli
+      .history
+      .as_deref()
+      .map(File::create)
+      .transpose()?
+      .map(BufWriter::new);
+    let (history, recorder) = history(writer);
+    match play_scenario(&scenario, history).await {
+      Ok(()) => println!("ok"),
+      Err(failure) => println!("{}", failure.message),
+    }
+    recorder.await??;
+    return Ok(());
+  }
+
+  let start = Instant::now();
+  let pb = ProgressBar::new(cli.games);
+  let mut failures = Vec::new();
+  let mut join_set = JoinSet::new();
+  let mut next = 0;
+  loop {
+    while next < cli.games && join_set.len() < cli.jobs && failures.len() < cli.max_failures {
+      let seed = cli.seed + next;
+      join_set.spawn(async move { (seed, play_new(seed).await) });
+      next += 1;
+    }
+    let Some(result) = join_set.join_next().await else {
+      break;
+    };
+    let (seed, result) = result?;
+    if let Err(failure) = result {
+      failures.push((seed, failure));
+    }
+    pb.inc(1);
+  }
+  pb.finish_and_clear();
+
+  println!("num_games: {}", pb.position());
+  println!("Time taken: {:?}", start.elapsed());
+  println!("failures: {}", failures.len());
+  failures.sort_by_key(|(seed, _)| *seed);
+  // 停下来之前已经在跑的对局也可能失败
+  failures.truncate(cli.max_failures);
+  for (seed, failure) in failures.iter() {
+    report(&cli, *seed, failure).await?;
+  }
+
+  Ok(())
+}
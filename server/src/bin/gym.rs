@@ -0,0 +1,74 @@
+use clap::Parser;
+use serde::Deserialize;
+use server::{GymEnv, GymStep};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+#[derive(Parser)]
+#[command(about = "Expose the game as a reinforcement-learning environment over JSON lines")]
+struct Cli {
+  /// Listen on a local TCP address such as 127.0.0.1:7100 instead of using stdin/stdout
+  #[arg(long)]
+  tcp: Option<String>,
+}
+
+// {"cmd": "reset", "seed": 1, "lineup": ["learner", "random", "v2", "random"]}
+// {"cmd": "step", "action": 12}
+// {"cmd": "close"}
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+enum GymRequest {
+  Reset { seed: Option<u64>, lineup: Vec<String> },
+  Step { action: usize },
+  Close,
+}
+
+async fn handle(env: &mut GymEnv, line: &str) -> anyhow::Result<Option<GymStep>> {
+  match serde_json::from_str::<GymRequest>(line)? {
+    GymRequest::Reset { seed, lineup } => Ok(Some(env.reset(seed.unwrap_or_else(rand::random), &lineup).await?)),
+    GymRequest::Step { action } => Ok(Some(env.step(action).await?)),
+    GymRequest::Close => Ok(None),
+  }
+}
+
+// 每行一个请求, 每个请求回复一行: GymStep 或者 {"error": "..."}
+async fn serve<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin>(reader: R, mut writer: W) -> anyhow::Result<()> {
+  let mut env = GymEnv::new();
+  let mut lines = reader.lines();
+  while let Some(line) = lines.next_line().await? {
+    if line.trim().is_empty() {
+      continue;
+    }
+    let resp = match handle(&mut env, &line).await {
+      Ok(Some(step)) => serde_json::to_string(&step)?,
+      Ok(None) => break,
+      Err(e) => serde_json::json!({ "error": e.to_string() }).to_string(),
+    };
+    writer.write_all(resp.as_bytes()).await?;
+    writer.write_all(b"\n").await?;
+    writer.flush().await?;
+  }
+  Ok(())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+  let cli = Cli::parse();
+
+  match cli.tcp {
+    Some(addr) => {
+      let listener = TcpListener::bind(&addr).await?;
+      eprintln!("listening on {}", addr);
+      loop {
+        let (stream, peer) = listener.accept().await?;
+        tokio::spawn(async move {
+          let (reader, writer) = stream.into_split();
+          if let Err(e) = serve(BufReader::new(reader), writer).await {
+            eprintln!("{}: {}", peer, e);
+          }
+        });
+      }
+    },
+    None => serve(BufReader::new(tokio::io::stdin()), tokio::io::stdout()).await,
+  }
+}
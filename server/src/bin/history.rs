@@ -1,12 +1,69 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use clap::Parser;
 use futures_util::{SinkExt, StreamExt};
-use server::{Config, HistoryReqEvent, HistoryRespEvent, init_log};
+use server::{Config, HistoryArchiveWriter, HistoryReqEvent, HistoryRespEvent, WireFormat, init_log};
 use tokio_tungstenite::connect_async;
 use tokio_tungstenite::tungstenite::Message;
 
-async fn work() -> anyhow::Result<()> {
+#[derive(Parser)]
+struct Cli {
+  /// Record events to this file: JSONL if it ends with `.jsonl`, otherwise the binary archive format
+  #[arg(long)]
+  output: Option<String>,
+  /// `json` (text frames) or `msgpack` (binary frames)
+  #[arg(long, default_value = "json")]
+  format: WireFormat,
+}
+
+enum Recorder {
+  Jsonl(BufWriter<File>),
+  Archive(HistoryArchiveWriter<BufWriter<File>>),
+}
+
+impl Recorder {
+  fn create(path: &str) -> anyhow::Result<Self> {
+    let writer = BufWriter::new(File::create(path)?);
+    if path.ends_with(".jsonl") {
+      Ok(Recorder::Jsonl(writer))
+    } else {
+      Ok(Recorder::Archive(HistoryArchiveWriter::new(writer)?))
+    }
+  }
+
+  fn record(&mut self, text: &str, event: &HistoryReqEvent) -> anyhow::Result<()> {
+    match self {
+      Recorder::Jsonl(writer) => {
+        writer.write_all(text.as_bytes())?;
+        writer.write_all(b"\n")?;
+      },
+      Recorder::Archive(writer) => writer.write_event(event)?,
+    }
+    Ok(())
+  }
+
+  fn finish(self) -> anyhow::Result<()> {
+    match self {
+      Recorder::Jsonl(mut writer) => writer.flush()?,
+      Recorder::Archive(writer) => writer.finish()?.flush()?,
+    }
+    Ok(())
+  }
+}
+
+async fn work(cli: Cli) -> anyhow::Result<()> {
   let config = Config::load("config.toml")?;
 
-  let addr = format!("ws://{}:{}/{}", config.host, config.port, config.history_uuid);
+  let mut recorder = cli.output.as_deref().map(Recorder::create).transpose()?;
+
+  let addr = format!(
+    "ws://{}:{}/{}{}",
+    config.host,
+    config.port,
+    config.history_uuid,
+    cli.format.query()
+  );
   println!("addr: {}", addr);
 
   let (mut ws_stream, _) = connect_async(&addr).await.unwrap();
@@ -17,7 +74,12 @@ async fn work() -> anyhow::Result<()> {
         println!("History stream closed: {:?}", close_frame);
         break;
       },
-      Ok(Message::Text(text)) => {
+      Ok(msg @ (Message::Text(_) | Message::Binary(_))) => {
+        // JSONL 记录的是 JSON 文本, binary frame 先转回来
+        let text = match msg {
+          Message::Text(text) => text.to_string(),
+          msg => cli.format.decode_json_text(&msg.into_data())?,
+        };
         let event: HistoryReqEvent = serde_json::from_str(&text).unwrap();
         match event {
           HistoryReqEvent::WaitForReady { .. } => {
@@ -25,10 +87,20 @@ async fn work() -> anyhow::Result<()> {
             let event = HistoryRespEvent::Ready;
             let json = serde_json::to_string(&event).unwrap();
             println!("> {}", json);
-            ws_stream.send(Message::Text(json.into())).await.unwrap();
+            let msg = match cli.format {
+              WireFormat::Json => Message::Text(json.into()),
+              WireFormat::MessagePack => Message::Binary(cli.format.encode_json_text(&json)?.into()),
+            };
+            ws_stream.send(msg).await.unwrap();
           },
           _ => {
             println!("received other event");
+            if let Some(rationale) = event.rationale() {
+              println!("  rationale: {}", rationale.summary());
+            }
+            if let Some(recorder) = recorder.as_mut() {
+              recorder.record(&text, &event)?;
+            }
           },
         }
       },
@@ -41,6 +113,10 @@ async fn work() -> anyhow::Result<()> {
     }
   }
 
+  if let Some(recorder) = recorder {
+    recorder.finish()?;
+  }
+
   Ok(())
 }
 
@@ -48,7 +124,7 @@ async fn work() -> anyhow::Result<()> {
 async fn main() -> anyhow::Result<()> {
   let _guard = init_log("history");
 
-  work().await?;
+  work(Cli::parse()).await?;
 
   Ok(())
 }
@@ -0,0 +1,47 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+
+use clap::{Parser, Subcommand};
+use server::{archive_to_jsonl, jsonl_to_archive};
+
+#[derive(Parser)]
+#[command(about = "Convert game histories between JSONL and the compact binary archive format")]
+struct Cli {
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+  /// JSONL -> binary archive
+  Encode { input: String, output: String },
+  /// binary archive -> JSONL
+  Decode { input: String, output: String },
+}
+
+fn main() -> anyhow::Result<()> {
+  let cli = Cli::parse();
+
+  let (input, output, cnt) = match cli.command {
+    Command::Encode { input, output } => {
+      let reader = BufReader::new(File::open(&input)?);
+      let writer = BufWriter::new(File::create(&output)?);
+      let cnt = jsonl_to_archive(reader, writer)?;
+      (input, output, cnt)
+    },
+    Command::Decode { input, output } => {
+      let reader = BufReader::new(File::open(&input)?);
+      let writer = BufWriter::new(File::create(&output)?);
+      let cnt = archive_to_jsonl(reader, writer)?;
+      (input, output, cnt)
+    },
+  };
+
+  let input_len = std::fs::metadata(&input)?.len();
+  let output_len = std::fs::metadata(&output)?.len();
+  println!("events: {}", cnt);
+  println!("{}: {} bytes", input, input_len);
+  println!("{}: {} bytes", output, output_len);
+
+  Ok(())
+}
@@ -0,0 +1,64 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use clap::Parser;
+use server::domain::{Camp, PlayerIndex};
+use server::{HistoryViewer, infer_camps, load_history, project_history};
+
+#[derive(Parser)]
+#[command(about = "Project a full game history onto what one seat, team or spectator was allowed to see")]
+struct Cli {
+  /// History file, JSONL if it ends with `.jsonl`, otherwise the binary archive format
+  input: String,
+  /// Output JSONL file of redacted events
+  output: String,
+  #[arg(long, conflicts_with = "team")]
+  seat: Option<usize>,
+  /// 楚 or 汉
+  #[arg(long)]
+  team: Option<String>,
+  /// Also print the decision rationales visible to the viewer, keyed by output line
+  #[arg(long)]
+  rationales: bool,
+}
+
+fn main() -> anyhow::Result<()> {
+  let cli = Cli::parse();
+
+  let viewer = match (cli.seat, cli.team.as_deref()) {
+    (Some(seat), _) => HistoryViewer::Seat(PlayerIndex::from_usize(seat)),
+    (None, Some("楚")) => HistoryViewer::Team(Camp::楚),
+    (None, Some("汉")) => HistoryViewer::Team(Camp::汉),
+    (None, Some(team)) => anyhow::bail!("unknown team: {}", team),
+    (None, None) => HistoryViewer::Spectator,
+  };
+
+  let events = load_history(&cli.input)?;
+  if let HistoryViewer::Seat(seat) = viewer {
+    let num_players = infer_camps(&events).len();
+    if seat.value() >= num_players {
+      anyhow::bail!(
+        "seat {} out of range, the game has {} players",
+        seat.value(),
+        num_players
+      );
+    }
+  }
+  let views = project_history(&events, viewer);
+
+  let mut writer = BufWriter::new(File::create(&cli.output)?);
+  for (line, view) in views.iter().enumerate() {
+    serde_json::to_writer(&mut writer, view)?;
+    writer.write_all(b"\n")?;
+    if cli.rationales
+      && let Some(rationale) = view.rationale()
+    {
+      println!("line {}: {}", line + 1, rationale.summary());
+    }
+  }
+  writer.flush()?;
+
+  println!("events: {}", views.len());
+
+  Ok(())
+}
@@ -1,14 +1,33 @@
+use std::fs::File;
+use std::io::BufWriter;
 use std::time::Instant;
 
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
-use server::fa_agents::RedisProxyFAAgent;
+use room_context::domain::factories::RoomConfigFactory;
+use room_context::domain::repositories::RoomRepository;
+use room_context::infra::PostgresRoomRepository;
+use server::agent_transports::RedisTransport;
+use server::fa_agents::RemoteFAAgent;
 use server::{
-  AbstractFAAgent, AbstractFYIAgent, Config, Game, History, IdGen, NoopFYIAgent, Player, PlayerIndexedVec,
-  RandomFAAgent, V2FAAgent, init_log,
+  AbstractFAAgent, AbstractFYIAgent, Config, Game, History, HistoryArchiveWriter, HistoryReqEvent, IdGen,
+  NoopFYIAgent, Player, PlayerIndexedVec, RandomFAAgent, TimeControl, V2FAAgent, init_log,
 };
 use tokio::sync::mpsc;
 
+// 房间用 room.set_time_control 设置的决策时限, 没有指定房间时用 config.toml 中的
+async fn time_control(config: &Config) -> anyhow::Result<TimeControl> {
+  let Some(room_id) = &config.room_id else {
+    return Ok(config.time_control);
+  };
+  let room_config = RoomConfigFactory::new().load()?;
+  let pool = common_context::database::create_postgres_pool(&room_config.db).await?;
+  let Some(room) = PostgresRoomRepository::new(pool).find_by_id(room_id.parse()?).await? else {
+    anyhow::bail!("room {} not found", room_id);
+  };
+  Ok(room.time_control())
+}
+
 async fn work() -> anyhow::Result<(f64, f64)> {
   let config = Config::load("config.toml")?;
 
@@ -24,7 +43,23 @@ async fn work() -> anyhow::Result<(f64, f64)> {
   //   .add_end_point(config.history_uuid, history_req_bcast_receiver, history_resp_sender)
   //   .await;
   let /*mut*/ history = History::new(history_req_bcast_sender, history_resp_receiver);
-  tokio::spawn(async move { while history_req_bcast_receiver.recv().await.is_some() {} });
+  // 历史记录以 GameReport 结尾, 一起写入归档
+  let mut writer = config
+    .history_archive
+    .as_deref()
+    .map(|path| HistoryArchiveWriter::new(BufWriter::new(File::create(path)?)))
+    .transpose()?;
+  let recorder = tokio::spawn(async move {
+    while let Some(event) = history_req_bcast_receiver.recv().await {
+      if let Some(writer) = writer.as_mut() {
+        writer.write_event(&serde_json::from_str::<HistoryReqEvent>(&event)?)?;
+      }
+    }
+    if let Some(writer) = writer {
+      writer.finish()?;
+    }
+    anyhow::Ok(())
+  });
 
   let id_gen = IdGen::new();
 
@@ -37,10 +72,12 @@ async fn work() -> anyhow::Result<(f64, f64)> {
   //   .await;
   let redis_client = redis::Client::open("redis://127.0.0.1:6379")?;
   let redis_conn: redis::aio::MultiplexedConnection = redis_client.get_multiplexed_async_connection().await?;
-  let mut ws_agent = RedisProxyFAAgent::new(config.ws_agent_uuid, id_gen, redis_conn, fallback);
+  let transport = RedisTransport::new(config.ws_agent_uuid, redis_conn).with_format(config.redis_format);
+  let mut ws_agent = RemoteFAAgent::new(id_gen, transport, fallback).with_time_control(time_control(&config).await?);
 
   // history.wait_for_ready().await;
   ws_agent.wait_for_ready().await;
+  ws_agent.handshake().await?;
 
   // TODO: wrap to fn
   let (num_players, players, agents, fyi_agents) = if rng.random_range(0..2) == 0 {
@@ -96,7 +133,12 @@ async fn work() -> anyhow::Result<(f64, f64)> {
   };
 
   let mut game = Game::new(num_players, players, agents, fyi_agents, rng, history).await;
-  let result = game.run().await;
+  if cfg!(debug_assertions) && config.check_obs_leaks {
+    game.enable_leak_checks();
+  }
+  let result = game.run().await?.result;
+  drop(game);
+  recorder.await??;
 
   Ok((result.0, result.1))
 }
@@ -0,0 +1,121 @@
+use clap::{Parser, Subcommand};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use server::domain::Camp;
+use server::{
+  AbstractFAAgent, AbstractFYIAgent, AgentSpec, Game, History, HistoryReqEvent, NoopFYIAgent, Player,
+  PlayerIndexedVec, RoleBook, load_history, replay_outcome,
+};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+const BATCH_SIZE: usize = 256;
+
+#[derive(Parser)]
+#[command(about = "Build a role-selection book from simulated or recorded games")]
+struct Cli {
+  #[command(subcommand)]
+  source: Source,
+  #[arg(long, default_value = "role_book.json")]
+  output: String,
+  /// Add to an existing book instead of starting from scratch
+  #[arg(long)]
+  merge: bool,
+}
+
+#[derive(Subcommand)]
+enum Source {
+  /// Recorded histories, JSONL if the file ends with `.jsonl`, otherwise the binary archive format
+  History { inputs: Vec<String> },
+  /// Simulate games with the same agent on every seat: random, heuristic[:<weights.toml>],
+  /// policy:<weights.json>[@temperature] or `exec:<command>`
+  Sim {
+    #[arg(long, default_value_t = 10000)]
+    games: usize,
+    #[arg(long, default_value = "random")]
+    agent: String,
+  },
+}
+
+async fn simulate(spec: AgentSpec) -> anyhow::Result<(Vec<HistoryReqEvent>, [f64; 2])> {
+  let mut rng = StdRng::seed_from_u64(rand::random());
+
+  let (history_req_bcast_sender, mut history_req_bcast_receiver) = mpsc::channel::<String>(1024);
+  let (_, history_resp_receiver) = mpsc::channel::<String>(1024);
+  let history = History::new(history_req_bcast_sender, history_resp_receiver);
+
+  let collector = tokio::spawn(async move {
+    let mut events = Vec::new();
+    while let Some(event) = history_req_bcast_receiver.recv().await {
+      events.push(serde_json::from_str::<HistoryReqEvent>(&event).unwrap());
+    }
+    events
+  });
+
+  let num_players = if rng.random_range(0..2) == 0 { 4 } else { 6 };
+  let names = ["刘邦", "项羽", "张良", "范增", "韩信", "龙且"];
+  let mut players = PlayerIndexedVec::<Player>::new();
+  let mut agents = PlayerIndexedVec::<Box<dyn AbstractFAAgent>>::new();
+  let mut fyi_agents = PlayerIndexedVec::<Box<dyn AbstractFYIAgent>>::new();
+  for (i, name) in names.iter().take(num_players).enumerate() {
+    if i % 2 == 0 {
+      players.push(Player::new_汉(uuid::Uuid::new_v4(), name.to_string()));
+    } else {
+      players.push(Player::new_楚(uuid::Uuid::new_v4(), name.to_string()));
+    }
+    agents.push(spec.agent(rng.random()).await?);
+    fyi_agents.push(Box::new(NoopFYIAgent::new()));
+  }
+
+  let mut game = Game::new(num_players, players, agents, fyi_agents, rng, history).await;
+  let (chu, han) = game.run().await?.result;
+  drop(game);
+
+  Ok((collector.await?, [chu, han]))
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+  let cli = Cli::parse();
+
+  let mut book = if cli.merge && std::path::Path::new(&cli.output).exists() {
+    RoleBook::load(&cli.output)?
+  } else {
+    RoleBook::new()
+  };
+
+  match cli.source {
+    Source::History { inputs } => {
+      for input in inputs.iter() {
+        let events = load_history(input)?;
+        let outcome = replay_outcome(&events);
+        book.add_game(&events, [outcome[0] as f64, outcome[1] as f64]);
+      }
+    },
+    Source::Sim { games, agent } => {
+      // 每个座位都是同一个 agent, 要能坐两个阵营
+      let spec = AgentSpec::parse(&agent)?;
+      spec.check_seat(1, Camp::楚)?;
+      // 分批模拟, 避免同时在内存里保存所有对局的历史
+      let mut remaining = games;
+      while remaining > 0 {
+        let batch = remaining.min(BATCH_SIZE);
+        let mut join_set = JoinSet::new();
+        for _ in 0..batch {
+          join_set.spawn(simulate(spec.clone()));
+        }
+        while let Some(result) = join_set.join_next().await {
+          let (events, outcome) = result??;
+          book.add_game(&events, outcome);
+        }
+        remaining -= batch;
+      }
+    },
+  }
+
+  book.save(&cli.output)?;
+  println!("games: {}", book.num_games());
+  println!("situations: {}", book.num_situations());
+
+  Ok(())
+}
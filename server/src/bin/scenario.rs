@@ -0,0 +1,111 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use anyhow::bail;
+use clap::Parser;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use server::{AbstractFAAgent, AbstractFYIAgent, AgentSpec, Game, History, NoopFYIAgent, PlayerIndexedVec, Scenario};
+use tokio::sync::mpsc;
+
+#[derive(Parser)]
+#[command(about = "Play games starting from a custom position described by a scenario file")]
+struct Cli {
+  /// Scenario file, JSON if it ends with `.json`, otherwise TOML
+  scenario: String,
+  /// Comma separated agent per seat: random, v2, heuristic[:<weights.toml>], policy:<weights.json>[@temperature]
+  /// or `exec:<command>` (a subprocess speaking JSON lines over stdio). A single agent is used for every seat
+  #[arg(long, default_value = "heuristic")]
+  agents: String,
+  #[arg(long, default_value_t = 1)]
+  games: usize,
+  /// Overrides the seed in the scenario file; game i uses seed + i
+  #[arg(long)]
+  seed: Option<u64>,
+  /// Record the history of a single game as JSONL
+  #[arg(long)]
+  history: Option<String>,
+}
+
+// 返回按座位号排列的得分, 以及 (楚, 汉) 的胜负
+async fn play(
+  scenario: &Scenario, specs: &[AgentSpec], seed: Option<u64>, history_path: Option<&str>,
+) -> anyhow::Result<(Vec<u32>, (f64, f64))> {
+  let (history_req_bcast_sender, mut history_req_bcast_receiver) = mpsc::channel::<String>(1024);
+  let (_, history_resp_receiver) = mpsc::channel::<String>(1024);
+  let history = History::new(history_req_bcast_sender, history_resp_receiver);
+
+  let mut writer = history_path.map(File::create).transpose()?.map(BufWriter::new);
+  let recorder = tokio::spawn(async move {
+    while let Some(event) = history_req_bcast_receiver.recv().await {
+      if let Some(writer) = writer.as_mut() {
+        writer.write_all(event.as_bytes())?;
+        writer.write_all(b"\n")?;
+      }
+    }
+    if let Some(mut writer) = writer {
+      writer.flush()?;
+    }
+    anyhow::Ok(())
+  });
+
+  let mut scenario = scenario.clone();
+  if seed.is_some() {
+    scenario.seed = seed;
+  }
+  let mut agents = PlayerIndexedVec::<Box<dyn AbstractFAAgent>>::new();
+  let mut fyi_agents = PlayerIndexedVec::<Box<dyn AbstractFYIAgent>>::new();
+  for i in 0..scenario.players.len() {
+    agents.push(specs[i % specs.len()].agent(rand::random()).await?);
+    fyi_agents.push(Box::new(NoopFYIAgent::new()));
+  }
+
+  let rng = StdRng::seed_from_u64(rand::random());
+  let mut game = Game::from_scenario(&scenario, agents, fyi_agents, rng, history)?;
+  let result = game.run().await?.result;
+  let scores = game.scores();
+  drop(game);
+  recorder.await??;
+
+  Ok((scores, result))
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+  let cli = Cli::parse();
+
+  let scenario = Scenario::load(&cli.scenario)?;
+  let specs = cli
+    .agents
+    .split(',')
+    .map(AgentSpec::parse)
+    .collect::<anyhow::Result<Vec<_>>>()?;
+  if specs.len() != 1 && specs.len() != scenario.players.len() {
+    bail!("expected 1 or {} agents, got {}", scenario.players.len(), specs.len());
+  }
+  for (i, player) in scenario.players.iter().enumerate() {
+    specs[i % specs.len()].check_seat(i, player.camp)?;
+  }
+  if cli.history.is_some() && cli.games != 1 {
+    bail!("--history records a single game, got --games {}", cli.games);
+  }
+
+  let mut win_rate = [0.0, 0.0];
+  for g in 0..cli.games {
+    let seed = cli.seed.map(|seed| seed + g as u64);
+    let (scores, (chu, han)) = play(&scenario, &specs, seed, cli.history.as_deref()).await?;
+    win_rate[0] += chu;
+    win_rate[1] += han;
+    if cli.games == 1 {
+      for (i, (player, score)) in scenario.players.iter().zip(scores).enumerate() {
+        println!("seat {} {} {}: {}", i, player.name, player.camp.name(), score);
+      }
+    }
+  }
+
+  println!("num_games: {}", cli.games);
+  println!("win rate 楚: {}", win_rate[0] / cli.games as f64);
+  println!("win rate 汉: {}", win_rate[1] / cli.games as f64);
+
+  Ok(())
+}
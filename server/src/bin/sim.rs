@@ -1,17 +1,34 @@
+use std::sync::Arc;
 use std::time::Instant;
 
+use clap::Parser;
 use indicatif::ProgressBar;
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
+use server::domain::Camp;
 use server::{
-  AbstractFAAgent, AbstractFYIAgent, Game, History, NoopFYIAgent, Player, PlayerIndexedVec, RandomFAAgent, V2FAAgent,
-  init_log,
+  AbstractFAAgent, AbstractFYIAgent, AgentSpec, Game, History, NoopFYIAgent, Player, PlayerIndexedVec, RandomFAAgent,
+  V2FAAgent, init_log,
 };
 use tokio::sync::mpsc;
 use tokio::task::JoinSet;
 use tracing::info;
 
-async fn work() -> (f64, f64) {
+#[derive(Parser)]
+#[command(about = "Simulate many games and report the win rate of each team")]
+struct Cli {
+  #[arg(long, default_value_t = 1000)]
+  games: u64,
+  /// Games played at the same time
+  #[arg(long, default_value_t = 1000)]
+  jobs: usize,
+  /// Agent of each seat, 4 or 6 of random, v2, heuristic[:<weights.toml>], policy:<weights.json>[@temperature]
+  /// or `exec:<command>` (a subprocess speaking JSON lines over stdio); even seats are 汉 and v2 only plays 汉. Defaults to v2 for 汉 and random for 楚 with 4 or 6 seats at random
+  #[arg(long, value_delimiter = ',')]
+  lineup: Vec<String>,
+}
+
+async fn work(lineup: Arc<Vec<AgentSpec>>) -> anyhow::Result<(f64, f64)> {
   let mut rng = StdRng::seed_from_u64(rand::random());
 
   let (history_req_bcast_sender, mut history_req_bcast_receiver) = mpsc::channel::<String>(1024);
@@ -25,6 +42,24 @@ async fn work() -> (f64, f64) {
     }
   });
 
+  if !lineup.is_empty() {
+    let names = ["刘邦", "项羽", "张良", "范增", "韩信", "龙且"];
+    let mut players = PlayerIndexedVec::<Player>::new();
+    let mut agents = PlayerIndexedVec::<Box<dyn AbstractFAAgent>>::new();
+    let mut fyi_agents = PlayerIndexedVec::<Box<dyn AbstractFYIAgent>>::new();
+    for (i, spec) in lineup.iter().enumerate() {
+      if i % 2 == 0 {
+        players.push(Player::new_汉(uuid::Uuid::new_v4(), names[i].to_string()));
+      } else {
+        players.push(Player::new_楚(uuid::Uuid::new_v4(), names[i].to_string()));
+      }
+      agents.push(spec.agent(rng.random()).await?);
+      fyi_agents.push(Box::new(NoopFYIAgent::new()));
+    }
+    let mut game = Game::new(lineup.len(), players, agents, fyi_agents, rng, history).await;
+    return Ok(game.run().await?.result);
+  }
+
   // TODO: wrap to fn
   let (num_players, players, agents, fyi_agents) = if rng.random_range(0..2) == 0 {
     let first = PlayerIndexedVec::from4(
@@ -79,30 +114,46 @@ async fn work() -> (f64, f64) {
   };
 
   let mut game = Game::new(num_players, players, agents, fyi_agents, rng, history).await;
-  let result = game.run().await;
-
-  (result.0, result.1)
+  Ok(game.run().await?.result)
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> anyhow::Result<()> {
+  let cli = Cli::parse();
   let _guard = init_log("sim");
 
+  if !cli.lineup.is_empty() && cli.lineup.len() != 4 && cli.lineup.len() != 6 {
+    anyhow::bail!("lineup must have 4 or 6 seats, got {}", cli.lineup.len());
+  }
+  let lineup = cli
+    .lineup
+    .iter()
+    .map(|spec| AgentSpec::parse(spec))
+    .collect::<anyhow::Result<Vec<_>>>()?;
+  for (i, spec) in lineup.iter().enumerate() {
+    spec.check_seat(i, if i % 2 == 0 { Camp::汉 } else { Camp::楚 })?;
+  }
+  let lineup = Arc::new(lineup);
+
   let mut win_rate = [0.0, 0.0];
 
-  let num_games = 1000;
+  let num_games = cli.games;
 
   let start = Instant::now();
 
   let pb = ProgressBar::new(num_games);
 
   let mut join_set = JoinSet::new();
-  for _ in 0..num_games {
-    join_set.spawn(work());
-  }
-
-  while let Some(result) = join_set.join_next().await {
-    let (win_rate_0, win_rate_1) = result.unwrap();
+  let mut spawned = 0;
+  while spawned < num_games || !join_set.is_empty() {
+    while spawned < num_games && join_set.len() < cli.jobs.max(1) {
+      join_set.spawn(work(lineup.clone()));
+      spawned += 1;
+    }
+    let Some(result) = join_set.join_next().await else {
+      break;
+    };
+    let (win_rate_0, win_rate_1) = result??;
     win_rate[0] += win_rate_0;
     win_rate[1] += win_rate_1;
 
@@ -114,4 +165,5 @@ async fn main() {
   println!("Time taken: {:?}", duration);
   println!("win rate 楚: {}", win_rate[0] / num_games as f64);
   println!("win rate 汉: {}", win_rate[1] / num_games as f64);
+  Ok(())
 }
@@ -0,0 +1,27 @@
+// 子进程 agent 的参考实现: 从 stdin 一行读一个 AgentReqEvent, 往 stdout 一行写一个 AgentRespEvent.
+// stdout 是协议通道, 日志只能写到 stderr, 服务端会把 stderr 收进对局日志
+
+use agent_sdk::{Endpoint, connect};
+use clap::Parser;
+use server::AgentSpec;
+
+#[derive(Parser)]
+#[command(about = "Play as a subprocess agent over stdin/stdout JSON lines")]
+struct Cli {
+  /// random, v2, heuristic[:<weights.toml>] or policy:<weights.json>[@temperature]
+  #[arg(long, default_value = "v2")]
+  agent: String,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+  tracing_subscriber::fmt()
+    .with_writer(std::io::stderr)
+    .with_ansi(false)
+    .init();
+  let cli = Cli::parse();
+  let mut agent = AgentSpec::parse(&cli.agent)?.local_agent(rand::random())?;
+
+  connect(Endpoint::Stdio).await?.run(agent.as_mut()).await?;
+  Ok(())
+}
@@ -0,0 +1,159 @@
+use anyhow::bail;
+use clap::Parser;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use server::domain::Camp;
+use server::{
+  AbstractFAAgent, AbstractFYIAgent, AgentSpec, Game, History, NoopFYIAgent, Player, PlayerIndexedVec, init_log,
+};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+#[derive(Parser)]
+#[command(about = "Round robin between agents: every pair plays both camps, then the standings are printed")]
+struct Cli {
+  /// Entrants: random, v2, heuristic[:<weights.toml>], policy:<weights.json>[@temperature] or `exec:<command>`
+  /// (a subprocess speaking JSON lines over stdio). v2 only plays 汉
+  #[arg(required = true, num_args = 2..)]
+  entrants: Vec<String>,
+  /// Games per pairing and camp
+  #[arg(long, default_value_t = 100)]
+  games: u64,
+  /// 4 or 6 seats; each entrant takes every seat of its camp
+  #[arg(long, default_value_t = 4)]
+  players: usize,
+  /// Game i of a pairing uses seed + i, so both camp assignments see the same deals
+  #[arg(long, default_value_t = 0)]
+  seed: u64,
+  /// Games played at the same time
+  #[arg(long, default_value_t = 64)]
+  jobs: usize,
+}
+
+// 汉坐偶数座位, 楚坐奇数座位. 返回 (楚, 汉) 的胜负
+async fn play(han: AgentSpec, chu: AgentSpec, num_players: usize, seed: u64) -> anyhow::Result<(f64, f64)> {
+  let (history_req_bcast_sender, mut history_req_bcast_receiver) = mpsc::channel::<String>(1024);
+  let (_, history_resp_receiver) = mpsc::channel::<String>(1024);
+  let history = History::new(history_req_bcast_sender, history_resp_receiver);
+  tokio::spawn(async move { while history_req_bcast_receiver.recv().await.is_some() {} });
+
+  let names = ["刘邦", "项羽", "张良", "范增", "韩信", "龙且"];
+  let mut players = PlayerIndexedVec::<Player>::new();
+  let mut agents = PlayerIndexedVec::<Box<dyn AbstractFAAgent>>::new();
+  let mut fyi_agents = PlayerIndexedVec::<Box<dyn AbstractFYIAgent>>::new();
+  for (i, name) in names.iter().enumerate().take(num_players) {
+    let (camp, spec) = if i.is_multiple_of(2) {
+      (Camp::汉, &han)
+    } else {
+      (Camp::楚, &chu)
+    };
+    players.push(Player::new(uuid::Uuid::new_v4(), name.to_string(), camp));
+    agents.push(spec.agent(seed ^ ((i as u64 + 1) << 48)).await?);
+    fyi_agents.push(Box::new(NoopFYIAgent::new()));
+  }
+
+  let rng = StdRng::seed_from_u64(seed);
+  let mut game = Game::new(num_players, players, agents, fyi_agents, rng, history).await;
+  Ok(game.run().await?.result)
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+  let cli = Cli::parse();
+  let _guard = init_log("tournament");
+
+  if cli.players != 4 && cli.players != 6 {
+    bail!("--players must be 4 or 6, got {}", cli.players);
+  }
+  let specs = cli
+    .entrants
+    .iter()
+    .map(|spec| AgentSpec::parse(spec))
+    .collect::<anyhow::Result<Vec<_>>>()?;
+
+  // 每一对选手在两个阵营各打 games 局; v2 不能坐楚的座位, 这样的组合跳过
+  let mut pairings = Vec::new();
+  for han in 0..specs.len() {
+    for (chu, spec) in specs.iter().enumerate() {
+      if han == chu {
+        continue;
+      }
+      if !spec.supports(Camp::楚) {
+        println!(
+          "skip {} (汉) vs {} (楚): v2 only plays 汉",
+          cli.entrants[han], cli.entrants[chu]
+        );
+        continue;
+      }
+      pairings.push((han, chu));
+    }
+  }
+
+  // wins[a][b]: a 对 b 赢的局数 (平局各算半局), games[a][b]: a 对 b 打的局数
+  let mut wins = vec![vec![0.0; specs.len()]; specs.len()];
+  let mut games = vec![vec![0u64; specs.len()]; specs.len()];
+  let mut join_set = JoinSet::new();
+  let mut queue = pairings
+    .iter()
+    .flat_map(|&(han, chu)| (0..cli.games).map(move |g| (han, chu, cli.seed + g)));
+  loop {
+    while join_set.len() < cli.jobs.max(1) {
+      let Some((han, chu, seed)) = queue.next() else {
+        break;
+      };
+      let (han_spec, chu_spec) = (specs[han].clone(), specs[chu].clone());
+      join_set.spawn(async move { (han, chu, play(han_spec, chu_spec, cli.players, seed).await) });
+    }
+    let Some(result) = join_set.join_next().await else {
+      break;
+    };
+    let (han, chu, result) = result?;
+    let (chu_result, han_result) = result?;
+    wins[han][chu] += han_result;
+    wins[chu][han] += chu_result;
+    games[han][chu] += 1;
+    games[chu][han] += 1;
+  }
+
+  let mut standings = (0..specs.len())
+    .map(|a| {
+      let won: f64 = wins[a].iter().sum();
+      let played: u64 = games[a].iter().sum();
+      (a, won, played)
+    })
+    .collect::<Vec<_>>();
+  standings.sort_by(|x, y| {
+    let rate = |(_, won, played): &(usize, f64, u64)| won / (*played).max(1) as f64;
+    rate(y).total_cmp(&rate(x))
+  });
+
+  println!(
+    "{:<4} {:<40} {:>8} {:>8} {:>8}",
+    "rank", "entrant", "games", "wins", "win rate"
+  );
+  for (rank, (a, won, played)) in standings.iter().enumerate() {
+    let rate = won / (*played).max(1) as f64;
+    println!(
+      "{:<4} {:<40} {:>8} {:>8} {:>8.3}",
+      rank + 1,
+      cli.entrants[*a],
+      played,
+      won,
+      rate
+    );
+  }
+
+  println!();
+  println!("win rate of the row entrant against the column entrant:");
+  for a in 0..specs.len() {
+    let row = (0..specs.len())
+      .map(|b| match games[a][b] {
+        0 => format!("{:>8}", "-"),
+        played => format!("{:>8.3}", wins[a][b] / played as f64),
+      })
+      .collect::<String>();
+    println!("{:<40}{}", cli.entrants[a], row);
+  }
+
+  Ok(())
+}
@@ -0,0 +1,209 @@
+use std::path::Path;
+use std::time::Instant;
+
+use clap::Parser;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use server::domain::Camp;
+use server::{
+  AbstractFAAgent, AbstractFYIAgent, AgentSpec, Game, HeuristicFAAgent, HeuristicWeights, History, NoopFYIAgent,
+  Player, PlayerIndexedVec,
+};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+#[derive(Parser)]
+#[command(about = "Tune HeuristicFAAgent weights with a (mu, lambda) evolution strategy over simulated games")]
+struct Cli {
+  /// Initial weights, defaults to HeuristicWeights::default()
+  #[arg(long)]
+  init: Option<String>,
+  /// Where the best weights found so far are written after every generation
+  #[arg(long, default_value = "heuristic.toml")]
+  output: String,
+  /// Progress checkpoint, resumed from automatically if it exists
+  #[arg(long, default_value = "tune_checkpoint.json")]
+  checkpoint: String,
+  /// random, heuristic[:<weights.toml>], policy:<weights.json>[@temperature] or `exec:<command>`; plays the 楚 seats
+  #[arg(long, default_value = "heuristic")]
+  opponent: String,
+  #[arg(long, default_value_t = 50)]
+  generations: usize,
+  /// Candidates per generation
+  #[arg(long, default_value_t = 16)]
+  population: usize,
+  /// Simulated games per candidate
+  #[arg(long, default_value_t = 200)]
+  games: usize,
+  #[arg(long, default_value_t = 0.5)]
+  sigma: f64,
+  #[arg(long, default_value_t = 0.97)]
+  sigma_decay: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+  generation: usize,
+  mean: Vec<f64>,
+  sigma: f64,
+  best: Vec<f64>,
+  best_fitness: f64,
+}
+
+impl Checkpoint {
+  fn load(path: &str) -> anyhow::Result<Self> {
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+  }
+
+  // 先写临时文件再 rename, 中途被杀掉也不会留下半个 checkpoint
+  fn save(&self, path: &str) -> anyhow::Result<()> {
+    let tmp = format!("{}.tmp", path);
+    std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+  }
+}
+
+// 候选参数坐汉的座位, 返回汉的得分 (胜 1, 平 0.5, 负 0)
+async fn play(seed: u64, weights: HeuristicWeights, opponent: AgentSpec) -> anyhow::Result<f64> {
+  let mut rng = StdRng::seed_from_u64(seed);
+
+  let (history_req_bcast_sender, mut history_req_bcast_receiver) = mpsc::channel::<String>(1024);
+  let (_, history_resp_receiver) = mpsc::channel::<String>(1024);
+  let history = History::new(history_req_bcast_sender, history_resp_receiver);
+  tokio::spawn(async move { while history_req_bcast_receiver.recv().await.is_some() {} });
+
+  let num_players = if rng.random_range(0..2) == 0 { 4 } else { 6 };
+  let names = ["刘邦", "项羽", "张良", "范增", "韩信", "龙且"];
+  let mut players = PlayerIndexedVec::<Player>::new();
+  let mut agents = PlayerIndexedVec::<Box<dyn AbstractFAAgent>>::new();
+  let mut fyi_agents = PlayerIndexedVec::<Box<dyn AbstractFYIAgent>>::new();
+  for (i, name) in names.iter().take(num_players).enumerate() {
+    if i % 2 == 0 {
+      players.push(Player::new_汉(uuid::Uuid::new_v4(), name.to_string()));
+      agents.push(Box::new(HeuristicFAAgent::new(weights.clone())));
+    } else {
+      players.push(Player::new_楚(uuid::Uuid::new_v4(), name.to_string()));
+      agents.push(opponent.agent(rng.random()).await?);
+    }
+    fyi_agents.push(Box::new(NoopFYIAgent::new()));
+  }
+
+  let mut game = Game::new(num_players, players, agents, fyi_agents, rng, history).await;
+  let (_, han) = game.run().await?.result;
+  Ok(han)
+}
+
+// 同一代的候选使用相同的种子, 减少比较时的噪声
+async fn evaluate(
+  candidates: &[Vec<f64>], games: usize, generation: usize, opponent: &AgentSpec,
+) -> anyhow::Result<Vec<f64>> {
+  let mut join_set = JoinSet::new();
+  for (i, candidate) in candidates.iter().enumerate() {
+    for g in 0..games {
+      let seed = (generation * games + g) as u64;
+      let weights = HeuristicWeights::from_vec(candidate);
+      let opponent = opponent.clone();
+      join_set.spawn(async move { (i, play(seed, weights, opponent).await) });
+    }
+  }
+
+  let mut fitness = vec![0.0; candidates.len()];
+  while let Some(result) = join_set.join_next().await {
+    let (i, score) = result?;
+    fitness[i] += score? / games as f64;
+  }
+  Ok(fitness)
+}
+
+fn gaussian(rng: &mut StdRng) -> f64 {
+  // Box-Muller
+  let u1: f64 = rng.random_range(f64::EPSILON..1.0);
+  let u2: f64 = rng.random_range(0.0..1.0);
+  (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+  let cli = Cli::parse();
+
+  let opponent = AgentSpec::parse(&cli.opponent)?;
+  opponent.check_seat(1, Camp::楚)?;
+
+  let mut checkpoint = if Path::new(&cli.checkpoint).exists() {
+    let checkpoint = Checkpoint::load(&cli.checkpoint)?;
+    if checkpoint.mean.len() != HeuristicWeights::DIM {
+      anyhow::bail!(
+        "checkpoint has {} weights, expected {}",
+        checkpoint.mean.len(),
+        HeuristicWeights::DIM
+      );
+    }
+    println!(
+      "resume from generation {}, best fitness {:.4}",
+      checkpoint.generation, checkpoint.best_fitness
+    );
+    checkpoint
+  } else {
+    let init = match &cli.init {
+      Some(path) => HeuristicWeights::load(path)?,
+      None => HeuristicWeights::default(),
+    };
+    Checkpoint {
+      generation: 0,
+      mean: init.to_vec(),
+      sigma: cli.sigma,
+      best: init.to_vec(),
+      best_fitness: f64::MIN,
+    }
+  };
+
+  let mu = (cli.population / 2).max(1);
+  while checkpoint.generation < cli.generations {
+    let start = Instant::now();
+    let mut rng = StdRng::seed_from_u64(checkpoint.generation as u64);
+
+    // 第一个候选是当前均值本身
+    let mut candidates = vec![checkpoint.mean.clone()];
+    while candidates.len() < cli.population {
+      let candidate = checkpoint
+        .mean
+        .iter()
+        .map(|m| m + checkpoint.sigma * gaussian(&mut rng))
+        .collect::<Vec<_>>();
+      candidates.push(candidate);
+    }
+
+    let fitness = evaluate(&candidates, cli.games, checkpoint.generation, &opponent).await?;
+    let mut order = (0..candidates.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| fitness[b].total_cmp(&fitness[a]));
+
+    let dim = checkpoint.mean.len();
+    checkpoint.mean = (0..dim)
+      .map(|d| order[..mu].iter().map(|&i| candidates[i][d]).sum::<f64>() / mu as f64)
+      .collect();
+    if fitness[order[0]] > checkpoint.best_fitness {
+      checkpoint.best_fitness = fitness[order[0]];
+      checkpoint.best = candidates[order[0]].clone();
+    }
+    checkpoint.sigma *= cli.sigma_decay;
+    checkpoint.generation += 1;
+
+    HeuristicWeights::from_vec(&checkpoint.best).save(&cli.output)?;
+    checkpoint.save(&cli.checkpoint)?;
+
+    println!(
+      "generation {}: top {:.4}, mean of top {} {:.4}, best {:.4}, sigma {:.4}, {:?}",
+      checkpoint.generation,
+      fitness[order[0]],
+      mu,
+      order[..mu].iter().map(|&i| fitness[i]).sum::<f64>() / mu as f64,
+      checkpoint.best_fitness,
+      checkpoint.sigma,
+      start.elapsed(),
+    );
+  }
+
+  Ok(())
+}
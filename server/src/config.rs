@@ -1,12 +1,25 @@
 use serde::Deserialize;
 use uuid::Uuid;
 
+use crate::time_control::TimeControl;
+use crate::wire_format::WireFormat;
+
 #[derive(Deserialize)]
 struct RawConfig {
   history_uuid: String,
   ws_agent_uuid: String,
   host: String,
   port: u16,
+  #[serde(default)]
+  check_obs_leaks: bool,
+  #[serde(default)]
+  time_control: TimeControl,
+  #[serde(default)]
+  room_id: Option<String>,
+  #[serde(default)]
+  redis_format: WireFormat,
+  #[serde(default)]
+  history_archive: Option<String>,
 }
 
 impl RawConfig {
@@ -21,6 +34,16 @@ pub struct Config {
   pub ws_agent_uuid: Uuid,
   pub host: String,
   pub port: u16,
+  // 只在 debug 构建中生效, 见 Game::enable_leak_checks
+  pub check_obs_leaks: bool,
+  // 远程 agent 的决策时限, 不写为不限
+  pub time_control: TimeControl,
+  // 对局所在的房间, 写了就用房间设置的决策时限代替 time_control
+  pub room_id: Option<String>,
+  // Redis 队列上 agent 请求和回复的编码, 默认 JSON
+  pub redis_format: WireFormat,
+  // 对局的历史记录 (含赛后报告) 写入的归档文件, 不写为不保存
+  pub history_archive: Option<String>,
 }
 
 impl Config {
@@ -31,6 +54,11 @@ impl Config {
       ws_agent_uuid: Uuid::parse_str(&raw_config.ws_agent_uuid)?,
       host: raw_config.host,
       port: raw_config.port,
+      check_obs_leaks: raw_config.check_obs_leaks,
+      time_control: raw_config.time_control,
+      room_id: raw_config.room_id,
+      redis_format: raw_config.redis_format,
+      history_archive: raw_config.history_archive,
     })
   }
 }
@@ -0,0 +1,640 @@
+// 把对局历史转成监督学习样本: 每个决策一条, 包含 Obs 的定长特征, 合法动作 mask, 实际动作, 以及决策者所在队伍的最终胜负.
+//
+// 动作空间是所有决策类型共用的一个定长离散空间, 每一维的含义见 action_names().
+// 输出为 CSV 或 NPY 分片, 以及描述每一维特征和动作的 schema.json.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use serde::Serialize;
+use strum::{EnumCount, IntoEnumIterator};
+use uuid::Uuid;
+
+use crate::agent_protocol::DecisionKind;
+use crate::domain::{Camp, Card, DestroyTarget, MagicianSkill, Oper, PlayerIndex, PlayerOffset, Role, RoleSet};
+use crate::history::HistoryReqEvent;
+use crate::history_view::infer_camps;
+use crate::obs::{MAX_PLAYERS, Obs};
+use crate::player::Player;
+
+const NUM_ROLES: usize = 8;
+
+const CARD_BASE: usize = 0;
+const ROLE_BASE: usize = CARD_BASE + Card::COUNT;
+// 放弃, 制衡, 和 offset 为 1..MAX_PLAYERS 的玩家换牌
+const MAGIC_BASE: usize = ROLE_BASE + NUM_ROLES;
+// 不拆, 和 offset 为 0..MAX_PLAYERS 的玩家的每种建筑
+const DESTROY_BASE: usize = MAGIC_BASE + 1 + MAX_PLAYERS;
+const TOMB_BASE: usize = DESTROY_BASE + 1 + MAX_PLAYERS * Card::COUNT;
+// EndRound, Card3Choose1, Card2Choose1, Card2Choose2, Gold, BuyCard, Build(card), SellCard(card)
+const OPER_BASE: usize = TOMB_BASE + 2;
+pub const NUM_ACTIONS: usize = OPER_BASE + 6 + 2 * Card::COUNT;
+
+pub struct Sample {
+  pub game: usize,
+  pub seat: PlayerIndex,
+  pub kind: DecisionKind,
+  pub features: Vec<f32>,
+  pub mask: Vec<bool>,
+  pub action: usize,
+  pub outcome: f32,
+}
+
+// 牌在 Card::iter() 中的位置, 0..Card::COUNT. 按牌统计的数组都用它做下标
+pub(crate) fn card_index(card: Card) -> usize {
+  Card::iter().position(|c| c == card).unwrap()
+}
+
+pub(crate) fn card_action(card: Card) -> usize {
+  CARD_BASE + card_index(card)
+}
+
+pub(crate) fn role_action(role: Role) -> usize {
+  ROLE_BASE + Role::population().iter().position(|&r| r == role).unwrap()
+}
+
+pub(crate) fn magic_action(skill: &MagicianSkill) -> usize {
+  match skill {
+    MagicianSkill::放弃 => MAGIC_BASE,
+    MagicianSkill::制衡(_) => MAGIC_BASE + 1,
+    MagicianSkill::Swap(offset) => MAGIC_BASE + 1 + offset.value(),
+  }
+}
+
+pub(crate) fn destroy_action(target: Option<(PlayerOffset, Card)>) -> usize {
+  match target {
+    None => DESTROY_BASE,
+    Some((offset, card)) => DESTROY_BASE + 1 + offset.value() * Card::COUNT + card_action(card),
+  }
+}
+
+pub(crate) fn tomb_action(chosen: bool) -> usize {
+  TOMB_BASE + chosen as usize
+}
+
+pub(crate) fn oper_action(oper: Oper) -> usize {
+  match oper {
+    Oper::EndRound => OPER_BASE,
+    Oper::Card3Choose1 => OPER_BASE + 1,
+    Oper::Card2Choose1 => OPER_BASE + 2,
+    Oper::Card2Choose2 => OPER_BASE + 3,
+    Oper::Gold(_) => OPER_BASE + 4,
+    Oper::BuyCard => OPER_BASE + 5,
+    Oper::Build(card) => OPER_BASE + 6 + card_action(card),
+    Oper::SellCard(card) => OPER_BASE + 6 + Card::COUNT + card_action(card),
+  }
+}
+
+pub fn action_names() -> Vec<String> {
+  let mut names = Vec::with_capacity(NUM_ACTIONS);
+  for c in Card::iter() {
+    names.push(format!("card.{}", c.name()));
+  }
+  for r in Role::population() {
+    names.push(format!("role.{}", r.name()));
+  }
+  names.push("magic.放弃".to_string());
+  names.push("magic.制衡".to_string());
+  for i in 1..MAX_PLAYERS {
+    names.push(format!("magic.swap.offset{}", i));
+  }
+  names.push("destroy.none".to_string());
+  for i in 0..MAX_PLAYERS {
+    for c in Card::iter() {
+      names.push(format!("destroy.offset{}.{}", i, c.name()));
+    }
+  }
+  names.push("tomb.no".to_string());
+  names.push("tomb.yes".to_string());
+  for oper in [
+    "EndRound",
+    "Card3Choose1",
+    "Card2Choose1",
+    "Card2Choose2",
+    "Gold",
+    "BuyCard",
+  ] {
+    names.push(format!("oper.{}", oper));
+  }
+  for c in Card::iter() {
+    names.push(format!("oper.build.{}", c.name()));
+  }
+  for c in Card::iter() {
+    names.push(format!("oper.sell.{}", c.name()));
+  }
+  assert_eq!(names.len(), NUM_ACTIONS);
+  names
+}
+
+pub(crate) fn mask_of(actions: impl IntoIterator<Item = usize>) -> Vec<bool> {
+  let mut mask = vec![false; NUM_ACTIONS];
+  for action in actions {
+    mask[action] = true;
+  }
+  mask
+}
+
+// 以下 *_candidates 给出每种决策的合法动作和对应的选择, 供直接在动作空间上决策的 agent 使用
+
+pub(crate) fn card_candidates(cards: &[Card]) -> Vec<(usize, Card)> {
+  cards.iter().map(|&c| (card_action(c), c)).collect()
+}
+
+pub(crate) fn role_candidates(roles: RoleSet) -> Vec<(usize, Role)> {
+  Role::population()
+    .into_iter()
+    .filter(|&r| roles.contains(r))
+    .map(|r| (role_action(r), r))
+    .collect()
+}
+
+// 动作空间里制衡不区分弃哪些牌, 选中制衡时换掉全部手牌
+pub(crate) fn magic_candidates(obs: &Obs) -> Vec<(usize, MagicianSkill)> {
+  let mut candidates = vec![(magic_action(&MagicianSkill::放弃), MagicianSkill::放弃)];
+  if obs.actor_num_cards() > 0 {
+    let cards = (0..obs.actor_num_cards())
+      .map(|i| obs.hero_card_at(i))
+      .collect::<Vec<_>>();
+    let skill = MagicianSkill::制衡(cards);
+    candidates.push((magic_action(&skill), skill));
+  }
+  for i in 1..obs.num_players() {
+    let skill = MagicianSkill::Swap(PlayerOffset::from_usize(i));
+    candidates.push((magic_action(&skill), skill));
+  }
+  candidates
+}
+
+pub(crate) fn destroy_candidates(choices: &[DestroyTarget]) -> Vec<(usize, Option<DestroyTarget>)> {
+  let mut candidates = vec![(destroy_action(None), None)];
+  for &t in choices {
+    candidates.push((destroy_action(Some((t.player_offset, t.card))), Some(t)));
+  }
+  candidates
+}
+
+pub(crate) fn tomb_candidates() -> Vec<(usize, bool)> {
+  vec![(tomb_action(false), false), (tomb_action(true), true)]
+}
+
+pub(crate) fn oper_candidates(choices: &[Oper]) -> Vec<(usize, Oper)> {
+  choices.iter().map(|&oper| (oper_action(oper), oper)).collect()
+}
+
+pub(crate) fn candidates_mask<T>(candidates: &[(usize, T)]) -> Vec<bool> {
+  mask_of(candidates.iter().map(|(action, _)| *action))
+}
+
+// 得到每个阵营的最终胜负, 下标为 Camp as usize. 有 GameReport 时直接取, 旧的记录重放建筑后用 Player::score 计分
+pub fn replay_outcome(events: &[HistoryReqEvent]) -> [f32; 2] {
+  for event in events.iter().rev() {
+    if let HistoryReqEvent::GameReport { report, .. } = event {
+      let mut outcome = [0.0; 2];
+      for team in report.teams.iter() {
+        outcome[team.camp as usize] = team.result as f32;
+      }
+      return outcome;
+    }
+  }
+
+  let mut total_score = [0, 0];
+  for player in replay_players(events, &infer_camps(events)) {
+    total_score[player.camp() as usize] += player.score();
+  }
+
+  match total_score[Camp::楚 as usize].cmp(&total_score[Camp::汉 as usize]) {
+    std::cmp::Ordering::Greater => [1.0, 0.0],
+    std::cmp::Ordering::Less => [0.0, 1.0],
+    std::cmp::Ordering::Equal => [0.5, 0.5],
+  }
+}
+
+// 从历史记录重放出每个座位的最终建筑, 计分直接用 Player::score
+pub(crate) fn replay_players(events: &[HistoryReqEvent], camps: &[Camp]) -> Vec<Player> {
+  let mut players = camps
+    .iter()
+    .map(|&camp| Player::new(Uuid::nil(), String::new(), camp))
+    .collect::<Vec<_>>();
+  let mut final_round = None;
+  for event in events {
+    match event {
+      HistoryReqEvent::LoadScenario { players: seats, .. } => {
+        for (player, seat) in players.iter_mut().zip(seats) {
+          seat.buildings.iter().for_each(|&card| player.add_building(card));
+        }
+      },
+      HistoryReqEvent::StartRound { round, .. } => final_round = Some(*round),
+      HistoryReqEvent::Build { actor, card, round, .. } => players[actor.value()].place_building(*card, *round),
+      HistoryReqEvent::DestroyResp {
+        chosen_index: Some(index),
+        chosen_card: Some(card),
+        ..
+      } => players[index.value()].remove_building(*card),
+      HistoryReqEvent::First8Buildings { actor, .. } => players[actor.value()].set_is_first_8_buildings(),
+      _ => {},
+    }
+  }
+  if let Some(round) = final_round {
+    players.iter_mut().for_each(|player| player.set_final_round(round));
+  }
+  players
+}
+
+struct PendingDecision {
+  actor: PlayerIndex,
+  kind: DecisionKind,
+  features: Vec<f32>,
+  mask: Vec<bool>,
+}
+
+// outcome 下标为 Camp as usize, 模拟的对局取 Game::run 的结果, 录制的对局可以用 replay_outcome
+pub fn extract_samples(game: usize, events: &[HistoryReqEvent], outcome: [f32; 2]) -> Vec<Sample> {
+  let camps = infer_camps(events);
+  let num_players = camps.len();
+  let mut pending = HashMap::<u32, PendingDecision>::new();
+  let mut samples = Vec::new();
+
+  let mut push = |decision: PendingDecision, action: usize| {
+    debug_assert!(decision.mask[action]);
+    samples.push(Sample {
+      game,
+      seat: decision.actor,
+      kind: decision.kind,
+      features: decision.features,
+      mask: decision.mask,
+      action,
+      outcome: outcome[camps[decision.actor.value()] as usize],
+    });
+  };
+
+  for event in events {
+    let (id, actor, kind, obs, mask) = match event {
+      HistoryReqEvent::InitCardReq { id, actor, obs, c0, c1 } => (
+        id,
+        actor,
+        DecisionKind::InitCard,
+        obs,
+        candidates_mask(&card_candidates(&[*c0, *c1])),
+      ),
+      HistoryReqEvent::KillReq {
+        id,
+        actor,
+        obs,
+        choices,
+      } => (
+        id,
+        actor,
+        DecisionKind::Kill,
+        obs,
+        candidates_mask(&role_candidates(*choices)),
+      ),
+      HistoryReqEvent::StealReq {
+        id,
+        actor,
+        obs,
+        choices,
+      } => (
+        id,
+        actor,
+        DecisionKind::Steal,
+        obs,
+        candidates_mask(&role_candidates(*choices)),
+      ),
+      HistoryReqEvent::MagicReq { id, actor, obs } => (
+        id,
+        actor,
+        DecisionKind::Magic,
+        obs,
+        candidates_mask(&magic_candidates(obs)),
+      ),
+      HistoryReqEvent::DestroyReq {
+        id,
+        actor,
+        obs,
+        choices,
+      } => {
+        let mask = candidates_mask(&destroy_candidates(choices));
+        (id, actor, DecisionKind::Destroy, obs, mask)
+      },
+      HistoryReqEvent::TombReq { id, actor, obs, .. } => {
+        (id, actor, DecisionKind::Tomb, obs, candidates_mask(&tomb_candidates()))
+      },
+      HistoryReqEvent::OperReq {
+        id,
+        actor,
+        obs,
+        choices,
+      } => (
+        id,
+        actor,
+        DecisionKind::Oper,
+        obs,
+        candidates_mask(&oper_candidates(choices)),
+      ),
+      HistoryReqEvent::ChooseFrom2Req { id, actor, obs, c0, c1 } => (
+        id,
+        actor,
+        DecisionKind::ChooseFrom2,
+        obs,
+        candidates_mask(&card_candidates(&[*c0, *c1])),
+      ),
+      HistoryReqEvent::ChooseFrom3Req {
+        id,
+        actor,
+        obs,
+        c0,
+        c1,
+        c2,
+      } => (
+        id,
+        actor,
+        DecisionKind::ChooseFrom3,
+        obs,
+        candidates_mask(&card_candidates(&[*c0, *c1, *c2])),
+      ),
+      // ChooseRoleResp 自带 obs 和 choices, 不需要和 ChooseRoleReq 配对
+      HistoryReqEvent::ChooseRoleResp {
+        actor,
+        obs,
+        choices,
+        chosen,
+        ..
+      } => {
+        let decision = PendingDecision {
+          actor: *actor,
+          kind: DecisionKind::ChooseRole,
+          features: obs.features(),
+          mask: candidates_mask(&role_candidates(*choices)),
+        };
+        push(decision, role_action(*chosen));
+        continue;
+      },
+      HistoryReqEvent::InitCardResp { req_id, chosen, .. }
+      | HistoryReqEvent::ChooseFrom2Resp { req_id, chosen, .. }
+      | HistoryReqEvent::ChooseFrom3Resp { req_id, chosen, .. } => {
+        if let Some(decision) = pending.remove(req_id) {
+          push(decision, card_action(*chosen));
+        }
+        continue;
+      },
+      HistoryReqEvent::KillResp { req_id, chosen, .. } | HistoryReqEvent::StealResp { req_id, chosen, .. } => {
+        if let Some(decision) = pending.remove(req_id) {
+          push(decision, role_action(*chosen));
+        }
+        continue;
+      },
+      HistoryReqEvent::MagicResp { req_id, chosen, .. } => {
+        if let Some(decision) = pending.remove(req_id) {
+          push(decision, magic_action(chosen));
+        }
+        continue;
+      },
+      HistoryReqEvent::DestroyResp {
+        req_id,
+        chosen_index,
+        chosen_card,
+        ..
+      } => {
+        if let Some(decision) = pending.remove(req_id) {
+          let target = chosen_index
+            .zip(*chosen_card)
+            .map(|(index, card)| (PlayerOffset::from_index(index, decision.actor, num_players), card));
+          push(decision, destroy_action(target));
+        }
+        continue;
+      },
+      HistoryReqEvent::TombResp { req_id, chosen, .. } => {
+        if let Some(decision) = pending.remove(req_id) {
+          push(decision, tomb_action(*chosen));
+        }
+        continue;
+      },
+      HistoryReqEvent::OperResp { req_id, chosen, .. } => {
+        if let Some(decision) = pending.remove(req_id) {
+          push(decision, oper_action(*chosen));
+        }
+        continue;
+      },
+      _ => continue,
+    };
+    pending.insert(
+      *id,
+      PendingDecision {
+        actor: *actor,
+        kind,
+        features: obs.features(),
+        mask,
+      },
+    );
+  }
+
+  samples
+}
+
+fn first_obs(events: &[HistoryReqEvent]) -> Option<&Obs> {
+  events.iter().find_map(|event| match event {
+    HistoryReqEvent::InitCardReq { obs, .. } | HistoryReqEvent::ChooseRoleReq { obs, .. } => Some(obs),
+    _ => None,
+  })
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum DatasetFormat {
+  Csv,
+  Npy,
+}
+
+#[derive(Serialize)]
+struct Schema {
+  format: &'static str,
+  num_features: usize,
+  features: Vec<String>,
+  num_actions: usize,
+  actions: Vec<String>,
+  kinds: Vec<DecisionKind>,
+  outcome: &'static str,
+  files: Vec<&'static str>,
+}
+
+pub struct DatasetWriter {
+  out_dir: PathBuf,
+  format: DatasetFormat,
+  shard_size: usize,
+  buffer: Vec<Sample>,
+  num_shards: usize,
+  num_samples: usize,
+  has_schema: bool,
+}
+
+impl DatasetWriter {
+  pub fn new(out_dir: impl Into<PathBuf>, format: DatasetFormat, shard_size: usize) -> anyhow::Result<Self> {
+    let out_dir = out_dir.into();
+    std::fs::create_dir_all(&out_dir)?;
+    Ok(Self {
+      out_dir,
+      format,
+      shard_size,
+      buffer: Vec::new(),
+      num_shards: 0,
+      num_samples: 0,
+      has_schema: false,
+    })
+  }
+
+  pub fn write_game(&mut self, game: usize, events: &[HistoryReqEvent], outcome: [f32; 2]) -> anyhow::Result<()> {
+    if !self.has_schema
+      && let Some(obs) = first_obs(events)
+    {
+      self.write_schema(obs)?;
+    }
+    for sample in extract_samples(game, events, outcome) {
+      self.buffer.push(sample);
+      if self.buffer.len() >= self.shard_size {
+        self.flush_shard()?;
+      }
+    }
+    Ok(())
+  }
+
+  // 返回 (样本数, 分片数)
+  pub fn finish(mut self) -> anyhow::Result<(usize, usize)> {
+    if !self.buffer.is_empty() {
+      self.flush_shard()?;
+    }
+    Ok((self.num_samples, self.num_shards))
+  }
+
+  fn write_schema(&mut self, obs: &Obs) -> anyhow::Result<()> {
+    let features = obs.feature_names();
+    let (format, files) = match self.format {
+      DatasetFormat::Csv => ("csv", vec!["shard-NNNNN.csv"]),
+      DatasetFormat::Npy => (
+        "npy",
+        vec![
+          "shard-NNNNN.game.npy",
+          "shard-NNNNN.seat.npy",
+          "shard-NNNNN.kind.npy",
+          "shard-NNNNN.features.npy",
+          "shard-NNNNN.mask.npy",
+          "shard-NNNNN.action.npy",
+          "shard-NNNNN.outcome.npy",
+        ],
+      ),
+    };
+    let schema = Schema {
+      format,
+      num_features: features.len(),
+      features,
+      num_actions: NUM_ACTIONS,
+      actions: action_names(),
+      kinds: DecisionKind::all().to_vec(),
+      outcome: "final result of the actor's team: 1 win, 0.5 draw, 0 loss",
+      files,
+    };
+    let writer = BufWriter::new(File::create(self.out_dir.join("schema.json"))?);
+    serde_json::to_writer_pretty(writer, &schema)?;
+    self.has_schema = true;
+    Ok(())
+  }
+
+  fn flush_shard(&mut self) -> anyhow::Result<()> {
+    let samples = std::mem::take(&mut self.buffer);
+    let name = format!("shard-{:05}", self.num_shards);
+    match self.format {
+      DatasetFormat::Csv => self.write_csv(&name, &samples)?,
+      DatasetFormat::Npy => self.write_npy(&name, &samples)?,
+    }
+    self.num_shards += 1;
+    self.num_samples += samples.len();
+    Ok(())
+  }
+
+  fn write_csv(&self, name: &str, samples: &[Sample]) -> anyhow::Result<()> {
+    let mut writer = BufWriter::new(File::create(self.out_dir.join(format!("{}.csv", name)))?);
+    let num_features = samples[0].features.len();
+    write!(writer, "game,seat,kind,action,outcome")?;
+    for i in 0..num_features {
+      write!(writer, ",f{}", i)?;
+    }
+    for i in 0..NUM_ACTIONS {
+      write!(writer, ",m{}", i)?;
+    }
+    writeln!(writer)?;
+    for sample in samples {
+      write!(
+        writer,
+        "{},{},{:?},{},{}",
+        sample.game,
+        sample.seat.value(),
+        sample.kind,
+        sample.action,
+        sample.outcome
+      )?;
+      for value in sample.features.iter() {
+        write!(writer, ",{}", value)?;
+      }
+      for &legal in sample.mask.iter() {
+        write!(writer, ",{}", legal as u8)?;
+      }
+      writeln!(writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+  }
+
+  fn write_npy(&self, name: &str, samples: &[Sample]) -> anyhow::Result<()> {
+    let n = samples.len();
+    let num_features = samples[0].features.len();
+    let ints = |f: fn(&Sample) -> usize| {
+      samples
+        .iter()
+        .flat_map(|s| (f(s) as i64).to_le_bytes())
+        .collect::<Vec<_>>()
+    };
+
+    self.write_npy_file(name, "game", "<i8", &[n], &ints(|s| s.game))?;
+    self.write_npy_file(name, "seat", "<i8", &[n], &ints(|s| s.seat.value()))?;
+    let kind = |s: &Sample| DecisionKind::all().iter().position(|&k| k == s.kind).unwrap();
+    self.write_npy_file(name, "kind", "<i8", &[n], &ints(kind))?;
+    let features = samples
+      .iter()
+      .flat_map(|s| s.features.iter().flat_map(|v| v.to_le_bytes()))
+      .collect::<Vec<_>>();
+    self.write_npy_file(name, "features", "<f4", &[n, num_features], &features)?;
+    let mask = samples
+      .iter()
+      .flat_map(|s| s.mask.iter().map(|&m| m as u8))
+      .collect::<Vec<_>>();
+    self.write_npy_file(name, "mask", "|u1", &[n, NUM_ACTIONS], &mask)?;
+    self.write_npy_file(name, "action", "<i8", &[n], &ints(|s| s.action))?;
+    let outcome = samples.iter().flat_map(|s| s.outcome.to_le_bytes()).collect::<Vec<_>>();
+    self.write_npy_file(name, "outcome", "<f4", &[n], &outcome)?;
+    Ok(())
+  }
+
+  // NPY 1.0: magic + version + u16 header 长度 + header, 整个文件头按 64 字节对齐
+  fn write_npy_file(&self, name: &str, field: &str, descr: &str, shape: &[usize], data: &[u8]) -> anyhow::Result<()> {
+    let shape = match shape {
+      [n] => format!("({},)", n),
+      _ => format!(
+        "({})",
+        shape.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(", ")
+      ),
+    };
+    let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}", descr, shape);
+    while (10 + header.len() + 1) % 64 != 0 {
+      header.push(' ');
+    }
+    header.push('\n');
+
+    let mut writer = BufWriter::new(File::create(self.out_dir.join(format!("{}.{}.npy", name, field)))?);
+    writer.write_all(b"\x93NUMPY\x01\x00")?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    writer.write_all(data)?;
+    writer.flush()?;
+    Ok(())
+  }
+}
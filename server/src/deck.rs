@@ -1,9 +1,12 @@
+use rand::SeedableRng;
 use rand::prelude::SliceRandom;
 use rand::rngs::StdRng;
 use strum::IntoEnumIterator;
 
 use crate::domain::Card;
 use crate::history::History;
+use crate::leak_check;
+use crate::obs::DeckState;
 
 pub struct Deck {
   rng: StdRng,
@@ -30,6 +33,11 @@ impl Deck {
     }
   }
 
+  // 用给定的牌堆和弃牌堆开局, deck 最后一张先摸
+  pub fn from_cards(rng: StdRng, deck: Vec<Card>, drop: Vec<Card>) -> Self {
+    Self { rng, deck, drop }
+  }
+
   pub async fn take(&mut self, history: &mut History) -> Option<Card> {
     if self.deck.is_empty() {
       std::mem::swap(&mut self.deck, &mut self.drop);
@@ -51,7 +59,26 @@ impl Deck {
     &self.drop
   }
 
+  // 只保留张数, 牌堆和弃牌堆的内容都换成占位牌
+  pub(crate) fn redacted(&self) -> Self {
+    Self {
+      rng: StdRng::seed_from_u64(0),
+      deck: vec![leak_check::HIDDEN_CARD; self.deck.len()],
+      drop: vec![leak_check::HIDDEN_CARD; self.drop.len()],
+    }
+  }
+
   pub fn rng(&mut self) -> &mut StdRng {
     &mut self.rng
   }
 }
+
+impl DeckState for Deck {
+  fn peek_deck(&self) -> &[Card] {
+    &self.deck
+  }
+
+  fn peek_drop(&self) -> &[Card] {
+    &self.drop
+  }
+}
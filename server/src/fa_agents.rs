@@ -1,11 +1,18 @@
+mod guarded_fa_agent;
+mod gym_fa_agent;
+mod heuristic_fa_agent;
 mod noop_fa_agent;
+mod policy_fa_agent;
 mod random_fa_agent;
-mod redis_proxy_fa_agent;
+mod remote_fa_agent;
 mod v2_fa_agent;
-mod ws_proxy_fa_agent;
 
+pub use guarded_fa_agent::GuardedFAAgent;
+pub use gym_fa_agent::{GymDecision, GymFAAgent};
+pub(crate) use heuristic_fa_agent::ROLE_BOOK_PRIOR_PICKS;
+pub use heuristic_fa_agent::{HeuristicFAAgent, HeuristicWeights};
 pub use noop_fa_agent::NoopFAAgent;
+pub use policy_fa_agent::{PolicyFAAgent, PolicyModel};
 pub use random_fa_agent::RandomFAAgent;
-pub use redis_proxy_fa_agent::RedisProxyFAAgent;
+pub use remote_fa_agent::RemoteFAAgent;
 pub use v2_fa_agent::V2FAAgent;
-pub use ws_proxy_fa_agent::WsProxyFAAgent;
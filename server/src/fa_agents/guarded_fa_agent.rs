@@ -0,0 +1,241 @@
+// 由 Game 包在每个玩家的 agent 外面, 检查每次决策是否合法. agent panic 或做出不合法的决策时换成 fallback,
+// 这一次和之后的决策都由 fallback 做出. fallback 的决策同样检查. 没有 fallback (严格模式) 或 fallback 也出错时
+// 返回 GameError::Agent, 由各个 service 用 ? 传给 Game::run
+
+use std::panic::AssertUnwindSafe;
+
+use futures::FutureExt;
+use tracing::warn;
+
+use crate::abstract_fa_agent::AbstractFAAgent;
+use crate::agent_protocol::AgentProtocol;
+use crate::domain::{Card, DestroyTarget, MagicianSkill, Oper, PlayerIndex, PlayerOffset, Rationale, Role, RoleSet};
+use crate::game_error::{GameError, panic_message};
+use crate::obs::Obs;
+use crate::time_control::DecisionTimeout;
+
+pub struct GuardedFAAgent {
+  seat: PlayerIndex,
+  inner: Box<dyn AbstractFAAgent>,
+  fallback: Option<Box<dyn AbstractFAAgent>>,
+  replaced: bool,
+  failure: Option<String>,
+}
+
+// 每个 choose_* 的恢复策略: agent 还没被换掉时先让它决策, panic 或不合法时换成 fallback 再决策一次.
+// fallback 出错 (或严格模式下 agent 出错) 时返回 GameError::Agent
+macro_rules! guarded {
+  ($self:ident, $validate:expr, $agent:ident => $call:expr) => {{
+    let validate = $validate;
+    if !$self.replaced {
+      let $agent = &mut $self.inner;
+      let result = AssertUnwindSafe($call).catch_unwind().await;
+      match check(result, &validate) {
+        Ok(chosen) => return Ok(chosen),
+        Err(reason) => $self.fail(reason)?,
+      }
+    }
+    let $agent = $self.fallback()?;
+    let result = AssertUnwindSafe($call).catch_unwind().await;
+    check(result, &validate).map_err(|reason| $self.fallback_failed(reason))
+  }};
+}
+
+// 一次决策的结果, 不合法或 panic 时为原因
+fn check<T>(result: std::thread::Result<T>, validate: impl Fn(&T) -> Result<(), String>) -> Result<T, String> {
+  let chosen = result.map_err(|payload| format!("panicked: {}", panic_message(payload)))?;
+  validate(&chosen)?;
+  Ok(chosen)
+}
+
+fn is_one_of(chosen: Card, cards: &[Card]) -> Result<(), String> {
+  if cards.contains(&chosen) {
+    Ok(())
+  } else {
+    Err(format!("card {:?} not in {:?}", chosen, cards))
+  }
+}
+
+fn is_role_in(what: &str, chosen: Role, roles: RoleSet) -> Result<(), String> {
+  if roles.contains(chosen) {
+    Ok(())
+  } else {
+    Err(format!("{} {:?} not in {:?}", what, chosen, roles))
+  }
+}
+
+// 魔术师: 不能和自己换, 制衡的牌要都在手里
+fn is_valid_skill(obs: &Obs, skill: &MagicianSkill) -> Result<(), String> {
+  let valid = match skill {
+    MagicianSkill::Swap(offset) => offset.value() != PlayerOffset::ZERO.value() && offset.value() < obs.num_players(),
+    MagicianSkill::制衡(cards) => {
+      let mut hand = obs.hero_cards().to_vec();
+      cards.iter().all(|card| match hand.iter().position(|c| c == card) {
+        Some(p) => {
+          hand.swap_remove(p);
+          true
+        },
+        None => false,
+      })
+    },
+    MagicianSkill::放弃 => true,
+  };
+  if valid {
+    Ok(())
+  } else {
+    Err(format!("invalid magician skill {:?}", skill))
+  }
+}
+
+fn is_valid_target(choices: &[DestroyTarget], target: &Option<DestroyTarget>) -> Result<(), String> {
+  match target {
+    Some(target)
+      if !choices
+        .iter()
+        .any(|t| t.player_offset.value() == target.player_offset.value() && t.card == target.card) =>
+    {
+      Err(format!("destroy target {:?} not in choices", target))
+    },
+    _ => Ok(()),
+  }
+}
+
+impl GuardedFAAgent {
+  pub fn new(seat: PlayerIndex, inner: Box<dyn AbstractFAAgent>, fallback: Option<Box<dyn AbstractFAAgent>>) -> Self {
+    Self {
+      seat,
+      inner,
+      fallback,
+      replaced: false,
+      failure: None,
+    }
+  }
+
+  // 严格模式: 出错时直接结束对局
+  pub fn remove_fallback(&mut self) {
+    self.fallback = None;
+  }
+
+  fn error(&self, reason: String) -> GameError {
+    GameError::Agent {
+      actor: self.seat.value(),
+      reason,
+    }
+  }
+
+  // agent 出错, 之后换成 fallback. 没有 fallback 时返回错误
+  fn fail(&mut self, reason: String) -> Result<(), GameError> {
+    if self.fallback.is_none() {
+      return Err(self.error(reason));
+    }
+    warn!("agent {} replaced by fallback: {}", self.seat.value(), reason);
+    self.replaced = true;
+    self.failure = Some(reason);
+    Ok(())
+  }
+
+  fn fallback(&mut self) -> Result<&mut Box<dyn AbstractFAAgent>, GameError> {
+    let seat = self.seat.value();
+    self.fallback.as_mut().ok_or_else(|| GameError::Agent {
+      actor: seat,
+      reason: "replaced without fallback".to_string(),
+    })
+  }
+
+  fn fallback_failed(&self, reason: String) -> GameError {
+    self.error(format!("fallback {}", reason))
+  }
+
+  pub fn name(&self) -> &str {
+    self.inner.name()
+  }
+
+  pub async fn wait_for_ready(&mut self) {
+    self.inner.wait_for_ready().await;
+  }
+
+  pub async fn choose_init_card(&mut self, obs: &Obs, c0: Card, c1: Card) -> Result<Card, GameError> {
+    guarded!(self, |chosen: &Card| is_one_of(*chosen, &[c0, c1]), agent => agent.choose_init_card(obs, c0, c1))
+  }
+
+  pub async fn choose_role(&mut self, obs: &Obs, roles: RoleSet) -> Result<Role, GameError> {
+    guarded!(self, |chosen: &Role| is_role_in("role", *chosen, roles), agent => agent.choose_role(obs, roles))
+  }
+
+  pub async fn choose_kill_target(&mut self, obs: &Obs, choices: RoleSet) -> Result<Role, GameError> {
+    guarded!(
+      self,
+      |chosen: &Role| is_role_in("kill target", *chosen, choices),
+      agent => agent.choose_kill_target(obs, choices)
+    )
+  }
+
+  pub async fn choose_steal_target(&mut self, obs: &Obs, choices: RoleSet) -> Result<Role, GameError> {
+    guarded!(
+      self,
+      |chosen: &Role| is_role_in("steal target", *chosen, choices),
+      agent => agent.choose_steal_target(obs, choices)
+    )
+  }
+
+  pub async fn choose_swap_target(&mut self, obs: &Obs) -> Result<MagicianSkill, GameError> {
+    guarded!(self, |chosen: &MagicianSkill| is_valid_skill(obs, chosen), agent => agent.choose_swap_target(obs))
+  }
+
+  pub async fn choose_destory_target(
+    &mut self, obs: &Obs, choices: &[DestroyTarget],
+  ) -> Result<Option<DestroyTarget>, GameError> {
+    guarded!(
+      self,
+      |chosen: &Option<DestroyTarget>| is_valid_target(choices, chosen),
+      agent => agent.choose_destory_target(obs, choices)
+    )
+  }
+
+  pub async fn choose_tomb(&mut self, obs: &Obs, c: Card) -> Result<bool, GameError> {
+    guarded!(self, |_: &bool| Ok(()), agent => agent.choose_tomb(obs, c))
+  }
+
+  pub async fn choose_oper(&mut self, obs: &Obs, choices: &[Oper]) -> Result<Oper, GameError> {
+    let validate = |chosen: &Oper| {
+      if choices.contains(chosen) {
+        Ok(())
+      } else {
+        Err(format!("oper {:?} not in {:?}", chosen, choices))
+      }
+    };
+    guarded!(self, validate, agent => agent.choose_oper(obs, choices))
+  }
+
+  pub async fn choose_from_2(&mut self, obs: &Obs, c0: Card, c1: Card) -> Result<Card, GameError> {
+    guarded!(self, |chosen: &Card| is_one_of(*chosen, &[c0, c1]), agent => agent.choose_from_2(obs, c0, c1))
+  }
+
+  pub async fn choose_from_3(&mut self, obs: &Obs, c0: Card, c1: Card, c2: Card) -> Result<Card, GameError> {
+    guarded!(
+      self,
+      |chosen: &Card| is_one_of(*chosen, &[c0, c1, c2]),
+      agent => agent.choose_from_3(obs, c0, c1, c2)
+    )
+  }
+
+  pub fn take_rationale(&mut self) -> Option<Rationale> {
+    match &mut self.fallback {
+      Some(fallback) if self.replaced => fallback.take_rationale(),
+      _ => self.inner.take_rationale(),
+    }
+  }
+
+  pub fn take_timeout(&mut self) -> Option<DecisionTimeout> {
+    self.inner.take_timeout()
+  }
+
+  // 取走上一次决策中 agent 出错被换成 fallback 的原因
+  pub fn take_failure(&mut self) -> Option<String> {
+    self.failure.take()
+  }
+
+  pub fn protocol(&self) -> Option<AgentProtocol> {
+    self.inner.protocol()
+  }
+}
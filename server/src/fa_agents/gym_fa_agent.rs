@@ -0,0 +1,117 @@
+use async_trait::async_trait;
+use tokio::sync::{mpsc, oneshot};
+use tracing::warn;
+
+use crate::abstract_fa_agent::AbstractFAAgent;
+use crate::agent_protocol::DecisionKind;
+use crate::dataset::{
+  candidates_mask, card_candidates, destroy_candidates, magic_candidates, oper_candidates, role_candidates,
+  tomb_candidates,
+};
+use crate::domain::{Card, DestroyTarget, MagicianSkill, Oper, Role, RoleSet};
+use crate::obs::Obs;
+
+pub struct GymDecision {
+  pub obs: Obs,
+  pub kind: DecisionKind,
+  pub mask: Vec<bool>,
+  pub action_sender: oneshot::Sender<usize>,
+}
+
+// 把每个决策交给 GymEnv, 由环境外部的学习者在 dataset 的动作空间上选择
+pub struct GymFAAgent {
+  decision_sender: mpsc::Sender<GymDecision>,
+}
+
+impl GymFAAgent {
+  pub fn new(decision_sender: mpsc::Sender<GymDecision>) -> Self {
+    Self { decision_sender }
+  }
+
+  async fn choose<T: Clone>(&mut self, obs: &Obs, kind: DecisionKind, candidates: &[(usize, T)]) -> T {
+    let (action_sender, action_receiver) = oneshot::channel();
+    let decision = GymDecision {
+      obs: obs.clone(),
+      kind,
+      mask: candidates_mask(candidates),
+      action_sender,
+    };
+    // 环境已经关闭或者给出了不合法的动作时, 取第一个合法动作让对局继续到结束
+    if self.decision_sender.send(decision).await.is_err() {
+      warn!("gym env closed, playing the first legal action");
+      return candidates[0].1.clone();
+    }
+    let Ok(action) = action_receiver.await else {
+      warn!("gym env dropped the decision, playing the first legal action");
+      return candidates[0].1.clone();
+    };
+    match candidates.iter().find(|(a, _)| *a == action) {
+      Some((_, chosen)) => chosen.clone(),
+      None => {
+        warn!("gym action {} is not legal, playing the first legal action", action);
+        candidates[0].1.clone()
+      },
+    }
+  }
+}
+
+#[async_trait]
+impl AbstractFAAgent for GymFAAgent {
+  fn name(&self) -> &str {
+    "GymAgent"
+  }
+
+  async fn wait_for_ready(&mut self) {
+    // GymAgent does not need to be ready
+  }
+
+  async fn choose_init_card(&mut self, obs: &Obs, c0: Card, c1: Card) -> Card {
+    self
+      .choose(obs, DecisionKind::InitCard, &card_candidates(&[c0, c1]))
+      .await
+  }
+
+  async fn choose_role(&mut self, obs: &Obs, roles: RoleSet) -> Role {
+    self
+      .choose(obs, DecisionKind::ChooseRole, &role_candidates(roles))
+      .await
+  }
+
+  async fn choose_kill_target(&mut self, obs: &Obs, choices: RoleSet) -> Role {
+    self.choose(obs, DecisionKind::Kill, &role_candidates(choices)).await
+  }
+
+  async fn choose_steal_target(&mut self, obs: &Obs, choices: RoleSet) -> Role {
+    self.choose(obs, DecisionKind::Steal, &role_candidates(choices)).await
+  }
+
+  async fn choose_swap_target(&mut self, obs: &Obs) -> MagicianSkill {
+    self.choose(obs, DecisionKind::Magic, &magic_candidates(obs)).await
+  }
+
+  async fn choose_destory_target(&mut self, obs: &Obs, choices: &[DestroyTarget]) -> Option<DestroyTarget> {
+    self
+      .choose(obs, DecisionKind::Destroy, &destroy_candidates(choices))
+      .await
+  }
+
+  async fn choose_tomb(&mut self, obs: &Obs, _c: Card) -> bool {
+    self.choose(obs, DecisionKind::Tomb, &tomb_candidates()).await
+  }
+
+  async fn choose_oper(&mut self, obs: &Obs, choices: &[Oper]) -> Oper {
+    self.choose(obs, DecisionKind::Oper, &oper_candidates(choices)).await
+  }
+
+  async fn choose_from_2(&mut self, obs: &Obs, c0: Card, c1: Card) -> Card {
+    self
+      .choose(obs, DecisionKind::ChooseFrom2, &card_candidates(&[c0, c1]))
+      .await
+  }
+
+  async fn choose_from_3(&mut self, obs: &Obs, c0: Card, c1: Card, c2: Card) -> Card {
+    self
+      .choose(obs, DecisionKind::ChooseFrom3, &card_candidates(&[c0, c1, c2]))
+      .await
+  }
+}
@@ -0,0 +1,362 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::abstract_fa_agent::AbstractFAAgent;
+use crate::domain::{Card, DestroyTarget, MagicianSkill, Oper, PlayerOffset, Rationale, Role, RoleSet};
+use crate::obs::{MAX_PLAYERS, Obs};
+use crate::role_book::RoleBook;
+
+// 启发式 agent 的全部参数, 角色相关的数组按 Role::population() 的顺序.
+// 选角色的偏好随选角色的次序变化, 其余参数与座位和角色无关
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HeuristicWeights {
+  pub role_preference: [f64; 8],
+  // 第 i 个选角色时加到 role_preference 上, 旧的权重文件没有这一项时全为 0
+  #[serde(default)]
+  pub role_position_preference: [[f64; 8]; MAX_PLAYERS],
+  pub kill_preference: [f64; 8],
+  pub steal_preference: [f64; 8],
+  // 金币少于 gold_target 或者手牌不少于 hand_target 时拿金币, 否则拿牌
+  pub gold_target: f64,
+  pub hand_target: f64,
+  // 卡牌价值 = card_score_weight * 分数 - card_fee_weight * 费用
+  pub card_score_weight: f64,
+  pub card_fee_weight: f64,
+  pub build_threshold: f64,
+  pub destroy_threshold: f64,
+  pub tomb_threshold: f64,
+  // 对手比自己多 swap_hand_diff 张以上手牌时和他换牌
+  pub swap_hand_diff: f64,
+  pub buy_card_gold: f64,
+}
+
+impl Default for HeuristicWeights {
+  fn default() -> Self {
+    Self {
+      role_preference: [1.0, 1.0, 1.0, 2.0, 1.0, 2.0, 2.0, 1.5],
+      role_position_preference: [[0.0; 8]; MAX_PLAYERS],
+      kill_preference: [0.0, 1.0, 1.0, 1.5, 1.0, 2.0, 2.0, 1.5],
+      steal_preference: [0.0, 0.0, 1.0, 1.5, 1.0, 2.0, 1.5, 1.5],
+      gold_target: 4.0,
+      hand_target: 2.0,
+      card_score_weight: 1.0,
+      card_fee_weight: 0.3,
+      build_threshold: 0.0,
+      destroy_threshold: 3.0,
+      tomb_threshold: 2.0,
+      swap_hand_diff: 2.0,
+      buy_card_gold: 6.0,
+    }
+  }
+}
+
+impl HeuristicWeights {
+  pub const DIM: usize = 8 * 3 + 9 + 8 * MAX_PLAYERS;
+
+  pub fn load(path: &str) -> anyhow::Result<Self> {
+    let file_content = std::fs::read_to_string(path)?;
+    Ok(toml::from_str(&file_content)?)
+  }
+
+  pub fn save(&self, path: &str) -> anyhow::Result<()> {
+    std::fs::write(path, toml::to_string_pretty(self)?)?;
+    Ok(())
+  }
+
+  // 展开成定长向量, 供调参使用
+  pub fn to_vec(&self) -> Vec<f64> {
+    let mut v = Vec::with_capacity(Self::DIM);
+    v.extend_from_slice(&self.role_preference);
+    v.extend_from_slice(&self.kill_preference);
+    v.extend_from_slice(&self.steal_preference);
+    v.extend_from_slice(&[
+      self.gold_target,
+      self.hand_target,
+      self.card_score_weight,
+      self.card_fee_weight,
+      self.build_threshold,
+      self.destroy_threshold,
+      self.tomb_threshold,
+      self.swap_hand_diff,
+      self.buy_card_gold,
+    ]);
+    for preference in self.role_position_preference.iter() {
+      v.extend_from_slice(preference);
+    }
+    v
+  }
+
+  pub fn from_vec(v: &[f64]) -> Self {
+    assert_eq!(v.len(), Self::DIM);
+    Self {
+      role_preference: v[0..8].try_into().unwrap(),
+      kill_preference: v[8..16].try_into().unwrap(),
+      steal_preference: v[16..24].try_into().unwrap(),
+      gold_target: v[24],
+      hand_target: v[25],
+      card_score_weight: v[26],
+      card_fee_weight: v[27],
+      build_threshold: v[28],
+      destroy_threshold: v[29],
+      tomb_threshold: v[30],
+      swap_hand_diff: v[31],
+      buy_card_gold: v[32],
+      role_position_preference: std::array::from_fn(|i| v[33 + 8 * i..41 + 8 * i].try_into().unwrap()),
+    }
+  }
+
+  // 本轮第 position 个选角色时的偏好
+  fn role_preference_at(&self, position: usize) -> [f64; 8] {
+    let bonus = &self.role_position_preference[position.min(MAX_PLAYERS - 1)];
+    std::array::from_fn(|i| self.role_preference[i] + bonus[i])
+  }
+
+  fn card_value(&self, card: Card) -> f64 {
+    self.card_score_weight * card.score() as f64 - self.card_fee_weight * card.fee() as f64
+  }
+}
+
+// 选角色开局库中样本数的先验, 见 RoleStats::smoothed_win_rate
+pub(crate) const ROLE_BOOK_PRIOR_PICKS: f64 = 20.0;
+
+pub struct HeuristicFAAgent {
+  weights: HeuristicWeights,
+  role_book: Option<Arc<RoleBook>>,
+  rationale: Option<Rationale>,
+}
+
+impl HeuristicFAAgent {
+  pub fn new(weights: HeuristicWeights) -> Self {
+    Self {
+      weights,
+      role_book: None,
+      rationale: None,
+    }
+  }
+
+  // 有开局库时按库中的胜率选角色, role_preference 只用来打破平局
+  pub fn with_role_book(weights: HeuristicWeights, role_book: Arc<RoleBook>) -> Self {
+    Self {
+      weights,
+      role_book: Some(role_book),
+      rationale: None,
+    }
+  }
+
+  fn best_role(preference: &[f64; 8], roles: RoleSet) -> Role {
+    Role::population()
+      .into_iter()
+      .enumerate()
+      .filter(|(_, r)| roles.contains(*r))
+      .max_by(|(i, _), (j, _)| preference[*i].total_cmp(&preference[*j]))
+      .map(|(_, r)| r)
+      .unwrap()
+  }
+
+  fn role_rationale(text: &str, preference: &[f64; 8], roles: RoleSet) -> Rationale {
+    Role::population()
+      .into_iter()
+      .enumerate()
+      .filter(|(_, r)| roles.contains(*r))
+      .fold(Rationale::new(text), |rationale, (i, r)| {
+        rationale.with_alternative(r.name(), preference[i])
+      })
+  }
+
+  // 已经建过的牌不能再建, 价值最低
+  fn hand_card_value(&self, obs: &Obs, c: Card) -> f64 {
+    if obs.hero_has_building(c) {
+      f64::MIN
+    } else {
+      self.weights.card_value(c)
+    }
+  }
+
+  fn best_card(&mut self, obs: &Obs, cards: &[Card]) -> Card {
+    self.rationale = Some(cards.iter().fold(Rationale::new("选价值最高的牌"), |rationale, &c| {
+      rationale.with_alternative(c.name(), self.hand_card_value(obs, c))
+    }));
+    *cards
+      .iter()
+      .max_by(|a, b| {
+        self
+          .hand_card_value(obs, **a)
+          .total_cmp(&self.hand_card_value(obs, **b))
+      })
+      .unwrap()
+  }
+}
+
+#[async_trait]
+impl AbstractFAAgent for HeuristicFAAgent {
+  fn name(&self) -> &str {
+    "HeuristicAgent"
+  }
+
+  async fn wait_for_ready(&mut self) {
+    // HeuristicAgent does not need to be ready
+  }
+
+  async fn choose_init_card(&mut self, obs: &Obs, c0: Card, c1: Card) -> Card {
+    self.best_card(obs, &[c0, c1])
+  }
+
+  async fn choose_role(&mut self, obs: &Obs, roles: RoleSet) -> Role {
+    if let Some(role_book) = &self.role_book
+      && !role_book.lookup(obs, roles).is_empty()
+    {
+      let preference = self.weights.role_preference_at(obs.role_select_position());
+      let index = |r: Role| Role::population().iter().position(|&p| p == r).unwrap();
+      let prior = role_book.prior(obs, roles, ROLE_BOOK_PRIOR_PICKS);
+      self.rationale = Some(
+        prior
+          .iter()
+          .fold(Rationale::new("按开局库胜率选角色"), |rationale, (r, w)| {
+            rationale.with_alternative(r.name(), *w)
+          }),
+      );
+      return prior
+        .into_iter()
+        .max_by(|(a, wa), (b, wb)| {
+          wa.total_cmp(wb)
+            .then(preference[index(*a)].total_cmp(&preference[index(*b)]))
+        })
+        .map(|(r, _)| r)
+        .unwrap();
+    }
+    let preference = self.weights.role_preference_at(obs.role_select_position());
+    self.rationale = Some(Self::role_rationale("按角色偏好选角色", &preference, roles));
+    Self::best_role(&preference, roles)
+  }
+
+  async fn choose_kill_target(&mut self, _obs: &Obs, choices: RoleSet) -> Role {
+    self.rationale = Some(Self::role_rationale(
+      "按刺杀偏好选目标",
+      &self.weights.kill_preference,
+      choices,
+    ));
+    Self::best_role(&self.weights.kill_preference, choices)
+  }
+
+  async fn choose_steal_target(&mut self, _obs: &Obs, choices: RoleSet) -> Role {
+    self.rationale = Some(Self::role_rationale(
+      "按偷窃偏好选目标",
+      &self.weights.steal_preference,
+      choices,
+    ));
+    Self::best_role(&self.weights.steal_preference, choices)
+  }
+
+  async fn choose_swap_target(&mut self, obs: &Obs) -> MagicianSkill {
+    let hero_num_cards = obs.actor_num_cards() as f64;
+    let text = format!("对手比自己多 {} 张以上手牌时换牌", self.weights.swap_hand_diff);
+    self.rationale = Some((1..obs.num_players()).map(PlayerOffset::from_usize).fold(
+      Rationale::new(text),
+      |rationale, offset| {
+        let diff = obs.villain_num_cards(offset) as f64 - hero_num_cards;
+        rationale.with_alternative(format!("换牌:{}", offset.value()), diff)
+      },
+    ));
+    let target = (1..obs.num_players())
+      .map(PlayerOffset::from_usize)
+      .max_by_key(|&offset| obs.villain_num_cards(offset));
+    match target {
+      Some(offset) if obs.villain_num_cards(offset) as f64 - hero_num_cards >= self.weights.swap_hand_diff => {
+        MagicianSkill::Swap(offset)
+      },
+      _ => MagicianSkill::放弃,
+    }
+  }
+
+  async fn choose_destory_target(&mut self, obs: &Obs, choices: &[DestroyTarget]) -> Option<DestroyTarget> {
+    let enemies = choices
+      .iter()
+      .filter(|t| !t.player_offset.is_zero() && obs.villain_camp(t.player_offset) != obs.hero_camp());
+    let text = format!("拆敌方分数不低于 {} 的建筑", self.weights.destroy_threshold);
+    self.rationale = Some(enemies.clone().fold(Rationale::new(text), |rationale, t| {
+      rationale.with_alternative(
+        format!("{}:{}", t.player_offset.value(), t.card.name()),
+        t.card.score() as f64,
+      )
+    }));
+    enemies
+      .filter(|t| t.card.score() as f64 >= self.weights.destroy_threshold)
+      .max_by_key(|t| t.card.score())
+      .copied()
+  }
+
+  async fn choose_tomb(&mut self, _obs: &Obs, c: Card) -> bool {
+    let value = self.weights.card_value(c);
+    let text = format!("价值不低于 {} 时收回", self.weights.tomb_threshold);
+    self.rationale = Some(Rationale::new(text).with_alternative(c.name(), value));
+    value >= self.weights.tomb_threshold
+  }
+
+  async fn choose_oper(&mut self, obs: &Obs, choices: &[Oper]) -> Oper {
+    let gold = choices.iter().find(|oper| matches!(oper, Oper::Gold(_)));
+    let draw = choices
+      .iter()
+      .find(|oper| matches!(oper, Oper::Card3Choose1 | Oper::Card2Choose2 | Oper::Card2Choose1));
+    if let (Some(gold), Some(draw)) = (gold, draw) {
+      let hero_gold = obs.hero_gold() as f64;
+      let hand = obs.actor_num_cards() as f64;
+      let text = format!(
+        "金币 {} (目标 {}), 手牌 {} (目标 {})",
+        hero_gold, self.weights.gold_target, hand, self.weights.hand_target
+      );
+      self.rationale = Some(Rationale::new(text));
+      return if hero_gold < self.weights.gold_target || hand >= self.weights.hand_target {
+        *gold
+      } else {
+        *draw
+      };
+    }
+
+    let build = choices
+      .iter()
+      .filter_map(|oper| match oper {
+        Oper::Build(card) => Some(*card),
+        _ => None,
+      })
+      .max_by(|a, b| self.weights.card_value(*a).total_cmp(&self.weights.card_value(*b)));
+    let text = format!("建造价值不低于 {} 的建筑", self.weights.build_threshold);
+    self.rationale = Some(
+      choices
+        .iter()
+        .filter_map(|oper| match oper {
+          Oper::Build(card) => Some(*card),
+          _ => None,
+        })
+        .fold(Rationale::new(text), |rationale, card| {
+          rationale.with_alternative(card.name(), self.weights.card_value(card))
+        }),
+    );
+    if let Some(card) = build
+      && self.weights.card_value(card) >= self.weights.build_threshold
+    {
+      return Oper::Build(card);
+    }
+
+    if choices.iter().any(|oper| matches!(oper, Oper::BuyCard))
+      && obs.hero_gold() as f64 >= self.weights.buy_card_gold
+    {
+      return Oper::BuyCard;
+    }
+
+    Oper::EndRound
+  }
+
+  async fn choose_from_2(&mut self, obs: &Obs, c0: Card, c1: Card) -> Card {
+    self.best_card(obs, &[c0, c1])
+  }
+
+  async fn choose_from_3(&mut self, obs: &Obs, c0: Card, c1: Card, c2: Card) -> Card {
+    self.best_card(obs, &[c0, c1, c2])
+  }
+
+  fn take_rationale(&mut self) -> Option<Rationale> {
+    self.rationale.take()
+  }
+}
@@ -0,0 +1,234 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::bail;
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+
+use crate::abstract_fa_agent::AbstractFAAgent;
+use crate::dataset::{
+  NUM_ACTIONS, action_names, candidates_mask, card_candidates, destroy_candidates, magic_candidates, oper_candidates,
+  role_candidates, tomb_candidates,
+};
+use crate::domain::{Card, DestroyTarget, MagicianSkill, Oper, Rationale, Role, RoleSet};
+use crate::obs::Obs;
+
+#[derive(Deserialize)]
+struct Layer {
+  weights: Vec<Vec<f32>>, // [输出维度][输入维度]
+  bias: Vec<f32>,
+}
+
+impl Layer {
+  fn forward(&self, input: &[f32]) -> Vec<f32> {
+    self
+      .weights
+      .iter()
+      .zip(self.bias.iter())
+      .map(|(row, b)| row.iter().zip(input).map(|(w, x)| w * x).sum::<f32>() + b)
+      .collect()
+  }
+}
+
+// 输入为 Obs::features, 输出为 dataset 动作空间上的 logits.
+// 只有一层时是线性模型, 多层时层与层之间用 ReLU.
+#[derive(Deserialize)]
+pub struct PolicyModel {
+  layers: Vec<Layer>,
+}
+
+impl PolicyModel {
+  // 权重文件为 JSON: {"layers": [{"weights": [[...], ...], "bias": [...]}, ...]}
+  pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+    let model: Self = serde_json::from_reader(std::io::BufReader::new(std::fs::File::open(path)?))?;
+    model.validate()?;
+    Ok(model)
+  }
+
+  fn validate(&self) -> anyhow::Result<()> {
+    if self.layers.is_empty() {
+      bail!("policy model has no layers");
+    }
+    for (i, layer) in self.layers.iter().enumerate() {
+      if layer.weights.len() != layer.bias.len() {
+        bail!(
+          "layer {}: {} rows but {} biases",
+          i,
+          layer.weights.len(),
+          layer.bias.len()
+        );
+      }
+      let in_dim = layer.weights.first().map_or(0, |row| row.len());
+      if layer.weights.iter().any(|row| row.len() != in_dim) {
+        bail!("layer {}: rows have different lengths", i);
+      }
+      if i > 0 && in_dim != self.layers[i - 1].bias.len() {
+        bail!("layer {}: input dim {} does not match previous output dim", i, in_dim);
+      }
+    }
+    if self.input_dim() != Obs::num_features() {
+      bail!(
+        "policy model takes {} features, expected {}",
+        self.input_dim(),
+        Obs::num_features()
+      );
+    }
+    let out_dim = self.layers.last().unwrap().bias.len();
+    if out_dim != NUM_ACTIONS {
+      bail!("policy model outputs {} actions, expected {}", out_dim, NUM_ACTIONS);
+    }
+    Ok(())
+  }
+
+  pub fn input_dim(&self) -> usize {
+    self.layers[0].weights.first().map_or(0, |row| row.len())
+  }
+
+  pub fn logits(&self, features: &[f32]) -> Vec<f32> {
+    assert_eq!(features.len(), self.input_dim());
+    let mut x = features.to_vec();
+    for (i, layer) in self.layers.iter().enumerate() {
+      x = layer.forward(&x);
+      if i + 1 < self.layers.len() {
+        x.iter_mut().for_each(|v| *v = v.max(0.0));
+      }
+    }
+    x
+  }
+}
+
+// temperature 为 0 时取 argmax, 否则按 softmax(logits / temperature) 采样, 越大越随机
+pub struct PolicyFAAgent {
+  model: Arc<PolicyModel>,
+  temperature: f32,
+  rng: StdRng,
+  action_names: Vec<String>,
+  rationale: Option<Rationale>,
+}
+
+impl PolicyFAAgent {
+  pub fn new(model: Arc<PolicyModel>, temperature: f32) -> Self {
+    Self::with_seed(model, temperature, rand::random())
+  }
+
+  // 固定种子, 用于复现对局
+  pub fn with_seed(model: Arc<PolicyModel>, temperature: f32, seed: u64) -> Self {
+    Self {
+      model,
+      temperature,
+      rng: StdRng::seed_from_u64(seed),
+      action_names: action_names(),
+      rationale: None,
+    }
+  }
+
+  fn choose<T: Clone>(&mut self, obs: &Obs, candidates: &[(usize, T)]) -> T {
+    let logits = self.model.logits(&obs.features());
+    let mask = candidates_mask(candidates);
+    let legal = (0..NUM_ACTIONS).filter(|&a| mask[a]).collect::<Vec<_>>();
+
+    let best = legal
+      .iter()
+      .copied()
+      .max_by(|&a, &b| logits[a].total_cmp(&logits[b]))
+      .unwrap();
+    let action = if self.temperature <= 0.0 {
+      best
+    } else {
+      let weights = legal
+        .iter()
+        .map(|&a| ((logits[a] - logits[best]) / self.temperature).exp())
+        .collect::<Vec<_>>();
+      let total = weights.iter().sum::<f32>();
+      if total.is_finite() && total > 0.0 {
+        let mut v = self.rng.random_range(0.0..total);
+        let mut chosen = best;
+        for (i, w) in weights.iter().enumerate() {
+          if v < *w {
+            chosen = legal[i];
+            break;
+          }
+          v -= w;
+        }
+        chosen
+      } else {
+        // logits 溢出或温度极端时权重不能采样, 退回合法动作中的 argmax
+        best
+      }
+    };
+
+    // 解释中给出所有合法动作的 logit. JSON 存不下 inf 和 NaN, 溢出的 logit 不写
+    let text = if self.temperature <= 0.0 {
+      "取 logit 最大的动作".to_string()
+    } else {
+      format!("按温度 {} 采样", self.temperature)
+    };
+    self.rationale = Some(
+      legal
+        .iter()
+        .filter(|&&a| logits[a].is_finite())
+        .fold(Rationale::new(text), |rationale, &a| {
+          rationale.with_alternative(self.action_names[a].clone(), logits[a] as f64)
+        }),
+    );
+
+    candidates.iter().find(|(a, _)| *a == action).unwrap().1.clone()
+  }
+}
+
+#[async_trait]
+impl AbstractFAAgent for PolicyFAAgent {
+  fn name(&self) -> &str {
+    "PolicyAgent"
+  }
+
+  async fn wait_for_ready(&mut self) {
+    // PolicyAgent does not need to be ready
+  }
+
+  async fn choose_init_card(&mut self, obs: &Obs, c0: Card, c1: Card) -> Card {
+    self.choose(obs, &card_candidates(&[c0, c1]))
+  }
+
+  async fn choose_role(&mut self, obs: &Obs, roles: RoleSet) -> Role {
+    self.choose(obs, &role_candidates(roles))
+  }
+
+  async fn choose_kill_target(&mut self, obs: &Obs, choices: RoleSet) -> Role {
+    self.choose(obs, &role_candidates(choices))
+  }
+
+  async fn choose_steal_target(&mut self, obs: &Obs, choices: RoleSet) -> Role {
+    self.choose(obs, &role_candidates(choices))
+  }
+
+  async fn choose_swap_target(&mut self, obs: &Obs) -> MagicianSkill {
+    self.choose(obs, &magic_candidates(obs))
+  }
+
+  async fn choose_destory_target(&mut self, obs: &Obs, choices: &[DestroyTarget]) -> Option<DestroyTarget> {
+    self.choose(obs, &destroy_candidates(choices))
+  }
+
+  async fn choose_tomb(&mut self, obs: &Obs, _c: Card) -> bool {
+    self.choose(obs, &tomb_candidates())
+  }
+
+  async fn choose_oper(&mut self, obs: &Obs, choices: &[Oper]) -> Oper {
+    self.choose(obs, &oper_candidates(choices))
+  }
+
+  async fn choose_from_2(&mut self, obs: &Obs, c0: Card, c1: Card) -> Card {
+    self.choose(obs, &card_candidates(&[c0, c1]))
+  }
+
+  async fn choose_from_3(&mut self, obs: &Obs, c0: Card, c1: Card, c2: Card) -> Card {
+    self.choose(obs, &card_candidates(&[c0, c1, c2]))
+  }
+
+  fn take_rationale(&mut self) -> Option<Rationale> {
+    self.rationale.take()
+  }
+}
@@ -23,6 +23,13 @@ impl RandomFAAgent {
       rng: StdRng::seed_from_u64(rand::random()),
     }
   }
+
+  // 固定种子, 用于复现对局
+  pub fn with_seed(seed: u64) -> Self {
+    Self {
+      rng: StdRng::seed_from_u64(seed),
+    }
+  }
 }
 
 #[async_trait]
@@ -0,0 +1,370 @@
+// 经 AgentTransport 连到进程外的 agent. 超时, 连接断开或者断线超过宽限期时由 fallback 决策, 连接断开且重连失败后不再发请求.
+// 握手时协商了增量模式的, 决策请求的 obs 由 ObsEncoder 编码, agent 回复 Resync 时带完整的 obs 重发一次
+
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tracing::{error, info, warn};
+
+use crate::IdGen;
+use crate::abstract_fa_agent::AbstractFAAgent;
+use crate::agent_protocol::TransportError;
+use crate::agent_protocol::{
+  AgentProtocol, HandshakeError, PROTOCOL_VERSION, RULESET, card_catalog, negotiate, roles,
+};
+use crate::agent_transport::AgentTransport;
+use crate::agent_transports::SubprocessTransport;
+use crate::domain::{
+  AgentReqEvent, AgentRespEvent, Card, DestroyTarget, MagicianSkill, Oper, Rationale, Role, RoleSet,
+};
+use crate::obs::{Obs, ObsEncoder, ObsMessage};
+use crate::time_control::{Clock, DecisionTimeout, TimeControl};
+
+pub struct RemoteFAAgent<T: AgentTransport> {
+  id_gen: IdGen,
+  transport: T,
+  fallback: Box<dyn AbstractFAAgent>,
+  clock: Clock,
+  disconnected: bool,
+  protocol: Option<AgentProtocol>,
+  obs_encoder: Option<ObsEncoder>,
+  rationale: Option<Rationale>, // 上一次远程回复带的解释
+  fallback_used: bool,          // 上一次决策由 fallback 做出, 解释要找 fallback 取
+}
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+impl<T: AgentTransport> RemoteFAAgent<T> {
+  pub fn new(id_gen: IdGen, transport: T, fallback: Box<dyn AbstractFAAgent>) -> Self {
+    Self {
+      id_gen,
+      transport,
+      fallback,
+      clock: Clock::new(TimeControl::default()),
+      disconnected: false,
+      protocol: None,
+      obs_encoder: None,
+      rationale: None,
+      fallback_used: false,
+    }
+  }
+
+  // 决策时限, 默认不限. 超时后由 fallback 决策
+  pub fn with_time_control(mut self, time_control: TimeControl) -> Self {
+    self.clock = Clock::new(time_control);
+    self
+  }
+
+  pub fn is_disconnected(&self) -> bool {
+    self.disconnected
+  }
+
+  // 发出 Hello 协商协议. 不兼容时发出 Reject, 之后的决策都由 fallback 做出
+  pub async fn handshake(&mut self) -> Result<AgentProtocol, HandshakeError> {
+    let event = AgentReqEvent::Hello {
+      id: self.id_gen.gen_next(),
+      protocol_version: PROTOCOL_VERSION,
+      ruleset: RULESET.to_string(),
+      roles: roles(),
+      cards: card_catalog(),
+      resume_token: None,
+    };
+    let result = match self
+      .transport
+      .request(&event, Some(Instant::now() + HANDSHAKE_TIMEOUT))
+      .await
+    {
+      Ok(AgentRespEvent::Hello {
+        protocol_version,
+        name,
+        decisions,
+        obs_deltas,
+        ..
+      }) => negotiate(protocol_version, name, decisions, obs_deltas),
+      Ok(resp) => Err(HandshakeError::Unexpected(format!("{:?}", resp))),
+      Err(e) => Err(e.into()),
+    };
+    match &result {
+      Ok(protocol) => {
+        info!(
+          "{} speaks protocol {} as {}",
+          self.transport.name(),
+          protocol.version,
+          protocol.name
+        );
+        self.protocol = Some(protocol.clone());
+        self.obs_encoder = protocol.obs_deltas.then(ObsEncoder::new);
+      },
+      Err(e) => {
+        error!("{} handshake failed: {}", self.transport.name(), e);
+        let reject = AgentReqEvent::Reject {
+          id: self.id_gen.gen_next(),
+          reason: e.to_string(),
+        };
+        let _ = self.transport.send(&reject).await;
+        self.disconnected = true;
+      },
+    }
+    result
+  }
+
+  // 断开后重连, 握手过的要重新握手
+  async fn reconnect(&mut self) {
+    if !self.transport.reconnect().await {
+      self.disconnected = true;
+    } else if self.protocol.is_some() {
+      let _ = self.handshake().await;
+    }
+  }
+
+  fn encode_obs(&mut self, obs: &Obs) -> ObsMessage {
+    match &mut self.obs_encoder {
+      Some(encoder) => encoder.encode(obs),
+      None => ObsMessage::Full(obs.clone()),
+    }
+  }
+
+  // 和请求对不上的回复, 这一次由 fallback 决策
+  fn unexpected(&mut self, resp: Option<AgentRespEvent>) {
+    self.fallback_used = true;
+    if let Some(resp) = resp {
+      error!("{} unexpected event {:?}", self.transport.name(), resp);
+    }
+  }
+
+  // 用 build 生成请求, 返回 None 时由 fallback 决策
+  async fn decide(
+    &mut self, obs: &Obs, build: impl Fn(u32, ObsMessage) -> AgentReqEvent + Send,
+  ) -> Option<AgentRespEvent> {
+    self.rationale = None;
+    self.fallback_used = false;
+    if self.disconnected {
+      return None;
+    }
+    let deadline = self.clock.start();
+    let mut obs = obs.clone();
+    obs.set_clock(self.clock.info());
+    let event = build(self.id_gen.gen_next(), self.encode_obs(&obs));
+    let mut result = self.transport.request(&event, deadline).await;
+    if let (Ok(AgentRespEvent::Resync { .. }), Some(encoder)) = (&result, &mut self.obs_encoder) {
+      warn!("{} resync obs", self.transport.name());
+      encoder.resync();
+      let event = build(self.id_gen.gen_next(), self.encode_obs(&obs));
+      result = self.transport.request(&event, deadline).await;
+    }
+    match result {
+      Ok(resp) => {
+        self.clock.stop();
+        self.rationale = resp.rationale().cloned();
+        Some(resp)
+      },
+      Err(TransportError::Timeout) => {
+        info!("{} decision timeout", self.transport.name());
+        self.clock.expire();
+        None
+      },
+      // 连接还在等重连, 不用重连也不用重新握手
+      Err(TransportError::Away) => {
+        info!("{} away", self.transport.name());
+        self.clock.stop();
+        None
+      },
+      Err(e) => {
+        error!("{} {}", self.transport.name(), e);
+        self.clock.stop();
+        self.reconnect().await;
+        None
+      },
+    }
+  }
+}
+
+impl RemoteFAAgent<SubprocessTransport> {
+  // 启动子进程 agent 并握手, 启动限时 10 秒, 每次决策默认限时 5 秒
+  pub async fn spawn(command: &str, fallback: Box<dyn AbstractFAAgent>) -> anyhow::Result<Self> {
+    let transport = SubprocessTransport::spawn(command, Duration::from_secs(10)).await?;
+    let mut agent = Self::new(IdGen::new(), transport, fallback).with_time_control(TimeControl {
+      decision_ms: 5000,
+      ..TimeControl::default()
+    });
+    agent.handshake().await?;
+    Ok(agent)
+  }
+}
+
+#[async_trait]
+impl<T: AgentTransport> AbstractFAAgent for RemoteFAAgent<T> {
+  fn name(&self) -> &str {
+    self.transport.name()
+  }
+
+  // 远程 agent 回复带的解释, 由 fallback 决策时是 fallback 的解释
+  fn take_rationale(&mut self) -> Option<Rationale> {
+    if std::mem::take(&mut self.fallback_used) {
+      return self.fallback.take_rationale();
+    }
+    self.rationale.take()
+  }
+
+  fn take_timeout(&mut self) -> Option<DecisionTimeout> {
+    self.clock.take_timeout()
+  }
+
+  fn protocol(&self) -> Option<AgentProtocol> {
+    self.protocol.clone()
+  }
+
+  async fn wait_for_ready(&mut self) {
+    while !self.disconnected {
+      let event = AgentReqEvent::WaitForReady {
+        id: self.id_gen.gen_next(),
+      };
+      match self.transport.request(&event, None).await {
+        Ok(AgentRespEvent::WaitForReady { .. }) => return,
+        Ok(resp) => self.unexpected(Some(resp)),
+        Err(e) => {
+          error!("{} {}", self.transport.name(), e);
+          self.reconnect().await;
+        },
+      }
+    }
+  }
+
+  async fn choose_init_card(&mut self, obs: &Obs, c0: Card, c1: Card) -> Card {
+    let resp = self
+      .decide(obs, |id, obs| AgentReqEvent::ChooseInitCard { id, obs, c0, c1 })
+      .await;
+    match resp {
+      Some(AgentRespEvent::InitCard { chosen, .. }) => chosen,
+      resp => {
+        self.unexpected(resp);
+        self.fallback.choose_init_card(obs, c0, c1).await
+      },
+    }
+  }
+
+  async fn choose_role(&mut self, obs: &Obs, roles: RoleSet) -> Role {
+    let resp = self
+      .decide(obs, |id, obs| AgentReqEvent::ChooseRole { id, obs, roles })
+      .await;
+    match resp {
+      Some(AgentRespEvent::Role { chosen, .. }) => chosen,
+      resp => {
+        self.unexpected(resp);
+        self.fallback.choose_role(obs, roles).await
+      },
+    }
+  }
+
+  async fn choose_kill_target(&mut self, obs: &Obs, choices: RoleSet) -> Role {
+    let resp = self
+      .decide(obs, |id, obs| AgentReqEvent::ChooseKillTarget { id, obs, choices })
+      .await;
+    match resp {
+      Some(AgentRespEvent::KillTarget { chosen, .. }) => chosen,
+      resp => {
+        self.unexpected(resp);
+        self.fallback.choose_kill_target(obs, choices).await
+      },
+    }
+  }
+
+  async fn choose_steal_target(&mut self, obs: &Obs, choices: RoleSet) -> Role {
+    let resp = self
+      .decide(obs, |id, obs| AgentReqEvent::ChooseStealTarget { id, obs, choices })
+      .await;
+    match resp {
+      Some(AgentRespEvent::StealTarget { chosen, .. }) => chosen,
+      resp => {
+        self.unexpected(resp);
+        self.fallback.choose_steal_target(obs, choices).await
+      },
+    }
+  }
+
+  async fn choose_swap_target(&mut self, obs: &Obs) -> MagicianSkill {
+    let resp = self
+      .decide(obs, |id, obs| AgentReqEvent::ChooseMagicTarget { id, obs })
+      .await;
+    match resp {
+      Some(AgentRespEvent::MagicTarget { chosen, .. }) => chosen,
+      resp => {
+        self.unexpected(resp);
+        self.fallback.choose_swap_target(obs).await
+      },
+    }
+  }
+
+  async fn choose_destory_target(&mut self, obs: &Obs, choices: &[DestroyTarget]) -> Option<DestroyTarget> {
+    let resp = self
+      .decide(obs, |id, obs| AgentReqEvent::ChooseDestoryTarget {
+        id,
+        obs,
+        choices: choices.to_vec(),
+      })
+      .await;
+    match resp {
+      Some(AgentRespEvent::DestoryTarget { chosen, .. }) => chosen,
+      resp => {
+        self.unexpected(resp);
+        self.fallback.choose_destory_target(obs, choices).await
+      },
+    }
+  }
+
+  async fn choose_tomb(&mut self, obs: &Obs, c: Card) -> bool {
+    let resp = self
+      .decide(obs, |id, obs| AgentReqEvent::ChooseTomb { id, obs, c })
+      .await;
+    match resp {
+      Some(AgentRespEvent::Tomb { chosen, .. }) => chosen,
+      resp => {
+        self.unexpected(resp);
+        self.fallback.choose_tomb(obs, c).await
+      },
+    }
+  }
+
+  async fn choose_oper(&mut self, obs: &Obs, choices: &[Oper]) -> Oper {
+    let resp = self
+      .decide(obs, |id, obs| AgentReqEvent::ChooseOper {
+        id,
+        obs,
+        choices: choices.to_vec(),
+      })
+      .await;
+    match resp {
+      Some(AgentRespEvent::Oper { chosen, .. }) => chosen,
+      resp => {
+        self.unexpected(resp);
+        self.fallback.choose_oper(obs, choices).await
+      },
+    }
+  }
+
+  async fn choose_from_2(&mut self, obs: &Obs, c0: Card, c1: Card) -> Card {
+    let resp = self
+      .decide(obs, |id, obs| AgentReqEvent::ChooseFrom2 { id, obs, c0, c1 })
+      .await;
+    match resp {
+      Some(AgentRespEvent::From2 { chosen, .. }) => chosen,
+      resp => {
+        self.unexpected(resp);
+        self.fallback.choose_from_2(obs, c0, c1).await
+      },
+    }
+  }
+
+  async fn choose_from_3(&mut self, obs: &Obs, c0: Card, c1: Card, c2: Card) -> Card {
+    let resp = self
+      .decide(obs, |id, obs| AgentReqEvent::ChooseFrom3 { id, obs, c0, c1, c2 })
+      .await;
+    match resp {
+      Some(AgentRespEvent::From3 { chosen, .. }) => chosen,
+      resp => {
+        self.unexpected(resp);
+        self.fallback.choose_from_3(obs, c0, c1, c2).await
+      },
+    }
+  }
+}
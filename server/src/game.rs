@@ -1,28 +1,44 @@
 use std::cmp::Ordering;
+use std::panic::AssertUnwindSafe;
+
+use futures::FutureExt;
 
-use rand::Rng;
 use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 use crate::RoleSelectService;
 use crate::abstract_fa_agent::AbstractFAAgent;
 use crate::abstract_fyi_agent::AbstractFYIAgent;
 use crate::deck::Deck;
-use crate::domain::{Camp, OptionRole, PlayerIndex, RoleSet};
+use crate::domain::{Camp, OptionRole, PlayerIndex, Role, RoleSet};
+use crate::fa_agents::{GuardedFAAgent, HeuristicFAAgent, HeuristicWeights};
+use crate::game_error::{GameError, GameOutcome};
+use crate::game_report::{GameReport, PlayerReport, RoundRole, TeamReport};
 use crate::history::History;
+use crate::invariants;
 use crate::obs::Obs;
 use crate::player::Player;
 use crate::player_indexed_vec::PlayerIndexedVec;
+use crate::scenario::{Scenario, ScenarioPlayer};
 use crate::services::{InitService, RoleExecutionService};
 
 pub struct Game {
   num_players: usize,
   players: PlayerIndexedVec<Player>,
-  fa_agents: PlayerIndexedVec<Box<dyn AbstractFAAgent>>,
+  fa_agents: PlayerIndexedVec<GuardedFAAgent>,
   fyi_agents: PlayerIndexedVec<Box<dyn AbstractFYIAgent>>,
   crown: PlayerIndex,
   deck: Deck,
   observes: PlayerIndexedVec<Obs>,
   history: History,
+  first_round: u32,
+  last_round: Option<u32>,
+  drop_roles: Vec<Role>,
+  from_scenario: bool,
+  check_invariants: bool,
+  check_leaks: bool,
+  round_snapshot: Option<Scenario>,
+  report: Option<GameReport>,
 }
 
 pub struct RoundStats {
@@ -33,6 +49,7 @@ pub struct RoundStats {
   pub stealer: Option<PlayerIndex>, // TODO: replace with offset
   pub has_first_8_buildings: bool,
   pub crown: PlayerIndex,
+  pub revealed_roles: RoleSet, // 已经翻开的角色
 }
 
 impl Game {
@@ -48,19 +65,125 @@ impl Game {
     Self {
       num_players,
       players,
-      fa_agents: agents,
+      fa_agents: guard(agents),
       fyi_agents,
       crown,
       deck,
       observes: PlayerIndexedVec::<Obs>::new(),
       history,
+      first_round: 1,
+      last_round: None,
+      drop_roles: Vec::new(),
+      from_scenario: false,
+      check_invariants: false,
+      check_leaks: false,
+      round_snapshot: None,
+      report: None,
+    }
+  }
+
+  // 从自定义局面开局, agents 按座位号排列. 局面中没有写 seed 时用 rng
+  pub fn from_scenario(
+    scenario: &Scenario, agents: PlayerIndexedVec<Box<dyn AbstractFAAgent>>,
+    fyi_agents: PlayerIndexedVec<Box<dyn AbstractFYIAgent>>, rng: StdRng, history: History,
+  ) -> anyhow::Result<Self> {
+    scenario.validate()?;
+    let num_players = scenario.players.len();
+    anyhow::ensure!(
+      agents.len() == num_players && fyi_agents.len() == num_players,
+      "scenario has {} players, got {} agents",
+      num_players,
+      agents.len()
+    );
+
+    let mut players = PlayerIndexedVec::<Player>::new();
+    for (i, p) in scenario.players.iter().enumerate() {
+      let name = if p.name.is_empty() {
+        format!("player{}", i)
+      } else {
+        p.name.clone()
+      };
+      let mut player = Player::new(uuid::Uuid::new_v4(), name, p.camp);
+      player.set_index(PlayerIndex::from_usize(i));
+      player.set_gold(p.gold);
+      player.stats_mut().gold_income.initial = p.gold;
+      for &card in p.hand.iter() {
+        player.add_card(card);
+      }
+      for &card in p.buildings.iter() {
+        player.add_building(card);
+      }
+      players.push(player);
+    }
+
+    let mut rng = match scenario.seed {
+      Some(seed) => StdRng::seed_from_u64(seed),
+      None => rng,
+    };
+    let deck_cards = scenario.deck_cards(&mut rng);
+    let deck = Deck::from_cards(rng, deck_cards, scenario.drop.clone());
+
+    Ok(Self {
+      num_players,
+      players,
+      fa_agents: guard(agents),
+      fyi_agents,
+      crown: PlayerIndex::from_usize(scenario.crown),
+      deck,
+      observes: PlayerIndexedVec::<Obs>::new(),
+      history,
+      first_round: scenario.round,
+      last_round: scenario.rounds.map(|rounds| scenario.round + rounds - 1),
+      drop_roles: scenario.drop_roles.clone(),
+      from_scenario: true,
+      check_invariants: false,
+      check_leaks: false,
+      round_snapshot: None,
+      report: None,
+    })
+  }
+
+  // 每次状态变化后和每次向 agent 提问前检查引擎不变量, 违反时 run 返回 GameError::Invariant. 同时在每轮开始时记下局面, 见 round_snapshot
+  pub fn enable_invariant_checks(&mut self) {
+    self.check_invariants = true;
+  }
+
+  // 每次发出 obs 时检查其中没有玩家无权知道的信息, 有则 run 返回 GameError::Invariant. 见 leak_check
+  pub fn enable_leak_checks(&mut self) {
+    self.check_leaks = true;
+  }
+
+  // agent 出错时不换成 fallback, 直接以 GameError::Agent 结束. 用于 fuzz 和测试, 以免 agent 的 bug 被掩盖
+  pub fn enable_strict_agents(&mut self) {
+    for agent in self.fa_agents.iter_mut() {
+      agent.remove_fallback();
+    }
+  }
+
+  // 出错都以 GameError 一路返回. 这里只接住引擎自身 bug 造成的 panic, 只结束这一局
+  pub async fn run(&mut self) -> Result<GameOutcome, GameError> {
+    match AssertUnwindSafe(self.play()).catch_unwind().await {
+      Ok(outcome) => outcome,
+      Err(payload) => Err(GameError::from_panic(payload)),
+    }
+  }
+
+  fn check_history(&self) -> Result<(), GameError> {
+    if self.history.is_closed() {
+      return Err(GameError::Transport("history receiver closed".to_string()));
     }
+    Ok(())
   }
 
-  pub async fn run(&mut self) -> (f64, f64) {
+  async fn play(&mut self) -> Result<GameOutcome, GameError> {
     self.history.game_start(self.crown).await;
+    for i in (0..self.num_players).map(PlayerIndex::from_usize) {
+      if let Some(protocol) = self.fa_agents[i].protocol() {
+        self.history.agent_protocol(i, &protocol).await;
+      }
+    }
 
-    InitService {
+    let mut init_service = InitService {
       players: &mut self.players,
       history: &mut self.history,
       observes: &mut self.observes,
@@ -68,23 +191,43 @@ impl Game {
       deck: &mut self.deck,
       fa_agents: &mut self.fa_agents,
       fyi_agents: &mut self.fyi_agents,
+    };
+    if self.from_scenario {
+      init_service
+        .history
+        .load_scenario(self.first_round, self.crown, init_service.players, init_service.deck)
+        .await;
+      init_service.init_obs();
+      for i in (0..self.num_players).map(PlayerIndex::from_usize) {
+        self.observes[i].update_infos(&self.deck, &self.players, i);
+        self.fyi_agents[i].obs_changed(&self.observes[i]).await;
+      }
+    } else {
+      init_service.run().await?;
     }
-    .run()
-    .await;
+    self.check_history()?;
 
-    let mut round: u32 = 0;
+    let mut round: u32 = self.first_round - 1;
     loop {
       round += 1;
+      if self.check_invariants {
+        invariants::check_state(&self.deck, &self.players)?;
+        self.round_snapshot = Some(self.snapshot(round));
+      }
       let mut has_8_buildings = false;
-      self.run_round(round, &mut has_8_buildings).await;
-      self.check_total_card_number();
+      self.run_round(round, &mut has_8_buildings).await?;
+      self.check_total_card_number()?;
+      self.check_history()?;
 
-      if has_8_buildings {
-        self.history.finish_game().await;
+      if has_8_buildings || self.last_round.is_some_and(|last_round| round >= last_round) {
         break;
       }
     }
 
+    for player in self.players.iter_mut() {
+      player.set_final_round(round);
+    }
+
     let mut total_score = [0, 0];
 
     for player in self.players.iter() {
@@ -93,7 +236,7 @@ impl Game {
     // println!("total score 楚: {}", total_score[Camp::楚 as usize]);
     // println!("total score 汉: {}", total_score[Camp::汉 as usize]);
 
-    match total_score[Camp::楚 as usize].cmp(&total_score[Camp::汉 as usize]) {
+    let result = match total_score[Camp::楚 as usize].cmp(&total_score[Camp::汉 as usize]) {
       Ordering::Greater => {
         // println!("楚胜");
 
@@ -109,10 +252,34 @@ impl Game {
 
         (0.5, 0.5)
       },
-    }
+    };
+
+    // 终局加分要在 set_final_round 之后算
+    let report = GameReport {
+      rounds: round + 1 - self.first_round,
+      players: self.players.iter().map(PlayerReport::new).collect(),
+      teams: vec![
+        TeamReport {
+          camp: Camp::楚,
+          score: total_score[Camp::楚 as usize],
+          result: result.0,
+        },
+        TeamReport {
+          camp: Camp::汉,
+          score: total_score[Camp::汉 as usize],
+          result: result.1,
+        },
+      ],
+    };
+    self.history.game_report(&report).await;
+    self.history.finish_game().await;
+    self.check_history()?;
+    self.report = Some(report.clone());
+
+    Ok(GameOutcome { result, report })
   }
 
-  async fn run_round(&mut self, round: u32, has_8_buildings: &mut bool) {
+  async fn run_round(&mut self, round: u32, has_8_buildings: &mut bool) -> Result<(), GameError> {
     self.history.start_round(round, self.crown, &self.deck).await;
     for observer in (0..self.players.len()).map(PlayerIndex::from_usize) {
       self.observes[observer].set_round(round);
@@ -126,12 +293,15 @@ impl Game {
       fa_agents: &mut self.fa_agents,
       players: &mut self.players,
       round,
-      rng: self.deck.rng(),
+      deck: &mut self.deck,
       history: &mut self.history,
       crown: self.crown,
+      drop_roles: std::mem::take(&mut self.drop_roles),
+      check_invariants: self.check_invariants,
+      check_leaks: self.check_leaks,
     }
     .run()
-    .await;
+    .await?;
 
     RoleExecutionService {
       num_players: self.num_players,
@@ -142,24 +312,75 @@ impl Game {
       history: &mut self.history,
       round_stats: &mut round_stats,
       deck: &mut self.deck,
+      check_invariants: self.check_invariants,
+      check_leaks: self.check_leaks,
     }
     .run()
-    .await;
+    .await?;
 
     self.crown = round_stats.crown;
 
     for player in self.players.iter_mut() {
-      player.unset_role();
+      let role = player.role()?;
+      player.stats_mut().roles.push(RoundRole { round, role });
+      player.unset_role()?;
     }
 
-    for obs in self.observes.iter_mut() {
+    for (i, obs) in self.observes.iter_mut().enumerate() {
       obs.reset();
+      obs.update_infos(&self.deck, &self.players, PlayerIndex::from_usize(i));
     }
 
     *has_8_buildings = round_stats.has_first_8_buildings;
+    Ok(())
   }
 
-  pub fn check_total_card_number(&self) {
+  // 当前局面, 从 round 那一轮的选角色开始. 只在两轮之间有意义
+  pub fn snapshot(&self, round: u32) -> Scenario {
+    let players = self
+      .players
+      .iter()
+      .map(|player| ScenarioPlayer {
+        name: player.get_name().to_string(),
+        camp: player.camp(),
+        gold: player.gold(),
+        hand: player.cards().clone(),
+        buildings: player.iter_buildings().collect(),
+      })
+      .collect();
+    Scenario {
+      round,
+      crown: self.crown.value(),
+      players,
+      deck: self.deck.peek_deck().iter().rev().copied().collect(),
+      drop: self.deck.peek_drop().to_vec(),
+      shuffle_rest: false,
+      drop_roles: Vec::new(),
+      rounds: None,
+      seed: None,
+    }
+  }
+
+  // 开启不变量检查后, 最近一轮开始时的局面. 对局中途 panic 后可以用它复现
+  pub fn round_snapshot(&self) -> Option<&Scenario> {
+    self.round_snapshot.as_ref()
+  }
+
+  // 对局结束后的统计报告, run 结束前为 None
+  pub fn report(&self) -> Option<&GameReport> {
+    self.report.as_ref()
+  }
+
+  pub fn players(&self) -> &PlayerIndexedVec<Player> {
+    &self.players
+  }
+
+  // 按座位号排列的最终得分
+  pub fn scores(&self) -> Vec<u32> {
+    self.players.iter().map(|player| player.score()).collect()
+  }
+
+  pub fn check_total_card_number(&self) -> Result<(), GameError> {
     let mut total = 0;
     total += self.deck.peek_deck().len();
     total += self.deck.peek_drop().len();
@@ -167,6 +388,24 @@ impl Game {
       total += player.cards_len();
       total += player.buildings_len();
     }
-    assert_eq!(total, 66);
+    if total != 66 {
+      return Err(invariants::violation(
+        "card conservation",
+        format_args!("{} cards in play, expected 66", total),
+      ));
+    }
+    Ok(())
   }
 }
+
+// 每个座位的 agent 出错时换成启发式 agent, 它不分阵营
+fn guard(agents: PlayerIndexedVec<Box<dyn AbstractFAAgent>>) -> PlayerIndexedVec<GuardedFAAgent> {
+  agents
+    .into_iter()
+    .enumerate()
+    .map(|(i, agent)| {
+      let fallback: Box<dyn AbstractFAAgent> = Box::new(HeuristicFAAgent::new(HeuristicWeights::default()));
+      GuardedFAAgent::new(PlayerIndex::from_usize(i), agent, Some(fallback))
+    })
+    .collect()
+}
@@ -0,0 +1,57 @@
+// Game::run 的结果. 出错时对局中止, 其他对局不受影响.
+// 引擎内部的检查 (不变量, 严格模式下的 agent 出错) 返回 GameError, 一路用 ? 传到 Game::run
+
+use thiserror::Error;
+
+use crate::game_report::GameReport;
+
+#[derive(Debug, Clone)]
+pub struct GameOutcome {
+  pub result: (f64, f64), // 楚, 汉
+  pub report: GameReport,
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum GameError {
+  // agent panic 或做出了不合法的决策, 且没有 fallback 可以替换 (严格模式)
+  #[error("agent {actor}: {reason}")]
+  Agent { actor: usize, reason: String },
+
+  // 历史记录的接收端关闭了, 对局记录已经不完整
+  #[error("transport: {0}")]
+  Transport(String),
+
+  #[error("invariant {name}: {detail}")]
+  Invariant { name: String, detail: String },
+
+  // 其他的 panic, 都是引擎的 bug
+  #[error("panic: {0}")]
+  Panic(String),
+}
+
+impl GameError {
+  // 出错的种类: 不变量的名字, 其他错误的类别. fuzz 用它判断缩小后的局面是否还是同一个问题
+  pub fn kind(&self) -> &str {
+    match self {
+      GameError::Agent { .. } => "agent",
+      GameError::Transport(_) => "transport",
+      GameError::Invariant { name, .. } => name,
+      GameError::Panic(_) => "panic",
+    }
+  }
+
+  // Game::run 中接住的 panic, 只可能来自引擎的 bug
+  pub(crate) fn from_panic(payload: Box<dyn std::any::Any + Send>) -> Self {
+    GameError::Panic(panic_message(payload))
+  }
+}
+
+pub(crate) fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+  match payload.downcast::<String>() {
+    Ok(message) => *message,
+    Err(payload) => match payload.downcast::<&str>() {
+      Ok(message) => message.to_string(),
+      Err(_) => "unknown panic".to_string(),
+    },
+  }
+}
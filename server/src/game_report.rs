@@ -0,0 +1,102 @@
+// 对局结束后的统计报告, 由 Game 在结束时生成, 写入历史记录, 也可以通过 Game::report 取得.
+// 报告里的信息在对局结束后都是公开的, 各视角的历史记录只按座位旋转.
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{Camp, Card, Role};
+use crate::player::Player;
+
+// 分数构成, total 为各项之和
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ScoreBreakdown {
+  pub base: u32, // 建筑费用之和
+  pub all_colors: u32,
+  pub eight_buildings: u32,
+  pub first_eight_buildings: u32,
+  pub bonus: u32, // 龙门/大学各 +2
+  pub total: u32,
+}
+
+// 金币收入按来源分类
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct GoldIncome {
+  pub initial: u32,    // 开局发的, 自定义局面为局面中的金币
+  pub resources: u32,  // 回合中拿钱的 2 金
+  pub rent: u32,       // 拿钱时按建筑颜色收的租
+  pub merchant: u32,   // 商人技能
+  pub stolen: u32,     // 小偷偷来的
+  pub sold_cards: u32, // 实验室卖牌
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RoundRole {
+  pub round: u32,
+  pub role: Role,
+}
+
+// 对局中累计的统计, 由 service 在事件发生时记录
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PlayerStats {
+  pub gold_income: GoldIncome,
+  pub cards_drawn: u32, // 从牌堆进入手牌的牌
+  pub cards_built: u32,
+  pub cards_sold: u32,
+  pub buildings_destroyed: u32, // 作为军阀拆掉别人的
+  pub buildings_lost: u32,      // 被别人拆掉的
+  pub times_killed: u32,
+  pub times_robbed: u32,
+  pub roles: Vec<RoundRole>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PlayerReport {
+  pub name: String,
+  pub camp: Camp,
+  pub score: ScoreBreakdown,
+  pub final_gold: u32,
+  pub buildings: Vec<Card>,
+  #[serde(flatten)]
+  pub stats: PlayerStats,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TeamReport {
+  pub camp: Camp,
+  pub score: u32,
+  pub result: f64, // 胜 1, 平 0.5, 负 0
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GameReport {
+  pub rounds: u32,
+  pub players: Vec<PlayerReport>, // 按座位号排列
+  pub teams: Vec<TeamReport>,     // 楚, 汉
+}
+
+impl PlayerReport {
+  pub(crate) fn new(player: &Player) -> Self {
+    let buildings = player.iter_buildings().collect::<Vec<_>>();
+    let base = buildings.iter().map(|card| card.fee()).sum::<u32>();
+    let bonus = buildings.iter().map(|&card| card.score() - card.fee()).sum::<u32>();
+    let score = ScoreBreakdown {
+      base,
+      all_colors: if player.has_all_colors() { 3 } else { 0 },
+      eight_buildings: if buildings.len() == 8 { 2 } else { 0 },
+      first_eight_buildings: if player.is_first_8_buildings() { 2 } else { 0 },
+      bonus,
+      total: player.score(),
+    };
+    debug_assert_eq!(
+      score.total,
+      score.base + score.all_colors + score.eight_buildings + score.first_eight_buildings + score.bonus
+    );
+    Self {
+      name: player.get_name().to_string(),
+      camp: player.camp(),
+      score,
+      final_gold: player.gold(),
+      buildings,
+      stats: player.stats().clone(),
+    }
+  }
+}
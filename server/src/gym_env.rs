@@ -0,0 +1,215 @@
+// 强化学习环境: 一个座位由外部的学习者控制, 其余座位由指定的 agent 控制.
+//
+// reset 开局并运行到学习者的第一个决策, step 提交动作并运行到学习者的下一个决策或者对局结束.
+// 动作空间和特征与 dataset 相同. 只有最后一步有奖励, 为学习者所在队伍的结果: 胜 1, 平 0.5, 负 0.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::abstract_fa_agent::AbstractFAAgent;
+use crate::abstract_fyi_agent::AbstractFYIAgent;
+use crate::agent_protocol::DecisionKind;
+use crate::agent_spec::AgentSpec;
+use crate::domain::Camp;
+use crate::fa_agents::{GymDecision, GymFAAgent};
+use crate::fyi_agents::NoopFYIAgent;
+use crate::game::Game;
+use crate::game_error::{GameError, GameOutcome};
+use crate::history::History;
+use crate::obs::Obs;
+use crate::player::Player;
+use crate::player_indexed_vec::PlayerIndexedVec;
+
+pub const LEARNER: &str = "learner";
+
+#[derive(Serialize)]
+pub struct GymStep {
+  pub obs: Option<Obs>,
+  pub features: Option<Vec<f32>>,
+  pub kind: Option<DecisionKind>,
+  pub mask: Vec<bool>,
+  pub reward: f32,
+  pub done: bool,
+}
+
+pub struct GymEnv {
+  specs: HashMap<String, AgentSpec>,
+  game: Option<JoinHandle<Result<GameOutcome, GameError>>>,
+  decision_receiver: Option<mpsc::Receiver<GymDecision>>,
+  pending: Option<GymDecision>,
+  learner_camp: Camp,
+}
+
+impl Default for GymEnv {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl GymEnv {
+  pub fn new() -> Self {
+    Self {
+      specs: HashMap::new(),
+      game: None,
+      decision_receiver: None,
+      pending: None,
+      learner_camp: Camp::汉,
+    }
+  }
+
+  // lineup 为每个座位的 agent: learner 或者 AgentSpec 的写法.
+  // 必须恰好有一个 learner. 偶数座位为汉, 奇数座位为楚, v2 只能坐在汉的座位上.
+  // seed 决定发牌, 初始皇冠和对手 agent 的随机数. exec 的外部进程不受控制, 只有它的 fallback 用 seed
+  pub async fn reset(&mut self, seed: u64, lineup: &[String]) -> anyhow::Result<GymStep> {
+    self.close();
+
+    if lineup.len() != 4 && lineup.len() != 6 {
+      bail!("lineup must have 4 or 6 seats, got {}", lineup.len());
+    }
+    if lineup.iter().filter(|name| *name == LEARNER).count() != 1 {
+      bail!("lineup must contain exactly one {}", LEARNER);
+    }
+    // 在创建任何 agent (包括启动外部进程) 之前检查整个 lineup
+    let mut specs = Vec::new();
+    for (i, agent) in lineup.iter().enumerate() {
+      if agent == LEARNER {
+        specs.push(None);
+        continue;
+      }
+      let spec = self.parse_spec(agent).map_err(|e| anyhow!("seat {}: {}", i, e))?;
+      spec.check_seat(i, camp(i))?;
+      specs.push(Some(spec));
+    }
+
+    let (decision_sender, decision_receiver) = mpsc::channel(1);
+    let names = ["刘邦", "项羽", "张良", "范增", "韩信", "龙且"];
+    let mut players = PlayerIndexedVec::<Player>::new();
+    let mut agents = PlayerIndexedVec::<Box<dyn AbstractFAAgent>>::new();
+    let mut fyi_agents = PlayerIndexedVec::<Box<dyn AbstractFYIAgent>>::new();
+    for (i, spec) in specs.iter().enumerate() {
+      let camp = camp(i);
+      players.push(Player::new(uuid::Uuid::new_v4(), names[i].to_string(), camp));
+      let agent: Box<dyn AbstractFAAgent> = match spec {
+        Some(spec) => spec.agent(agent_seed(seed, i)).await?,
+        None => {
+          self.learner_camp = camp;
+          Box::new(GymFAAgent::new(decision_sender.clone()))
+        },
+      };
+      agents.push(agent);
+      fyi_agents.push(Box::new(NoopFYIAgent::new()));
+    }
+    drop(decision_sender);
+
+    let (history_req_bcast_sender, mut history_req_bcast_receiver) = mpsc::channel::<String>(1024);
+    let (_, history_resp_receiver) = mpsc::channel::<String>(1024);
+    let history = History::new(history_req_bcast_sender, history_resp_receiver);
+    tokio::spawn(async move { while history_req_bcast_receiver.recv().await.is_some() {} });
+
+    let num_players = lineup.len();
+    let rng = StdRng::seed_from_u64(seed);
+    self.game = Some(tokio::spawn(async move {
+      let mut game = Game::new(num_players, players, agents, fyi_agents, rng, history).await;
+      // 学习者的决策都经过 mask, 出错说明环境有 bug, 不要悄悄换成 fallback
+      game.enable_strict_agents();
+      game.run().await
+    }));
+    self.decision_receiver = Some(decision_receiver);
+
+    self.advance().await
+  }
+
+  pub async fn step(&mut self, action: usize) -> anyhow::Result<GymStep> {
+    let decision = self
+      .pending
+      .take()
+      .ok_or_else(|| anyhow!("no pending decision, call reset first"))?;
+    if !decision.mask.get(action).copied().unwrap_or(false) {
+      self.pending = Some(decision);
+      bail!("illegal action {}", action);
+    }
+    decision
+      .action_sender
+      .send(action)
+      .map_err(|_| anyhow!("game stopped unexpectedly"))?;
+    self.advance().await
+  }
+
+  pub fn close(&mut self) {
+    self.pending = None;
+    self.decision_receiver = None;
+    if let Some(game) = self.game.take() {
+      game.abort();
+    }
+  }
+
+  // 权重文件只在第一次用到时加载
+  fn parse_spec(&mut self, spec: &str) -> anyhow::Result<AgentSpec> {
+    if let Some(parsed) = self.specs.get(spec) {
+      return Ok(parsed.clone());
+    }
+    let parsed = AgentSpec::parse(spec)?;
+    self.specs.insert(spec.to_string(), parsed.clone());
+    Ok(parsed)
+  }
+
+  async fn advance(&mut self) -> anyhow::Result<GymStep> {
+    let receiver = self
+      .decision_receiver
+      .as_mut()
+      .ok_or_else(|| anyhow!("call reset first"))?;
+    match receiver.recv().await {
+      Some(decision) => {
+        let step = GymStep {
+          features: Some(decision.obs.features()),
+          obs: Some(decision.obs.clone()),
+          kind: Some(decision.kind),
+          mask: decision.mask.clone(),
+          reward: 0.0,
+          done: false,
+        };
+        self.pending = Some(decision);
+        Ok(step)
+      },
+      None => {
+        // 学习者的 agent 随 Game 一起被 drop, 说明对局已经结束
+        self.decision_receiver = None;
+        let game = self.game.take().ok_or_else(|| anyhow!("call reset first"))?;
+        let (chu, han) = game.await??.result;
+        let reward = match self.learner_camp {
+          Camp::楚 => chu,
+          Camp::汉 => han,
+        };
+        Ok(GymStep {
+          obs: None,
+          features: None,
+          kind: None,
+          mask: Vec::new(),
+          reward: reward as f32,
+          done: true,
+        })
+      },
+    }
+  }
+}
+
+fn camp(seat: usize) -> Camp {
+  if seat.is_multiple_of(2) { Camp::汉 } else { Camp::楚 }
+}
+
+// 与 fuzz 相同, 每个座位的 agent 用从 seed 派生出的种子
+fn agent_seed(seed: u64, seat: usize) -> u64 {
+  seed ^ ((seat as u64 + 1) << 48)
+}
+
+impl Drop for GymEnv {
+  fn drop(&mut self) {
+    self.close();
+  }
+}
@@ -0,0 +1,709 @@
+// 提示服务: 给定某个座位当前的 Obs 和待做的决策, 在时间预算内估计每个合法动作的胜率, 按胜率排序返回建议.
+//
+// 胜率来自模拟: 每局先从 obs 猜一个与之相符的局面 (别人的手牌和牌堆从没见过的牌里随机发),
+// 从本轮选角色开始, 所有座位由配置好的 agent 打到结束. 自己的座位在本轮第一次能做候选动作时做它,
+// 选角色时选回 obs 里自己的角色. 模拟对所有候选轮流进行, 到了预算或者 max_rollouts 就停, 已有的结果照常返回.
+// 每局模拟在阻塞线程上运行, 超出预算时不再等待.
+// 另外让 agent 直接对当前决策做一次选择, 标出它的选择和解释. 选角色且配置了开局库时, 每个角色另外给出库中的平滑胜率.
+// 决策由浏览器转发, 先检查其中的选项与 obs 是否一致, 不一致的拒绝.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail};
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+use crate::abstract_fa_agent::AbstractFAAgent;
+use crate::abstract_fyi_agent::AbstractFYIAgent;
+use crate::agent_protocol::DecisionKind;
+use crate::agent_spec::AgentSpec;
+use crate::dataset::{
+  action_names, card_action, card_candidates, destroy_action, destroy_candidates, magic_action, magic_candidates,
+  oper_action, oper_candidates, role_action, role_candidates, tomb_action, tomb_candidates,
+};
+use crate::domain::{
+  AgentReqEvent, AgentRespEvent, Camp, Card, DestroyTarget, MagicianSkill, Oper, PlayerOffset, Rationale, Role,
+  RoleSet,
+};
+use crate::fa_agents::{HeuristicFAAgent, ROLE_BOOK_PRIOR_PICKS, RandomFAAgent};
+use crate::fyi_agents::NoopFYIAgent;
+use crate::game::Game;
+use crate::history::History;
+use crate::obs::{Obs, ObsMessage};
+use crate::player_indexed_vec::PlayerIndexedVec;
+use crate::role_book::RoleBook;
+use crate::scenario::{Scenario, ScenarioPlayer};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct HintConfig {
+  // AgentSpec 的写法, exec 除外
+  pub agent: String,
+  #[serde(default)]
+  pub role_book: Option<String>,
+  #[serde(default = "default_budget_ms")]
+  pub budget_ms: u64,
+  // 所有候选加起来最多模拟几局
+  #[serde(default = "default_max_rollouts")]
+  pub max_rollouts: usize,
+}
+
+const fn default_budget_ms() -> u64 {
+  500
+}
+
+const fn default_max_rollouts() -> usize {
+  256
+}
+
+#[derive(Serialize, Debug)]
+pub struct HintSuggestion {
+  // 采纳该建议时应当回复的事件
+  pub response: AgentRespEvent,
+  pub action: usize,
+  pub name: String,
+  // 模拟中做这个动作的一方的胜率 (平局算半局), 没有模拟完一局时为 None
+  pub win_rate: Option<f64>,
+  pub rollouts: usize,
+  // agent 直接做决策时选的就是它
+  pub agent_choice: bool,
+  // 开局库中选该角色的平滑胜率, 只有选角色时才有
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub role_win_rate: Option<f64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub rationale: Option<Rationale>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Hint {
+  pub suggestions: Vec<HintSuggestion>,
+  pub rollouts: usize,
+  pub elapsed_ms: u64,
+}
+
+pub struct HintService {
+  agent: AgentSpec,
+  role_book: Option<Arc<RoleBook>>,
+  budget: Duration,
+  max_rollouts: usize,
+  action_names: Vec<String>,
+}
+
+impl HintService {
+  pub fn new(config: &HintConfig) -> anyhow::Result<Self> {
+    let agent = AgentSpec::parse(&config.agent)?;
+    // 提示要在请求里同步算完, 不能等外部进程
+    if let AgentSpec::Exec(_) = agent {
+      bail!("hint agent must run in-process, got {}", config.agent);
+    }
+    let role_book = config
+      .role_book
+      .as_deref()
+      .map(RoleBook::load)
+      .transpose()?
+      .map(Arc::new);
+    Ok(Self {
+      agent,
+      role_book,
+      budget: Duration::from_millis(config.budget_ms),
+      max_rollouts: config.max_rollouts,
+      action_names: action_names(),
+    })
+  }
+
+  // 自己座位上的 agent, 配置了开局库时启发式 agent 用它选角色
+  fn build_agent(&self, seed: u64) -> anyhow::Result<Box<dyn AbstractFAAgent>> {
+    match (&self.agent, &self.role_book) {
+      (AgentSpec::Heuristic(weights), Some(role_book)) => Ok(Box::new(HeuristicFAAgent::with_role_book(
+        weights.as_ref().clone(),
+        role_book.clone(),
+      ))),
+      (agent, _) => agent.local_agent(seed),
+    }
+  }
+
+  // 模拟中其他座位的 agent, 配置的 agent 坐不了的阵营 (v2 坐楚) 用 random
+  fn villain_agent(&self, camp: Camp, seed: u64) -> anyhow::Result<Box<dyn AbstractFAAgent>> {
+    if self.agent.supports(camp) {
+      self.agent.local_agent(seed)
+    } else {
+      Ok(Box::new(RandomFAAgent::with_seed(seed)))
+    }
+  }
+
+  fn rollout(&self, obs: &Obs, forced: (DecisionKind, usize), seed: u64) -> anyhow::Result<Rollout> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let scenario = determinize(obs, &mut rng)?;
+    let mut agents = PlayerIndexedVec::<Box<dyn AbstractFAAgent>>::new();
+    agents.push(Box::new(RolloutHeroAgent {
+      inner: self.build_agent(rng.random())?,
+      round: obs.round(),
+      role: obs.hero_role(),
+      forced: Some(forced),
+    }));
+    for player in &scenario.players[1..] {
+      agents.push(self.villain_agent(player.camp, rng.random())?);
+    }
+    Ok(Rollout {
+      scenario,
+      agents,
+      camp: obs.hero_camp(),
+      seed: rng.random(),
+    })
+  }
+
+  pub async fn hint(&self, decision: &AgentReqEvent) -> anyhow::Result<Hint> {
+    let start = Instant::now();
+    let deadline = tokio::time::Instant::from_std(start + self.budget);
+    // 浏览器转发的决策都带完整的 obs
+    let Some(obs) = decision.obs().and_then(ObsMessage::full) else {
+      bail!("{:?} is not a decision with a full obs", decision);
+    };
+    check_decision(obs, decision)?;
+    self.agent.check_seat(0, obs.hero_camp())?;
+    let (kind, candidates) = candidates(obs, decision)?;
+
+    // agent 的决策是同步计算的, 放到阻塞线程上才能在超时后不再等它. 预算用完了就不再开始新的计算
+    let mut agent_choice = None;
+    let mut rationale = None;
+    if tokio::time::Instant::now() < deadline {
+      let mut agent = self.build_agent(rand::random())?;
+      let (agent_obs, agent_decision) = (obs.clone(), decision.clone());
+      let task = tokio::task::spawn_blocking(move || {
+        let action = futures::executor::block_on(decide(agent.as_mut(), &agent_obs, &agent_decision));
+        (action, agent.take_rationale())
+      });
+      if let Ok(joined) = tokio::time::timeout_at(deadline, task).await {
+        let (action, taken) = joined?;
+        agent_choice = Some(action?);
+        rationale = taken;
+      }
+    }
+
+    // 所有候选轮流模拟, 同时在跑的不超过候选的个数
+    let mut results = vec![(0usize, 0.0f64); candidates.len()];
+    let mut started = 0;
+    let mut join_set = JoinSet::new();
+    loop {
+      while started < self.max_rollouts && join_set.len() < candidates.len() && tokio::time::Instant::now() < deadline
+      {
+        let index = started % candidates.len();
+        let rollout = self.rollout(obs, (kind, candidates[index].0), rand::random())?;
+        join_set.spawn_blocking(move || (index, futures::executor::block_on(rollout.run())));
+        started += 1;
+      }
+      let Ok(Some(joined)) = tokio::time::timeout_at(deadline, join_set.join_next()).await else {
+        break;
+      };
+      let (index, result) = joined?;
+      let (rollouts, wins) = &mut results[index];
+      *rollouts += 1;
+      *wins += result?;
+    }
+
+    let role_win_rates = match (decision, &self.role_book) {
+      (AgentReqEvent::ChooseRole { roles, .. }, Some(role_book)) => {
+        role_book.prior(obs, *roles, ROLE_BOOK_PRIOR_PICKS)
+      },
+      _ => Vec::new(),
+    };
+
+    let mut rationales = HashMap::new();
+    if let (Some(action), Some(rationale)) = (agent_choice, rationale) {
+      rationales.insert(action, rationale);
+    }
+    let mut suggestions = candidates
+      .into_iter()
+      .zip(results)
+      .map(|((action, response), (rollouts, wins))| HintSuggestion {
+        response,
+        action,
+        name: self.action_names[action].clone(),
+        win_rate: (rollouts > 0).then(|| wins / rollouts as f64),
+        rollouts,
+        agent_choice: agent_choice == Some(action),
+        role_win_rate: role_win_rates
+          .iter()
+          .find(|(role, _)| role_action(*role) == action)
+          .map(|(_, win_rate)| *win_rate),
+        rationale: rationales.remove(&action),
+      })
+      .collect::<Vec<_>>();
+    // 没有模拟结果的排在最后; 胜率相同时 agent 的选择在前, 再按开局库的胜率, 最后按动作编号
+    suggestions.sort_by(|a, b| {
+      b.win_rate
+        .unwrap_or(-1.0)
+        .total_cmp(&a.win_rate.unwrap_or(-1.0))
+        .then(b.agent_choice.cmp(&a.agent_choice))
+        .then(
+          b.role_win_rate
+            .unwrap_or(0.0)
+            .total_cmp(&a.role_win_rate.unwrap_or(0.0)),
+        )
+        .then(a.action.cmp(&b.action))
+    });
+
+    Ok(Hint {
+      rollouts: suggestions.iter().map(|s| s.rollouts).sum(),
+      suggestions,
+      elapsed_ms: start.elapsed().as_millis() as u64,
+    })
+  }
+}
+
+// 与 obs 相符的一个局面, 从本轮选角色开始, 自己坐 0 号, offset 为 k 的玩家坐 k 号.
+// 看得见的金币, 建筑和自己的手牌照抄; 别人的手牌从没见过的牌里随机发, 剩下的洗匀作为牌堆
+fn determinize(obs: &Obs, rng: &mut StdRng) -> anyhow::Result<Scenario> {
+  let mut unseen = Card::iter()
+    .flat_map(|card| std::iter::repeat_n(card, card.number() as usize))
+    .collect::<Vec<_>>();
+  let mut remove = |card: Card| match unseen.iter().position(|&c| c == card) {
+    Some(index) => Ok(unseen.swap_remove(index)),
+    None => Err(anyhow!("obs has more {} than the deck", card.name())),
+  };
+  let hero = ScenarioPlayer {
+    name: String::new(),
+    camp: obs.hero_camp(),
+    gold: obs.hero_gold(),
+    hand: obs.hero_cards().to_vec(),
+    buildings: obs.hero_buildings().collect(),
+  };
+  for &card in hero.hand.iter().chain(&hero.buildings) {
+    remove(card)?;
+  }
+  let mut players = vec![hero];
+  for i in 1..obs.num_players() {
+    let offset = PlayerOffset::from_usize(i);
+    let buildings = obs.villain_buildings(offset).collect::<Vec<_>>();
+    for &card in &buildings {
+      remove(card)?;
+    }
+    players.push(ScenarioPlayer {
+      name: String::new(),
+      camp: obs.villain_camp(offset),
+      gold: obs.villain_gold(offset),
+      hand: Vec::new(),
+      buildings,
+    });
+  }
+  unseen.shuffle(rng);
+  for (i, player) in players.iter_mut().enumerate().skip(1) {
+    let num_cards = obs.villain_num_cards(PlayerOffset::from_usize(i));
+    if num_cards > unseen.len() {
+      bail!("obs has more cards in hand than the deck");
+    }
+    player.hand = unseen.split_off(unseen.len() - num_cards);
+  }
+
+  // 公开弃掉的角色照旧, 暗弃的随机
+  let drop_roles = Role::population()
+    .into_iter()
+    .filter(|&role| obs.roles_public_dropped().contains(role))
+    .collect();
+  let scenario = Scenario {
+    round: obs.round(),
+    crown: obs.crown().value(),
+    players,
+    deck: unseen,
+    drop: Vec::new(),
+    shuffle_rest: false,
+    drop_roles,
+    rounds: None,
+    seed: Some(rng.random()),
+  };
+  scenario.validate()?;
+  Ok(scenario)
+}
+
+struct Rollout {
+  scenario: Scenario,
+  agents: PlayerIndexedVec<Box<dyn AbstractFAAgent>>,
+  camp: Camp,
+  seed: u64,
+}
+
+impl Rollout {
+  // 0 号所在阵营的结果: 胜 1, 平 0.5, 负 0
+  async fn run(self) -> anyhow::Result<f64> {
+    let (history_req_bcast_sender, mut history_req_bcast_receiver) = mpsc::channel::<String>(1024);
+    let (_, history_resp_receiver) = mpsc::channel::<String>(1024);
+    let history = History::new(history_req_bcast_sender, history_resp_receiver);
+    let mut fyi_agents = PlayerIndexedVec::<Box<dyn AbstractFYIAgent>>::new();
+    for _ in 0..self.scenario.players.len() {
+      fyi_agents.push(Box::new(NoopFYIAgent::new()));
+    }
+    let mut game = Game::from_scenario(
+      &self.scenario,
+      self.agents,
+      fyi_agents,
+      StdRng::seed_from_u64(self.seed),
+      history,
+    )?;
+    // 在同一个线程上一边打一边丢掉历史记录, Game 结束后 drop 掉发送端, 接收端才会结束
+    let play = async move { game.run().await };
+    let drain = async { while history_req_bcast_receiver.recv().await.is_some() {} };
+    let (outcome, ()) = futures::join!(play, drain);
+    let (chu, han) = outcome?.result;
+    Ok(match self.camp {
+      Camp::楚 => chu,
+      Camp::汉 => han,
+    })
+  }
+}
+
+// 模拟中自己座位的 agent: 本轮选角色时选回 obs 里的角色, 第一次能做候选动作时做它, 其余交给 inner
+struct RolloutHeroAgent {
+  inner: Box<dyn AbstractFAAgent>,
+  round: u32,
+  role: Option<Role>,
+  forced: Option<(DecisionKind, usize)>,
+}
+
+impl RolloutHeroAgent {
+  fn force<T: Clone>(&mut self, obs: &Obs, kind: DecisionKind, candidates: &[(usize, T)]) -> Option<T> {
+    if obs.round() != self.round {
+      self.forced = None;
+    }
+    let (forced_kind, action) = self.forced?;
+    if forced_kind != kind {
+      return None;
+    }
+    let (_, chosen) = candidates.iter().find(|(a, _)| *a == action)?;
+    self.forced = None;
+    Some(chosen.clone())
+  }
+}
+
+#[async_trait]
+impl AbstractFAAgent for RolloutHeroAgent {
+  fn name(&self) -> &str {
+    "RolloutHeroAgent"
+  }
+
+  async fn wait_for_ready(&mut self) {}
+
+  async fn choose_init_card(&mut self, obs: &Obs, c0: Card, c1: Card) -> Card {
+    match self.force(obs, DecisionKind::InitCard, &card_candidates(&[c0, c1])) {
+      Some(chosen) => chosen,
+      None => self.inner.choose_init_card(obs, c0, c1).await,
+    }
+  }
+
+  async fn choose_role(&mut self, obs: &Obs, roles: RoleSet) -> Role {
+    if let Some(chosen) = self.force(obs, DecisionKind::ChooseRole, &role_candidates(roles)) {
+      return chosen;
+    }
+    match self.role.take() {
+      Some(role) if obs.round() == self.round && roles.contains(role) => role,
+      _ => self.inner.choose_role(obs, roles).await,
+    }
+  }
+
+  async fn choose_kill_target(&mut self, obs: &Obs, choices: RoleSet) -> Role {
+    match self.force(obs, DecisionKind::Kill, &role_candidates(choices)) {
+      Some(chosen) => chosen,
+      None => self.inner.choose_kill_target(obs, choices).await,
+    }
+  }
+
+  async fn choose_steal_target(&mut self, obs: &Obs, choices: RoleSet) -> Role {
+    match self.force(obs, DecisionKind::Steal, &role_candidates(choices)) {
+      Some(chosen) => chosen,
+      None => self.inner.choose_steal_target(obs, choices).await,
+    }
+  }
+
+  async fn choose_swap_target(&mut self, obs: &Obs) -> MagicianSkill {
+    match self.force(obs, DecisionKind::Magic, &magic_candidates(obs)) {
+      Some(chosen) => chosen,
+      None => self.inner.choose_swap_target(obs).await,
+    }
+  }
+
+  async fn choose_destory_target(&mut self, obs: &Obs, choices: &[DestroyTarget]) -> Option<DestroyTarget> {
+    match self.force(obs, DecisionKind::Destroy, &destroy_candidates(choices)) {
+      Some(chosen) => chosen,
+      None => self.inner.choose_destory_target(obs, choices).await,
+    }
+  }
+
+  async fn choose_tomb(&mut self, obs: &Obs, c: Card) -> bool {
+    match self.force(obs, DecisionKind::Tomb, &tomb_candidates()) {
+      Some(chosen) => chosen,
+      None => self.inner.choose_tomb(obs, c).await,
+    }
+  }
+
+  async fn choose_oper(&mut self, obs: &Obs, choices: &[Oper]) -> Oper {
+    match self.force(obs, DecisionKind::Oper, &oper_candidates(choices)) {
+      Some(chosen) => chosen,
+      None => self.inner.choose_oper(obs, choices).await,
+    }
+  }
+
+  async fn choose_from_2(&mut self, obs: &Obs, c0: Card, c1: Card) -> Card {
+    match self.force(obs, DecisionKind::ChooseFrom2, &card_candidates(&[c0, c1])) {
+      Some(chosen) => chosen,
+      None => self.inner.choose_from_2(obs, c0, c1).await,
+    }
+  }
+
+  async fn choose_from_3(&mut self, obs: &Obs, c0: Card, c1: Card, c2: Card) -> Card {
+    match self.force(obs, DecisionKind::ChooseFrom3, &card_candidates(&[c0, c1, c2])) {
+      Some(chosen) => chosen,
+      None => self.inner.choose_from_3(obs, c0, c1, c2).await,
+    }
+  }
+}
+
+// 决策中的选项必须是当前局面下可能出现的, 否则是伪造或过期的决策
+fn check_decision(obs: &Obs, decision: &AgentReqEvent) -> anyhow::Result<()> {
+  let overlaps = |roles: RoleSet, other: RoleSet| {
+    Role::population()
+      .into_iter()
+      .any(|r| roles.contains(r) && other.contains(r))
+  };
+  match decision {
+    AgentReqEvent::ChooseRole { roles, .. }
+      if roles.is_empty() || overlaps(*roles, obs.roles_public_dropped() | obs.roles_chosen_before()) =>
+    {
+      bail!("roles {:?} are not available", roles);
+    },
+    AgentReqEvent::ChooseKillTarget { choices, .. } if choices.is_empty() || choices.contains(Role::刺客) => {
+      bail!("kill targets {:?} are not legal", choices);
+    },
+    AgentReqEvent::ChooseStealTarget { choices, .. }
+      if choices.is_empty() || choices.contains(Role::刺客) || choices.contains(Role::小偷) =>
+    {
+      bail!("steal targets {:?} are not legal", choices);
+    },
+    AgentReqEvent::ChooseDestoryTarget { choices, .. } => {
+      for target in choices {
+        let offset = target.player_offset;
+        if offset.value() == 0
+          || offset.value() >= obs.num_players()
+          || !obs.villain_has_building(offset, target.card)
+        {
+          bail!("destroy target {:?} is not a building of another player", target);
+        }
+      }
+    },
+    AgentReqEvent::ChooseOper { choices, .. } => {
+      if choices.is_empty() {
+        bail!("no opers to choose from");
+      }
+      for oper in choices {
+        if let Oper::Build(card) | Oper::SellCard(card) = oper
+          && !obs.hero_cards().contains(card)
+        {
+          bail!("{:?} needs a card that is not in hand", oper);
+        }
+      }
+    },
+    _ => {},
+  }
+  Ok(())
+}
+
+// 决策的种类和全部合法动作, 动作编号与 dataset 相同
+fn candidates(obs: &Obs, decision: &AgentReqEvent) -> anyhow::Result<(DecisionKind, Vec<(usize, AgentRespEvent)>)> {
+  let candidates = match decision {
+    AgentReqEvent::WaitForReady { .. }
+    | AgentReqEvent::Hello { .. }
+    | AgentReqEvent::Reject { .. }
+    | AgentReqEvent::Resumed { .. } => {
+      return Err(anyhow!("{:?} is not a decision", decision));
+    },
+    AgentReqEvent::ChooseInitCard { id, c0, c1, .. } => (
+      DecisionKind::InitCard,
+      card_candidates(&[*c0, *c1])
+        .into_iter()
+        .map(|(a, chosen)| {
+          (
+            a,
+            AgentRespEvent::InitCard {
+              id: *id,
+              chosen,
+              rationale: None,
+            },
+          )
+        })
+        .collect(),
+    ),
+    AgentReqEvent::ChooseRole { id, roles, .. } => (
+      DecisionKind::ChooseRole,
+      role_candidates(*roles)
+        .into_iter()
+        .map(|(a, chosen)| {
+          (
+            a,
+            AgentRespEvent::Role {
+              id: *id,
+              chosen,
+              rationale: None,
+            },
+          )
+        })
+        .collect(),
+    ),
+    AgentReqEvent::ChooseKillTarget { id, choices, .. } => (
+      DecisionKind::Kill,
+      role_candidates(*choices)
+        .into_iter()
+        .map(|(a, chosen)| {
+          (
+            a,
+            AgentRespEvent::KillTarget {
+              id: *id,
+              chosen,
+              rationale: None,
+            },
+          )
+        })
+        .collect(),
+    ),
+    AgentReqEvent::ChooseStealTarget { id, choices, .. } => (
+      DecisionKind::Steal,
+      role_candidates(*choices)
+        .into_iter()
+        .map(|(a, chosen)| {
+          (
+            a,
+            AgentRespEvent::StealTarget {
+              id: *id,
+              chosen,
+              rationale: None,
+            },
+          )
+        })
+        .collect(),
+    ),
+    AgentReqEvent::ChooseMagicTarget { id, .. } => (
+      DecisionKind::Magic,
+      magic_candidates(obs)
+        .into_iter()
+        .map(|(a, chosen)| {
+          (
+            a,
+            AgentRespEvent::MagicTarget {
+              id: *id,
+              chosen,
+              rationale: None,
+            },
+          )
+        })
+        .collect(),
+    ),
+    AgentReqEvent::ChooseDestoryTarget { id, choices, .. } => (
+      DecisionKind::Destroy,
+      destroy_candidates(choices)
+        .into_iter()
+        .map(|(a, chosen)| {
+          (
+            a,
+            AgentRespEvent::DestoryTarget {
+              id: *id,
+              chosen,
+              rationale: None,
+            },
+          )
+        })
+        .collect(),
+    ),
+    AgentReqEvent::ChooseTomb { id, .. } => (
+      DecisionKind::Tomb,
+      tomb_candidates()
+        .into_iter()
+        .map(|(a, chosen)| {
+          (
+            a,
+            AgentRespEvent::Tomb {
+              id: *id,
+              chosen,
+              rationale: None,
+            },
+          )
+        })
+        .collect(),
+    ),
+    AgentReqEvent::ChooseOper { id, choices, .. } => (
+      DecisionKind::Oper,
+      oper_candidates(choices)
+        .into_iter()
+        .map(|(a, chosen)| {
+          (
+            a,
+            AgentRespEvent::Oper {
+              id: *id,
+              chosen,
+              rationale: None,
+            },
+          )
+        })
+        .collect(),
+    ),
+    AgentReqEvent::ChooseFrom2 { id, c0, c1, .. } => (
+      DecisionKind::ChooseFrom2,
+      card_candidates(&[*c0, *c1])
+        .into_iter()
+        .map(|(a, chosen)| {
+          (
+            a,
+            AgentRespEvent::From2 {
+              id: *id,
+              chosen,
+              rationale: None,
+            },
+          )
+        })
+        .collect(),
+    ),
+    AgentReqEvent::ChooseFrom3 { id, c0, c1, c2, .. } => (
+      DecisionKind::ChooseFrom3,
+      card_candidates(&[*c0, *c1, *c2])
+        .into_iter()
+        .map(|(a, chosen)| {
+          (
+            a,
+            AgentRespEvent::From3 {
+              id: *id,
+              chosen,
+              rationale: None,
+            },
+          )
+        })
+        .collect(),
+    ),
+  };
+  Ok(candidates)
+}
+
+async fn decide(agent: &mut dyn AbstractFAAgent, obs: &Obs, decision: &AgentReqEvent) -> anyhow::Result<usize> {
+  let action = match decision {
+    AgentReqEvent::WaitForReady { .. }
+    | AgentReqEvent::Hello { .. }
+    | AgentReqEvent::Reject { .. }
+    | AgentReqEvent::Resumed { .. } => bail!("{:?} is not a decision", decision),
+    AgentReqEvent::ChooseInitCard { c0, c1, .. } => card_action(agent.choose_init_card(obs, *c0, *c1).await),
+    AgentReqEvent::ChooseRole { roles, .. } => role_action(agent.choose_role(obs, *roles).await),
+    AgentReqEvent::ChooseKillTarget { choices, .. } => role_action(agent.choose_kill_target(obs, *choices).await),
+    AgentReqEvent::ChooseStealTarget { choices, .. } => role_action(agent.choose_steal_target(obs, *choices).await),
+    AgentReqEvent::ChooseMagicTarget { .. } => magic_action(&agent.choose_swap_target(obs).await),
+    AgentReqEvent::ChooseDestoryTarget { choices, .. } => {
+      let target = agent.choose_destory_target(obs, choices).await;
+      destroy_action(target.map(|t| (t.player_offset, t.card)))
+    },
+    AgentReqEvent::ChooseTomb { c, .. } => tomb_action(agent.choose_tomb(obs, *c).await),
+    AgentReqEvent::ChooseOper { choices, .. } => oper_action(agent.choose_oper(obs, choices).await),
+    AgentReqEvent::ChooseFrom2 { c0, c1, .. } => card_action(agent.choose_from_2(obs, *c0, *c1).await),
+    AgentReqEvent::ChooseFrom3 { c0, c1, c2, .. } => card_action(agent.choose_from_3(obs, *c0, *c1, *c2).await),
+  };
+  Ok(action)
+}
@@ -7,14 +7,22 @@ use std::sync::atomic::{AtomicU32, Ordering};
 use serde::{Deserialize, Serialize};
 use tokio::select;
 use tokio::sync::mpsc;
-use tracing::info;
+use tracing::{error, info, warn};
 use valuable::Valuable;
 
+use crate::agent_protocol::AgentProtocol;
 use crate::deck::Deck;
-use crate::domain::{Card, DestroyTarget, MagicianSkill, Oper, PlayerIndex, Role, RoleSet};
+use crate::domain::{Card, DestroyTarget, MagicianSkill, Oper, PlayerIndex, Rationale, Role, RoleSet};
+use crate::fa_agents::GuardedFAAgent;
+use crate::game_report::GameReport;
 use crate::obs::Obs;
+use crate::player::Player;
+use crate::player_indexed_vec::PlayerIndexedVec;
+use crate::scenario::ScenarioPlayer;
+use crate::time_control::DecisionTimeout;
 
 const EVENT_START_GAME: &str = "StartGame";
+const EVENT_LOAD_SCENARIO: &str = "LoadScenario";
 const EVENT_INIT_GOLD: &str = "InitGold";
 const EVENT_INIT_CARD_REQ: &str = "InitCardReq";
 const EVENT_INIT_CARD_RESP: &str = "InitCardResp";
@@ -59,6 +67,10 @@ const EVENT_SKIP_KILLED_TURN: &str = "SkipKilledTurn";
 const EVENT_STEAL_GOLD: &str = "StealGold";
 const EVENT_SWAP_CARDS: &str = "SwapCards";
 const EVENT_REPLACE_CARDS: &str = "ReplaceCards";
+const EVENT_AGENT_PROTOCOL: &str = "AgentProtocol";
+const EVENT_DECISION_TIMEOUT: &str = "DecisionTimeout";
+const EVENT_AGENT_REPLACED: &str = "AgentReplaced";
+const EVENT_GAME_REPORT: &str = "GameReport";
 const EVENT_FHINSH_GAME: &str = "FinishGame";
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -70,6 +82,15 @@ pub enum HistoryReqEvent {
     id: u32,
     init_crown: PlayerIndex,
   },
+  // 从 Scenario 开局, 代替 InitGold 和 InitCard*. players 按座位号排列, deck 从顶到底
+  LoadScenario {
+    id: u32,
+    round: u32,
+    crown: PlayerIndex,
+    players: Vec<ScenarioPlayer>,
+    deck: Vec<Card>,
+    drop: Vec<Card>,
+  },
   InitGold {
     id: u32,
     actor: PlayerIndex,
@@ -87,6 +108,8 @@ pub enum HistoryReqEvent {
     req_id: u32,
     chosen: Card,
     drop: Card,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
   },
   StartRound {
     id: u32,
@@ -120,6 +143,8 @@ pub enum HistoryReqEvent {
     obs: Obs,
     choices: RoleSet,
     chosen: Role,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
   },
   KillReq {
     id: u32,
@@ -131,6 +156,8 @@ pub enum HistoryReqEvent {
     id: u32,
     req_id: u32,
     chosen: Role,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
   },
   StealReq {
     id: u32,
@@ -142,6 +169,8 @@ pub enum HistoryReqEvent {
     id: u32,
     req_id: u32,
     chosen: Role,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
   },
   MagicReq {
     id: u32,
@@ -152,6 +181,8 @@ pub enum HistoryReqEvent {
     id: u32,
     req_id: u32,
     chosen: MagicianSkill,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
   },
   Merchant {
     id: u32,
@@ -176,6 +207,8 @@ pub enum HistoryReqEvent {
     req_id: u32,
     chosen_index: Option<PlayerIndex>,
     chosen_card: Option<Card>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
   },
   TombReq {
     id: u32,
@@ -187,6 +220,8 @@ pub enum HistoryReqEvent {
     id: u32,
     req_id: u32,
     chosen: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
   },
   OperReq {
     id: u32,
@@ -198,6 +233,8 @@ pub enum HistoryReqEvent {
     id: u32,
     req_id: u32,
     chosen: Oper,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
   },
   Draw2Cards {
     id: u32,
@@ -247,6 +284,8 @@ pub enum HistoryReqEvent {
     req_id: u32,
     chosen: Card,
     drop: Card,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
   },
   ChooseFrom3Req {
     id: u32,
@@ -262,6 +301,8 @@ pub enum HistoryReqEvent {
     chosen: Card,
     drop0: Card,
     drop1: Card,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
   },
   Gold {
     id: u32,
@@ -332,11 +373,74 @@ pub enum HistoryReqEvent {
     removed: Vec<Card>,
     drawn: Vec<Card>,
   },
+  // 远程 agent 没有在时限内决策, 由 fallback 代为决策. 紧接在对应的 *Resp 之前
+  // 远程 agent 握手协商的协议, 开局时每个远程 agent 一条, 紧接在 StartGame 之后
+  AgentProtocol {
+    id: u32,
+    actor: PlayerIndex,
+    version: u32,
+    name: String,
+  },
+  DecisionTimeout {
+    id: u32,
+    actor: PlayerIndex,
+    budget_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bank_ms: Option<u64>,
+  },
+  // agent 出错, 从这次决策起由 fallback 代替. 紧接在对应的 *Resp 之前
+  AgentReplaced {
+    id: u32,
+    actor: PlayerIndex,
+    reason: String,
+  },
+  // 对局结束后的统计, 紧接在 FinishGame 之前
+  GameReport {
+    id: u32,
+    report: GameReport,
+  },
   FinishGame {
     id: u32,
   },
 }
 
+impl HistoryReqEvent {
+  // 决策者附带的解释, 只有 *Resp 事件可能有
+  pub fn rationale(&self) -> Option<&Rationale> {
+    match self {
+      HistoryReqEvent::InitCardResp { rationale, .. }
+      | HistoryReqEvent::ChooseRoleResp { rationale, .. }
+      | HistoryReqEvent::KillResp { rationale, .. }
+      | HistoryReqEvent::StealResp { rationale, .. }
+      | HistoryReqEvent::MagicResp { rationale, .. }
+      | HistoryReqEvent::DestroyResp { rationale, .. }
+      | HistoryReqEvent::TombResp { rationale, .. }
+      | HistoryReqEvent::OperResp { rationale, .. }
+      | HistoryReqEvent::ChooseFrom2Resp { rationale, .. }
+      | HistoryReqEvent::ChooseFrom3Resp { rationale, .. } => rationale.as_ref(),
+      _ => None,
+    }
+  }
+
+  // 决策请求中的 obs 和它所属的 actor
+  pub fn actor_obs(&self) -> Option<(PlayerIndex, &Obs)> {
+    match self {
+      HistoryReqEvent::InitCardReq { actor, obs, .. }
+      | HistoryReqEvent::ChooseRoleReq { actor, obs, .. }
+      | HistoryReqEvent::ChooseRoleResp { actor, obs, .. }
+      | HistoryReqEvent::KillReq { actor, obs, .. }
+      | HistoryReqEvent::StealReq { actor, obs, .. }
+      | HistoryReqEvent::MagicReq { actor, obs, .. }
+      | HistoryReqEvent::DestroyReq { actor, obs, .. }
+      | HistoryReqEvent::TombReq { actor, obs, .. }
+      | HistoryReqEvent::OperReq { actor, obs, .. }
+      | HistoryReqEvent::ChooseFrom2Req { actor, obs, .. }
+      | HistoryReqEvent::ChooseFrom3Req { actor, obs, .. } => Some((*actor, obs)),
+      _ => None,
+    }
+  }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub enum HistoryRespEvent {
   Ready,
@@ -346,6 +450,7 @@ pub struct History {
   id: AtomicU32,
   req_bcast_sender: mpsc::Sender<String>,
   resp_receiver: mpsc::Receiver<String>,
+  closed: bool, // 接收端已经关闭, 之后的事件都丢掉
 }
 
 impl History {
@@ -354,6 +459,22 @@ impl History {
       id: AtomicU32::new(0),
       req_bcast_sender,
       resp_receiver,
+      closed: false,
+    }
+  }
+
+  // 历史记录的接收端关闭后对局记录不完整, Game 在每轮结束时检查, 见 GameError::Transport
+  pub fn is_closed(&self) -> bool {
+    self.closed
+  }
+
+  async fn send(&mut self, json: String) {
+    if self.closed {
+      return;
+    }
+    if self.req_bcast_sender.send(json).await.is_err() {
+      warn!("history receiver closed, dropping further events");
+      self.closed = true;
     }
   }
 
@@ -370,12 +491,22 @@ impl History {
     let id = self.next_id();
     let req_event = HistoryReqEvent::WaitForReady { id };
     let json = serde_json::to_string(&req_event).unwrap();
-    self.req_bcast_sender.send(json.clone()).await.unwrap();
+    self.send(json).await;
 
-    loop {
+    while !self.closed {
       select! {
         resp = self.resp_receiver.recv() => {
-          let resp_event: HistoryRespEvent = serde_json::from_str(&resp.unwrap()).unwrap();
+          let Some(resp) = resp else {
+            self.closed = true;
+            return;
+          };
+          let resp_event: HistoryRespEvent = match serde_json::from_str(&resp) {
+            Ok(resp_event) => resp_event,
+            Err(e) => {
+              error!("history bad resp {:?}: {}", resp, e);
+              continue;
+            },
+          };
           match resp_event {
             HistoryRespEvent::Ready => {
               println!("Ready");
@@ -387,7 +518,7 @@ impl History {
           info!("history wait for ready timeout");
           let req_event = HistoryReqEvent::WaitForReady { id: self.id.fetch_add(1, Ordering::Relaxed) };
           let json = serde_json::to_string(&req_event).unwrap();
-          self.req_bcast_sender.send(json.clone()).await.unwrap();
+          self.send(json).await;
         }
       }
     }
@@ -400,7 +531,45 @@ impl History {
 
     let record = HistoryReqEvent::StartGame { id, init_crown };
     let json = serde_json::to_string(&record).unwrap();
-    self.req_bcast_sender.send(json.clone()).await.unwrap();
+    self.send(json).await;
+  }
+
+  pub async fn load_scenario(
+    &mut self, round: u32, crown: PlayerIndex, players: &PlayerIndexedVec<Player>, deck: &Deck,
+  ) {
+    let id = self.next_id();
+    let players = players
+      .iter()
+      .map(|player| ScenarioPlayer {
+        name: player.get_name().to_string(),
+        camp: player.camp(),
+        gold: player.gold(),
+        hand: player.cards().clone(),
+        buildings: player.iter_buildings().collect(),
+      })
+      .collect::<Vec<_>>();
+    let drop = deck.peek_drop().to_vec();
+    let deck = deck.peek_deck().iter().rev().copied().collect::<Vec<_>>();
+
+    info!(
+      id,
+      event = EVENT_LOAD_SCENARIO,
+      round = round,
+      crown = crown.value(),
+      deck = deck.as_value(),
+      drop = drop.as_value(),
+    );
+
+    let event = HistoryReqEvent::LoadScenario {
+      id,
+      round,
+      crown,
+      players,
+      deck,
+      drop,
+    };
+    let json = serde_json::to_string(&event).unwrap();
+    self.send(json).await;
   }
 
   pub async fn init_gold(&mut self, actor: PlayerIndex, gold: u32) {
@@ -410,7 +579,7 @@ impl History {
 
     let record = HistoryReqEvent::InitGold { id, actor, gold };
     let json = serde_json::to_string(&record).unwrap();
-    self.req_bcast_sender.send(json.clone()).await.unwrap();
+    self.send(json).await;
   }
 
   pub async fn init_card_req(&mut self, actor: PlayerIndex, obs: &Obs, c0: Card, c1: Card) -> u32 {
@@ -433,21 +602,24 @@ impl History {
       c1,
     };
     let json = serde_json::to_string(&record).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     id
   }
 
-  pub async fn init_card_resp(&mut self, req_id: u32, actor: PlayerIndex, obs: &Obs, chosen: Card, drop: Card) {
+  pub async fn init_card_resp(
+    &mut self, req_id: u32, actor: PlayerIndex, obs: &Obs, chosen: Card, drop: Card, rationale: Option<Rationale>,
+  ) {
     let id = self.next_id();
     let event = HistoryReqEvent::InitCardResp {
       id,
       req_id,
       chosen,
       drop,
+      rationale: rationale.clone(),
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -456,6 +628,7 @@ impl History {
       obs = obs.as_value(),
       chosen = chosen.as_value(),
       drop = drop.as_value(),
+      rationale = rationale.as_value(),
     );
   }
 
@@ -463,7 +636,7 @@ impl History {
     let id = self.next_id();
     let event = HistoryReqEvent::StartRound { id, round, crown };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -479,7 +652,7 @@ impl History {
     let id = self.next_id();
     let event = HistoryReqEvent::PublicDropRoles { id, round, roles };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(id, event = EVENT_PUBLIC_DROP_ROLES, round, roles = roles.as_value(),);
   }
@@ -488,7 +661,7 @@ impl History {
     let id = self.next_id();
     let event = HistoryReqEvent::SecretFirstDropRole { id, round, role };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(id, event = EVENT_SECRET_FIRST_DROP_ROLE, round, role = role.as_value(),);
   }
@@ -497,7 +670,7 @@ impl History {
     let id = self.next_id();
     let event = HistoryReqEvent::SecretLastDropRole { id, round, role };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(id, event = EVENT_SECRET_LAST_DROP_ROLE, round, role = role.as_value(),);
   }
@@ -511,7 +684,7 @@ impl History {
       choices,
     };
     let json = serde_json::to_string(&record).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       event = EVENT_CHOOSE_ROLE_REQ,
@@ -521,7 +694,9 @@ impl History {
     );
   }
 
-  pub async fn choose_role_resp(&mut self, actor: PlayerIndex, obs: &Obs, choices: RoleSet, chosen_role: Role) {
+  pub async fn choose_role_resp(
+    &mut self, actor: PlayerIndex, obs: &Obs, choices: RoleSet, chosen_role: Role, rationale: Option<Rationale>,
+  ) {
     let id = self.next_id();
     let record = HistoryReqEvent::ChooseRoleResp {
       id,
@@ -529,9 +704,10 @@ impl History {
       obs: obs.clone(),
       choices,
       chosen: chosen_role,
+      rationale: rationale.clone(),
     };
     let json = serde_json::to_string(&record).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       event = EVENT_CHOOSE_ROLE_RESP,
@@ -539,6 +715,7 @@ impl History {
       obs = obs.as_value(),
       choices = choices.as_value(),
       chosen_role = chosen_role.as_value(),
+      rationale = rationale.as_value(),
     );
   }
 
@@ -551,7 +728,7 @@ impl History {
       choices,
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -564,21 +741,23 @@ impl History {
     id
   }
 
-  pub async fn kill_resp(&mut self, req_id: u32, chosen_role: Role) {
+  pub async fn kill_resp(&mut self, req_id: u32, chosen_role: Role, rationale: Option<Rationale>) {
     let id = self.next_id();
     let event = HistoryReqEvent::KillResp {
       id,
       req_id,
       chosen: chosen_role,
+      rationale: rationale.clone(),
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       event = EVENT_KILL_RESP,
       id,
       req_id,
       chosen_role = chosen_role.as_value(),
+      rationale = rationale.as_value(),
     );
   }
 
@@ -591,7 +770,7 @@ impl History {
       choices,
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -603,21 +782,23 @@ impl History {
     id
   }
 
-  pub async fn steal_resp(&mut self, req_id: u32, chosen_role: Role) {
+  pub async fn steal_resp(&mut self, req_id: u32, chosen_role: Role, rationale: Option<Rationale>) {
     let id = self.next_id();
     let event = HistoryReqEvent::StealResp {
       id,
       req_id,
       chosen: chosen_role,
+      rationale: rationale.clone(),
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
       event = EVENT_STEAL_RESP,
       req_id,
       chosen_role = chosen_role.as_value(),
+      rationale = rationale.as_value(),
     );
   }
 
@@ -629,28 +810,30 @@ impl History {
       obs: obs.clone(),
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(id, event = EVENT_MAGIC_REQ, actor = actor.value(), obs = obs.as_value(),);
 
     id
   }
 
-  pub async fn magic_resp(&mut self, req_id: u32, chosen_skill: &MagicianSkill) {
+  pub async fn magic_resp(&mut self, req_id: u32, chosen_skill: &MagicianSkill, rationale: Option<Rationale>) {
     let id = self.next_id();
     let event = HistoryReqEvent::MagicResp {
       id,
       req_id,
       chosen: chosen_skill.clone(),
+      rationale: rationale.clone(),
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
       event = EVENT_MAGIC_RESP,
       req_id,
       chosen_skill = chosen_skill.as_value(),
+      rationale = rationale.as_value(),
     );
   }
 
@@ -659,7 +842,7 @@ impl History {
     info!(id, event = EVENT_MERCHANT, actor = actor.value(), round = round);
     let event = HistoryReqEvent::Merchant { id, actor, round };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
   }
 
   pub async fn architect_draw_2_cards(&mut self, actor: PlayerIndex, round: u32, c0: Option<Card>, c1: Option<Card>) {
@@ -680,7 +863,7 @@ impl History {
       c1,
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
   }
 
   pub async fn destroy_req(&mut self, actor: PlayerIndex, obs: &Obs, choices: &[DestroyTarget]) -> u32 {
@@ -692,7 +875,7 @@ impl History {
       choices: choices.to_vec(),
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -705,7 +888,10 @@ impl History {
     id
   }
 
-  pub async fn destroy_resp(&mut self, req_id: u32, chosen_index: Option<PlayerIndex>, chosen_card: Option<Card>) {
+  pub async fn destroy_resp(
+    &mut self, req_id: u32, chosen_index: Option<PlayerIndex>, chosen_card: Option<Card>,
+    rationale: Option<Rationale>,
+  ) {
     let id = self.next_id();
     info!(
       id,
@@ -713,15 +899,17 @@ impl History {
       req_id,
       chosen_index = chosen_index.as_value(),
       chosen_card = chosen_card.as_value(),
+      rationale = rationale.as_value(),
     );
     let event = HistoryReqEvent::DestroyResp {
       id,
       req_id,
       chosen_index,
       chosen_card,
+      rationale,
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
   }
 
   pub async fn tomb_req(&mut self, actor: PlayerIndex, obs: &Obs, card: Card) -> u32 {
@@ -733,7 +921,7 @@ impl History {
       card,
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       event = EVENT_TOMB_REQ,
@@ -745,13 +933,24 @@ impl History {
     id
   }
 
-  pub async fn tomb_resp(&mut self, req_id: u32, chosen: bool) {
+  pub async fn tomb_resp(&mut self, req_id: u32, chosen: bool, rationale: Option<Rationale>) {
     let id = self.next_id();
-    let event = HistoryReqEvent::TombResp { id, req_id, chosen };
+    let event = HistoryReqEvent::TombResp {
+      id,
+      req_id,
+      chosen,
+      rationale: rationale.clone(),
+    };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
-    info!(id, event = EVENT_TOMB_RESP, req_id, chosen = chosen);
+    info!(
+      id,
+      event = EVENT_TOMB_RESP,
+      req_id,
+      chosen = chosen,
+      rationale = rationale.as_value(),
+    );
   }
 
   pub async fn oper_req(&mut self, actor: PlayerIndex, obs: &Obs, choices: &[Oper]) -> u32 {
@@ -763,7 +962,7 @@ impl History {
       choices: choices.to_vec(),
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       event = EVENT_OPER_REQ,
@@ -775,13 +974,24 @@ impl History {
     id
   }
 
-  pub async fn oper_resp(&mut self, req_id: u32, chosen: Oper) {
+  pub async fn oper_resp(&mut self, req_id: u32, chosen: Oper, rationale: Option<Rationale>) {
     let id = self.next_id();
-    let event = HistoryReqEvent::OperResp { id, req_id, chosen };
+    let event = HistoryReqEvent::OperResp {
+      id,
+      req_id,
+      chosen,
+      rationale: rationale.clone(),
+    };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
-    info!(id, event = EVENT_OPER_RESP, req_id, chosen = chosen.as_value(),)
+    info!(
+      id,
+      event = EVENT_OPER_RESP,
+      req_id,
+      chosen = chosen.as_value(),
+      rationale = rationale.as_value(),
+    )
   }
 
   pub async fn draw_2_cards(&mut self, round: u32, actor: PlayerIndex, c0: Option<Card>, c1: Option<Card>) {
@@ -794,7 +1004,7 @@ impl History {
       c1,
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       event = EVENT_DRAW_2_CARDS,
@@ -818,7 +1028,7 @@ impl History {
       c2,
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -841,7 +1051,7 @@ impl History {
       c1,
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       event = EVENT_PEEK_2_CARDS,
@@ -865,7 +1075,7 @@ impl History {
       c2,
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -882,7 +1092,7 @@ impl History {
     let id = self.next_id();
     let event = HistoryReqEvent::ChooseFrom1 { id, actor, round, c };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id = id,
@@ -903,7 +1113,7 @@ impl History {
       c1,
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -917,16 +1127,17 @@ impl History {
     id
   }
 
-  pub async fn choose_from_2_resp(&mut self, req_id: u32, chosen: Card, drop: Card) {
+  pub async fn choose_from_2_resp(&mut self, req_id: u32, chosen: Card, drop: Card, rationale: Option<Rationale>) {
     let id = self.next_id();
     let event = HistoryReqEvent::ChooseFrom2Resp {
       id,
       req_id,
       chosen,
       drop,
+      rationale: rationale.clone(),
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -934,6 +1145,7 @@ impl History {
       req_id,
       chosen = chosen.as_value(),
       drop = drop.as_value(),
+      rationale = rationale.as_value(),
     )
   }
 
@@ -948,7 +1160,7 @@ impl History {
       c2,
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -963,7 +1175,9 @@ impl History {
     id
   }
 
-  pub async fn choose_from_3_resp(&mut self, req_id: u32, chosen: Card, drop0: Card, drop1: Card) {
+  pub async fn choose_from_3_resp(
+    &mut self, req_id: u32, chosen: Card, drop0: Card, drop1: Card, rationale: Option<Rationale>,
+  ) {
     let id = self.next_id();
     let event = HistoryReqEvent::ChooseFrom3Resp {
       id,
@@ -971,9 +1185,10 @@ impl History {
       chosen,
       drop0,
       drop1,
+      rationale: rationale.clone(),
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -982,6 +1197,7 @@ impl History {
       chosen = chosen.as_value(),
       drop0 = drop0.as_value(),
       drop1 = drop1.as_value(),
+      rationale = rationale.as_value(),
     )
   }
 
@@ -994,7 +1210,7 @@ impl History {
       amount,
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -1014,7 +1230,7 @@ impl History {
       card: c,
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -1029,7 +1245,7 @@ impl History {
     let id = self.next_id();
     let event = HistoryReqEvent::First8Buildings { id, actor, round };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -1043,7 +1259,7 @@ impl History {
     let id = self.next_id();
     let event = HistoryReqEvent::Nonfirst8Buildings { id, actor, round };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -1062,7 +1278,7 @@ impl History {
       card: c,
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -1077,7 +1293,7 @@ impl History {
     let id = self.next_id();
     let event = HistoryReqEvent::ShuffleDeck { id, deck: deck.clone() };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(id, event = EVENT_SHUFFLE_DECK, deck = deck.as_value(),)
   }
@@ -1086,7 +1302,7 @@ impl History {
     let id = self.next_id();
     let event = HistoryReqEvent::RevealRole { id, actor, round, role };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -1101,7 +1317,7 @@ impl History {
     let id = self.next_id();
     let event = HistoryReqEvent::MoveCrown { id, round, crown };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(id, event = EVENT_MOVE_CROWN, round = round, crown = crown.as_value(),)
   }
@@ -1110,7 +1326,7 @@ impl History {
     let id = self.next_id();
     let event = HistoryReqEvent::SkipKilledTurn { id, actor, round };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -1130,7 +1346,7 @@ impl History {
       amount,
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -1146,7 +1362,7 @@ impl History {
     let id = self.next_id();
     let event = HistoryReqEvent::SwapCards { id, actor, round, i, j };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
 
     info!(
       id,
@@ -1178,7 +1394,90 @@ impl History {
       drawn,
     };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
+  }
+
+  // 每次决策之后调用: 取走 agent 的解释, 记下超时和换人
+  pub(crate) async fn decision_notes(&mut self, actor: PlayerIndex, agent: &mut GuardedFAAgent) -> Option<Rationale> {
+    let rationale = agent.take_rationale();
+    self.decision_timeout(actor, agent.take_timeout()).await;
+    self.agent_replaced(actor, agent.take_failure()).await;
+    rationale
+  }
+
+  pub async fn agent_replaced(&mut self, actor: PlayerIndex, reason: Option<String>) {
+    let Some(reason) = reason else {
+      return;
+    };
+    let id = self.next_id();
+    info!(
+      id,
+      event = EVENT_AGENT_REPLACED,
+      actor = actor.value(),
+      reason = reason.as_str()
+    );
+    let event = HistoryReqEvent::AgentReplaced { id, actor, reason };
+    let json = serde_json::to_string(&event).unwrap();
+    self.send(json).await;
+  }
+
+  pub async fn agent_protocol(&mut self, actor: PlayerIndex, protocol: &AgentProtocol) {
+    let id = self.next_id();
+    info!(
+      id,
+      event = EVENT_AGENT_PROTOCOL,
+      actor = actor.value(),
+      version = protocol.version,
+      name = protocol.name,
+    );
+    let event = HistoryReqEvent::AgentProtocol {
+      id,
+      actor,
+      version: protocol.version,
+      name: protocol.name.clone(),
+    };
+    let json = serde_json::to_string(&event).unwrap();
+    self.send(json).await;
+  }
+
+  // timeout 为 AbstractFAAgent::take_timeout 的结果, None 时什么也不记
+  pub async fn decision_timeout(&mut self, actor: PlayerIndex, timeout: Option<DecisionTimeout>) {
+    let Some(timeout) = timeout else {
+      return;
+    };
+    let id = self.next_id();
+    info!(
+      id,
+      event = EVENT_DECISION_TIMEOUT,
+      actor = actor.value(),
+      budget_ms = timeout.budget_ms,
+      bank_ms = timeout.bank_ms,
+    );
+    let event = HistoryReqEvent::DecisionTimeout {
+      id,
+      actor,
+      budget_ms: timeout.budget_ms,
+      bank_ms: timeout.bank_ms,
+    };
+    let json = serde_json::to_string(&event).unwrap();
+    self.send(json).await;
+  }
+
+  pub async fn game_report(&mut self, report: &GameReport) {
+    let id = self.next_id();
+    let scores = report.players.iter().map(|p| p.score.total).collect::<Vec<_>>();
+    info!(
+      id,
+      event = EVENT_GAME_REPORT,
+      rounds = report.rounds,
+      scores = scores.as_value()
+    );
+    let event = HistoryReqEvent::GameReport {
+      id,
+      report: report.clone(),
+    };
+    let json = serde_json::to_string(&event).unwrap();
+    self.send(json).await;
   }
 
   pub async fn finish_game(&mut self) {
@@ -1186,6 +1485,6 @@ impl History {
     info!(id, event = EVENT_FHINSH_GAME);
     let event = HistoryReqEvent::FinishGame { id };
     let json = serde_json::to_string(&event).unwrap();
-    self.req_bcast_sender.send(json).await.unwrap();
+    self.send(json).await;
   }
 }
@@ -0,0 +1,157 @@
+// 历史记录的紧凑二进制格式
+//
+// 文件头为 MAGIC + VERSION, 之后是一个 zstd 流, 流中每个事件是一帧:
+// u32 (little endian) 长度 + MessagePack 编码的 HistoryReqEvent.
+// 与 JSONL (每行一个 serde_json 编码的 HistoryReqEvent) 可以无损互转.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+
+use anyhow::bail;
+
+use crate::history::HistoryReqEvent;
+
+const MAGIC: &[u8; 4] = b"CTHA";
+const VERSION: u8 = 1;
+const DEFAULT_LEVEL: i32 = 19;
+// 一帧的上限, 单个事件远小于这个值. 损坏的长度前缀不会让读的一方分配几个 GB
+const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+pub struct HistoryArchiveWriter<W: Write> {
+  encoder: zstd::Encoder<'static, W>,
+}
+
+impl<W: Write> HistoryArchiveWriter<W> {
+  pub fn new(writer: W) -> anyhow::Result<Self> {
+    Self::with_level(writer, DEFAULT_LEVEL)
+  }
+
+  pub fn with_level(mut writer: W, level: i32) -> anyhow::Result<Self> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION])?;
+    Ok(Self {
+      encoder: zstd::Encoder::new(writer, level)?,
+    })
+  }
+
+  pub fn write_event(&mut self, event: &HistoryReqEvent) -> anyhow::Result<()> {
+    // flatten 的结构体只能以 map 的形式编码, 所以这里统一用 named 编码
+    let frame = rmp_serde::to_vec_named(event)?;
+    if frame.len() > MAX_FRAME_SIZE {
+      bail!(
+        "history event of {} bytes exceeds the {} byte frame limit",
+        frame.len(),
+        MAX_FRAME_SIZE
+      );
+    }
+    self.encoder.write_all(&(frame.len() as u32).to_le_bytes())?;
+    self.encoder.write_all(&frame)?;
+    Ok(())
+  }
+
+  pub fn finish(self) -> anyhow::Result<W> {
+    Ok(self.encoder.finish()?)
+  }
+}
+
+pub struct HistoryArchiveReader<R: Read> {
+  decoder: zstd::Decoder<'static, BufReader<R>>,
+}
+
+impl<R: Read> HistoryArchiveReader<R> {
+  pub fn new(mut reader: R) -> anyhow::Result<Self> {
+    let mut header = [0u8; 5];
+    reader.read_exact(&mut header)?;
+    if &header[..4] != MAGIC {
+      bail!("not a history archive");
+    }
+    if header[4] != VERSION {
+      bail!("unsupported history archive version: {}", header[4]);
+    }
+    Ok(Self {
+      decoder: zstd::Decoder::new(reader)?,
+    })
+  }
+
+  // 返回 None 表示已经读完. 只有在帧的边界上结束才算读完, 文件截断时返回错误
+  pub fn read_event(&mut self) -> anyhow::Result<Option<HistoryReqEvent>> {
+    let mut len = [0u8; 4];
+    let mut read = 0;
+    while read < len.len() {
+      match self.decoder.read(&mut len[read..]) {
+        Ok(0) if read == 0 => return Ok(None),
+        Ok(0) => bail!("history archive truncated in a length prefix"),
+        Ok(n) => read += n,
+        Err(e) if e.kind() == ErrorKind::Interrupted => {},
+        Err(e) => return Err(e.into()),
+      }
+    }
+
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_FRAME_SIZE {
+      bail!(
+        "history archive frame of {} bytes exceeds the {} byte limit",
+        len,
+        MAX_FRAME_SIZE
+      );
+    }
+    let mut frame = vec![0u8; len];
+    match self.decoder.read_exact(&mut frame) {
+      Ok(()) => {},
+      Err(e) if e.kind() == ErrorKind::UnexpectedEof => bail!("history archive truncated in a frame"),
+      Err(e) => return Err(e.into()),
+    }
+    Ok(Some(rmp_serde::from_slice(&frame)?))
+  }
+}
+
+// 返回转换的事件个数
+pub fn jsonl_to_archive<R: BufRead, W: Write>(reader: R, writer: W) -> anyhow::Result<usize> {
+  let mut archive = HistoryArchiveWriter::new(writer)?;
+  let mut cnt = 0;
+  for line in reader.lines() {
+    let line = line?;
+    if line.trim().is_empty() {
+      continue;
+    }
+    let event: HistoryReqEvent = serde_json::from_str(&line)?;
+    archive.write_event(&event)?;
+    cnt += 1;
+  }
+  archive.finish()?;
+  Ok(cnt)
+}
+
+// 返回转换的事件个数
+pub fn archive_to_jsonl<R: Read, W: Write>(reader: R, mut writer: W) -> anyhow::Result<usize> {
+  let mut archive = HistoryArchiveReader::new(reader)?;
+  let mut cnt = 0;
+  while let Some(event) = archive.read_event()? {
+    serde_json::to_writer(&mut writer, &event)?;
+    writer.write_all(b"\n")?;
+    cnt += 1;
+  }
+  writer.flush()?;
+  Ok(cnt)
+}
+
+// 以 .jsonl 结尾的文件按 JSONL 读取, 否则按二进制归档读取
+pub fn load_history(path: &str) -> anyhow::Result<Vec<HistoryReqEvent>> {
+  let reader = BufReader::new(File::open(path)?);
+  let mut events = Vec::new();
+  if path.ends_with(".jsonl") {
+    for line in reader.lines() {
+      let line = line?;
+      if line.trim().is_empty() {
+        continue;
+      }
+      events.push(serde_json::from_str(&line)?);
+    }
+  } else {
+    let mut archive = HistoryArchiveReader::new(reader)?;
+    while let Some(event) = archive.read_event()? {
+      events.push(event);
+    }
+  }
+  Ok(events)
+}
@@ -0,0 +1,999 @@
+// 按观察者裁剪的历史记录, 用于给玩家发布复盘, 以及生成只包含玩家当时可见信息的训练数据.
+//
+// 与 Obs 一样使用 PlayerOffset 表示玩家: 座位视角以自己为 0, 队伍视角以队伍中座位号最小的玩家为 0,
+// 观战视角以 0 号座位为 0. 每个 *Req 事件中的 obs 属于该事件的 actor, 其中的 offset 也是相对于 actor 的.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{
+  Camp, Card, DestroyTarget, MagicianSkill, Oper, PlayerIndex, PlayerOffset, Rationale, Role, RoleSet,
+};
+use crate::game_report::GameReport;
+use crate::history::HistoryReqEvent;
+use crate::obs::Obs;
+
+#[derive(Clone, Copy, Debug)]
+pub enum HistoryViewer {
+  Seat(PlayerIndex),
+  Team(Camp),
+  Spectator,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ViewCards {
+  Visible(Vec<Card>),
+  Hidden(usize),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScenarioPlayerView {
+  pub name: String,
+  pub camp: Camp,
+  pub gold: u32,
+  pub hand: ViewCards,
+  pub buildings: Vec<Card>,
+}
+
+// 与 HistoryReqEvent 一一对应, 观察者看不到的字段为 None. 决策解释 rationale 只有决策者自己可见
+#[derive(Serialize, Deserialize, Debug)]
+pub enum HistoryViewEvent {
+  StartGame {
+    id: u32,
+    init_crown: PlayerOffset,
+  },
+  // players 按 offset 排列, 牌堆和弃牌堆只给出张数
+  LoadScenario {
+    id: u32,
+    round: u32,
+    crown: PlayerOffset,
+    players: Vec<ScenarioPlayerView>,
+    num_deck: usize,
+    num_drop: usize,
+  },
+  InitGold {
+    id: u32,
+    actor: PlayerOffset,
+    gold: u32,
+  },
+  InitCardReq {
+    id: u32,
+    actor: PlayerOffset,
+    obs: Option<Obs>,
+    c0: Option<Card>,
+    c1: Option<Card>,
+  },
+  InitCardResp {
+    id: u32,
+    req_id: u32,
+    chosen: Option<Card>,
+    drop: Option<Card>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+  StartRound {
+    id: u32,
+    round: u32,
+    crown: PlayerOffset,
+  },
+  PublicDropRoles {
+    id: u32,
+    round: u32,
+    roles: RoleSet,
+  },
+  SecretFirstDropRole {
+    id: u32,
+    round: u32,
+    role: Option<Role>,
+  },
+  SecretLastDropRole {
+    id: u32,
+    round: u32,
+    role: Option<Role>,
+  },
+  ChooseRoleReq {
+    id: u32,
+    actor: PlayerOffset,
+    obs: Option<Obs>,
+    choices: Option<RoleSet>,
+    num_choices: usize,
+  },
+  ChooseRoleResp {
+    id: u32,
+    actor: PlayerOffset,
+    chosen: Option<Role>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+  KillReq {
+    id: u32,
+    actor: PlayerOffset,
+    obs: Option<Obs>,
+    choices: RoleSet,
+  },
+  KillResp {
+    id: u32,
+    req_id: u32,
+    chosen: Role,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+  StealReq {
+    id: u32,
+    actor: PlayerOffset,
+    obs: Option<Obs>,
+    choices: RoleSet,
+  },
+  StealResp {
+    id: u32,
+    req_id: u32,
+    chosen: Role,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+  MagicReq {
+    id: u32,
+    actor: PlayerOffset,
+    obs: Option<Obs>,
+  },
+  MagicResp {
+    id: u32,
+    req_id: u32,
+    chosen: Option<MagicianSkill>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+  Merchant {
+    id: u32,
+    actor: PlayerOffset,
+    round: u32,
+  },
+  ArchitectDraw2Cards {
+    id: u32,
+    actor: PlayerOffset,
+    round: u32,
+    cards: ViewCards,
+  },
+  DestroyReq {
+    id: u32,
+    actor: PlayerOffset,
+    obs: Option<Obs>,
+    choices: Vec<DestroyTarget>,
+  },
+  DestroyResp {
+    id: u32,
+    req_id: u32,
+    chosen_offset: Option<PlayerOffset>,
+    chosen_card: Option<Card>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+  TombReq {
+    id: u32,
+    actor: PlayerOffset,
+    obs: Option<Obs>,
+    card: Card,
+  },
+  TombResp {
+    id: u32,
+    req_id: u32,
+    chosen: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+  OperReq {
+    id: u32,
+    actor: PlayerOffset,
+    obs: Option<Obs>,
+    choices: Option<Vec<Oper>>,
+  },
+  OperResp {
+    id: u32,
+    req_id: u32,
+    chosen: Option<Oper>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+  Draw2Cards {
+    id: u32,
+    actor: PlayerOffset,
+    round: u32,
+    cards: ViewCards,
+  },
+  Draw3Cards {
+    id: u32,
+    actor: PlayerOffset,
+    round: u32,
+    cards: ViewCards,
+  },
+  Peek2Cards {
+    id: u32,
+    actor: PlayerOffset,
+    round: u32,
+    cards: ViewCards,
+  },
+  Peek3Cards {
+    id: u32,
+    actor: PlayerOffset,
+    round: u32,
+    cards: ViewCards,
+  },
+  ChooseFrom1 {
+    id: u32,
+    actor: PlayerOffset,
+    round: u32,
+    c: Option<Card>,
+  },
+  ChooseFrom2Req {
+    id: u32,
+    actor: PlayerOffset,
+    obs: Option<Obs>,
+    c0: Option<Card>,
+    c1: Option<Card>,
+  },
+  ChooseFrom2Resp {
+    id: u32,
+    req_id: u32,
+    chosen: Option<Card>,
+    drop: Option<Card>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+  ChooseFrom3Req {
+    id: u32,
+    actor: PlayerOffset,
+    obs: Option<Obs>,
+    c0: Option<Card>,
+    c1: Option<Card>,
+    c2: Option<Card>,
+  },
+  ChooseFrom3Resp {
+    id: u32,
+    req_id: u32,
+    chosen: Option<Card>,
+    drop0: Option<Card>,
+    drop1: Option<Card>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rationale: Option<Rationale>,
+  },
+  Gold {
+    id: u32,
+    actor: PlayerOffset,
+    round: u32,
+    amount: u32,
+  },
+  Build {
+    id: u32,
+    actor: PlayerOffset,
+    round: u32,
+    card: Card,
+  },
+  First8Buildings {
+    id: u32,
+    actor: PlayerOffset,
+    round: u32,
+  },
+  Nonfirst8Buildings {
+    id: u32,
+    actor: PlayerOffset,
+    round: u32,
+  },
+  SellCard {
+    id: u32,
+    actor: PlayerOffset,
+    round: u32,
+    card: Option<Card>,
+  },
+  ShuffleDeck {
+    id: u32,
+    num_cards: usize,
+  },
+  RevealRole {
+    id: u32,
+    actor: PlayerOffset,
+    round: u32,
+    role: Role,
+  },
+  MoveCrown {
+    id: u32,
+    round: u32,
+    crown: PlayerOffset,
+  },
+  SkipKilledTurn {
+    id: u32,
+    actor: PlayerOffset,
+    round: u32,
+  },
+  StealGold {
+    id: u32,
+    from: PlayerOffset,
+    to: PlayerOffset,
+    round: u32,
+    amount: u32,
+  },
+  SwapCards {
+    id: u32,
+    actor: PlayerOffset,
+    round: u32,
+    i: PlayerOffset,
+    j: PlayerOffset,
+  },
+  ReplaceCards {
+    id: u32,
+    actor: PlayerOffset,
+    round: u32,
+    removed: ViewCards,
+    drawn: ViewCards,
+  },
+  AgentProtocol {
+    id: u32,
+    actor: PlayerOffset,
+    version: u32,
+    name: String,
+  },
+  // 超时是公开的, 大家都看得到时钟
+  DecisionTimeout {
+    id: u32,
+    actor: PlayerOffset,
+    budget_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bank_ms: Option<u64>,
+  },
+  AgentReplaced {
+    id: u32,
+    actor: PlayerOffset,
+    reason: String,
+  },
+  // 对局结束后全部公开, players 按 offset 排列
+  GameReport {
+    id: u32,
+    report: GameReport,
+  },
+  FinishGame {
+    id: u32,
+  },
+}
+
+impl HistoryViewEvent {
+  // 决策者附带的解释, 只有 *Resp 事件可能有
+  pub fn rationale(&self) -> Option<&Rationale> {
+    match self {
+      HistoryViewEvent::InitCardResp { rationale, .. }
+      | HistoryViewEvent::ChooseRoleResp { rationale, .. }
+      | HistoryViewEvent::KillResp { rationale, .. }
+      | HistoryViewEvent::StealResp { rationale, .. }
+      | HistoryViewEvent::MagicResp { rationale, .. }
+      | HistoryViewEvent::DestroyResp { rationale, .. }
+      | HistoryViewEvent::TombResp { rationale, .. }
+      | HistoryViewEvent::OperResp { rationale, .. }
+      | HistoryViewEvent::ChooseFrom2Resp { rationale, .. }
+      | HistoryViewEvent::ChooseFrom3Resp { rationale, .. } => rationale.as_ref(),
+      _ => None,
+    }
+  }
+}
+
+pub struct HistoryProjector {
+  viewer: HistoryViewer,
+  camps: Vec<Camp>,
+  origin: PlayerIndex,
+  req_actors: HashMap<u32, PlayerIndex>,
+}
+
+impl HistoryProjector {
+  // camps[i] 为 i 号座位的阵营
+  pub fn new(viewer: HistoryViewer, camps: Vec<Camp>) -> Self {
+    let origin = match viewer {
+      HistoryViewer::Seat(seat) => seat,
+      HistoryViewer::Team(camp) => PlayerIndex::from_usize(camps.iter().position(|&c| c == camp).unwrap_or(0)),
+      HistoryViewer::Spectator => PlayerIndex::from_usize(0),
+    };
+    Self {
+      viewer,
+      camps,
+      origin,
+      req_actors: HashMap::new(),
+    }
+  }
+
+  fn num_players(&self) -> usize {
+    self.camps.len()
+  }
+
+  fn offset(&self, player: PlayerIndex) -> PlayerOffset {
+    PlayerOffset::from_index(player, self.origin, self.num_players())
+  }
+
+  // actor 视角的 offset 换到观察者的视角
+  fn rebase(&self, actor: PlayerIndex, offset: PlayerOffset) -> PlayerOffset {
+    self.offset(offset.to_index(actor, self.num_players()))
+  }
+
+  fn sees(&self, actor: PlayerIndex) -> bool {
+    match self.viewer {
+      HistoryViewer::Seat(seat) => seat == actor,
+      // 推断不出阵营 (没有 LoadScenario 也没有决策) 时看不到任何私有信息
+      HistoryViewer::Team(camp) => self.camps.get(actor.value()) == Some(&camp),
+      HistoryViewer::Spectator => false,
+    }
+  }
+
+  fn sees_resp(&self, req_id: u32) -> bool {
+    self.req_actors.get(&req_id).is_some_and(|&actor| self.sees(actor))
+  }
+
+  fn private<T>(&self, actor: PlayerIndex, value: T) -> Option<T> {
+    if self.sees(actor) { Some(value) } else { None }
+  }
+
+  fn private_cards(&self, actor: PlayerIndex, cards: &[Option<Card>]) -> ViewCards {
+    let cards = cards.iter().flatten().copied().collect::<Vec<_>>();
+    if self.sees(actor) {
+      ViewCards::Visible(cards)
+    } else {
+      ViewCards::Hidden(cards.len())
+    }
+  }
+
+  fn private_resp<T>(&self, req_id: u32, value: T) -> Option<T> {
+    if self.sees_resp(req_id) { Some(value) } else { None }
+  }
+
+  // WaitForReady 不属于对局内容, 返回 None
+  pub fn project(&mut self, event: &HistoryReqEvent) -> Option<HistoryViewEvent> {
+    let view = match event {
+      HistoryReqEvent::WaitForReady { .. } => return None,
+      HistoryReqEvent::StartGame { id, init_crown } => HistoryViewEvent::StartGame {
+        id: *id,
+        init_crown: self.offset(*init_crown),
+      },
+      HistoryReqEvent::LoadScenario {
+        id,
+        round,
+        crown,
+        players,
+        deck,
+        drop,
+      } => {
+        let mut players = players
+          .iter()
+          .enumerate()
+          .map(|(i, player)| {
+            let hand = player.hand.iter().copied().map(Some).collect::<Vec<_>>();
+            ScenarioPlayerView {
+              name: player.name.clone(),
+              camp: player.camp,
+              gold: player.gold,
+              hand: self.private_cards(PlayerIndex::from_usize(i), &hand),
+              buildings: player.buildings.clone(),
+            }
+          })
+          .collect::<Vec<_>>();
+        players.rotate_left(self.origin.value());
+        HistoryViewEvent::LoadScenario {
+          id: *id,
+          round: *round,
+          crown: self.offset(*crown),
+          players,
+          num_deck: deck.len(),
+          num_drop: drop.len(),
+        }
+      },
+      HistoryReqEvent::InitGold { id, actor, gold } => HistoryViewEvent::InitGold {
+        id: *id,
+        actor: self.offset(*actor),
+        gold: *gold,
+      },
+      HistoryReqEvent::InitCardReq { id, actor, obs, c0, c1 } => {
+        self.req_actors.insert(*id, *actor);
+        HistoryViewEvent::InitCardReq {
+          id: *id,
+          actor: self.offset(*actor),
+          obs: self.private(*actor, obs.clone()),
+          c0: self.private(*actor, *c0),
+          c1: self.private(*actor, *c1),
+        }
+      },
+      HistoryReqEvent::InitCardResp {
+        id,
+        req_id,
+        chosen,
+        drop,
+        rationale,
+      } => HistoryViewEvent::InitCardResp {
+        id: *id,
+        req_id: *req_id,
+        chosen: self.private_resp(*req_id, *chosen),
+        drop: self.private_resp(*req_id, *drop),
+        rationale: self.private_resp(*req_id, rationale.clone()).flatten(),
+      },
+      HistoryReqEvent::StartRound { id, round, crown } => HistoryViewEvent::StartRound {
+        id: *id,
+        round: *round,
+        crown: self.offset(*crown),
+      },
+      HistoryReqEvent::PublicDropRoles { id, round, roles } => HistoryViewEvent::PublicDropRoles {
+        id: *id,
+        round: *round,
+        roles: *roles,
+      },
+      HistoryReqEvent::SecretFirstDropRole { id, round, .. } => HistoryViewEvent::SecretFirstDropRole {
+        id: *id,
+        round: *round,
+        role: None,
+      },
+      HistoryReqEvent::SecretLastDropRole { id, round, .. } => HistoryViewEvent::SecretLastDropRole {
+        id: *id,
+        round: *round,
+        role: None,
+      },
+      HistoryReqEvent::ChooseRoleReq {
+        id,
+        actor,
+        obs,
+        choices,
+      } => HistoryViewEvent::ChooseRoleReq {
+        id: *id,
+        actor: self.offset(*actor),
+        obs: self.private(*actor, obs.clone()),
+        choices: self.private(*actor, *choices),
+        num_choices: choices.len(),
+      },
+      HistoryReqEvent::ChooseRoleResp {
+        id,
+        actor,
+        chosen,
+        rationale,
+        ..
+      } => HistoryViewEvent::ChooseRoleResp {
+        id: *id,
+        actor: self.offset(*actor),
+        chosen: self.private(*actor, *chosen),
+        rationale: self.private(*actor, rationale.clone()).flatten(),
+      },
+      HistoryReqEvent::KillReq {
+        id,
+        actor,
+        obs,
+        choices,
+      } => {
+        self.req_actors.insert(*id, *actor);
+        HistoryViewEvent::KillReq {
+          id: *id,
+          actor: self.offset(*actor),
+          obs: self.private(*actor, obs.clone()),
+          choices: *choices,
+        }
+      },
+      HistoryReqEvent::KillResp {
+        id,
+        req_id,
+        chosen,
+        rationale,
+      } => HistoryViewEvent::KillResp {
+        id: *id,
+        req_id: *req_id,
+        chosen: *chosen,
+        rationale: self.private_resp(*req_id, rationale.clone()).flatten(),
+      },
+      HistoryReqEvent::StealReq {
+        id,
+        actor,
+        obs,
+        choices,
+      } => {
+        self.req_actors.insert(*id, *actor);
+        HistoryViewEvent::StealReq {
+          id: *id,
+          actor: self.offset(*actor),
+          obs: self.private(*actor, obs.clone()),
+          choices: *choices,
+        }
+      },
+      HistoryReqEvent::StealResp {
+        id,
+        req_id,
+        chosen,
+        rationale,
+      } => HistoryViewEvent::StealResp {
+        id: *id,
+        req_id: *req_id,
+        chosen: *chosen,
+        rationale: self.private_resp(*req_id, rationale.clone()).flatten(),
+      },
+      HistoryReqEvent::MagicReq { id, actor, obs } => {
+        self.req_actors.insert(*id, *actor);
+        HistoryViewEvent::MagicReq {
+          id: *id,
+          actor: self.offset(*actor),
+          obs: self.private(*actor, obs.clone()),
+        }
+      },
+      HistoryReqEvent::MagicResp {
+        id,
+        req_id,
+        chosen,
+        rationale,
+      } => {
+        // 换牌的对象是公开的, 制衡弃掉的牌不公开
+        let chosen = match chosen {
+          MagicianSkill::Swap(offset) => self
+            .req_actors
+            .get(req_id)
+            .map(|&actor| MagicianSkill::Swap(self.rebase(actor, *offset))),
+          MagicianSkill::放弃 => Some(MagicianSkill::放弃),
+          MagicianSkill::制衡(_) => self.private_resp(*req_id, chosen.clone()),
+        };
+        HistoryViewEvent::MagicResp {
+          id: *id,
+          req_id: *req_id,
+          chosen,
+          rationale: self.private_resp(*req_id, rationale.clone()).flatten(),
+        }
+      },
+      HistoryReqEvent::Merchant { id, actor, round } => HistoryViewEvent::Merchant {
+        id: *id,
+        actor: self.offset(*actor),
+        round: *round,
+      },
+      HistoryReqEvent::ArchitectDraw2Cards {
+        id,
+        actor,
+        round,
+        c0,
+        c1,
+      } => HistoryViewEvent::ArchitectDraw2Cards {
+        id: *id,
+        actor: self.offset(*actor),
+        round: *round,
+        cards: self.private_cards(*actor, &[*c0, *c1]),
+      },
+      HistoryReqEvent::DestroyReq {
+        id,
+        actor,
+        obs,
+        choices,
+      } => {
+        self.req_actors.insert(*id, *actor);
+        HistoryViewEvent::DestroyReq {
+          id: *id,
+          actor: self.offset(*actor),
+          obs: self.private(*actor, obs.clone()),
+          choices: choices
+            .iter()
+            .map(|choice| DestroyTarget {
+              player_offset: self.rebase(*actor, choice.player_offset),
+              ..*choice
+            })
+            .collect(),
+        }
+      },
+      HistoryReqEvent::DestroyResp {
+        id,
+        req_id,
+        chosen_index,
+        chosen_card,
+        rationale,
+      } => HistoryViewEvent::DestroyResp {
+        id: *id,
+        req_id: *req_id,
+        chosen_offset: chosen_index.map(|index| self.offset(index)),
+        chosen_card: *chosen_card,
+        rationale: self.private_resp(*req_id, rationale.clone()).flatten(),
+      },
+      HistoryReqEvent::TombReq { id, actor, obs, card } => {
+        self.req_actors.insert(*id, *actor);
+        HistoryViewEvent::TombReq {
+          id: *id,
+          actor: self.offset(*actor),
+          obs: self.private(*actor, obs.clone()),
+          card: *card,
+        }
+      },
+      HistoryReqEvent::TombResp {
+        id,
+        req_id,
+        chosen,
+        rationale,
+      } => HistoryViewEvent::TombResp {
+        id: *id,
+        req_id: *req_id,
+        chosen: *chosen,
+        rationale: self.private_resp(*req_id, rationale.clone()).flatten(),
+      },
+      HistoryReqEvent::OperReq {
+        id,
+        actor,
+        obs,
+        choices,
+      } => {
+        self.req_actors.insert(*id, *actor);
+        HistoryViewEvent::OperReq {
+          id: *id,
+          actor: self.offset(*actor),
+          obs: self.private(*actor, obs.clone()),
+          choices: self.private(*actor, choices.clone()),
+        }
+      },
+      HistoryReqEvent::OperResp {
+        id,
+        req_id,
+        chosen,
+        rationale,
+      } => {
+        // 卖掉的牌不公开
+        let chosen = match chosen {
+          Oper::SellCard(_) => self.private_resp(*req_id, *chosen),
+          _ => Some(*chosen),
+        };
+        HistoryViewEvent::OperResp {
+          id: *id,
+          req_id: *req_id,
+          chosen,
+          rationale: self.private_resp(*req_id, rationale.clone()).flatten(),
+        }
+      },
+      HistoryReqEvent::Draw2Cards {
+        id,
+        actor,
+        round,
+        c0,
+        c1,
+      } => HistoryViewEvent::Draw2Cards {
+        id: *id,
+        actor: self.offset(*actor),
+        round: *round,
+        cards: self.private_cards(*actor, &[*c0, *c1]),
+      },
+      HistoryReqEvent::Draw3Cards {
+        id,
+        actor,
+        round,
+        c0,
+        c1,
+        c2,
+      } => HistoryViewEvent::Draw3Cards {
+        id: *id,
+        actor: self.offset(*actor),
+        round: *round,
+        cards: self.private_cards(*actor, &[*c0, *c1, *c2]),
+      },
+      HistoryReqEvent::Peek2Cards {
+        id,
+        actor,
+        round,
+        c0,
+        c1,
+      } => HistoryViewEvent::Peek2Cards {
+        id: *id,
+        actor: self.offset(*actor),
+        round: *round,
+        cards: self.private_cards(*actor, &[*c0, *c1]),
+      },
+      HistoryReqEvent::Peek3Cards {
+        id,
+        actor,
+        round,
+        c0,
+        c1,
+        c2,
+      } => HistoryViewEvent::Peek3Cards {
+        id: *id,
+        actor: self.offset(*actor),
+        round: *round,
+        cards: self.private_cards(*actor, &[*c0, *c1, *c2]),
+      },
+      HistoryReqEvent::ChooseFrom1 { id, actor, round, c } => HistoryViewEvent::ChooseFrom1 {
+        id: *id,
+        actor: self.offset(*actor),
+        round: *round,
+        c: self.private(*actor, *c),
+      },
+      HistoryReqEvent::ChooseFrom2Req { id, actor, obs, c0, c1 } => {
+        self.req_actors.insert(*id, *actor);
+        HistoryViewEvent::ChooseFrom2Req {
+          id: *id,
+          actor: self.offset(*actor),
+          obs: self.private(*actor, obs.clone()),
+          c0: self.private(*actor, *c0),
+          c1: self.private(*actor, *c1),
+        }
+      },
+      HistoryReqEvent::ChooseFrom2Resp {
+        id,
+        req_id,
+        chosen,
+        drop,
+        rationale,
+      } => HistoryViewEvent::ChooseFrom2Resp {
+        id: *id,
+        req_id: *req_id,
+        chosen: self.private_resp(*req_id, *chosen),
+        drop: self.private_resp(*req_id, *drop),
+        rationale: self.private_resp(*req_id, rationale.clone()).flatten(),
+      },
+      HistoryReqEvent::ChooseFrom3Req {
+        id,
+        actor,
+        obs,
+        c0,
+        c1,
+        c2,
+      } => {
+        self.req_actors.insert(*id, *actor);
+        HistoryViewEvent::ChooseFrom3Req {
+          id: *id,
+          actor: self.offset(*actor),
+          obs: self.private(*actor, obs.clone()),
+          c0: self.private(*actor, *c0),
+          c1: self.private(*actor, *c1),
+          c2: self.private(*actor, *c2),
+        }
+      },
+      HistoryReqEvent::ChooseFrom3Resp {
+        id,
+        req_id,
+        chosen,
+        drop0,
+        drop1,
+        rationale,
+      } => HistoryViewEvent::ChooseFrom3Resp {
+        id: *id,
+        req_id: *req_id,
+        chosen: self.private_resp(*req_id, *chosen),
+        drop0: self.private_resp(*req_id, *drop0),
+        drop1: self.private_resp(*req_id, *drop1),
+        rationale: self.private_resp(*req_id, rationale.clone()).flatten(),
+      },
+      HistoryReqEvent::Gold {
+        id,
+        actor,
+        round,
+        amount,
+      } => HistoryViewEvent::Gold {
+        id: *id,
+        actor: self.offset(*actor),
+        round: *round,
+        amount: *amount,
+      },
+      HistoryReqEvent::Build { id, actor, round, card } => HistoryViewEvent::Build {
+        id: *id,
+        actor: self.offset(*actor),
+        round: *round,
+        card: *card,
+      },
+      HistoryReqEvent::First8Buildings { id, actor, round } => HistoryViewEvent::First8Buildings {
+        id: *id,
+        actor: self.offset(*actor),
+        round: *round,
+      },
+      HistoryReqEvent::Nonfirst8Buildings { id, actor, round } => HistoryViewEvent::Nonfirst8Buildings {
+        id: *id,
+        actor: self.offset(*actor),
+        round: *round,
+      },
+      HistoryReqEvent::SellCard { id, actor, round, card } => HistoryViewEvent::SellCard {
+        id: *id,
+        actor: self.offset(*actor),
+        round: *round,
+        card: self.private(*actor, *card),
+      },
+      HistoryReqEvent::ShuffleDeck { id, deck } => HistoryViewEvent::ShuffleDeck {
+        id: *id,
+        num_cards: deck.len(),
+      },
+      HistoryReqEvent::RevealRole { id, actor, round, role } => HistoryViewEvent::RevealRole {
+        id: *id,
+        actor: self.offset(*actor),
+        round: *round,
+        role: *role,
+      },
+      HistoryReqEvent::MoveCrown { id, round, crown } => HistoryViewEvent::MoveCrown {
+        id: *id,
+        round: *round,
+        crown: self.offset(*crown),
+      },
+      HistoryReqEvent::SkipKilledTurn { id, actor, round } => HistoryViewEvent::SkipKilledTurn {
+        id: *id,
+        actor: self.offset(*actor),
+        round: *round,
+      },
+      HistoryReqEvent::StealGold {
+        id,
+        from,
+        to,
+        round,
+        amount,
+      } => HistoryViewEvent::StealGold {
+        id: *id,
+        from: self.offset(*from),
+        to: self.offset(*to),
+        round: *round,
+        amount: *amount,
+      },
+      HistoryReqEvent::SwapCards { id, actor, round, i, j } => HistoryViewEvent::SwapCards {
+        id: *id,
+        actor: self.offset(*actor),
+        round: *round,
+        i: self.offset(*i),
+        j: self.offset(*j),
+      },
+      HistoryReqEvent::ReplaceCards {
+        id,
+        actor,
+        round,
+        removed,
+        drawn,
+      } => {
+        let removed = removed.iter().copied().map(Some).collect::<Vec<_>>();
+        let drawn = drawn.iter().copied().map(Some).collect::<Vec<_>>();
+        HistoryViewEvent::ReplaceCards {
+          id: *id,
+          actor: self.offset(*actor),
+          round: *round,
+          removed: self.private_cards(*actor, &removed),
+          drawn: self.private_cards(*actor, &drawn),
+        }
+      },
+      HistoryReqEvent::AgentProtocol {
+        id,
+        actor,
+        version,
+        name,
+      } => HistoryViewEvent::AgentProtocol {
+        id: *id,
+        actor: self.offset(*actor),
+        version: *version,
+        name: name.clone(),
+      },
+      HistoryReqEvent::DecisionTimeout {
+        id,
+        actor,
+        budget_ms,
+        bank_ms,
+      } => HistoryViewEvent::DecisionTimeout {
+        id: *id,
+        actor: self.offset(*actor),
+        budget_ms: *budget_ms,
+        bank_ms: *bank_ms,
+      },
+      HistoryReqEvent::AgentReplaced { id, actor, reason } => HistoryViewEvent::AgentReplaced {
+        id: *id,
+        actor: self.offset(*actor),
+        reason: reason.clone(),
+      },
+      HistoryReqEvent::GameReport { id, report } => {
+        let mut report = report.clone();
+        report.players.rotate_left(self.origin.value());
+        HistoryViewEvent::GameReport { id: *id, report }
+      },
+      HistoryReqEvent::FinishGame { id } => HistoryViewEvent::FinishGame { id: *id },
+    };
+    Some(view)
+  }
+}
+
+// 每个座位的阵营. 自定义局面直接取 LoadScenario 中的阵营, 否则从第一个决策请求的 obs 推断,
+// obs 中有所有人的阵营. 没有任何决策的记录推断不出来, 返回空
+pub fn infer_camps(events: &[HistoryReqEvent]) -> Vec<Camp> {
+  for event in events {
+    if let HistoryReqEvent::LoadScenario { players, .. } = event {
+      return players.iter().map(|p| p.camp).collect();
+    }
+    if let Some((actor, obs)) = event.actor_obs() {
+      let n = obs.num_players();
+      let mut camps = vec![obs.hero_camp(); n];
+      for offset in (1..n).map(PlayerOffset::from_usize) {
+        camps[offset.to_index(actor, n).value()] = obs.villain_camp(offset);
+      }
+      return camps;
+    }
+  }
+  Vec::new()
+}
+
+pub fn project_history(events: &[HistoryReqEvent], viewer: HistoryViewer) -> Vec<HistoryViewEvent> {
+  let mut projector = HistoryProjector::new(viewer, infer_camps(events));
+  events.iter().filter_map(|event| projector.project(event)).collect()
+}
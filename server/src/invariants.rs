@@ -0,0 +1,75 @@
+// 引擎不变量, 用于 fuzz. 开启检查后每次状态变化后和每次向 agent 提问前检查, 违反时返回 GameError::Invariant,
+// fuzz 用不变量的名字判断缩小后的局面是否还是同一个问题
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use strum::IntoEnumIterator;
+
+use crate::deck::Deck;
+use crate::domain::{Card, PlayerIndex};
+use crate::game_error::GameError;
+use crate::obs::Obs;
+use crate::player::Player;
+use crate::player_indexed_vec::PlayerIndexedVec;
+
+pub(crate) fn violation(name: &str, detail: impl Display) -> GameError {
+  GameError::Invariant {
+    name: name.to_string(),
+    detail: detail.to_string(),
+  }
+}
+
+// 牌的总数守恒, 建筑不超过 8 个且不重复, 同一轮的角色不重复
+pub(crate) fn check_state(deck: &Deck, players: &PlayerIndexedVec<Player>) -> Result<(), GameError> {
+  let mut counts = HashMap::new();
+  let cards = deck.peek_deck().iter().chain(deck.peek_drop().iter()).copied();
+  let players_cards = players
+    .iter()
+    .flat_map(|player| player.cards().iter().copied().chain(player.iter_buildings()));
+  for card in cards.chain(players_cards) {
+    *counts.entry(card.name()).or_insert(0) += 1;
+  }
+  for card in Card::iter() {
+    let count = counts.get(card.name()).copied().unwrap_or(0);
+    if count != card.number() {
+      return Err(violation(
+        "card conservation",
+        format_args!("{} {} in play, expected {}", count, card.name(), card.number()),
+      ));
+    }
+  }
+
+  for (i, player) in players.iter().enumerate() {
+    if player.buildings_len() > 8 {
+      return Err(violation(
+        "at most 8 buildings",
+        format_args!("player {} has {}", i, player.buildings_len()),
+      ));
+    }
+    let buildings = player.iter_buildings().collect::<Vec<_>>();
+    for (j, card) in buildings.iter().enumerate() {
+      if buildings[..j].contains(card) {
+        return Err(violation(
+          "unique buildings",
+          format_args!("player {} has two {}", i, card.name()),
+        ));
+      }
+    }
+    if let Some(role) = player.current_role()
+      && players.iter().take(i).any(|p| p.current_role() == Some(role))
+    {
+      return Err(violation("unique roles", format_args!("{} chosen twice", role.name())));
+    }
+  }
+  Ok(())
+}
+
+// 交给 actor 的 agent 做决策的 obs 必须和真实局面一致
+pub(crate) fn check_obs(
+  obs: &Obs, deck: &Deck, players: &PlayerIndexedVec<Player>, actor: PlayerIndex,
+) -> Result<(), GameError> {
+  obs
+    .check_consistency(deck, players, actor)
+    .map_err(|detail| violation("obs consistency", format_args!("player {}: {}", actor.value(), detail)))
+}
@@ -0,0 +1,52 @@
+// 观察信息泄露检查: 发给玩家的每个 Obs 只能包含这个玩家有权知道的信息.
+//
+// 做法是把玩家看不到的信息 (别人的手牌, 牌堆和弃牌堆的内容, 还没公开的角色) 全部换掉, 用换掉后的局面为这个玩家
+// 重新生成 obs, 再和发出去的比较. 不同说明 obs 依赖了隐藏信息, 或者是用别人的座位号生成的.
+// 角色只由 service 在公开时写入, 单独检查.
+
+use crate::deck::Deck;
+use crate::domain::{Card, PlayerIndex, PlayerOffset, RoleSet};
+use crate::game_error::GameError;
+use crate::invariants;
+use crate::obs::Obs;
+use crate::player::Player;
+use crate::player_indexed_vec::PlayerIndexedVec;
+
+// 替换隐藏牌用的占位牌
+pub(crate) const HIDDEN_CARD: Card = Card::酒馆;
+
+// revealed 为本轮已经公开的角色
+pub(crate) fn check(
+  obs: &Obs, deck: &Deck, players: &PlayerIndexedVec<Player>, observer: PlayerIndex, revealed: RoleSet,
+) -> Result<(), GameError> {
+  let num_players = players.len();
+  for offset in (1..num_players).map(PlayerOffset::from_usize) {
+    if let Some(role) = obs.villain_role(offset)
+      && !revealed.contains(role)
+    {
+      return Err(invariants::violation(
+        "no obs leak",
+        format_args!(
+          "player {} sees the role {} of player {} before it is revealed",
+          observer.value(),
+          role.name(),
+          offset.to_index(observer, num_players).value()
+        ),
+      ));
+    }
+  }
+
+  let mut entitled = PlayerIndexedVec::<Player>::new();
+  for player in players.iter() {
+    let is_observer = player.index() == observer;
+    let role_public = player.current_role().is_some_and(|role| revealed.contains(role));
+    entitled.push(player.redacted(!is_observer, !is_observer && !role_public));
+  }
+  if let Some(diff) = obs.refresh_diff(&deck.redacted(), &entitled, observer) {
+    return Err(invariants::violation(
+      "no obs leak",
+      format_args!("player {} depends on hidden information: {}", observer.value(), diff),
+    ));
+  }
+  Ok(())
+}
@@ -1,32 +1,67 @@
-mod abstract_fa_agent;
 mod abstract_fyi_agent;
-mod bit;
+mod agent_spec;
+mod agent_transport;
+pub mod agent_transports;
+mod analytics;
 mod config;
+mod dataset;
 mod deck;
-pub mod domain;
 pub mod fa_agents;
 mod fyi_agents;
 mod game;
+mod game_error;
+mod game_report;
+mod gym_env;
+mod hint_service;
 mod history;
+mod history_archive;
+mod history_view;
 mod id_gen;
+mod invariants;
+mod leak_check;
 mod log;
-mod obs;
 mod player;
-mod player_indexed_vec;
+mod role_book;
+mod scenario;
 mod services;
 mod ws_dispatcher;
 
-pub use abstract_fa_agent::AbstractFAAgent;
+// 协议类型在 game_protocol, 这里按原路径转出, crate 内的 use 不用改
+pub use game_protocol::domain;
+use game_protocol::{abstract_fa_agent, agent_protocol, bit, obs, player_indexed_vec, time_control, wire_format};
+
 pub use abstract_fyi_agent::AbstractFYIAgent;
+pub use agent_spec::AgentSpec;
+pub use agent_transport::AgentTransport;
+pub use analytics::{Analytics, Table};
 pub use config::Config;
-pub use fa_agents::{RandomFAAgent, V2FAAgent, WsProxyFAAgent}; // TODO: remove
+pub use dataset::{DatasetFormat, DatasetWriter, NUM_ACTIONS, Sample, action_names, extract_samples, replay_outcome};
+pub use fa_agents::{
+  HeuristicFAAgent, HeuristicWeights, PolicyFAAgent, PolicyModel, RandomFAAgent, RemoteFAAgent, V2FAAgent,
+}; // TODO: remove
 pub use fyi_agents::NoopFYIAgent;
 pub use game::Game;
+pub use game_error::{GameError, GameOutcome};
+pub use game_report::{GameReport, GoldIncome, PlayerReport, PlayerStats, RoundRole, ScoreBreakdown, TeamReport};
+pub use gym_env::{GymEnv, GymStep};
+pub use hint_service::{Hint, HintConfig, HintService, HintSuggestion};
 pub use history::{History, HistoryReqEvent, HistoryRespEvent};
+pub use history_archive::{
+  HistoryArchiveReader, HistoryArchiveWriter, archive_to_jsonl, jsonl_to_archive, load_history,
+};
+pub use history_view::{
+  HistoryProjector, HistoryViewEvent, HistoryViewer, ScenarioPlayerView, ViewCards, infer_camps, project_history,
+};
 pub use id_gen::IdGen;
 pub use log::init_log;
-pub use obs::Obs;
 pub use player::Player;
-pub use player_indexed_vec::PlayerIndexedVec;
+pub use role_book::{RoleBook, RoleSituation, RoleStats};
+pub use scenario::{Scenario, ScenarioPlayer};
 pub use services::RoleSelectService;
 pub use ws_dispatcher::WsDispatcher;
+
+pub use game_protocol::{
+  AbstractFAAgent, AgentProtocol, CardInfo, Clock, ClockInfo, DecisionKind, DecisionTimeout, HandshakeError,
+  MIN_PROTOCOL_VERSION, Obs, ObsEncoder, ObsMessage, ObsMirror, ObsPatch, ObsSync, PROTOCOL_VERSION,
+  PlayerIndexedVec, RULESET, TimeControl, TransportError, WireFormat, card_catalog, respond,
+};
@@ -1,10 +1,14 @@
 use uuid::Uuid;
 
-use crate::abstract_fa_agent::AbstractFAAgent;
 use crate::deck::Deck;
 use crate::domain::{Camp, Card, Color, PlayerIndex, Role};
+use crate::fa_agents::GuardedFAAgent;
+use crate::game_error::GameError;
+use crate::game_report::PlayerStats;
 use crate::history::History;
-use crate::obs::Obs;
+use crate::invariants;
+use crate::leak_check;
+use crate::obs::{Obs, PlayerState};
 
 pub struct Player {
   index: PlayerIndex,
@@ -16,6 +20,9 @@ pub struct Player {
   buildings: Vec<Card>,
   is_first_8_buildings: bool,
   role: Option<Role>,
+  鬼城_round: Option<u32>, // 建成鬼城的轮数, 开局前就有的为 None
+  final_round: Option<u32>,
+  stats: PlayerStats, // 对局统计, 用于赛后报告
 }
 
 impl Player {
@@ -38,6 +45,31 @@ impl Player {
       buildings: Vec::new(),
       is_first_8_buildings: false,
       role: None,
+      鬼城_round: None,
+      final_round: None,
+      stats: PlayerStats::default(),
+    }
+  }
+
+  // 别人眼中的自己: 手牌换成同样张数的占位牌, 角色没公开时去掉. 用于检查 obs 有没有泄露隐藏信息
+  pub(crate) fn redacted(&self, hide_cards: bool, hide_role: bool) -> Self {
+    Self {
+      index: self.index,
+      uuid: self.uuid,
+      name: self.name.clone(),
+      camp: self.camp,
+      gold: self.gold,
+      cards: if hide_cards {
+        vec![leak_check::HIDDEN_CARD; self.cards.len()]
+      } else {
+        self.cards.clone()
+      },
+      buildings: self.buildings.clone(),
+      is_first_8_buildings: self.is_first_8_buildings,
+      role: if hide_role { None } else { self.role },
+      鬼城_round: self.鬼城_round,
+      final_round: self.final_round,
+      stats: self.stats.clone(),
     }
   }
 
@@ -54,14 +86,26 @@ impl Player {
     self.camp
   }
 
-  pub fn set_role(&mut self, role: Role) {
-    assert!(self.role.is_none());
+  pub fn set_role(&mut self, role: Role) -> Result<(), GameError> {
+    if let Some(current) = self.role {
+      return Err(invariants::violation(
+        "one role per round",
+        format_args!("player {} has {:?}, got {:?}", self.index.value(), current, role),
+      ));
+    }
     self.role = Some(role);
+    Ok(())
   }
 
-  pub fn unset_role(&mut self) {
-    assert!(self.role.is_some());
+  pub fn unset_role(&mut self) -> Result<(), GameError> {
+    if self.role.is_none() {
+      return Err(invariants::violation(
+        "role chosen",
+        format_args!("player {} has no role to unset", self.index.value()),
+      ));
+    }
     self.role = None;
+    Ok(())
   }
 
   pub fn index(&self) -> PlayerIndex {
@@ -72,8 +116,23 @@ impl Player {
     self.index = index;
   }
 
-  pub fn role(&self) -> Role {
-    self.role.unwrap()
+  pub fn role(&self) -> Result<Role, GameError> {
+    self
+      .role
+      .ok_or_else(|| invariants::violation("role chosen", format_args!("player {} has no role", self.index.value())))
+  }
+
+  // 选角色之前为 None
+  pub fn current_role(&self) -> Option<Role> {
+    self.role
+  }
+
+  pub fn stats(&self) -> &PlayerStats {
+    &self.stats
+  }
+
+  pub(crate) fn stats_mut(&mut self) -> &mut PlayerStats {
+    &mut self.stats
   }
 
   pub fn gold(&self) -> u32 {
@@ -88,8 +147,17 @@ impl Player {
     self.gold += amount;
   }
 
-  pub fn sub_gold(&mut self, amount: u32) {
-    self.gold -= amount;
+  pub fn sub_gold(&mut self, amount: u32) -> Result<(), GameError> {
+    match self.gold.checked_sub(amount) {
+      Some(gold) => {
+        self.gold = gold;
+        Ok(())
+      },
+      None => Err(invariants::violation(
+        "non-negative gold",
+        format_args!("{} pays {} with {} gold", self.name, amount, self.gold),
+      )),
+    }
   }
 
   pub fn cards_len(&self) -> usize {
@@ -100,6 +168,11 @@ impl Player {
     self.buildings.len()
   }
 
+  // 不经过建造直接放置建筑, 用于自定义局面
+  pub fn add_building(&mut self, c: Card) {
+    self.buildings.push(c);
+  }
+
   pub fn remove_building(&mut self, c: Card) {
     self.buildings.retain(|v: &Card| *v != c);
   }
@@ -126,22 +199,38 @@ impl Player {
     }
   }
 
-  pub fn remove_cards(&mut self, cards: Vec<Card>, deck: &mut Deck) -> Vec<Card> {
+  // 从牌堆摸到手里的牌, 计入统计
+  pub(crate) fn add_drawn_card(&mut self, c: Option<Card>) {
+    if let Some(c) = c {
+      self.cards.push(c);
+      self.stats.cards_drawn += 1;
+    }
+  }
+
+  pub fn remove_cards(&mut self, cards: Vec<Card>, deck: &mut Deck) -> Result<Vec<Card>, GameError> {
     let mut removed = Vec::new();
 
     for c in cards {
-      let ok = self.remove_first_card(c);
-      assert!(ok);
+      if !self.remove_first_card(c) {
+        return Err(invariants::violation(
+          "cards in hand",
+          format_args!("player {} has no {}", self.index.value(), c.name()),
+        ));
+      }
       deck.drop(c);
       removed.push(c);
     }
 
-    removed
+    Ok(removed)
   }
 
   // None表示不可以拆
   pub fn building_destroy_fee(&self, c: Card) -> Option<u32> {
-    let role = self.role;
+    self.building_destroy_fee_as(c, self.role)
+  }
+
+  // 假设角色为 role 时的拆除费用, obs 中只能用已经公开的角色
+  pub fn building_destroy_fee_as(&self, c: Card, role: Option<Role>) -> Option<u32> {
     match role {
       Some(Role::主教) | Some(Role::军阀) => return None,
       _ => {},
@@ -152,7 +241,8 @@ impl Player {
 
     match c {
       Card::要塞 => None,
-      Card::城墙 => Some(c.fee()),
+      // 城墙只影响其他建筑, 拆城墙本身照常少花 1 金
+      Card::城墙 => Some(c.fee() - 1),
       _ => {
         if self.has_building(Card::城墙) {
           Some(c.fee())
@@ -171,6 +261,11 @@ impl Player {
     self.buildings.iter().filter(|c: &&Card| c.color() == color).count() as u32
   }
 
+  // 拿钱时的收租, 魔法学院可以算任意色
+  pub fn rent(&self, color: Color) -> u32 {
+    self.building_cnt(color) + self.has_building(Card::魔法学院) as u32
+  }
+
   pub fn iter_buildings(&self) -> impl Iterator<Item = Card> {
     self.buildings.iter().copied()
   }
@@ -179,12 +274,23 @@ impl Player {
     self.is_first_8_buildings = true;
   }
 
+  // 鬼城可以视为任何色, 但最终轮才建成的只能视为紫色
   pub fn has_all_colors(&self) -> bool {
     let mut has_color = [false, false, false, false, false];
-    for c in self.buildings.iter() {
-      has_color[c.color() as usize] = true;
+    let mut has_wildcard = false;
+    for &c in self.buildings.iter() {
+      if c == Card::鬼城 && (self.final_round.is_none() || self.鬼城_round != self.final_round) {
+        has_wildcard = true;
+      } else {
+        has_color[c.color() as usize] = true;
+      }
     }
-    has_color.iter().all(|&c| c)
+    let missing = has_color.iter().filter(|&&c| !c).count();
+    missing == 0 || (has_wildcard && missing == 1)
+  }
+
+  pub fn set_final_round(&mut self, round: u32) {
+    self.final_round = Some(round);
   }
 
   pub fn is_first_8_buildings(&self) -> bool {
@@ -215,51 +321,56 @@ impl Player {
   }
 
   pub async fn choose_from_2(
-    &mut self, fa_agent: &mut Box<dyn AbstractFAAgent>, obs: &Obs, c0: Option<Card>, c1: Option<Card>,
-    deck: &mut Deck, history: &mut History,
-  ) {
+    &mut self, fa_agent: &mut GuardedFAAgent, obs: &Obs, c0: Option<Card>, c1: Option<Card>, deck: &mut Deck,
+    history: &mut History,
+  ) -> Result<(), GameError> {
     let c0 = match c0 {
       Some(c) => c,
-      None => return,
+      None => return Ok(()),
     };
     let c1 = match c1 {
       Some(c) => c,
       None => {
         history.choose_from_1(self.index, obs.round(), c0).await;
         self.cards.push(c0);
-        return;
+        self.stats.cards_drawn += 1;
+        return Ok(());
       },
     };
 
     let history_id = history.choose_from_2_req(self.index, obs, c0, c1).await;
-    let chosen = fa_agent.choose_from_2(obs, c0, c1).await;
+    let chosen = fa_agent.choose_from_2(obs, c0, c1).await?;
     let drop = if chosen == c0 { c1 } else { c0 };
-    history.choose_from_2_resp(history_id, chosen, drop).await;
+    let rationale = history.decision_notes(self.index, fa_agent).await;
+    history.choose_from_2_resp(history_id, chosen, drop, rationale).await;
 
     self.cards.push(chosen);
+    self.stats.cards_drawn += 1;
     deck.drop(drop);
+    Ok(())
   }
 
   pub async fn choose_from_3(
-    &mut self, agent: &mut Box<dyn AbstractFAAgent>, obs: &Obs, cards: [Option<Card>; 3], deck: &mut Deck,
+    &mut self, agent: &mut GuardedFAAgent, obs: &Obs, cards: [Option<Card>; 3], deck: &mut Deck,
     history: &mut History,
-  ) {
+  ) -> Result<(), GameError> {
     let c0 = match cards[0] {
       Some(c) => c,
-      None => return,
+      None => return Ok(()),
     };
     let c1 = match cards[1] {
       Some(c) => c,
       None => {
         history.choose_from_1(self.index, obs.round(), c0).await;
         self.cards.push(c0);
-        return;
+        self.stats.cards_drawn += 1;
+        return Ok(());
       },
     };
     match cards[2] {
       Some(c2) => {
         let history_id = history.choose_from_3_req(self.index, obs, c0, c1, c2).await;
-        let chosen = agent.choose_from_3(obs, c0, c1, c2).await;
+        let chosen = agent.choose_from_3(obs, c0, c1, c2).await?;
         let (drop0, drop1) = if chosen == c0 {
           (c1, c2)
         } else if chosen == c1 {
@@ -268,28 +379,45 @@ impl Player {
           (c0, c1)
         };
 
-        history.choose_from_3_resp(history_id, chosen, drop0, drop1).await;
+        let rationale = history.decision_notes(self.index, agent).await;
+        history
+          .choose_from_3_resp(history_id, chosen, drop0, drop1, rationale)
+          .await;
 
         self.cards.push(chosen);
+        self.stats.cards_drawn += 1;
         deck.drop(drop0);
         deck.drop(drop1);
       },
       None => {
         let history_id = history.choose_from_2_req(self.index, obs, c0, c1).await;
-        let chosen = agent.choose_from_2(obs, c0, c1).await;
+        let chosen = agent.choose_from_2(obs, c0, c1).await?;
         let drop = if chosen == c0 { c1 } else { c0 };
-        history.choose_from_2_resp(history_id, chosen, drop).await;
+        let rationale = history.decision_notes(self.index, agent).await;
+        history.choose_from_2_resp(history_id, chosen, drop, rationale).await;
 
         self.cards.push(chosen);
+        self.stats.cards_drawn += 1;
         deck.drop(drop);
       },
     }
+    Ok(())
   }
 
-  pub fn build(&mut self, card: Card) {
+  pub fn build(&mut self, card: Card, round: u32) -> Result<(), GameError> {
     self.remove_first_card(card);
+    self.place_building(card, round);
+    self.sub_gold(card.fee())?;
+    self.stats.cards_built += 1;
+    Ok(())
+  }
+
+  // 建成一座建筑, 不扣钱也不动手牌. 重放历史记录时直接用它
+  pub(crate) fn place_building(&mut self, card: Card, round: u32) {
     self.buildings.push(card);
-    self.sub_gold(card.fee());
+    if card == Card::鬼城 {
+      self.鬼城_round = Some(round);
+    }
   }
 
   // TODO: must use return value
@@ -312,7 +440,50 @@ impl Player {
         drawn.push(c);
       }
     }
+    self.stats.cards_drawn += drawn.len() as u32;
 
     drawn
   }
 }
+
+impl PlayerState for Player {
+  fn camp(&self) -> Camp {
+    self.camp
+  }
+
+  fn gold(&self) -> u32 {
+    self.gold
+  }
+
+  fn cards(&self) -> &[Card] {
+    &self.cards
+  }
+
+  fn current_role(&self) -> Option<Role> {
+    self.role
+  }
+
+  fn score(&self) -> u32 {
+    Player::score(self)
+  }
+
+  fn buildings_len(&self) -> usize {
+    self.buildings.len()
+  }
+
+  fn iter_buildings(&self) -> impl Iterator<Item = Card> {
+    Player::iter_buildings(self)
+  }
+
+  fn building_destroy_fee_as(&self, c: Card, role: Option<Role>) -> Option<u32> {
+    Player::building_destroy_fee_as(self, c, role)
+  }
+
+  fn has_all_colors(&self) -> bool {
+    Player::has_all_colors(self)
+  }
+
+  fn is_first_8_buildings(&self) -> bool {
+    self.is_first_8_buildings
+  }
+}
@@ -0,0 +1,210 @@
+// 选角色开局库: 从大量对局中统计每种抽象局面下选每个角色的胜率, 供 agent 作为先验.
+//
+// 局面按人数, 选角色的顺位 (皇冠为 0), 可选的角色, 以及金币/手牌/建筑数的粗分档来区分.
+// 文件为带版本号的 JSON, 只保存出现过的局面.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{Role, RoleSet};
+use crate::history::HistoryReqEvent;
+use crate::history_view::infer_camps;
+use crate::obs::Obs;
+
+const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RoleSituation {
+  pub num_players: usize,
+  pub position: usize,
+  pub choices: RoleSet,
+  pub gold: u8,
+  pub hand: u8,
+  pub buildings: u8,
+}
+
+impl RoleSituation {
+  pub fn new(obs: &Obs, choices: RoleSet) -> Self {
+    Self {
+      num_players: obs.num_players(),
+      position: obs.role_select_position(),
+      choices,
+      gold: match obs.hero_gold() {
+        0..=1 => 0,
+        2..=3 => 1,
+        4..=6 => 2,
+        _ => 3,
+      },
+      hand: match obs.actor_num_cards() {
+        0 => 0,
+        1..=2 => 1,
+        _ => 2,
+      },
+      buildings: match obs.hero_num_buildings() {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+      },
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct RoleStats {
+  pub role: Role,
+  pub picks: u64,
+  // 选这个角色的玩家所在队伍的得分之和, 胜 1, 平 0.5, 负 0
+  pub wins: f64,
+}
+
+impl RoleStats {
+  pub fn win_rate(&self) -> f64 {
+    if self.picks == 0 {
+      0.5
+    } else {
+      self.wins / self.picks as f64
+    }
+  }
+
+  // 向 0.5 收缩, 样本少的统计不会压过样本多的
+  pub fn smoothed_win_rate(&self, prior_picks: f64) -> f64 {
+    (self.wins + 0.5 * prior_picks) / (self.picks as f64 + prior_picks)
+  }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+  situation: RoleSituation,
+  stats: Vec<RoleStats>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawRoleBook {
+  version: u32,
+  num_games: u64,
+  entries: Vec<Entry>,
+}
+
+#[derive(Default)]
+pub struct RoleBook {
+  num_games: u64,
+  entries: HashMap<RoleSituation, Vec<RoleStats>>,
+}
+
+impl RoleBook {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn load(path: &str) -> anyhow::Result<Self> {
+    let raw: RawRoleBook = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+    if raw.version != VERSION {
+      bail!("unsupported role book version: {}", raw.version);
+    }
+    Ok(Self {
+      num_games: raw.num_games,
+      entries: raw.entries.into_iter().map(|e| (e.situation, e.stats)).collect(),
+    })
+  }
+
+  pub fn save(&self, path: &str) -> anyhow::Result<()> {
+    let mut entries = self
+      .entries
+      .iter()
+      .map(|(situation, stats)| Entry {
+        situation: *situation,
+        stats: stats.clone(),
+      })
+      .collect::<Vec<_>>();
+    entries.sort_by_key(|e| {
+      (
+        e.situation.num_players,
+        e.situation.position,
+        e.stats.iter().map(|s| s.picks).sum::<u64>(),
+      )
+    });
+    let raw = RawRoleBook {
+      version: VERSION,
+      num_games: self.num_games,
+      entries,
+    };
+    serde_json::to_writer(BufWriter::new(File::create(path)?), &raw)?;
+    Ok(())
+  }
+
+  pub fn num_games(&self) -> u64 {
+    self.num_games
+  }
+
+  pub fn num_situations(&self) -> usize {
+    self.entries.len()
+  }
+
+  // outcome 下标为 Camp as usize, 与 Game::run 的返回值相同
+  pub fn add_game(&mut self, events: &[HistoryReqEvent], outcome: [f64; 2]) {
+    let camps = infer_camps(events);
+    for event in events {
+      if let HistoryReqEvent::ChooseRoleResp {
+        actor,
+        obs,
+        choices,
+        chosen,
+        ..
+      } = event
+      {
+        let stats = self.entries.entry(RoleSituation::new(obs, *choices)).or_default();
+        let index = match stats.iter().position(|s| s.role == *chosen) {
+          Some(index) => index,
+          None => {
+            stats.push(RoleStats {
+              role: *chosen,
+              picks: 0,
+              wins: 0.0,
+            });
+            stats.len() - 1
+          },
+        };
+        stats[index].picks += 1;
+        stats[index].wins += outcome[camps[actor.value()] as usize];
+      }
+    }
+    self.num_games += 1;
+  }
+
+  pub fn lookup(&self, obs: &Obs, choices: RoleSet) -> &[RoleStats] {
+    self
+      .entries
+      .get(&RoleSituation::new(obs, choices))
+      .map_or(&[], |stats| stats.as_slice())
+  }
+
+  // 每个可选角色的先验胜率, 没有统计的角色为 0.5
+  pub fn prior(&self, obs: &Obs, choices: RoleSet, prior_picks: f64) -> Vec<(Role, f64)> {
+    let stats = self.lookup(obs, choices);
+    Role::population()
+      .into_iter()
+      .filter(|&r| choices.contains(r))
+      .map(|r| {
+        let win_rate = stats
+          .iter()
+          .find(|s| s.role == r)
+          .map_or(0.5, |s| s.smoothed_win_rate(prior_picks));
+        (r, win_rate)
+      })
+      .collect()
+  }
+
+  // 至少有 min_picks 次统计的角色中胜率最高的
+  pub fn recommend(&self, obs: &Obs, choices: RoleSet, min_picks: u64) -> Option<Role> {
+    self
+      .lookup(obs, choices)
+      .iter()
+      .filter(|s| s.picks >= min_picks)
+      .max_by(|a, b| a.win_rate().total_cmp(&b.win_rate()))
+      .map(|s| s.role)
+  }
+}
@@ -0,0 +1,174 @@
+// 自定义开局局面, 用于测试卡牌技能和训练边界情况.
+//
+// 对局从 round 那一轮的选角色开始, 跳过 InitService 的发金币和初始选牌. 文件为 TOML 或 JSON (按扩展名区分),
+// 座位号即 players 中的下标. 牌堆 deck 从顶到底排列, 先摸到的写在前面.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, ensure};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+use crate::domain::{Camp, Card, Role};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScenarioPlayer {
+  #[serde(default)]
+  pub name: String,
+  pub camp: Camp,
+  #[serde(default)]
+  pub gold: u32,
+  #[serde(default)]
+  pub hand: Vec<Card>,
+  #[serde(default)]
+  pub buildings: Vec<Card>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Scenario {
+  // 第一轮为 1
+  #[serde(default = "default_round")]
+  pub round: u32,
+  pub crown: usize,
+  pub players: Vec<ScenarioPlayer>,
+  #[serde(default)]
+  pub deck: Vec<Card>,
+  #[serde(default)]
+  pub drop: Vec<Card>,
+  // 为 true 时没有写出来的牌洗匀后放到 deck 下面, 否则所有位置的牌加起来必须正好是一整副
+  #[serde(default)]
+  pub shuffle_rest: bool,
+  // 第一轮弃掉的角色, 按顺序代替随机弃牌: 4 人局前 2 张公开弃, 第 3 张暗弃; 6 人局 1 张暗弃. 没写的仍然随机
+  #[serde(default)]
+  pub drop_roles: Vec<Role>,
+  // 最多打几轮, 到了就算分结束, 不写则打到有人建满 8 个建筑
+  #[serde(default)]
+  pub rounds: Option<u32>,
+  // 决定 shuffle_rest 的洗牌和之后的角色弃牌与洗牌, 不写则随机
+  #[serde(default)]
+  pub seed: Option<u64>,
+}
+
+fn default_round() -> u32 {
+  1
+}
+
+impl Scenario {
+  pub fn load(path: &str) -> anyhow::Result<Self> {
+    let text = std::fs::read_to_string(path)?;
+    let scenario: Self = if path.ends_with(".json") {
+      serde_json::from_str(&text)?
+    } else {
+      toml::from_str(&text)?
+    };
+    scenario.validate()?;
+    Ok(scenario)
+  }
+
+  pub fn validate(&self) -> anyhow::Result<()> {
+    let num_players = self.players.len();
+    ensure!(
+      num_players == 4 || num_players == 6,
+      "scenario must have 4 or 6 players, got {}",
+      num_players
+    );
+    ensure!(self.round >= 1, "round starts from 1");
+    ensure!(
+      self.crown < num_players,
+      "crown {} out of range for {} players",
+      self.crown,
+      num_players
+    );
+    let num_楚 = self.players.iter().filter(|p| p.camp == Camp::楚).count();
+    ensure!(
+      num_楚 * 2 == num_players,
+      "each camp must have {} players, got {} 楚",
+      num_players / 2,
+      num_楚
+    );
+
+    ensure!(self.rounds != Some(0), "rounds must be at least 1");
+    let max_drops = if num_players == 4 { 3 } else { 1 };
+    ensure!(
+      self.drop_roles.len() <= max_drops,
+      "at most {} drop_roles for {} players, got {}",
+      max_drops,
+      num_players,
+      self.drop_roles.len()
+    );
+    for (i, role) in self.drop_roles.iter().enumerate() {
+      if self.drop_roles[..i].contains(role) {
+        bail!("duplicate drop role {}", role.name());
+      }
+    }
+
+    for (i, player) in self.players.iter().enumerate() {
+      // 8 个建筑时对局已经结束了
+      ensure!(
+        player.buildings.len() < 8,
+        "player {}: at most 7 buildings, got {}",
+        i,
+        player.buildings.len()
+      );
+      for (j, &card) in player.buildings.iter().enumerate() {
+        if player.buildings[..j].contains(&card) {
+          bail!("player {}: duplicate building {}", i, card.name());
+        }
+      }
+    }
+
+    let counts = self.card_counts();
+    for card in Card::iter() {
+      let count = counts.get(card.name()).copied().unwrap_or(0);
+      if count > card.number() {
+        bail!(
+          "{} appears {} times, the deck only has {}",
+          card.name(),
+          count,
+          card.number()
+        );
+      }
+      if !self.shuffle_rest && count != card.number() {
+        bail!(
+          "{} appears {} times, expected {} (set shuffle_rest to fill up the deck)",
+          card.name(),
+          count,
+          card.number()
+        );
+      }
+    }
+    Ok(())
+  }
+
+  // 手牌, 建筑, 牌堆, 弃牌堆中每种牌的数量
+  fn card_counts(&self) -> HashMap<&'static str, u32> {
+    let mut counts = HashMap::new();
+    let players = self
+      .players
+      .iter()
+      .flat_map(|p| p.hand.iter().chain(p.buildings.iter()));
+    for card in players.chain(self.deck.iter()).chain(self.drop.iter()) {
+      *counts.entry(card.name()).or_insert(0) += 1;
+    }
+    counts
+  }
+
+  // 按 Deck 的顺序 (最后一张先摸) 返回牌堆, shuffle_rest 时补上没写出来的牌
+  pub(crate) fn deck_cards(&self, rng: &mut StdRng) -> Vec<Card> {
+    let mut rest = Vec::new();
+    if self.shuffle_rest {
+      let counts = self.card_counts();
+      for card in Card::iter() {
+        let count = counts.get(card.name()).copied().unwrap_or(0);
+        for _ in count..card.number() {
+          rest.push(card);
+        }
+      }
+      rest.shuffle(rng);
+    }
+    rest.extend(self.deck.iter().rev());
+    rest
+  }
+}
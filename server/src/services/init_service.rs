@@ -1,7 +1,10 @@
 use crate::deck::Deck;
 use crate::domain::{PlayerIndex, PlayerOffset};
+use crate::fa_agents::GuardedFAAgent;
+use crate::game_error::GameError;
+use crate::invariants;
 use crate::obs::{HeroInfo, VillainInfo};
-use crate::{AbstractFAAgent, AbstractFYIAgent, History, Obs, Player, PlayerIndexedVec};
+use crate::{AbstractFYIAgent, History, Obs, Player, PlayerIndexedVec};
 
 pub struct InitService<'a> {
   pub players: &'a mut PlayerIndexedVec<Player>,
@@ -9,20 +12,21 @@ pub struct InitService<'a> {
   pub observes: &'a mut PlayerIndexedVec<Obs>,
   pub crown: PlayerIndex,
   pub deck: &'a mut Deck,
-  pub fa_agents: &'a mut PlayerIndexedVec<Box<dyn AbstractFAAgent>>,
+  pub fa_agents: &'a mut PlayerIndexedVec<GuardedFAAgent>,
   pub fyi_agents: &'a mut PlayerIndexedVec<Box<dyn AbstractFYIAgent>>,
 }
 
 impl<'a> InitService<'a> {
-  pub async fn run(&mut self) {
+  pub async fn run(&mut self) -> Result<(), GameError> {
     self.init_gold().await;
     self.init_obs();
-    self.init_card().await;
+    self.init_card().await
   }
 
   pub async fn init_gold(&mut self) {
     for player in self.players.iter_mut() {
       player.set_gold(2);
+      player.stats_mut().gold_income.initial = 2;
       self.history.init_gold(player.index(), player.gold()).await;
     }
   }
@@ -49,14 +53,19 @@ impl<'a> InitService<'a> {
     }
   }
 
-  async fn init_card(&mut self) {
+  async fn init_card(&mut self) -> Result<(), GameError> {
     let mut init_choices = Vec::new();
     let mut req_history_ids = PlayerIndexedVec::<u32>::with_len(self.players.len());
 
     for player in self.players.iter() {
       let actor = player.index();
-      let c0 = self.deck.take(self.history).await.unwrap(); // 初始状态牌的数量肯定是够的
-      let c1 = self.deck.take(self.history).await.unwrap(); // 初始状态牌的数量肯定是够的
+      // 初始状态牌的数量肯定是够的, 不够说明局面坏了
+      let (Some(c0), Some(c1)) = (self.deck.take(self.history).await, self.deck.take(self.history).await) else {
+        return Err(invariants::violation(
+          "initial deck",
+          format_args!("not enough cards to deal player {}", actor.value()),
+        ));
+      };
       init_choices.push((actor, c0, c1));
 
       let history_id = self.history.init_card_req(actor, &self.observes[actor], c0, c1).await;
@@ -67,26 +76,38 @@ impl<'a> InitService<'a> {
     let mut join_set = JoinSet::new();
 
     // Create futures that don't borrow from self
-    for (&(actor, c1, c2), agent) in init_choices.iter().zip(self.fa_agents.iter_mut()) {
+    // agent 暂时移进各自的任务, 全部回复后再放回去. 出错时对局结束, 不用放回
+    for (&(actor, c1, c2), mut fa_agent) in init_choices.iter().zip(std::mem::take(self.fa_agents)) {
       let obs = self.observes[actor].clone(); // Clone the observation to avoid borrowing
-      let mut fa_agent = std::mem::replace(agent, Box::new(crate::fa_agents::NoopFAAgent::new())); // Move agent out temporarily
       let future = async move {
         let chosen = fa_agent.choose_init_card(&obs, c1, c2).await;
-        let drop = if chosen == c1 { c2 } else { c1 };
-        (actor, chosen, drop, fa_agent)
+        (actor, c1, c2, chosen, fa_agent)
       };
       join_set.spawn(future);
     }
 
     // Handle responses on arrival
+    let mut fa_agents: Vec<Option<GuardedFAAgent>> = (0..self.players.len()).map(|_| None).collect();
     while let Some(result) = join_set.join_next().await {
-      let (actor, chosen, drop, fa_agent) = result.unwrap();
-      self.fa_agents[actor] = fa_agent; // Put the agent back
+      let (actor, c1, c2, chosen, mut fa_agent) = result.map_err(|e| GameError::Panic(e.to_string()))?;
+      let chosen = chosen?;
+      let drop = if chosen == c1 { c2 } else { c1 };
+      let rationale = fa_agent.take_rationale();
+      self.history.decision_timeout(actor, fa_agent.take_timeout()).await;
+      self.history.agent_replaced(actor, fa_agent.take_failure()).await;
+      fa_agents[actor.value()] = Some(fa_agent);
       self
         .history
-        .init_card_resp(req_history_ids[actor], actor, &self.observes[actor], chosen, drop)
+        .init_card_resp(
+          req_history_ids[actor],
+          actor,
+          &self.observes[actor],
+          chosen,
+          drop,
+          rationale,
+        )
         .await;
-      self.players[actor].add_card(chosen);
+      self.players[actor].add_drawn_card(Some(chosen));
       self.deck.drop(drop);
 
       for i in (0..self.players.len()).map(PlayerIndex::from_usize) {
@@ -94,5 +115,7 @@ impl<'a> InitService<'a> {
         self.fyi_agents[i].obs_changed(&self.observes[i]).await;
       }
     }
+    *self.fa_agents = fa_agents.into_iter().flatten().collect();
+    Ok(())
   }
 }
@@ -1,12 +1,15 @@
 use std::cmp;
 use std::cmp::Ordering;
 
-use crate::abstract_fa_agent::AbstractFAAgent;
 use crate::abstract_fyi_agent::AbstractFYIAgent;
 use crate::deck::Deck;
 use crate::domain::{Card, Color, DestroyTarget, MagicianSkill, Oper, PlayerIndex, PlayerOffset, Role, RoleSet};
+use crate::fa_agents::GuardedFAAgent;
 use crate::game::RoundStats;
+use crate::game_error::GameError;
 use crate::history::History;
+use crate::invariants;
+use crate::leak_check;
 use crate::obs::Obs;
 use crate::player::Player;
 use crate::player_indexed_vec::PlayerIndexedVec;
@@ -15,20 +18,22 @@ pub struct RoleExecutionService<'a> {
   pub num_players: usize,
   pub observes: &'a mut PlayerIndexedVec<Obs>,
   pub fyi_agents: &'a mut PlayerIndexedVec<Box<dyn AbstractFYIAgent>>,
-  pub fa_agents: &'a mut PlayerIndexedVec<Box<dyn AbstractFAAgent>>,
+  pub fa_agents: &'a mut PlayerIndexedVec<GuardedFAAgent>,
   pub players: &'a mut PlayerIndexedVec<Player>,
   pub history: &'a mut History,
   pub round_stats: &'a mut RoundStats,
   pub deck: &'a mut Deck,
+  pub check_invariants: bool,
+  pub check_leaks: bool,
 }
 
 impl<'a> RoleExecutionService<'a> {
-  pub async fn run(&mut self) {
+  pub async fn run(&mut self) -> Result<(), GameError> {
     for role in Role::population() {
       let mut actor = None;
 
       for player in self.players.iter() {
-        let player_role = player.role();
+        let player_role = player.role()?;
         if player_role == role {
           let player_index = player.index();
           actor = Some(player_index);
@@ -38,27 +43,35 @@ impl<'a> RoleExecutionService<'a> {
 
       if let Some(actor) = actor {
         self.history.reveal_role(actor, self.round_stats.round, role).await;
+        self.round_stats.revealed_roles |= role;
 
         for (observer, obs) in self.observes.iter_mut().enumerate() {
           let observer = PlayerIndex::from_usize(observer);
           if observer != actor {
             let offset = PlayerOffset::from_index(actor, observer, self.num_players);
             obs.set_villain_role(offset, role);
+            // 公开的角色会影响拆除费用
+            obs.update_infos(self.deck, self.players, observer);
             self.fyi_agents[observer].villain_choose_role_resped(offset, role).await;
           }
         }
+        for observer in (0..self.num_players).map(PlayerIndex::from_usize) {
+          self.check_leak(observer)?;
+        }
 
-        self.execute_player_turn(actor).await;
+        self.execute_player_turn(actor).await?;
       }
 
       if role == Role::小偷 {
         self.round_stats.stealer = actor;
       }
     }
+    Ok(())
   }
 
-  async fn execute_player_turn(&mut self, actor: PlayerIndex) {
-    if self.players[actor].role() == Role::国王 {
+  async fn execute_player_turn(&mut self, actor: PlayerIndex) -> Result<(), GameError> {
+    let role = self.players[actor].role()?;
+    if role == Role::国王 {
       self.round_stats.crown = actor;
 
       self
@@ -72,39 +85,43 @@ impl<'a> RoleExecutionService<'a> {
       }
     }
 
-    if self.round_stats.killed == self.players[actor].role() {
+    if self.round_stats.killed == role {
       self.history.skip_killed_turn(actor, self.round_stats.round).await;
+      self.players[actor].stats_mut().times_killed += 1;
 
       for observer in (0..self.num_players).map(PlayerIndex::from_usize) {
         self.observes[observer].set_killed(PlayerOffset::from_index(actor, observer, self.num_players));
         self.fyi_agents[observer].obs_changed(&self.observes[observer]).await;
       }
 
-      return;
+      return Ok(());
     }
 
-    if self.round_stats.stolen == self.players[actor].role() {
+    if self.round_stats.stolen == role {
+      let stealer = self
+        .round_stats
+        .stealer
+        .ok_or_else(|| invariants::violation("stealer", format_args!("{:?} was stolen but nobody stole", role)))?;
       let player_gold = self.players[actor].gold();
-      self.players[self.round_stats.stealer.unwrap()].add_gold(player_gold);
+      self.players[stealer].add_gold(player_gold);
+      self.players[stealer].stats_mut().gold_income.stolen += player_gold;
       self.players[actor].set_gold(0);
+      self.players[actor].stats_mut().times_robbed += 1;
+      self.check_state()?;
 
       self
         .history
-        .steal_gold(
-          actor,
-          self.round_stats.stealer.unwrap(),
-          self.round_stats.round,
-          player_gold,
-        )
+        .steal_gold(actor, stealer, self.round_stats.round, player_gold)
         .await;
 
       for observer in (0..self.num_players).map(PlayerIndex::from_usize) {
         self.observes[observer].set_stolen(PlayerOffset::from_index(actor, observer, self.num_players));
+        self.observes[observer].update_infos(self.deck, self.players, observer);
         self.fyi_agents[observer].obs_changed(&self.observes[observer]).await;
       }
     }
 
-    match self.players[actor].role() {
+    match role {
       Role::刺客 => {
         let mut choices = RoleSet::universal();
         let banned_roles = self.round_stats.pub_drop_roles | Role::刺客;
@@ -112,11 +129,13 @@ impl<'a> RoleExecutionService<'a> {
 
         // self.observes[actor].update_infos(&self.deck, &self.players, actor);
 
+        self.check_obs(actor)?;
         let history_id = self.history.kill_req(actor, &self.observes[actor], choices).await;
         let chosen_role = self.fa_agents[actor]
           .choose_kill_target(&self.observes[actor], choices)
-          .await;
-        self.history.kill_resp(history_id, chosen_role).await;
+          .await?;
+        let rationale = self.history.decision_notes(actor, &mut self.fa_agents[actor]).await;
+        self.history.kill_resp(history_id, chosen_role, rationale).await;
 
         self.round_stats.killed = chosen_role.into();
         for observer in (0..self.num_players).map(PlayerIndex::from_usize) {
@@ -131,11 +150,13 @@ impl<'a> RoleExecutionService<'a> {
 
         // self.observes[actor].update_infos(&self.deck, &self.players, actor);
 
+        self.check_obs(actor)?;
         let history_id = self.history.steal_req(actor, &self.observes[actor], choices).await;
         let chosen_role = self.fa_agents[actor]
           .choose_steal_target(&self.observes[actor], choices)
-          .await;
-        self.history.steal_resp(history_id, chosen_role).await;
+          .await?;
+        let rationale = self.history.decision_notes(actor, &mut self.fa_agents[actor]).await;
+        self.history.steal_resp(history_id, chosen_role, rationale).await;
 
         self.round_stats.stolen = chosen_role.into();
         for observer in (0..self.num_players).map(PlayerIndex::from_usize) {
@@ -144,9 +165,11 @@ impl<'a> RoleExecutionService<'a> {
         }
       },
       Role::魔术师 => {
+        self.check_obs(actor)?;
         let history_id = self.history.magic_req(actor, &self.observes[actor]).await;
-        let chosen_skill = self.fa_agents[actor].choose_swap_target(&self.observes[actor]).await;
-        self.history.magic_resp(history_id, &chosen_skill).await;
+        let chosen_skill = self.fa_agents[actor].choose_swap_target(&self.observes[actor]).await?;
+        let rationale = self.history.decision_notes(actor, &mut self.fa_agents[actor]).await;
+        self.history.magic_resp(history_id, &chosen_skill, rationale).await;
 
         match chosen_skill {
           MagicianSkill::Swap(offset) => {
@@ -161,9 +184,8 @@ impl<'a> RoleExecutionService<'a> {
                 let right_cards = right_players[0].cards_mut();
                 std::mem::swap(left_cards, right_cards);
               },
-              Ordering::Equal => {
-                panic!()
-              },
+              // 和自己交换, GuardedFAAgent 已经拒绝了这种决策
+              Ordering::Equal => {},
               Ordering::Greater => {
                 let (left_players, right_players) = self.players.split_at_mut(i);
                 let left_cards = left_players[j.value()].cards_mut();
@@ -177,10 +199,10 @@ impl<'a> RoleExecutionService<'a> {
 
             self.history.swap_cards(actor, self.round_stats.round, i, j).await;
 
-            self.update_observe_infos().await;
+            self.update_observe_infos().await?;
           },
           MagicianSkill::制衡(cards) => {
-            let removed = self.players[actor].remove_cards(cards, self.deck);
+            let removed = self.players[actor].remove_cards(cards, self.deck)?;
             let drawn = self.players[actor]
               .draw_card(removed.len(), self.deck, self.history)
               .await;
@@ -190,7 +212,7 @@ impl<'a> RoleExecutionService<'a> {
               .replace_cards(actor, self.round_stats.round, removed, drawn)
               .await;
 
-            self.update_observe_infos().await;
+            self.update_observe_infos().await?;
           },
           MagicianSkill::放弃 => {},
         }
@@ -198,8 +220,9 @@ impl<'a> RoleExecutionService<'a> {
       Role::商人 => {
         self.history.merchant(actor, self.round_stats.round).await;
         self.players[actor].add_gold(1);
+        self.players[actor].stats_mut().gold_income.merchant += 1;
 
-        self.update_observe_infos().await;
+        self.update_observe_infos().await?;
       },
       Role::建筑师 => {
         let c0 = self.deck.take(self.history).await;
@@ -210,9 +233,9 @@ impl<'a> RoleExecutionService<'a> {
           .architect_draw_2_cards(actor, self.round_stats.round, c0, c1)
           .await;
 
-        self.players[actor].add_option_card(c0);
-        self.players[actor].add_option_card(c1);
-        self.update_observe_infos().await;
+        self.players[actor].add_drawn_card(c0);
+        self.players[actor].add_drawn_card(c1);
+        self.update_observe_infos().await?;
       },
       Role::军阀 => {
         let mut choices = Vec::new();
@@ -236,10 +259,12 @@ impl<'a> RoleExecutionService<'a> {
 
         // self.observes[actor].update_infos(&self.deck, &self.players, actor);
 
+        self.check_obs(actor)?;
         let history_id = self.history.destroy_req(actor, &self.observes[actor], &choices).await;
         let target = self.fa_agents[actor]
           .choose_destory_target(&self.observes[actor], &choices)
-          .await;
+          .await?;
+        let rationale = self.history.decision_notes(actor, &mut self.fa_agents[actor]).await;
         let (chosen_offset, chosen_card) = match target {
           Some(target) => (
             Some(target.player_offset.to_index(actor, self.num_players)),
@@ -247,48 +272,65 @@ impl<'a> RoleExecutionService<'a> {
           ),
           None => (None, None),
         };
-        self.history.destroy_resp(history_id, chosen_offset, chosen_card).await;
+        self
+          .history
+          .destroy_resp(history_id, chosen_offset, chosen_card, rationale)
+          .await;
 
         if let Some(target) = target {
           let player_index = target.player_offset.to_index(actor, self.num_players);
-          let destroy_fee = self.players[player_index].building_destroy_fee(target.card).unwrap();
+          // 目标已由 GuardedFAAgent 检查在 choices 中, 拆不了只可能是引擎的 bug
+          let destroy_fee = self.players[player_index]
+            .building_destroy_fee(target.card)
+            .ok_or_else(|| {
+              invariants::violation(
+                "destroy fee",
+                format_args!(
+                  "{:?} of player {} has no destroy fee",
+                  target.card,
+                  player_index.value()
+                ),
+              )
+            })?;
           self.players[player_index].remove_building(target.card);
-          self.players[actor].sub_gold(destroy_fee);
-
-          self.update_observe_infos().await;
+          self.players[player_index].stats_mut().buildings_lost += 1;
+          self.players[actor].sub_gold(destroy_fee)?;
+          self.players[actor].stats_mut().buildings_destroyed += 1;
 
           let who_has_tomb = self.who_has_tomb();
 
           match who_has_tomb {
-            Some(who_has_tomb) => {
-              if who_has_tomb != actor && self.players[who_has_tomb].gold() >= 1 {
-                self.observes[who_has_tomb].update_infos(self.deck, self.players, actor);
-
-                let history_id = self
-                  .history
-                  .tomb_req(actor, &self.observes[who_has_tomb], target.card)
-                  .await;
-                let chosen = self.fa_agents[actor]
-                  .choose_tomb(&self.observes[who_has_tomb], target.card)
-                  .await;
-                self.history.tomb_resp(history_id, chosen).await;
-
-                if chosen {
-                  self.players[who_has_tomb].sub_gold(1);
-                  self.players[who_has_tomb].add_card(target.card);
-                } else {
-                  self.deck.drop(target.card);
-                }
+            // 由墓地的主人决定是否买回被拆的牌
+            Some(who_has_tomb) if who_has_tomb != actor && self.players[who_has_tomb].gold() >= 1 => {
+              self.observes[who_has_tomb].update_infos(self.deck, self.players, who_has_tomb);
+              self.check_obs(who_has_tomb)?;
+
+              let history_id = self
+                .history
+                .tomb_req(who_has_tomb, &self.observes[who_has_tomb], target.card)
+                .await;
+              let chosen = self.fa_agents[who_has_tomb]
+                .choose_tomb(&self.observes[who_has_tomb], target.card)
+                .await?;
+              let rationale = self
+                .history
+                .decision_notes(who_has_tomb, &mut self.fa_agents[who_has_tomb])
+                .await;
+              self.history.tomb_resp(history_id, chosen, rationale).await;
+
+              if chosen {
+                self.players[who_has_tomb].sub_gold(1)?;
+                self.players[who_has_tomb].add_card(target.card);
               } else {
                 self.deck.drop(target.card);
               }
             },
-            None => {
+            _ => {
               self.deck.drop(target.card);
             },
           };
 
-          self.update_observe_infos().await;
+          self.update_observe_infos().await?;
         }
       },
       _ => {},
@@ -314,11 +356,11 @@ impl<'a> RoleExecutionService<'a> {
           choices.push(Oper::Card2Choose1);
         }
 
-        let get_gold_amount = match self.players[actor].role() {
-          Role::国王 => 2 + self.players[actor].building_cnt(Color::黄),
-          Role::主教 => 2 + self.players[actor].building_cnt(Color::蓝),
-          Role::商人 => 2 + self.players[actor].building_cnt(Color::绿),
-          Role::军阀 => 2 + self.players[actor].building_cnt(Color::红),
+        let get_gold_amount = match role {
+          Role::国王 => 2 + self.players[actor].rent(Color::黄),
+          Role::主教 => 2 + self.players[actor].rent(Color::蓝),
+          Role::商人 => 2 + self.players[actor].rent(Color::绿),
+          Role::军阀 => 2 + self.players[actor].rent(Color::红),
           _ => 2,
         };
 
@@ -326,11 +368,7 @@ impl<'a> RoleExecutionService<'a> {
       }
 
       let build_quota = cmp::min(
-        if self.players[actor].role() == Role::建筑师 {
-          3
-        } else {
-          1
-        } - has_built_times,
+        if role == Role::建筑师 { 3 } else { 1 } - has_built_times,
         8 - self.players[actor].buildings_len() as u32,
       );
 
@@ -387,9 +425,13 @@ impl<'a> RoleExecutionService<'a> {
 
       // self.observes[actor].update_infos(&self.deck, &self.players, actor);
 
+      self.check_obs(actor)?;
       let history_id = self.history.oper_req(actor, &self.observes[actor], &choices).await;
-      let chosen_operation = self.fa_agents[actor].choose_oper(&self.observes[actor], &choices).await;
-      self.history.oper_resp(history_id, chosen_operation).await;
+      let chosen_operation = self.fa_agents[actor]
+        .choose_oper(&self.observes[actor], &choices)
+        .await?;
+      let rationale = self.history.decision_notes(actor, &mut self.fa_agents[actor]).await;
+      self.history.oper_resp(history_id, chosen_operation, rationale).await;
 
       match chosen_operation {
         Oper::EndRound => {
@@ -399,8 +441,8 @@ impl<'a> RoleExecutionService<'a> {
           let c0 = self.deck.take(self.history).await;
           let c1 = self.deck.take(self.history).await;
           self.history.draw_2_cards(self.round_stats.round, actor, c0, c1).await;
-          self.players[actor].add_option_card(c0);
-          self.players[actor].add_option_card(c1);
+          self.players[actor].add_drawn_card(c0);
+          self.players[actor].add_drawn_card(c1);
 
           got_resources = true;
         },
@@ -414,6 +456,7 @@ impl<'a> RoleExecutionService<'a> {
             .await;
 
           self.observes[actor].update_infos(self.deck, self.players, actor);
+          self.check_obs(actor)?;
           self.players[actor]
             .choose_from_3(
               &mut self.fa_agents[actor],
@@ -422,7 +465,7 @@ impl<'a> RoleExecutionService<'a> {
               self.deck,
               self.history,
             )
-            .await;
+            .await?;
 
           got_resources = true;
         },
@@ -432,6 +475,7 @@ impl<'a> RoleExecutionService<'a> {
           self.history.peek_2_cards(actor, self.round_stats.round, c0, c1).await;
 
           self.observes[actor].update_infos(self.deck, self.players, actor);
+          self.check_obs(actor)?;
           self.players[actor]
             .choose_from_2(
               &mut self.fa_agents[actor],
@@ -441,17 +485,21 @@ impl<'a> RoleExecutionService<'a> {
               self.deck,
               self.history,
             )
-            .await;
+            .await?;
           got_resources = true;
         },
         Oper::Gold(amount) => {
           self.history.gold(actor, self.round_stats.round, amount).await;
           self.players[actor].add_gold(amount);
+          // 基础的 2 金以外都是收租
+          let income = &mut self.players[actor].stats_mut().gold_income;
+          income.resources += amount.min(2);
+          income.rent += amount.saturating_sub(2);
           got_resources = true;
         },
         Oper::Build(card) => {
           self.history.build(actor, self.round_stats.round, card).await;
-          self.players[actor].build(card);
+          self.players[actor].build(card, self.round_stats.round)?;
           if self.players[actor].buildings_len() == 8 {
             if !self.round_stats.has_first_8_buildings {
               self.round_stats.has_first_8_buildings = true;
@@ -470,11 +518,14 @@ impl<'a> RoleExecutionService<'a> {
           self.players[actor].remove_first_card(card);
           self.deck.drop(card);
           self.players[actor].add_gold(1);
+          let stats = self.players[actor].stats_mut();
+          stats.cards_sold += 1;
+          stats.gold_income.sold_cards += 1;
 
           has_sold_card = true;
         },
         Oper::BuyCard => {
-          // TODO: 花钱
+          self.players[actor].sub_gold(2)?;
           let c0 = self.deck.take(self.history).await;
           let c1 = self.deck.take(self.history).await;
           let c2 = self.deck.take(self.history).await;
@@ -483,28 +534,32 @@ impl<'a> RoleExecutionService<'a> {
             .draw_3_cards(actor, self.round_stats.round, c0, c1, c2)
             .await;
 
-          self.players[actor].add_option_card(c0);
-          self.players[actor].add_option_card(c1);
-          self.players[actor].add_option_card(c2);
+          self.players[actor].add_drawn_card(c0);
+          self.players[actor].add_drawn_card(c1);
+          self.players[actor].add_drawn_card(c2);
 
           has_bought_card = true;
         },
       }
 
-      self.update_observe_infos().await;
+      self.update_observe_infos().await?;
     }
 
-    self.check_total_card_number();
+    self.check_total_card_number()?;
+    self.check_state()
   }
 
-  async fn update_observe_infos(&mut self) {
+  async fn update_observe_infos(&mut self) -> Result<(), GameError> {
+    self.check_state()?;
     for observer in (0..self.num_players).map(PlayerIndex::from_usize) {
       self.observes[observer].update_infos(self.deck, self.players, observer);
+      self.check_leak(observer)?;
       self.fyi_agents[observer].obs_changed(&self.observes[observer]).await;
     }
+    Ok(())
   }
 
-  pub fn check_total_card_number(&self) {
+  pub fn check_total_card_number(&self) -> Result<(), GameError> {
     // TODO: remove
     let mut total = 0;
     total += self.deck.peek_deck().len();
@@ -513,7 +568,40 @@ impl<'a> RoleExecutionService<'a> {
       total += player.cards_len();
       total += player.buildings_len();
     }
-    assert_eq!(total, 66);
+    if total != 66 {
+      return Err(invariants::violation(
+        "card conservation",
+        format_args!("{} cards in play, expected 66", total),
+      ));
+    }
+    Ok(())
+  }
+
+  fn check_state(&self) -> Result<(), GameError> {
+    if self.check_invariants {
+      invariants::check_state(self.deck, self.players)?;
+    }
+    Ok(())
+  }
+
+  fn check_obs(&self, actor: PlayerIndex) -> Result<(), GameError> {
+    if self.check_invariants {
+      invariants::check_obs(&self.observes[actor], self.deck, self.players, actor)?;
+    }
+    self.check_leak(actor)
+  }
+
+  fn check_leak(&self, observer: PlayerIndex) -> Result<(), GameError> {
+    if self.check_leaks {
+      leak_check::check(
+        &self.observes[observer],
+        self.deck,
+        self.players,
+        observer,
+        self.round_stats.revealed_roles,
+      )?;
+    }
+    Ok(())
   }
 
   fn who_has_tomb(&mut self) -> Option<PlayerIndex> {
@@ -1,11 +1,13 @@
-use rand::rngs::StdRng;
-
-use crate::abstract_fa_agent::AbstractFAAgent;
 use crate::abstract_fyi_agent::AbstractFYIAgent;
+use crate::deck::Deck;
 use crate::domain::PlayerIndex; // TODO: rename to Index
-use crate::domain::{OptionRole, PlayerOffset, PlayerOffsetSet, RoleSet};
+use crate::domain::{OptionRole, PlayerOffset, PlayerOffsetSet, Role, RoleSet};
+use crate::fa_agents::GuardedFAAgent;
 use crate::game::RoundStats;
+use crate::game_error::GameError;
 use crate::history::History;
+use crate::invariants;
+use crate::leak_check;
 use crate::obs::Obs;
 use crate::player::Player;
 use crate::player_indexed_vec::PlayerIndexedVec;
@@ -14,16 +16,35 @@ pub struct RoleSelectService<'a> {
   pub num_players: usize,
   pub observes: &'a mut PlayerIndexedVec<Obs>,
   pub fyi_agents: &'a mut PlayerIndexedVec<Box<dyn AbstractFYIAgent>>,
-  pub fa_agents: &'a mut PlayerIndexedVec<Box<dyn AbstractFAAgent>>,
+  pub fa_agents: &'a mut PlayerIndexedVec<GuardedFAAgent>,
   pub players: &'a mut PlayerIndexedVec<Player>,
   pub round: u32,
-  pub rng: &'a mut StdRng,
+  pub deck: &'a mut Deck,
   pub history: &'a mut History,
   pub crown: PlayerIndex,
+  // 预先指定的弃牌, 按顺序代替随机弃掉的角色 (4 人局先是 2 张公开的, 再是 1 张暗弃的), 用完后随机
+  pub drop_roles: Vec<Role>,
+  pub check_invariants: bool,
+  pub check_leaks: bool,
 }
 
 impl<'a> RoleSelectService<'a> {
-  pub async fn run(&mut self) -> RoundStats {
+  // 指定的弃牌已经被弃掉时返回错误, 比如重复指定了同一个角色
+  fn drop_role(&mut self, roles: RoleSet) -> Result<Role, GameError> {
+    if self.drop_roles.is_empty() {
+      return Ok(roles.random_choose(self.deck.rng()));
+    }
+    let role = self.drop_roles.remove(0);
+    if !roles.contains(role) {
+      return Err(invariants::violation(
+        "drop roles",
+        format!("{} is already dropped", role.name()),
+      ));
+    }
+    Ok(role)
+  }
+
+  pub async fn run(&mut self) -> Result<RoundStats, GameError> {
     let mut round_stats = RoundStats {
       round: self.round,
       pub_drop_roles: RoleSet::empty(),
@@ -32,14 +53,15 @@ impl<'a> RoleSelectService<'a> {
       stealer: None,
       has_first_8_buildings: false,
       crown: self.crown,
+      revealed_roles: RoleSet::empty(),
     };
 
     let mut roles = RoleSet::universal();
     if self.num_players == 4 {
-      let pub_drop_role_0 = roles.random_choose(self.rng);
+      let pub_drop_role_0 = self.drop_role(roles)?;
       roles -= pub_drop_role_0;
 
-      let pub_drop_role_1 = roles.random_choose(self.rng);
+      let pub_drop_role_1 = self.drop_role(roles)?;
       roles -= pub_drop_role_1;
 
       round_stats.pub_drop_roles = RoleSet::from_pair(pub_drop_role_0, pub_drop_role_1);
@@ -58,7 +80,7 @@ impl<'a> RoleSelectService<'a> {
     let mut roles_chosen = RoleSet::empty();
 
     {
-      let drop_role = roles.random_choose(self.rng);
+      let drop_role = self.drop_role(roles)?;
       roles -= drop_role;
 
       roles_chosen |= drop_role;
@@ -108,19 +130,30 @@ impl<'a> RoleSelectService<'a> {
 
       self.observes[actor].set_roles_chosen_before(roles_chosen);
 
+      if self.check_invariants {
+        invariants::check_obs(&self.observes[actor], self.deck, self.players, actor)?;
+      }
+      if self.check_leaks {
+        leak_check::check(&self.observes[actor], self.deck, self.players, actor, RoleSet::empty())?;
+      }
       self.history.choose_role_req(actor, &self.observes[actor], roles).await;
-      let chosen = self.fa_agents[actor].choose_role(&self.observes[actor], roles).await;
+      let chosen = self.fa_agents[actor].choose_role(&self.observes[actor], roles).await?;
+      let rationale = self.history.decision_notes(actor, &mut self.fa_agents[actor]).await;
       self
         .history
-        .choose_role_resp(actor, &self.observes[actor], roles, chosen)
+        .choose_role_resp(actor, &self.observes[actor], roles, chosen, rationale)
         .await;
 
-      self.players[actor].set_role(chosen);
+      self.players[actor].set_role(chosen)?;
       roles_chosen |= chosen;
       roles -= chosen;
 
       self.observes[actor].set_roles_chosen_after(roles);
       self.observes[actor].set_actor_role(chosen);
+      self.observes[actor].update_infos(self.deck, self.players, actor);
+      if self.check_invariants {
+        invariants::check_state(self.deck, self.players)?;
+      }
     }
 
     {
@@ -133,7 +166,7 @@ impl<'a> RoleSelectService<'a> {
         fyi_agent.last_role_dropped().await;
       }
 
-      round_stats
+      Ok(round_stats)
     }
   }
 }
@@ -1,10 +1,15 @@
 use core::net::SocketAddr;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use futures_util::future::join_all;
 use futures_util::{SinkExt, StreamExt};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::{Mutex, RwLock, mpsc};
+use tokio::sync::mpsc::error::SendTimeoutError;
+use tokio::sync::{Mutex, mpsc, oneshot, watch};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::WebSocketStream;
 use tokio_tungstenite::accept_hdr_async;
 use tokio_tungstenite::tungstenite::Message;
 use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
@@ -13,41 +18,239 @@ use tokio_tungstenite::tungstenite::protocol::frame::{CloseFrame, Utf8Bytes};
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
+use crate::agent_transports::WsTransport;
+use crate::domain::{AgentReqEvent, AgentRespEvent, PlayerIndex};
+use crate::history::History;
+use crate::obs::{ObsMessage, ObsMirror};
+use crate::wire_format::WireFormat;
+
+const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+// 每个连接待发的请求数. 满了之后 endpoint 等着, 超过 SLOW_CLIENT_TIMEOUT 就断开这个连接
+const CLIENT_BUFFER: usize = 256;
+const SLOW_CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+// 对局发往 endpoint 的请求数, 满了之后对局等着
+const END_POINT_BUFFER: usize = 64;
+
+// 一个 dispatcher 同时承载多局, 每局的 endpoint 随对局创建和移除. clone 出来的共享同一张路由表
+#[derive(Clone)]
 pub struct WsDispatcher {
-  end_points: Arc<Mutex<HashMap<uuid::Uuid, EndPoint>>>,
+  end_points: Arc<Mutex<HashMap<Route, EndPoint>>>,
+}
+
+// 连接的路径: /<uuid>, /<game>/<seat>, /<game>/history
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Route {
+  Single(Uuid),
+  Seat(Uuid, usize),
+  History(Uuid),
+}
+
+impl Route {
+  fn parse(path: &str) -> Option<Self> {
+    let mut parts = path.strip_prefix('/')?.split('/');
+    let id = Uuid::parse_str(parts.next()?).ok()?;
+    let route = match parts.next() {
+      None => Route::Single(id),
+      Some("history") => Route::History(id),
+      Some(seat) => Route::Seat(id, seat.parse().ok()?),
+    };
+    parts.next().is_none().then_some(route)
+  }
+
+  fn game(&self) -> Uuid {
+    match *self {
+      Route::Single(id) | Route::Seat(id, _) | Route::History(id) => id,
+    }
+  }
+}
+
+// endpoint 上的一个连接. kick 让连接带着 close code 断开, 直接 drop 则发完缓冲的请求后正常关闭
+struct Client {
+  sender: mpsc::Sender<String>,
+  kick: oneshot::Sender<(CloseCode, &'static str)>,
+}
+
+impl Client {
+  fn kick(self, code: CloseCode, reason: &'static str) {
+    let _ = self.kick.send((code, reason));
+  }
 }
 
 struct EndPoint {
-  pub req_client_all_senders: Arc<RwLock<Vec<mpsc::Sender<String>>>>,
-  pub resp_sender: mpsc::Sender<String>,
+  clients: Arc<Mutex<Vec<Client>>>,
+  resp_sender: mpsc::Sender<String>,
+  session: Option<Arc<Mutex<AgentSession>>>,
+  forward: JoinHandle<()>,
 }
 
-impl EndPoint {
-  pub fn new(mut req_bcast_receiver: mpsc::Receiver<String>, resp_sender: mpsc::Sender<String>) -> Self {
-    let req_client_all_senders = Arc::new(RwLock::new(Vec::<mpsc::Sender<String>>::new()));
-
-    let req_client_all_senders_clone = req_client_all_senders.clone();
-    tokio::spawn(async move {
-      while let Some(msg) = req_bcast_receiver.recv().await {
-        let mut all_senders = req_client_all_senders_clone.write().await;
-        // println!("req_bcast_receiver: {:?}, all_senders: {:?}", msg, all_senders);
-        all_senders.retain(|s| s.clone().try_send(msg.clone()).is_ok());
-      }
-      println!("req_bcast_receiver closed");
-      {
-        let mut all_senders = req_client_all_senders_clone.write().await;
-        all_senders.clear();
-        // for s in all_senders.iter_mut() {
-        //   drop(s);
-        // }
+// agent endpoint 上的连接状态, WsTransport 据此等待重连
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Presence {
+  Waiting, // 还没连上过
+  Connected,
+  Away(Instant), // 断线的时刻
+}
+
+// agent 的 endpoint 同时只有一个连接. 转发 Hello 时填上 resume token, 断线后凭 ?resume=<token> 重连,
+// 重连时先发 Resumed 和当前的 obs, 再补发没有回复的请求. 锁的顺序: 先 clients 后 session
+struct AgentSession {
+  resume_token: Uuid,
+  token_sent: bool,
+  presence: watch::Sender<Presence>,
+  mirror: ObsMirror,
+  obs: Option<ObsMessage>, // 最近一次决策请求的 obs, 增量模式下为完整的 ObsSync
+  outstanding: Option<AgentReqEvent>,
+}
+
+impl AgentSession {
+  fn new(presence: watch::Sender<Presence>) -> Self {
+    Self {
+      resume_token: Uuid::new_v4(),
+      token_sent: false,
+      presence,
+      mirror: ObsMirror::new(),
+      obs: None,
+      outstanding: None,
+    }
+  }
+
+  // 新的连接, Ok(true) 为重连
+  fn admit(&self, resume_token: Option<Uuid>, connected: bool) -> Result<bool, &'static str> {
+    match resume_token {
+      Some(token) if token == self.resume_token => Ok(true),
+      Some(_) => Err("bad resume token"),
+      None if !self.token_sent && !connected => Ok(false),
+      None => Err("resume token required"),
+    }
+  }
+
+  // 连上后先发给 agent 的消息. 没有回复的请求换成完整的 obs, agent 的副本可能是空的
+  fn greeting(&self, resumed: bool) -> Vec<String> {
+    let mut msgs = Vec::new();
+    if resumed {
+      let event = AgentReqEvent::Resumed {
+        id: 0,
+        obs: self.obs.clone(),
+      };
+      msgs.push(serde_json::to_string(&event).unwrap());
+    }
+    if let Some(mut event) = self.outstanding.clone() {
+      if let (Some(obs), Some(current)) = (event.obs_mut(), &self.obs) {
+        *obs = current.clone();
       }
-    });
+      msgs.push(serde_json::to_string(&event).unwrap());
+    }
+    msgs
+  }
+
+  fn on_request(&mut self, msg: String) -> String {
+    let Ok(mut event) = serde_json::from_str::<AgentReqEvent>(&msg) else {
+      return msg;
+    };
+    if let AgentReqEvent::Hello { resume_token, .. } = &mut event {
+      *resume_token = Some(self.resume_token);
+      self.token_sent = true;
+    }
+    if let Some(obs) = event.obs() {
+      self.obs = match obs {
+        ObsMessage::Full(_) => Some(obs.clone()),
+        ObsMessage::Sync(_) => {
+          self.mirror.receive(obs);
+          self.mirror.snapshot()
+        },
+      };
+    }
+    let msg = serde_json::to_string(&event).unwrap();
+    // Reject 不需要回复
+    self.outstanding = (!matches!(event, AgentReqEvent::Reject { .. })).then_some(event);
+    msg
+  }
 
+  fn on_response(&mut self, json: &str) {
+    if let Ok(resp) = serde_json::from_str::<AgentRespEvent>(json)
+      && self.outstanding.as_ref().is_some_and(|event| event.id() == resp.id())
+    {
+      self.outstanding = None;
+    }
+  }
+}
+
+impl EndPoint {
+  fn new(
+    req_bcast_receiver: mpsc::Receiver<String>, resp_sender: mpsc::Sender<String>,
+    session: Option<Arc<Mutex<AgentSession>>>,
+  ) -> Self {
+    let clients = Arc::new(Mutex::new(Vec::new()));
+    let forward = tokio::spawn(Self::forward(req_bcast_receiver, clients.clone(), session.clone()));
     Self {
-      req_client_all_senders,
+      clients,
       resp_sender,
+      session,
+      forward,
     }
   }
+
+  // 把对局的请求发给 endpoint 上的所有连接. 连接的缓冲满了就等, 对局随之等着; 等太久的连接断开.
+  // 等的时候不持有 clients 的锁, 否则慢连接会挡住重连和断线清理. 锁内记下 outstanding 和当时的连接,
+  // 之后才连上的连接由 greeting 补发这个请求
+  async fn forward(
+    mut req_bcast_receiver: mpsc::Receiver<String>, clients: Arc<Mutex<Vec<Client>>>,
+    session: Option<Arc<Mutex<AgentSession>>>,
+  ) {
+    while let Some(msg) = req_bcast_receiver.recv().await {
+      let (msg, senders) = {
+        let clients = clients.lock().await;
+        let msg = match &session {
+          Some(session) => session.lock().await.on_request(msg),
+          None => msg,
+        };
+        let senders: Vec<_> = clients.iter().map(|client| client.sender.clone()).collect();
+        (msg, senders)
+      };
+      // 同时发给所有连接, 一个请求最多等一个 SLOW_CLIENT_TIMEOUT
+      let sends = senders.into_iter().map(|sender| {
+        let msg = msg.clone();
+        async move {
+          match sender.send_timeout(msg, SLOW_CLIENT_TIMEOUT).await {
+            Ok(()) => None,
+            Err(SendTimeoutError::Timeout(_)) => {
+              warn!("Client too slow, dropping connection");
+              Some(sender)
+            },
+            Err(SendTimeoutError::Closed(_)) => Some(sender),
+          }
+        }
+      });
+      let gone: Vec<_> = join_all(sends).await.into_iter().flatten().collect();
+      if !gone.is_empty() {
+        let mut clients = clients.lock().await;
+        let (dropped, kept) = clients
+          .drain(..)
+          .partition(|client| gone.iter().any(|sender| sender.same_channel(&client.sender)));
+        *clients = kept;
+        for client in dropped {
+          client.kick(CloseCode::Again, "too slow");
+        }
+      }
+    }
+    // 对局结束, 连接发完缓冲的请求后关闭
+    clients.lock().await.clear();
+  }
+
+  async fn close(self, code: CloseCode, reason: &'static str) {
+    self.forward.abort();
+    for client in self.clients.lock().await.drain(..) {
+      client.kick(code, reason);
+    }
+  }
+}
+
+async fn close(ws_stream: &mut WebSocketStream<TcpStream>, code: CloseCode, reason: &'static str) {
+  let close_frame = CloseFrame {
+    code,
+    reason: Utf8Bytes::from(reason),
+  };
+  let _ = ws_stream.close(Some(close_frame)).await;
 }
 
 impl WsDispatcher {
@@ -55,18 +258,27 @@ impl WsDispatcher {
     let end_points = Arc::new(Mutex::new(HashMap::new()));
 
     tokio::spawn({
-      let end_points: Arc<Mutex<HashMap<Uuid, EndPoint>>> = end_points.clone();
+      let end_points = end_points.clone();
 
       async move {
-        let listener = TcpListener::bind(addr).await.unwrap();
+        let listener = match TcpListener::bind(&addr).await {
+          Ok(listener) => listener,
+          Err(e) => {
+            error!("Failed to bind {}: {}", addr, e);
+            return;
+          },
+        };
 
-        while let Ok((stream, _)) = listener.accept().await {
+        loop {
+          let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+              warn!("Failed to accept connection: {}", e);
+              continue;
+            },
+          };
           let end_points = end_points.clone();
-
-          let peer = stream.peer_addr().unwrap();
-          tokio::spawn(async move {
-            WsDispatcher::handle_connection(end_points, peer, stream).await; // TODO: join handle_connection
-          });
+          tokio::spawn(WsDispatcher::handle_connection(end_points, peer, stream));
         }
       }
     });
@@ -74,108 +286,285 @@ impl WsDispatcher {
     Self { end_points }
   }
 
-  async fn handle_connection(
-    end_points: Arc<Mutex<HashMap<uuid::Uuid, EndPoint>>>, peer: SocketAddr, stream: TcpStream,
-  ) {
+  async fn handle_connection(end_points: Arc<Mutex<HashMap<Route, EndPoint>>>, peer: SocketAddr, stream: TcpStream) {
     let mut captured_request: Option<Request> = None;
+    // 错误类型由 tungstenite 的 Callback 决定, 无法装箱
+    #[allow(clippy::result_large_err)]
     let callback = |req: &Request, response: Response| {
-      // Capture the request for later use
       captured_request = Some(req.clone());
-
       Ok(response)
     };
 
-    let mut ws_stream = accept_hdr_async(stream, callback).await.unwrap();
+    let mut ws_stream = match accept_hdr_async(stream, callback).await {
+      Ok(ws_stream) => ws_stream,
+      Err(e) => {
+        warn!("Handshake with {} failed: {}", peer, e);
+        return;
+      },
+    };
+    let Some(request) = captured_request else {
+      return;
+    };
 
-    let path = captured_request.unwrap().uri().path().to_string();
-    let id = path.strip_prefix("/").unwrap();
-    let id = Uuid::parse_str(id).unwrap();
+    // 每个连接自己选编码, 内部都是 JSON 文本
+    let format = match WireFormat::from_query(request.uri().query()) {
+      Ok(format) => format,
+      Err(e) => {
+        warn!("Bad format from {}: {}", peer, e);
+        close(&mut ws_stream, CloseCode::Unsupported, "unsupported format").await;
+        return;
+      },
+    };
+    let resume_token = request
+      .uri()
+      .query()
+      .unwrap_or_default()
+      .split('&')
+      .find_map(|pair| pair.strip_prefix("resume="))
+      .map(Uuid::parse_str);
+    let Ok(resume_token) = resume_token.transpose() else {
+      warn!("Bad resume token from {}", peer);
+      close(&mut ws_stream, CloseCode::Policy, "bad resume token").await;
+      return;
+    };
+    let Some(route) = Route::parse(request.uri().path()) else {
+      warn!("Bad path from {}: {}", peer, request.uri().path());
+      close(&mut ws_stream, CloseCode::Policy, "bad path").await;
+      return;
+    };
 
-    let (req_client_tx, mut req_client_rx) = mpsc::channel::<String>(1024);
+    let end_point = end_points.lock().await.get(&route).map(|end_point| {
+      (
+        end_point.clients.clone(),
+        end_point.resp_sender.clone(),
+        end_point.session.clone(),
+      )
+    });
+    let Some((clients, resp_tx, session)) = end_point else {
+      warn!("Unknown end point {:?} from {}", route, peer);
+      close(&mut ws_stream, CloseCode::Policy, "unknown end point").await;
+      return;
+    };
 
-    {
-      let mut end_points_guard = end_points.lock().await;
-      let end_point = end_points_guard.get_mut(&id).unwrap();
-      end_point.req_client_all_senders.write().await.push(req_client_tx);
-      // println!("end_point.req_client_all_senders: {:?}", end_point.req_client_all_senders.read().await);
-    }
-    let resp_tx = {
-      let end_points_guard = end_points.lock().await;
-      end_points_guard.get(&id).unwrap().resp_sender.clone()
+    let (req_client_tx, mut req_client_rx) = mpsc::channel::<String>(CLIENT_BUFFER);
+    let (kick_tx, mut kick_rx) = oneshot::channel();
+    let client = Client {
+      sender: req_client_tx,
+      kick: kick_tx,
     };
+    let admitted = {
+      let mut clients = clients.lock().await;
+      match &session {
+        Some(session) => {
+          let session = session.lock().await;
+          session.admit(resume_token, !clients.is_empty()).map(|resumed| {
+            // 重连时旧的连接可能还没断开, 去掉它
+            for old in clients.drain(..) {
+              old.kick(CloseCode::Normal, "replaced");
+            }
+            for msg in session.greeting(resumed) {
+              let _ = client.sender.try_send(msg);
+            }
+            clients.push(client);
+            session.presence.send_replace(Presence::Connected);
+            info!("Agent {:?} connected from {}, resumed: {}", route, peer, resumed);
+          })
+        },
+        None => {
+          clients.push(client);
+          Ok(())
+        },
+      }
+    };
+    if let Err(reason) = admitted {
+      warn!("Rejected {} on {:?}: {}", peer, route, reason);
+      close(&mut ws_stream, CloseCode::Policy, reason).await;
+      return;
+    }
 
+    let mut kicked = false;
     loop {
       tokio::select! {
+        biased;
+
+        kick = &mut kick_rx, if !kicked => {
+          kicked = true;
+          // Err 是 endpoint 不再转发给这个连接, 由 req_client_rx 关闭来处理
+          if let Ok((code, reason)) = kick {
+            info!("Closing {} on {:?}: {}", peer, route, reason);
+            close(&mut ws_stream, code, reason).await;
+            break;
+          }
+        }
+
         msg_result = req_client_rx.recv() => {
-          match msg_result {
-            Some(msg) => {
-              if msg.len() > 1024 * 1024 { // 1MB limit
-                error!("Message too large from {}, dropping connection", peer);
-                break;
-              }
-
-              let message = Message::Text(msg.clone().into());
-              if let Err(e) = ws_stream.send(message).await {
-                error!("Failed to send message to {}: {}", peer, e);
-                break;
-              }
-
-              info!("Sent message to {}: {}", peer, msg);
-            }
-            None => {
-              warn!("Client channel closed for {}, terminating connection", peer);
-              let _ = ws_stream.close(Some(CloseFrame { code: CloseCode::Normal, reason: Utf8Bytes::from("req_client_rx closed") })).await;
-              break;
-            }
+          let Some(msg) = msg_result else {
+            info!("End point {:?} closed, terminating connection to {}", route, peer);
+            close(&mut ws_stream, CloseCode::Normal, "game over").await;
+            break;
+          };
+          let message = match format {
+            WireFormat::Json => Message::Text(msg.clone().into()),
+            WireFormat::MessagePack => match format.encode_json_text(&msg) {
+              Ok(frame) => Message::Binary(frame.into()),
+              Err(e) => {
+                error!("Failed to encode message to {}: {}", peer, e);
+                continue;
+              },
+            },
+          };
+          if message.len() > MAX_MESSAGE_SIZE {
+            error!("Message too large for {}, dropping connection", peer);
+            close(&mut ws_stream, CloseCode::Size, "message too large").await;
+            break;
           }
+          if let Err(e) = ws_stream.send(message).await {
+            warn!("Failed to send message to {}: {}", peer, e);
+            break;
+          }
+          info!("Sent message to {}: {}", peer, msg);
         }
 
         msg_result = ws_stream.next() => {
-          match msg_result {
-            Some(msg) => {
-              match msg {
-                Ok(Message::Text(text)) => {
-                  if text.len() > 1024 * 1024 { // 1MB limit
-                    error!("Message too large from {}, dropping connection", peer);
-                    break;
-                  }
-                  info!("Received message from {}: {}", peer, text);
-
-                  resp_tx.send(text.to_string()).await.unwrap();
-                }
-                Ok(Message::Close(close_frame)) => {
-                  info!("Client {} requested connection close: {:?}", peer, close_frame);
-                  break;
-                }
-                _ => {
-                  error!("Received unknown message from {}, dropping connection", peer);
-                  break;
-                }
-              }
-            }
+          let json = match msg_result {
+            Some(Ok(Message::Text(text))) if text.len() <= MAX_MESSAGE_SIZE => Ok(text.to_string()),
+            Some(Ok(Message::Binary(frame))) if frame.len() <= MAX_MESSAGE_SIZE => {
+              WireFormat::MessagePack.decode_json_text(&frame)
+            },
+            Some(Ok(Message::Text(_) | Message::Binary(_))) => {
+              error!("Message too large from {}, dropping connection", peer);
+              close(&mut ws_stream, CloseCode::Size, "message too large").await;
+              break;
+            },
+            Some(Ok(Message::Close(close_frame))) => {
+              info!("Client {} requested connection close: {:?}", peer, close_frame);
+              break;
+            },
+            // tungstenite 自动回复 ping
+            Some(Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_))) => continue,
+            Some(Err(e)) => {
+              warn!("WebSocket error from {}: {}", peer, e);
+              break;
+            },
             None => {
               warn!("WebSocket stream ended for {}, terminating connection", peer);
               break;
-            }
+            },
+          };
+          let json = match json {
+            Ok(json) => json,
+            Err(e) => {
+              error!("Bad binary message from {}: {}", peer, e);
+              continue;
+            },
+          };
+          info!("Received message from {}: {}", peer, json);
+          if let Some(session) = &session {
+            session.lock().await.on_response(&json);
+          }
+          if resp_tx.send(json).await.is_err() {
+            close(&mut ws_stream, CloseCode::Away, "end point removed").await;
+            break;
           }
         }
       }
     }
 
-    // TODO: remove all_req_client_txs
+    // 去掉这个连接, agent 的 endpoint 没有连接了就开始计算宽限期
+    drop(req_client_rx);
+    let mut clients = clients.lock().await;
+    clients.retain(|client| !client.sender.is_closed());
+    if let Some(session) = &session
+      && clients.is_empty()
+    {
+      info!("Agent {:?} disconnected", route);
+      session
+        .lock()
+        .await
+        .presence
+        .send_replace(Presence::Away(Instant::now()));
+    }
+  }
+
+  async fn insert(&self, route: Route, end_point: EndPoint) {
+    let old = self.end_points.lock().await.insert(route, end_point);
+    if let Some(old) = old {
+      old.close(CloseCode::Away, "end point replaced").await;
+    }
   }
 
+  // 路径 /<end_point>, 请求发给所有连上的客户端, 比如历史记录的观众
   pub async fn add_end_point(
-    &mut self, end_point: uuid::Uuid, req_bcast_receiver: mpsc::Receiver<String>, resp_sender: mpsc::Sender<String>,
+    &self, end_point: Uuid, req_bcast_receiver: mpsc::Receiver<String>, resp_sender: mpsc::Sender<String>,
   ) {
     self
-      .end_points
-      .lock()
-      .await
-      .insert(end_point, EndPoint::new(req_bcast_receiver, resp_sender));
+      .insert(
+        Route::Single(end_point),
+        EndPoint::new(req_bcast_receiver, resp_sender, None),
+      )
+      .await;
+  }
+
+  fn agent_end_point(grace: Duration) -> (EndPoint, WsTransport) {
+    let (req_bcast_sender, req_bcast_receiver) = mpsc::channel::<String>(END_POINT_BUFFER);
+    let (resp_sender, resp_receiver) = mpsc::channel::<String>(END_POINT_BUFFER);
+    let (presence_sender, presence) = watch::channel(Presence::Waiting);
+    let session = Arc::new(Mutex::new(AgentSession::new(presence_sender)));
+    let end_point = EndPoint::new(req_bcast_receiver, resp_sender, Some(session));
+    let transport = WsTransport::new(req_bcast_sender, resp_receiver).with_session(presence, grace);
+    (end_point, transport)
+  }
+
+  // agent 的 endpoint, 只有一个连接. 断线后 grace 内连回来可以接着决策, 超过后由 fallback 决策, 直到连回来
+  pub async fn add_agent_end_point(&self, end_point: Uuid, grace: Duration) -> WsTransport {
+    let (agent, transport) = Self::agent_end_point(grace);
+    self.insert(Route::Single(end_point), agent).await;
+    transport
+  }
+
+  // 对局 game 中 seat 的 agent, 路径 /<game>/<seat>. 请求只发给这个座位的连接
+  pub async fn add_seat(&self, game: Uuid, seat: PlayerIndex, grace: Duration) -> WsTransport {
+    let (end_point, transport) = Self::agent_end_point(grace);
+    self.insert(Route::Seat(game, seat.value()), end_point).await;
+    transport
+  }
+
+  // 对局 game 的历史记录, 路径 /<game>/history, 可以有多个观众
+  pub async fn add_history(&self, game: Uuid) -> History {
+    let (req_bcast_sender, req_bcast_receiver) = mpsc::channel::<String>(END_POINT_BUFFER);
+    let (resp_sender, resp_receiver) = mpsc::channel::<String>(END_POINT_BUFFER);
+    self
+      .insert(
+        Route::History(game),
+        EndPoint::new(req_bcast_receiver, resp_sender, None),
+      )
+      .await;
+    History::new(req_bcast_sender, resp_receiver)
+  }
+
+  pub async fn remove_end_point(&self, end_point: Uuid) {
+    let removed = self.end_points.lock().await.remove(&Route::Single(end_point));
+    if let Some(removed) = removed {
+      removed.close(CloseCode::Away, "end point removed").await;
+    }
   }
 
-  pub async fn remove_end_point(&mut self, end_point: uuid::Uuid) {
-    self.end_points.lock().await.remove(&end_point);
+  // 移除对局的所有 endpoint, 连着的客户端以 Away 断开
+  pub async fn remove_game(&self, game: Uuid) {
+    let removed: Vec<_> = {
+      let mut end_points = self.end_points.lock().await;
+      let routes: Vec<_> = end_points
+        .keys()
+        .filter(|route| route.game() == game)
+        .copied()
+        .collect();
+      routes
+        .into_iter()
+        .filter_map(|route| end_points.remove(&route))
+        .collect()
+    };
+    for end_point in removed {
+      end_point.close(CloseCode::Away, "game removed").await;
+    }
   }
 }
@@ -0,0 +1,176 @@
+// 用 SDK 连接服务端: 通过 WsDispatcher 下完整的一局, 心跳没有回应时重连, 被拒绝时退出
+
+use std::time::Duration;
+
+use agent_sdk::connections::{LinesConnection, WsConnection};
+use agent_sdk::{AgentClient, Endpoint, SdkError, connect};
+use futures_util::{SinkExt, StreamExt};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use server::agent_transports::WsTransport;
+use server::domain::{AgentReqEvent, AgentRespEvent, Camp};
+use server::fa_agents::NoopFAAgent;
+use server::{
+  AbstractFAAgent, AbstractFYIAgent, Game, History, IdGen, NoopFYIAgent, Player, PlayerIndexedVec, RandomFAAgent,
+  RemoteFAAgent, V2FAAgent, WireFormat, WsDispatcher,
+};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_tungstenite::accept_async;
+use tokio_tungstenite::tungstenite::Message;
+
+fn history() -> History {
+  let (history_req_bcast_sender, mut history_req_bcast_receiver) = mpsc::channel::<String>(1024);
+  let (_, history_resp_receiver) = mpsc::channel::<String>(1024);
+  tokio::spawn(async move { while history_req_bcast_receiver.recv().await.is_some() {} });
+  History::new(history_req_bcast_sender, history_resp_receiver)
+}
+
+fn free_port() -> u16 {
+  std::net::TcpListener::bind("127.0.0.1:0")
+    .unwrap()
+    .local_addr()
+    .unwrap()
+    .port()
+}
+
+#[tokio::test]
+async fn ws_client_plays_a_whole_game_through_the_dispatcher() {
+  let port = free_port();
+  let dispatcher = WsDispatcher::new(format!("127.0.0.1:{}", port));
+  let end_point = uuid::Uuid::new_v4();
+  let (req_sender, req_receiver) = mpsc::channel::<String>(16);
+  let (resp_sender, resp_receiver) = mpsc::channel::<String>(16);
+  dispatcher.add_end_point(end_point, req_receiver, resp_sender).await;
+
+  let client = tokio::spawn(async move {
+    let endpoint = || Endpoint::Ws {
+      url: format!("ws://127.0.0.1:{}/{}", port, end_point),
+      format: WireFormat::MessagePack,
+    };
+    // dispatcher 可能还没开始监听
+    let mut client = loop {
+      match connect(endpoint()).await {
+        Ok(client) => break client,
+        Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+      }
+    };
+    client.run(&mut V2FAAgent::new()).await
+  });
+
+  let mut remote = RemoteFAAgent::new(
+    IdGen::new(),
+    WsTransport::new(req_sender, resp_receiver),
+    Box::new(NoopFAAgent::new()),
+  );
+  remote.wait_for_ready().await;
+  remote.handshake().await.unwrap();
+
+  let num_players = 4;
+  let mut players = PlayerIndexedVec::<Player>::new();
+  let mut agents = PlayerIndexedVec::<Box<dyn AbstractFAAgent>>::new();
+  let mut fyi_agents = PlayerIndexedVec::<Box<dyn AbstractFYIAgent>>::new();
+  agents.push(Box::new(remote));
+  for i in 0..num_players {
+    let camp = if i % 2 == 0 { Camp::汉 } else { Camp::楚 };
+    players.push(Player::new(uuid::Uuid::new_v4(), format!("player{}", i), camp));
+    if i > 0 {
+      agents.push(Box::new(RandomFAAgent::with_seed(i as u64)));
+    }
+    fyi_agents.push(Box::new(NoopFYIAgent::new()));
+  }
+  let mut game = Game::new(
+    num_players,
+    players,
+    agents,
+    fyi_agents,
+    StdRng::seed_from_u64(7),
+    history(),
+  )
+  .await;
+  game.enable_strict_agents();
+  game.run().await.unwrap();
+
+  // 对局结束后 dispatcher 关闭连接, run 正常返回
+  drop(game);
+  client.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn ws_client_reconnects_when_heartbeats_go_unanswered() {
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let url = format!("ws://{}", listener.local_addr().unwrap());
+  let server = tokio::spawn(async move {
+    let mut answered = Vec::new();
+    let mut stale = Vec::new();
+    for id in 1..=2 {
+      let (stream, _) = listener.accept().await.unwrap();
+      let mut ws_stream = accept_async(stream).await.unwrap();
+      let req = serde_json::to_string(&AgentReqEvent::WaitForReady { id }).unwrap();
+      ws_stream.send(Message::Text(req.into())).await.unwrap();
+      let Some(Ok(Message::Text(text))) = ws_stream.next().await else {
+        panic!("expected a text frame");
+      };
+      answered.push(serde_json::from_str::<AgentRespEvent>(&text).unwrap().id());
+      if id == 1 {
+        // 不再读, ping 得不到 pong
+        stale.push(ws_stream);
+      } else {
+        ws_stream.close(None).await.unwrap();
+        while ws_stream.next().await.is_some() {}
+      }
+    }
+    answered
+  });
+
+  let connection = WsConnection::connect(&url, WireFormat::Json).await.unwrap();
+  let mut client = AgentClient::new(Box::new(connection))
+    .with_heartbeat(Duration::from_millis(50))
+    .with_reconnects(3, Duration::from_millis(10));
+  client.run(&mut RandomFAAgent::new()).await.unwrap();
+  assert_eq!(server.await.unwrap(), vec![1, 2]);
+}
+
+#[tokio::test]
+async fn lines_client_answers_hello_and_stops_when_rejected() {
+  let (client_stream, server_stream) = tokio::io::duplex(1 << 16);
+  let (client_reader, client_writer) = tokio::io::split(client_stream);
+  let (server_reader, mut server_writer) = tokio::io::split(server_stream);
+
+  let hello = AgentReqEvent::Hello {
+    id: 1,
+    protocol_version: server::PROTOCOL_VERSION,
+    ruleset: "test".to_string(),
+    roles: Vec::new(),
+    cards: Vec::new(),
+    resume_token: None,
+  };
+  let reject = AgentReqEvent::Reject {
+    id: 2,
+    reason: "too weak".to_string(),
+  };
+  for req in [hello, reject] {
+    let mut line = serde_json::to_string(&req).unwrap();
+    line.push('\n');
+    tokio::io::AsyncWriteExt::write_all(&mut server_writer, line.as_bytes())
+      .await
+      .unwrap();
+  }
+
+  let connection = LinesConnection::new("TestAgent", client_reader, client_writer);
+  let result = AgentClient::new(Box::new(connection))
+    .run(&mut RandomFAAgent::new())
+    .await;
+  assert!(matches!(result, Err(SdkError::Rejected(reason)) if reason == "too weak"));
+
+  let mut lines = tokio::io::AsyncBufReadExt::lines(tokio::io::BufReader::new(server_reader));
+  let resp: AgentRespEvent = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+  assert!(matches!(
+    resp,
+    AgentRespEvent::Hello {
+      id: 1,
+      obs_deltas: true,
+      ..
+    }
+  ));
+}
@@ -0,0 +1,31 @@
+// 所有工具共用的 agent 写法
+
+use server::AgentSpec;
+use server::domain::Camp;
+
+#[tokio::test]
+async fn agent_specs_parse_the_same_everywhere() {
+  for spec in ["random", "v2", "heuristic"] {
+    AgentSpec::parse(spec).unwrap().agent(0).await.unwrap();
+  }
+  assert!(
+    matches!(AgentSpec::parse("exec:./bot --level 3").unwrap(), AgentSpec::Exec(command) if command == "./bot --level 3")
+  );
+  assert!(AgentSpec::parse("heuristic").unwrap().is_deterministic());
+  assert!(!AgentSpec::parse("random").unwrap().is_deterministic());
+
+  let error = AgentSpec::parse("alphago").err().unwrap();
+  assert!(error.to_string().contains("unknown agent: alphago"), "{}", error);
+  assert!(AgentSpec::parse("heuristic:/nonexistent/weights.toml").is_err());
+  assert!(AgentSpec::parse("policy:/nonexistent/weights.json").is_err());
+
+  // v2 只能坐汉的座位
+  let v2 = AgentSpec::parse("v2").unwrap();
+  v2.check_seat(0, Camp::汉).unwrap();
+  let error = v2.check_seat(1, Camp::楚).err().unwrap();
+  assert_eq!(error.to_string(), "seat 1: v2 only supports 汉 seats");
+  AgentSpec::parse("random").unwrap().check_seat(1, Camp::楚).unwrap();
+
+  // exec 不是进程内的 agent
+  assert!(AgentSpec::parse("exec:true").unwrap().local_agent(0).is_err());
+}
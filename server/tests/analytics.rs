@@ -0,0 +1,89 @@
+// 多局统计: 按 4 人局和 6 人局分别汇总
+
+use server::domain::{Oper, Role};
+use server::{Analytics, HistoryReqEvent, Table};
+
+mod common;
+
+use common::*;
+
+async fn fixture(num_players: usize) -> Vec<HistoryReqEvent> {
+  let seats = (0..num_players).map(|_| seat(2, &[], &[])).collect();
+  let (drop_roles, script) = if num_players == 4 {
+    (
+      vec![Role::主教, Role::建筑师, Role::魔术师],
+      Script::new().role(Role::国王).opers(&[Oper::Gold(2)]),
+    )
+  } else {
+    (vec![Role::主教], Script::default())
+  };
+  play(scenario(seats, &drop_roles, &[]), vec![script]).await.events
+}
+
+fn table<'a>(tables: &'a [Table], name: &str) -> &'a Table {
+  tables.iter().find(|table| table.name == name).unwrap()
+}
+
+// 某一列等于 value 的行
+fn rows<'a>(table: &'a Table, column: &str, value: &str) -> Vec<&'a Vec<String>> {
+  let index = table.header.iter().position(|h| *h == column).unwrap();
+  table.rows.iter().filter(|row| row[index] == value).collect()
+}
+
+fn cell<'a>(table: &Table, row: &'a [String], column: &str) -> &'a str {
+  &row[table.header.iter().position(|h| *h == column).unwrap()]
+}
+
+fn role_picks(events: &[HistoryReqEvent]) -> u64 {
+  events
+    .iter()
+    .filter(|event| matches!(event, HistoryReqEvent::ChooseRoleResp { .. }))
+    .count() as u64
+}
+
+#[tokio::test]
+async fn analytics_aggregates_4_and_6_player_games_separately() {
+  let four = fixture(4).await;
+  let six = fixture(6).await;
+  let mut analytics = Analytics::new();
+  analytics.add_game(&four);
+  analytics.add_game(&four);
+  analytics.add_game(&six);
+  // 没打完的对局不计入
+  analytics.add_game(&six[..six.len() - 1]);
+  assert_eq!(analytics.num_games(), 3);
+  assert_eq!(analytics.num_skipped(), 1);
+
+  let tables = analytics.tables();
+  let games = table(&tables, "games");
+  assert_eq!(games.rows.len(), 2);
+  for (players, count) in [("4", "2"), ("6", "1")] {
+    let row = rows(games, "players", players)[0];
+    assert_eq!(cell(games, row, "games"), count);
+    assert_eq!(cell(games, row, "avg_rounds"), "1.00");
+  }
+
+  // 每次选角色都按人数记了一次
+  let picks = table(&tables, "role_picks");
+  for (players, expected) in [("4", 2 * role_picks(&four)), ("6", role_picks(&six))] {
+    let total: u64 = rows(picks, "players", players)
+      .iter()
+      .map(|row| cell(picks, row, "picks").parse::<u64>().unwrap())
+      .sum();
+    assert_eq!(total, expected, "{} players", players);
+  }
+  // 4 人局 0 号拿着皇冠, 两局都先选了国王
+  let king = rows(picks, "role", Role::国王.name())
+    .into_iter()
+    .find(|row| cell(picks, row, "players") == "4" && cell(picks, row, "position") == "0")
+    .unwrap();
+  assert_eq!(cell(picks, king, "picks"), "2");
+
+  // CSV 与表格的行一一对应
+  let mut csv = Vec::new();
+  picks.write_csv(&mut csv).unwrap();
+  let csv = String::from_utf8(csv).unwrap();
+  assert_eq!(csv.lines().count(), picks.rows.len() + 1);
+  assert_eq!(csv.lines().next().unwrap(), picks.header.join(","));
+  assert!(picks.render().starts_with("== role_picks =="));
+}
@@ -0,0 +1,315 @@
+// 各测试文件共用的自定义局面和按剧本决策的 agent
+#![allow(dead_code)]
+
+use async_trait::async_trait;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use server::domain::{Camp, Card, DestroyTarget, MagicianSkill, Oper, PlayerIndex, Role, RoleSet};
+use server::{
+  AbstractFAAgent, AbstractFYIAgent, Game, History, HistoryReqEvent, NoopFYIAgent, Obs, Player, PlayerIndexedVec,
+  Scenario, ScenarioPlayer,
+};
+use tokio::sync::mpsc;
+
+// 剧本里没写的决策: 选角色取编号最小的, 刺杀和偷取取第一个选项, 魔术师放弃, 军阀不拆, 墓地不买,
+// 选牌取第一张, 操作用完后结束回合. 角色和角色技能只在第一轮按剧本来
+#[derive(Default, Clone)]
+pub struct Script {
+  role: Option<Role>,
+  kill: Option<Role>,
+  steal: Option<Role>,
+  magic: Option<MagicianSkill>,
+  destroy: Option<(usize, Card)>,
+  tomb: bool,
+  opers: Vec<Oper>,
+  keep: Vec<Card>,
+}
+
+impl Script {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn role(mut self, role: Role) -> Self {
+    self.role = Some(role);
+    self
+  }
+
+  pub fn kill(mut self, role: Role) -> Self {
+    self.kill = Some(role);
+    self
+  }
+
+  pub fn steal(mut self, role: Role) -> Self {
+    self.steal = Some(role);
+    self
+  }
+
+  pub fn magic(mut self, skill: MagicianSkill) -> Self {
+    self.magic = Some(skill);
+    self
+  }
+
+  // 拆 seat 号座位的 card
+  pub fn destroy(mut self, seat: usize, card: Card) -> Self {
+    self.destroy = Some((seat, card));
+    self
+  }
+
+  pub fn tomb(mut self) -> Self {
+    self.tomb = true;
+    self
+  }
+
+  pub fn opers(mut self, opers: &[Oper]) -> Self {
+    self.opers = opers.to_vec();
+    self
+  }
+
+  pub fn keep(mut self, cards: &[Card]) -> Self {
+    self.keep = cards.to_vec();
+    self
+  }
+}
+
+pub struct ScriptedAgent {
+  pub seat: PlayerIndex,
+  pub script: Script,
+}
+
+pub fn first_role(roles: RoleSet) -> Role {
+  Role::population()
+    .into_iter()
+    .find(|&role| roles.contains(role))
+    .unwrap()
+}
+
+#[async_trait]
+impl AbstractFAAgent for ScriptedAgent {
+  fn name(&self) -> &str {
+    "scripted"
+  }
+
+  async fn wait_for_ready(&mut self) {}
+
+  async fn choose_init_card(&mut self, _obs: &Obs, c0: Card, _c1: Card) -> Card {
+    c0
+  }
+
+  async fn choose_role(&mut self, _obs: &Obs, roles: RoleSet) -> Role {
+    match self.script.role.take() {
+      Some(role) => {
+        assert!(
+          roles.contains(role),
+          "seat {}: {:?} not offered",
+          self.seat.value(),
+          role
+        );
+        role
+      },
+      None => first_role(roles),
+    }
+  }
+
+  async fn choose_kill_target(&mut self, _obs: &Obs, choices: RoleSet) -> Role {
+    let role = self.script.kill.take().unwrap_or_else(|| first_role(choices));
+    assert!(choices.contains(role), "kill target {:?} not offered", role);
+    role
+  }
+
+  async fn choose_steal_target(&mut self, _obs: &Obs, choices: RoleSet) -> Role {
+    let role = self.script.steal.take().unwrap_or_else(|| first_role(choices));
+    assert!(choices.contains(role), "steal target {:?} not offered", role);
+    role
+  }
+
+  async fn choose_swap_target(&mut self, _obs: &Obs) -> MagicianSkill {
+    self.script.magic.take().unwrap_or(MagicianSkill::放弃)
+  }
+
+  async fn choose_destory_target(&mut self, obs: &Obs, choices: &[DestroyTarget]) -> Option<DestroyTarget> {
+    let (seat, card) = self.script.destroy.take()?;
+    let target = choices
+      .iter()
+      .find(|t| t.player_offset.to_index(self.seat, obs.num_players()).value() == seat && t.card == card);
+    assert!(target.is_some(), "destroy target {} {:?} not offered", seat, card);
+    target.copied()
+  }
+
+  async fn choose_tomb(&mut self, _obs: &Obs, _c: Card) -> bool {
+    self.script.tomb
+  }
+
+  async fn choose_oper(&mut self, _obs: &Obs, choices: &[Oper]) -> Oper {
+    if self.script.opers.is_empty() {
+      return Oper::EndRound;
+    }
+    let oper = self.script.opers.remove(0);
+    assert!(
+      choices.contains(&oper),
+      "seat {}: {:?} not in {:?}",
+      self.seat.value(),
+      oper,
+      choices
+    );
+    oper
+  }
+
+  async fn choose_from_2(&mut self, _obs: &Obs, c0: Card, c1: Card) -> Card {
+    if self.script.keep.contains(&c1) { c1 } else { c0 }
+  }
+
+  async fn choose_from_3(&mut self, _obs: &Obs, c0: Card, c1: Card, c2: Card) -> Card {
+    [c1, c2]
+      .into_iter()
+      .find(|c| self.script.keep.contains(c))
+      .unwrap_or(c0)
+  }
+}
+
+pub fn seat(gold: u32, hand: &[Card], buildings: &[Card]) -> ScenarioPlayer {
+  ScenarioPlayer {
+    name: String::new(),
+    camp: Camp::汉,
+    gold,
+    hand: hand.to_vec(),
+    buildings: buildings.to_vec(),
+  }
+}
+
+// 偶数座位为汉, 奇数座位为楚, 皇冠在 0 号, 只打一轮, 没写出来的牌洗进牌堆
+pub fn scenario(seats: Vec<ScenarioPlayer>, drop_roles: &[Role], deck: &[Card]) -> Scenario {
+  let players = seats
+    .into_iter()
+    .enumerate()
+    .map(|(i, mut p)| {
+      p.camp = if i % 2 == 0 { Camp::汉 } else { Camp::楚 };
+      p
+    })
+    .collect();
+  Scenario {
+    round: 1,
+    crown: 0,
+    players,
+    deck: deck.to_vec(),
+    drop: Vec::new(),
+    shuffle_rest: true,
+    drop_roles: drop_roles.to_vec(),
+    rounds: Some(1),
+    seed: Some(0),
+  }
+}
+
+pub struct Played {
+  pub game: Game,
+  pub events: Vec<HistoryReqEvent>,
+  pub result: (f64, f64),
+}
+
+impl Played {
+  pub fn player(&self, seat: usize) -> &Player {
+    &self.game.players()[PlayerIndex::from_usize(seat)]
+  }
+
+  pub fn buildings(&self, seat: usize) -> Vec<Card> {
+    self.player(seat).iter_buildings().collect()
+  }
+
+  pub fn oper_choices(&self, seat: usize) -> Vec<&Vec<Oper>> {
+    self
+      .events
+      .iter()
+      .filter_map(|event| match event {
+        HistoryReqEvent::OperReq { actor, choices, .. } if actor.value() == seat => Some(choices),
+        _ => None,
+      })
+      .collect()
+  }
+
+  pub fn destroy_choices(&self) -> Vec<(usize, Card)> {
+    let num_players = self.game.players().len();
+    self
+      .events
+      .iter()
+      .filter_map(|event| match event {
+        HistoryReqEvent::DestroyReq { actor, choices, .. } => Some(
+          choices
+            .iter()
+            .map(|t| (t.player_offset.to_index(*actor, num_players).value(), t.card))
+            .collect::<Vec<_>>(),
+        ),
+        _ => None,
+      })
+      .next()
+      .expect("no DestroyReq")
+  }
+
+  pub fn tomb_actors(&self) -> Vec<usize> {
+    self
+      .events
+      .iter()
+      .filter_map(|event| match event {
+        HistoryReqEvent::TombReq { actor, .. } => Some(actor.value()),
+        _ => None,
+      })
+      .collect()
+  }
+}
+
+pub fn history() -> (History, mpsc::Receiver<String>) {
+  // 容量足够装下整局的事件, 打完之后再一次性取出来
+  let (sender, receiver) = mpsc::channel::<String>(1 << 16);
+  let (_, resp_receiver) = mpsc::channel::<String>(1);
+  (History::new(sender, resp_receiver), receiver)
+}
+
+pub fn drain(mut receiver: mpsc::Receiver<String>) -> Vec<HistoryReqEvent> {
+  let mut events = Vec::new();
+  while let Ok(text) = receiver.try_recv() {
+    events.push(serde_json::from_str(&text).unwrap());
+  }
+  events
+}
+
+pub fn scripted_game(scenario: &Scenario, scripts: Vec<Script>, history: History) -> Game {
+  let mut agents = PlayerIndexedVec::<Box<dyn AbstractFAAgent>>::new();
+  let mut fyi_agents = PlayerIndexedVec::<Box<dyn AbstractFYIAgent>>::new();
+  for i in 0..scenario.players.len() {
+    agents.push(Box::new(ScriptedAgent {
+      seat: PlayerIndex::from_usize(i),
+      script: scripts.get(i).cloned().unwrap_or_default(),
+    }));
+    fyi_agents.push(Box::new(NoopFYIAgent::new()));
+  }
+  Game::from_scenario(scenario, agents, fyi_agents, StdRng::seed_from_u64(0), history).unwrap()
+}
+
+// 0 号是远程 agent, 其他人按默认剧本
+pub fn remote_game(scenario: &Scenario, remote: Box<dyn AbstractFAAgent>, history: History) -> Game {
+  let mut agents = PlayerIndexedVec::<Box<dyn AbstractFAAgent>>::new();
+  let mut fyi_agents = PlayerIndexedVec::<Box<dyn AbstractFYIAgent>>::new();
+  agents.push(remote);
+  for i in 1..scenario.players.len() {
+    agents.push(Box::new(ScriptedAgent {
+      seat: PlayerIndex::from_usize(i),
+      script: Script::default(),
+    }));
+  }
+  for _ in 0..scenario.players.len() {
+    fyi_agents.push(Box::new(NoopFYIAgent::new()));
+  }
+  Game::from_scenario(scenario, agents, fyi_agents, StdRng::seed_from_u64(0), history).unwrap()
+}
+
+pub async fn play(scenario: Scenario, scripts: Vec<Script>) -> Played {
+  let (history, receiver) = history();
+  let mut game = scripted_game(&scenario, scripts, history);
+  game.enable_leak_checks();
+  game.enable_strict_agents();
+  let result = game.run().await.unwrap().result;
+  Played {
+    game,
+    events: drain(receiver),
+    result,
+  }
+}
@@ -0,0 +1,393 @@
+// 监督学习数据集: 特征宽度和 schema 一致, 每种决策的 mask 正好是合法动作, CSV 和 NPY 分片的格式和数量.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use server::domain::{Camp, Card, MagicianSkill, Oper, PlayerOffset, Role, RoleSet};
+use server::{
+  AbstractFAAgent, AbstractFYIAgent, DatasetFormat, DatasetWriter, DecisionKind, Game, HistoryReqEvent, NUM_ACTIONS,
+  NoopFYIAgent, Obs, Player, PlayerIndexedVec, RandomFAAgent, action_names, extract_samples, replay_outcome,
+};
+
+mod common;
+
+use common::*;
+
+// 随机 agent 打一局完整的对局, 返回历史记录和以 Camp as usize 为下标的胜负
+async fn random_game(seed: u64, num_players: usize) -> (Vec<HistoryReqEvent>, [f32; 2]) {
+  let (history, receiver) = history();
+  let mut players = PlayerIndexedVec::<Player>::new();
+  let mut agents = PlayerIndexedVec::<Box<dyn AbstractFAAgent>>::new();
+  let mut fyi_agents = PlayerIndexedVec::<Box<dyn AbstractFYIAgent>>::new();
+  for i in 0..num_players {
+    let camp = if i % 2 == 0 { Camp::汉 } else { Camp::楚 };
+    players.push(Player::new(uuid::Uuid::new_v4(), format!("p{}", i), camp));
+    agents.push(Box::new(RandomFAAgent::with_seed(seed * 10 + i as u64)));
+    fyi_agents.push(Box::new(NoopFYIAgent::new()));
+  }
+  let mut game = Game::new(
+    num_players,
+    players,
+    agents,
+    fyi_agents,
+    StdRng::seed_from_u64(seed),
+    history,
+  )
+  .await;
+  let (楚, 汉) = game.run().await.unwrap().result;
+  (drain(receiver), [楚 as f32, 汉 as f32])
+}
+
+fn out_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("dataset_{}_{}", name, std::process::id()));
+  let _ = std::fs::remove_dir_all(&dir);
+  dir
+}
+
+fn role_names(roles: RoleSet) -> Vec<String> {
+  Role::population()
+    .into_iter()
+    .filter(|&role| roles.contains(role))
+    .map(|role| format!("role.{}", role.name()))
+    .collect()
+}
+
+fn card_name(card: Card) -> String {
+  format!("card.{}", card.name())
+}
+
+fn magic_name(skill: &MagicianSkill) -> String {
+  match skill {
+    MagicianSkill::放弃 => "magic.放弃".to_string(),
+    MagicianSkill::制衡(_) => "magic.制衡".to_string(),
+    MagicianSkill::Swap(offset) => format!("magic.swap.offset{}", offset.value()),
+  }
+}
+
+fn destroy_name(target: Option<(PlayerOffset, Card)>) -> String {
+  match target {
+    None => "destroy.none".to_string(),
+    Some((offset, card)) => format!("destroy.offset{}.{}", offset.value(), card.name()),
+  }
+}
+
+fn oper_name(oper: Oper) -> String {
+  match oper {
+    Oper::Gold(_) => "oper.Gold".to_string(),
+    Oper::Build(card) => format!("oper.build.{}", card.name()),
+    Oper::SellCard(card) => format!("oper.sell.{}", card.name()),
+    oper => format!("oper.{:?}", oper),
+  }
+}
+
+fn magic_names(obs: &Obs) -> Vec<String> {
+  let mut names = vec![magic_name(&MagicianSkill::放弃)];
+  if !obs.hero_cards().is_empty() {
+    names.push(magic_name(&MagicianSkill::制衡(obs.hero_cards().to_vec())));
+  }
+  for i in 1..obs.num_players() {
+    names.push(magic_name(&MagicianSkill::Swap(PlayerOffset::from_usize(i))));
+  }
+  names
+}
+
+struct Expected {
+  kind: DecisionKind,
+  legal: Vec<String>,
+  chosen: String,
+}
+
+// 不经过数据集的编码, 直接从请求里的选项和回复里的选择得到每个样本应有的合法动作和实际动作, 按回复的顺序排列
+fn expected_decisions(events: &[HistoryReqEvent]) -> Vec<Expected> {
+  let mut pending = HashMap::<u32, (DecisionKind, Vec<String>)>::new();
+  let mut destroyers = HashMap::new();
+  let mut expected = Vec::new();
+  for event in events {
+    let (req_id, chosen) = match event {
+      HistoryReqEvent::InitCardReq { id, c0, c1, .. } => {
+        pending.insert(*id, (DecisionKind::InitCard, vec![card_name(*c0), card_name(*c1)]));
+        continue;
+      },
+      HistoryReqEvent::ChooseFrom2Req { id, c0, c1, .. } => {
+        pending.insert(*id, (DecisionKind::ChooseFrom2, vec![card_name(*c0), card_name(*c1)]));
+        continue;
+      },
+      HistoryReqEvent::ChooseFrom3Req { id, c0, c1, c2, .. } => {
+        let legal = vec![card_name(*c0), card_name(*c1), card_name(*c2)];
+        pending.insert(*id, (DecisionKind::ChooseFrom3, legal));
+        continue;
+      },
+      HistoryReqEvent::KillReq { id, choices, .. } => {
+        pending.insert(*id, (DecisionKind::Kill, role_names(*choices)));
+        continue;
+      },
+      HistoryReqEvent::StealReq { id, choices, .. } => {
+        pending.insert(*id, (DecisionKind::Steal, role_names(*choices)));
+        continue;
+      },
+      HistoryReqEvent::MagicReq { id, obs, .. } => {
+        pending.insert(*id, (DecisionKind::Magic, magic_names(obs)));
+        continue;
+      },
+      HistoryReqEvent::DestroyReq {
+        id,
+        actor,
+        obs,
+        choices,
+      } => {
+        let mut legal = vec![destroy_name(None)];
+        legal.extend(choices.iter().map(|t| destroy_name(Some((t.player_offset, t.card)))));
+        pending.insert(*id, (DecisionKind::Destroy, legal));
+        destroyers.insert(*id, (*actor, obs.num_players()));
+        continue;
+      },
+      HistoryReqEvent::TombReq { id, .. } => {
+        let legal = vec!["tomb.no".to_string(), "tomb.yes".to_string()];
+        pending.insert(*id, (DecisionKind::Tomb, legal));
+        continue;
+      },
+      HistoryReqEvent::OperReq { id, choices, .. } => {
+        let legal = choices.iter().map(|&oper| oper_name(oper)).collect();
+        pending.insert(*id, (DecisionKind::Oper, legal));
+        continue;
+      },
+      HistoryReqEvent::ChooseRoleResp { choices, chosen, .. } => {
+        expected.push(Expected {
+          kind: DecisionKind::ChooseRole,
+          legal: role_names(*choices),
+          chosen: format!("role.{}", chosen.name()),
+        });
+        continue;
+      },
+      HistoryReqEvent::InitCardResp { req_id, chosen, .. }
+      | HistoryReqEvent::ChooseFrom2Resp { req_id, chosen, .. }
+      | HistoryReqEvent::ChooseFrom3Resp { req_id, chosen, .. } => (req_id, card_name(*chosen)),
+      HistoryReqEvent::KillResp { req_id, chosen, .. } | HistoryReqEvent::StealResp { req_id, chosen, .. } => {
+        (req_id, format!("role.{}", chosen.name()))
+      },
+      HistoryReqEvent::MagicResp { req_id, chosen, .. } => (req_id, magic_name(chosen)),
+      HistoryReqEvent::DestroyResp {
+        req_id,
+        chosen_index,
+        chosen_card,
+        ..
+      } => {
+        let (actor, num_players) = destroyers[req_id];
+        let target = chosen_index
+          .zip(*chosen_card)
+          .map(|(index, card)| (PlayerOffset::from_index(index, actor, num_players), card));
+        (req_id, destroy_name(target))
+      },
+      HistoryReqEvent::TombResp { req_id, chosen, .. } => {
+        (req_id, format!("tomb.{}", if *chosen { "yes" } else { "no" }))
+      },
+      HistoryReqEvent::OperResp { req_id, chosen, .. } => (req_id, oper_name(*chosen)),
+      _ => continue,
+    };
+    let (kind, legal) = pending.remove(req_id).unwrap();
+    expected.push(Expected { kind, legal, chosen });
+  }
+  expected
+}
+
+// 读出 NPY 文件的 header 和数据, 检查 magic 和 64 字节对齐
+fn read_npy(path: &Path) -> (String, Vec<u8>) {
+  let bytes = std::fs::read(path).unwrap();
+  assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00", "{}", path.display());
+  let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+  assert_eq!((10 + header_len) % 64, 0, "{}", path.display());
+  let header = String::from_utf8(bytes[10..10 + header_len].to_vec()).unwrap();
+  assert!(header.ends_with('\n'));
+  (header, bytes[10 + header_len..].to_vec())
+}
+
+#[tokio::test]
+async fn replayed_outcome_without_report_matches_the_game_result() {
+  let six = [
+    Card::酒馆,
+    Card::贸易站,
+    Card::庄园,
+    Card::神殿,
+    Card::教堂,
+    Card::瞭望台,
+  ];
+  let scenario = scenario(
+    vec![
+      seat(10, &[Card::市场], &[&six[..], &[Card::鬼城]].concat()),
+      seat(10, &[Card::市场], &[&six[..], &[Card::监狱]].concat()),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::刺客, Role::小偷, Role::军阀],
+    &[],
+  );
+  let played = play(
+    scenario,
+    vec![
+      Script::new().role(Role::建筑师).opers(&[Oper::Build(Card::市场)]),
+      Script::new().role(Role::国王).opers(&[Oper::Build(Card::市场)]),
+    ],
+  )
+  .await;
+
+  // 旧的记录没有 GameReport, 重放建筑后计分
+  let events = played
+    .events
+    .into_iter()
+    .filter(|event| !matches!(event, HistoryReqEvent::GameReport { .. }))
+    .collect::<Vec<_>>();
+  let (楚, 汉) = played.result;
+  assert_eq!(replay_outcome(&events), [楚 as f32, 汉 as f32]);
+}
+
+#[tokio::test]
+async fn feature_width_matches_the_schema_and_feature_names() {
+  for num_players in [4, 6] {
+    let (events, outcome) = random_game(num_players as u64, num_players).await;
+    let dir = out_dir(&format!("schema{}", num_players));
+    let mut writer = DatasetWriter::new(&dir, DatasetFormat::Csv, 1000).unwrap();
+    writer.write_game(0, &events, outcome).unwrap();
+    writer.finish().unwrap();
+
+    let schema: serde_json::Value =
+      serde_json::from_str(&std::fs::read_to_string(dir.join("schema.json")).unwrap()).unwrap();
+    let num_features = schema["num_features"].as_u64().unwrap() as usize;
+    assert_eq!(num_features, Obs::num_features());
+    assert_eq!(schema["features"].as_array().unwrap().len(), num_features);
+    assert_eq!(schema["num_actions"].as_u64().unwrap() as usize, NUM_ACTIONS);
+    assert_eq!(schema["actions"].as_array().unwrap().len(), NUM_ACTIONS);
+
+    // 人数不同特征也一样宽
+    for event in events.iter() {
+      if let HistoryReqEvent::OperReq { obs, .. } | HistoryReqEvent::ChooseRoleResp { obs, .. } = event {
+        assert_eq!(obs.feature_names().len(), num_features);
+        assert_eq!(obs.features().len(), num_features);
+      }
+    }
+    let samples = extract_samples(0, &events, outcome);
+    assert!(!samples.is_empty());
+    assert!(samples.iter().all(|sample| sample.features.len() == num_features));
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+}
+
+#[tokio::test]
+async fn every_decision_kind_masks_exactly_its_legal_actions() {
+  let names = action_names();
+  let mut seen = Vec::<DecisionKind>::new();
+  for seed in 0..40u64 {
+    let num_players = if seed.is_multiple_of(2) { 4 } else { 6 };
+    let (events, outcome) = random_game(seed, num_players).await;
+    let samples = extract_samples(seed as usize, &events, outcome);
+    let expected = expected_decisions(&events);
+    assert_eq!(samples.len(), expected.len(), "seed {}", seed);
+
+    for (sample, expected) in samples.iter().zip(expected) {
+      assert_eq!(sample.kind, expected.kind, "seed {}", seed);
+      assert_eq!(sample.mask.len(), NUM_ACTIONS);
+      assert!(sample.mask[sample.action], "seed {}: {:?}", seed, sample.kind);
+      assert_eq!(
+        names[sample.action], expected.chosen,
+        "seed {}: {:?}",
+        seed, sample.kind
+      );
+      let mut masked = (0..NUM_ACTIONS)
+        .filter(|&action| sample.mask[action])
+        .map(|action| names[action].clone())
+        .collect::<Vec<_>>();
+      let mut legal = expected.legal;
+      masked.sort();
+      legal.sort();
+      legal.dedup();
+      assert_eq!(masked, legal, "seed {}: {:?}", seed, sample.kind);
+      if !seen.contains(&sample.kind) {
+        seen.push(sample.kind);
+      }
+    }
+    if seen.len() == DecisionKind::all().len() {
+      return;
+    }
+  }
+  panic!("decision kinds never seen: {:?}", seen);
+}
+
+#[tokio::test]
+async fn csv_shards_split_at_the_shard_size() {
+  let (events, outcome) = random_game(1, 4).await;
+  let num_samples = extract_samples(0, &events, outcome).len() * 2;
+  let shard_size = num_samples / 3 + 1;
+  let dir = out_dir("csv");
+  let mut writer = DatasetWriter::new(&dir, DatasetFormat::Csv, shard_size).unwrap();
+  writer.write_game(0, &events, outcome).unwrap();
+  writer.write_game(1, &events, outcome).unwrap();
+  assert_eq!(writer.finish().unwrap(), (num_samples, 3));
+
+  let columns = 5 + Obs::num_features() + NUM_ACTIONS;
+  let mut rows = 0;
+  for shard in 0..3 {
+    let text = std::fs::read_to_string(dir.join(format!("shard-{:05}.csv", shard))).unwrap();
+    let lines = text.lines().collect::<Vec<_>>();
+    assert!(lines[0].starts_with("game,seat,kind,action,outcome,f0,"));
+    assert!(lines.iter().all(|line| line.split(',').count() == columns));
+    // 只有最后一个分片可以不满
+    let expected = if shard < 2 {
+      shard_size
+    } else {
+      num_samples - 2 * shard_size
+    };
+    assert_eq!(lines.len() - 1, expected, "shard {}", shard);
+    rows += lines.len() - 1;
+  }
+  assert_eq!(rows, num_samples);
+  assert!(!dir.join("shard-00003.csv").exists());
+  std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn npy_shards_have_valid_headers_and_shapes() {
+  let (events, outcome) = random_game(2, 4).await;
+  let samples = extract_samples(0, &events, outcome);
+  let num_samples = samples.len();
+  let shard_size = num_samples / 2 + 1;
+  let dir = out_dir("npy");
+  let mut writer = DatasetWriter::new(&dir, DatasetFormat::Npy, shard_size).unwrap();
+  writer.write_game(0, &events, outcome).unwrap();
+  assert_eq!(writer.finish().unwrap(), (num_samples, 2));
+
+  let num_features = Obs::num_features();
+  let mut actions = Vec::new();
+  for (shard, n) in [(0, shard_size), (1, num_samples - shard_size)] {
+    let path = |field: &str| dir.join(format!("shard-{:05}.{}.npy", shard, field));
+    for (field, descr, shape, width) in [
+      ("game", "<i8", format!("({},)", n), 8),
+      ("seat", "<i8", format!("({},)", n), 8),
+      ("kind", "<i8", format!("({},)", n), 8),
+      (
+        "features",
+        "<f4",
+        format!("({}, {})", n, num_features),
+        4 * num_features,
+      ),
+      ("mask", "|u1", format!("({}, {})", n, NUM_ACTIONS), NUM_ACTIONS),
+      ("action", "<i8", format!("({},)", n), 8),
+      ("outcome", "<f4", format!("({},)", n), 4),
+    ] {
+      let (header, data) = read_npy(&path(field));
+      assert!(header.contains(&format!("'descr': '{}'", descr)), "{}", header);
+      assert!(header.contains("'fortran_order': False"), "{}", header);
+      assert!(header.contains(&format!("'shape': {}", shape)), "{}", header);
+      assert_eq!(data.len(), n * width, "shard {} {}", shard, field);
+    }
+    let (_, data) = read_npy(&path("action"));
+    actions.extend(
+      data
+        .chunks(8)
+        .map(|bytes| i64::from_le_bytes(bytes.try_into().unwrap()) as usize),
+    );
+  }
+  assert_eq!(actions, samples.iter().map(|sample| sample.action).collect::<Vec<_>>());
+  assert!(!dir.join("shard-00002.action.npy").exists());
+  std::fs::remove_dir_all(&dir).unwrap();
+}
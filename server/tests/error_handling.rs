@@ -0,0 +1,167 @@
+// 出错处理: agent 出错时换成 fallback, 严格模式和历史记录关闭时以 GameError 结束
+
+use server::domain::{Card, Oper, PlayerIndex, Role, RoleSet};
+use server::fa_agents::GuardedFAAgent;
+use server::{AbstractFAAgent, GameError, HistoryReqEvent};
+
+mod common;
+
+use common::*;
+
+#[tokio::test]
+async fn agent_with_illegal_decision_is_replaced_by_fallback_for_the_rest_of_the_game() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  // 0 号没有龙门, 剧本里的建造不合法
+  let scripts = vec![Script::new().role(Role::国王).opers(&[Oper::Build(Card::龙门)])];
+  let (history, receiver) = history();
+  let mut game = scripted_game(&scenario, scripts, history);
+  game.run().await.unwrap();
+  let events = drain(receiver);
+
+  let replaced = events
+    .iter()
+    .filter_map(|event| match event {
+      HistoryReqEvent::AgentReplaced { actor, reason, .. } => Some((actor.value(), reason.clone())),
+      _ => None,
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(replaced.len(), 1);
+  assert_eq!(replaced[0].0, 0);
+  assert!(replaced[0].1.contains("not in"), "{}", replaced[0].1);
+  // 换人发生在 0 号的第一次操作, 紧接着的回复来自 fallback
+  let position = events
+    .iter()
+    .position(|event| matches!(event, HistoryReqEvent::AgentReplaced { .. }))
+    .unwrap();
+  assert!(matches!(
+    &events[position + 1],
+    HistoryReqEvent::OperResp { chosen, .. } if *chosen != Oper::Build(Card::龙门)
+  ));
+}
+
+#[tokio::test]
+async fn fallback_plays_for_楚_seats_too() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[Card::酒馆]),
+      seat(5, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  // 1 号是楚, 剧本里拆的建筑不存在, 换成 fallback 后由它来拆
+  let scripts = vec![Script::new(), Script::new().role(Role::军阀).destroy(0, Card::龙门)];
+  let (history, receiver) = history();
+  let mut game = scripted_game(&scenario, scripts, history);
+  game.run().await.unwrap();
+  let events = drain(receiver);
+
+  let replaced = events
+    .iter()
+    .filter_map(|event| match event {
+      HistoryReqEvent::AgentReplaced { actor, .. } => Some(actor.value()),
+      _ => None,
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(replaced, vec![1]);
+}
+
+#[tokio::test]
+async fn failing_fallback_returns_agent_error() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  let played = play(scenario, Vec::new()).await;
+  let obs = played
+    .events
+    .iter()
+    .find_map(|event| match event {
+      HistoryReqEvent::ChooseRoleReq { obs, .. } => Some(obs.clone()),
+      _ => None,
+    })
+    .unwrap();
+
+  // 原来的 agent 和 fallback 都选了没有提供的角色
+  let scripted = || -> Box<dyn AbstractFAAgent> {
+    Box::new(ScriptedAgent {
+      seat: PlayerIndex::from_usize(0),
+      script: Script::new().role(Role::国王),
+    })
+  };
+  let mut agent = GuardedFAAgent::new(PlayerIndex::from_usize(0), scripted(), Some(scripted()));
+  match agent.choose_role(&obs, RoleSet::empty() | Role::刺客).await {
+    Err(GameError::Agent { actor, reason }) => {
+      assert_eq!(actor, 0);
+      assert!(reason.starts_with("fallback"), "{}", reason);
+    },
+    other => panic!("expected agent error, got {:?}", other),
+  }
+  assert_eq!(
+    agent
+      .take_failure()
+      .as_deref()
+      .map(|reason| reason.contains("not offered")),
+    Some(true)
+  );
+}
+
+#[tokio::test]
+async fn strict_game_ends_with_agent_error_instead_of_replacing() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  let scripts = vec![
+    Script::new(),
+    Script::new().role(Role::国王).opers(&[Oper::Build(Card::龙门)]),
+  ];
+  let (history, _receiver) = history();
+  let mut game = scripted_game(&scenario, scripts, history);
+  game.enable_strict_agents();
+  match game.run().await {
+    Err(GameError::Agent { actor, .. }) => assert_eq!(actor, 1),
+    other => panic!("expected agent error, got {:?}", other.map(|outcome| outcome.result)),
+  }
+}
+
+#[tokio::test]
+async fn closed_history_receiver_ends_game_with_transport_error() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  let (history, receiver) = history();
+  drop(receiver);
+  let mut game = scripted_game(&scenario, Vec::new(), history);
+  assert!(matches!(game.run().await, Err(GameError::Transport(_))));
+}
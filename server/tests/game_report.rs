@@ -0,0 +1,142 @@
+// 赛后报告: 每个玩家的得分明细和统计, 写入历史
+
+use server::domain::{Camp, Card, Oper, PlayerIndex, Role};
+use server::{HistoryReqEvent, HistoryViewEvent, HistoryViewer, project_history};
+
+mod common;
+
+use common::*;
+
+#[tokio::test]
+async fn game_report_breaks_down_score_and_tracks_gold_cards_and_attacks() {
+  let scenario = scenario(
+    vec![
+      seat(0, &[], &[]),
+      seat(3, &[], &[]),
+      seat(3, &[Card::酒馆], &[]),
+      seat(0, &[], &[Card::庄园, Card::龙门]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  let played = play(
+    scenario,
+    vec![
+      Script::new()
+        .role(Role::小偷)
+        .steal(Role::商人)
+        .opers(&[Oper::Card2Choose1]),
+      Script::new().role(Role::商人).opers(&[Oper::Gold(2)]),
+      Script::new()
+        .role(Role::军阀)
+        .destroy(3, Card::庄园)
+        .opers(&[Oper::Gold(2), Oper::Build(Card::酒馆)]),
+      Script::new().role(Role::国王).opers(&[Oper::Gold(3)]),
+    ],
+  )
+  .await;
+  let report = played.game.report().unwrap();
+  assert_eq!(report.rounds, 1);
+
+  let thief = &report.players[0];
+  assert_eq!(thief.stats.gold_income.stolen, 3);
+  assert_eq!(thief.stats.cards_drawn, 1);
+  assert_eq!(thief.final_gold, 3);
+  let merchant = &report.players[1];
+  assert_eq!(merchant.stats.times_robbed, 1);
+  assert_eq!(merchant.stats.gold_income.initial, 3);
+  assert_eq!(merchant.stats.gold_income.merchant, 1);
+  assert_eq!(merchant.stats.gold_income.resources, 2);
+  let warlord = &report.players[2];
+  assert_eq!(warlord.stats.buildings_destroyed, 1);
+  assert_eq!(warlord.stats.cards_built, 1);
+  assert_eq!(warlord.score.total, 1);
+  let king = &report.players[3];
+  assert_eq!(king.stats.gold_income.resources, 2);
+  assert_eq!(king.stats.gold_income.rent, 1);
+  assert_eq!(king.stats.buildings_lost, 1);
+  assert_eq!(king.buildings, vec![Card::龙门]);
+  // 龙门费用 6, 另加 2 分
+  assert_eq!((king.score.base, king.score.bonus, king.score.total), (6, 2, 8));
+  for (player, role) in report
+    .players
+    .iter()
+    .zip([Role::小偷, Role::商人, Role::军阀, Role::国王])
+  {
+    assert_eq!(player.stats.roles.len(), 1);
+    assert_eq!(player.stats.roles[0].role, role);
+  }
+
+  let teams = report
+    .teams
+    .iter()
+    .map(|t| (t.camp, t.score, t.result))
+    .collect::<Vec<_>>();
+  assert_eq!(teams, vec![(Camp::楚, 8, 1.0), (Camp::汉, 1, 0.0)]);
+  assert_eq!(played.result, (1.0, 0.0));
+  assert_eq!(
+    played.game.scores(),
+    report.players.iter().map(|p| p.score.total).collect::<Vec<_>>()
+  );
+
+  // 报告写入历史, 紧接在 FinishGame 之前, 座位视角从自己开始排列
+  let recorded = match &played.events[played.events.len() - 2] {
+    HistoryReqEvent::GameReport { report, .. } => report,
+    event => panic!("expected GameReport, got {:?}", event),
+  };
+  assert_eq!(recorded, report);
+  let view = project_history(&played.events, HistoryViewer::Seat(PlayerIndex::from_usize(2)));
+  let HistoryViewEvent::GameReport { report: seen, .. } = &view[view.len() - 2] else {
+    panic!("expected GameReport");
+  };
+  assert_eq!(seen.players[0], report.players[2]);
+  assert_eq!(seen.players[1], report.players[3]);
+}
+
+#[tokio::test]
+async fn game_report_counts_all_colors_eight_buildings_and_killed_turns() {
+  let seven = [
+    Card::酒馆,
+    Card::贸易站,
+    Card::庄园,
+    Card::神殿,
+    Card::教堂,
+    Card::瞭望台,
+    Card::监狱,
+  ];
+  let scenario = scenario(
+    vec![
+      seat(10, &[Card::龙门], &seven),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::主教, Role::商人, Role::建筑师],
+    &[],
+  );
+  let played = play(
+    scenario,
+    vec![
+      Script::new().role(Role::国王).opers(&[Oper::Build(Card::龙门)]),
+      Script::new().role(Role::刺客).kill(Role::军阀),
+      Script::new().role(Role::军阀),
+      Script::new().role(Role::小偷).steal(Role::魔术师),
+    ],
+  )
+  .await;
+  let report = played.game.report().unwrap();
+
+  let builder = &report.players[0].score;
+  assert_eq!(
+    (
+      builder.all_colors,
+      builder.eight_buildings,
+      builder.first_eight_buildings,
+      builder.bonus
+    ),
+    (3, 2, 2, 2)
+  );
+  assert_eq!(builder.total, played.player(0).score());
+  assert_eq!(report.players[2].stats.times_killed, 1);
+  assert_eq!(report.players[2].stats.times_robbed, 0);
+}
@@ -0,0 +1,40 @@
+// 强化学习环境
+
+use server::GymEnv;
+
+fn lineup(names: &[&str]) -> Vec<String> {
+  names.iter().map(|name| name.to_string()).collect()
+}
+
+// 学习者每次都取第一个合法动作, 返回整局每一步的特征和最终奖励
+async fn play_first_legal(seed: u64, lineup: &[String]) -> (Vec<Vec<f32>>, f32) {
+  let mut env = GymEnv::new();
+  let mut step = env.reset(seed, lineup).await.unwrap();
+  let mut features = Vec::new();
+  while !step.done {
+    features.push(step.features.clone().unwrap());
+    let action = step.mask.iter().position(|&legal| legal).unwrap();
+    step = env.step(action).await.unwrap();
+  }
+  (features, step.reward)
+}
+
+#[tokio::test]
+async fn same_seed_replays_the_same_game_including_opponents() {
+  let lineup = lineup(&["learner", "random", "v2", "random"]);
+  let first = play_first_legal(7, &lineup).await;
+  let second = play_first_legal(7, &lineup).await;
+  assert!(!first.0.is_empty());
+  assert_eq!(first, second);
+}
+
+#[tokio::test]
+async fn v2_on_a_楚_seat_is_rejected_before_the_game_starts() {
+  let mut env = GymEnv::new();
+  let error = env
+    .reset(0, &lineup(&["learner", "v2", "random", "random"]))
+    .await
+    .err()
+    .unwrap();
+  assert!(error.to_string().contains("v2 only supports 汉 seats"), "{}", error);
+}
@@ -0,0 +1,31 @@
+// 启发式 agent 的参数
+
+use server::HeuristicWeights;
+
+#[test]
+fn weights_round_trip_through_the_tuning_vector() {
+  let mut weights = HeuristicWeights::default();
+  weights.role_position_preference[0][3] = 1.5;
+  weights.role_position_preference[5][7] = -2.0;
+  let v = weights.to_vec();
+  assert_eq!(v.len(), HeuristicWeights::DIM);
+  assert_eq!(HeuristicWeights::from_vec(&v).to_vec(), v);
+}
+
+#[test]
+fn weights_file_without_position_preference_still_loads() {
+  let path = std::env::temp_dir().join(format!("heuristic_weights_{}.toml", std::process::id()));
+  let weights = HeuristicWeights::default();
+  let text = toml::to_string(&weights).unwrap();
+  // 去掉 role_position_preference, 相当于加这一项之前保存的文件
+  let old = text
+    .lines()
+    .filter(|line| !line.starts_with("role_position_preference"))
+    .collect::<Vec<_>>()
+    .join("\n");
+  assert_ne!(old, text);
+  std::fs::write(&path, old).unwrap();
+  let loaded = HeuristicWeights::load(path.to_str().unwrap()).unwrap();
+  std::fs::remove_file(&path).unwrap();
+  assert_eq!(loaded.to_vec(), weights.to_vec());
+}
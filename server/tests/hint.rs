@@ -0,0 +1,185 @@
+// 提示服务: 检查转发来的决策, 按模拟出的胜率给候选排序, 到了时间预算就停
+
+use server::domain::{AgentReqEvent, Card, Oper, Role, RoleSet};
+use server::{HintConfig, HintService, HistoryReqEvent, ObsMessage};
+use strum::IntoEnumIterator;
+
+mod common;
+
+use common::*;
+
+fn service(budget_ms: u64, max_rollouts: usize) -> HintService {
+  HintService::new(&HintConfig {
+    agent: "heuristic".to_string(),
+    role_book: None,
+    budget_ms,
+    max_rollouts,
+  })
+  .unwrap()
+}
+
+// 4 人局 0 号拿着皇冠第一个选角色, 主教和建筑师公开弃掉
+async fn events() -> Vec<HistoryReqEvent> {
+  let seats = (0..4).map(|_| seat(2, &[], &[])).collect();
+  let scenario = scenario(seats, &[Role::主教, Role::建筑师, Role::魔术师], &[]);
+  play(scenario, vec![Script::new().role(Role::国王).opers(&[Oper::Gold(2)])])
+    .await
+    .events
+}
+
+fn choose_role(events: &[HistoryReqEvent]) -> AgentReqEvent {
+  events
+    .iter()
+    .find_map(|event| match event {
+      HistoryReqEvent::ChooseRoleReq { id, obs, choices, .. } => Some(AgentReqEvent::ChooseRole {
+        id: *id,
+        obs: ObsMessage::Full(obs.clone()),
+        roles: *choices,
+      }),
+      _ => None,
+    })
+    .unwrap()
+}
+
+fn choose_oper(events: &[HistoryReqEvent]) -> AgentReqEvent {
+  events
+    .iter()
+    .find_map(|event| match event {
+      HistoryReqEvent::OperReq { id, obs, choices, .. } => Some(AgentReqEvent::ChooseOper {
+        id: *id,
+        obs: ObsMessage::Full(obs.clone()),
+        choices: choices.clone(),
+      }),
+      _ => None,
+    })
+    .unwrap()
+}
+
+fn full_obs(decision: &AgentReqEvent) -> ObsMessage {
+  ObsMessage::Full(decision.obs().and_then(ObsMessage::full).unwrap().clone())
+}
+
+fn role_hand(decision: &AgentReqEvent) -> Vec<Card> {
+  decision.obs().and_then(ObsMessage::full).unwrap().hero_cards().to_vec()
+}
+
+#[tokio::test]
+async fn forged_decisions_and_non_decisions_are_rejected() {
+  let events = events().await;
+  let service = service(1000, 4);
+  let role = choose_role(&events);
+  let obs = full_obs(&role);
+
+  let rejected = |decision: AgentReqEvent, expected: &'static str| {
+    let service = &service;
+    async move {
+      let error = service.hint(&decision).await.err().unwrap();
+      assert!(error.to_string().contains(expected), "{}", error);
+    }
+  };
+  rejected(AgentReqEvent::WaitForReady { id: 0 }, "is not a decision").await;
+  // 公开弃掉的主教不能再选
+  rejected(
+    AgentReqEvent::ChooseRole {
+      id: 0,
+      obs: obs.clone(),
+      roles: RoleSet::from_pair(Role::主教, Role::国王),
+    },
+    "are not available",
+  )
+  .await;
+  rejected(
+    AgentReqEvent::ChooseRole {
+      id: 0,
+      obs: obs.clone(),
+      roles: RoleSet::empty(),
+    },
+    "are not available",
+  )
+  .await;
+  rejected(
+    AgentReqEvent::ChooseKillTarget {
+      id: 0,
+      obs: obs.clone(),
+      choices: RoleSet::from_pair(Role::刺客, Role::国王),
+    },
+    "are not legal",
+  )
+  .await;
+  rejected(
+    AgentReqEvent::ChooseStealTarget {
+      id: 0,
+      obs: obs.clone(),
+      choices: RoleSet::from_pair(Role::小偷, Role::国王),
+    },
+    "are not legal",
+  )
+  .await;
+  // 手里没有的牌不能建
+  let hand = role_hand(&role);
+  let missing = Card::iter().find(|card| !hand.contains(card)).unwrap();
+  rejected(
+    AgentReqEvent::ChooseOper {
+      id: 0,
+      obs,
+      choices: vec![Oper::Gold(2), Oper::Build(missing)],
+    },
+    "needs a card that is not in hand",
+  )
+  .await;
+
+  // 真实的决策可以给出提示
+  service.hint(&choose_oper(&events)).await.unwrap();
+}
+
+#[tokio::test]
+async fn suggestions_are_ranked_by_rollout_win_rate() {
+  let events = events().await;
+  let decision = choose_role(&events);
+  let HistoryReqEvent::ChooseRoleReq { choices, .. } = events
+    .iter()
+    .find(|event| matches!(event, HistoryReqEvent::ChooseRoleReq { .. }))
+    .unwrap()
+  else {
+    unreachable!()
+  };
+  let num_roles = choices.len();
+
+  // 预算足够时每个候选轮流模拟, 正好用完 max_rollouts
+  let hint = service(600_000, 3 * num_roles).hint(&decision).await.unwrap();
+  assert_eq!(hint.suggestions.len(), num_roles);
+  assert_eq!(hint.rollouts, 3 * num_roles);
+  for suggestion in &hint.suggestions {
+    assert_eq!(suggestion.rollouts, 3, "{}", suggestion.name);
+    let win_rate = suggestion.win_rate.unwrap();
+    assert!((0.0..=1.0).contains(&win_rate), "{}: {}", suggestion.name, win_rate);
+  }
+  // 启发式 agent 是确定性的, 只有一个选择
+  assert_eq!(hint.suggestions.iter().filter(|s| s.agent_choice).count(), 1);
+  // 胜率从高到低; 相同时 agent 的选择在前, 再按动作编号
+  for pair in hint.suggestions.windows(2) {
+    let (a, b) = (&pair[0], &pair[1]);
+    assert!(a.win_rate >= b.win_rate, "{} before {}", a.name, b.name);
+    if a.win_rate == b.win_rate && a.agent_choice == b.agent_choice {
+      assert!(a.action < b.action, "{} before {}", a.name, b.name);
+    }
+  }
+}
+
+#[tokio::test]
+async fn hints_stop_at_the_time_budget() {
+  let events = events().await;
+  let decision = choose_role(&events);
+
+  // 预算为 0 时不模拟, 候选仍然全部按动作编号列出
+  let hint = service(0, 1000).hint(&decision).await.unwrap();
+  assert_eq!(hint.rollouts, 0);
+  assert!(!hint.suggestions.is_empty());
+  assert!(hint.suggestions.iter().all(|s| s.win_rate.is_none() && s.rollouts == 0));
+  assert!(hint.suggestions.windows(2).all(|pair| pair[0].action < pair[1].action));
+
+  // 模拟很多局要的时间远超预算, 到时间就返回已有的结果
+  let hint = service(200, 1_000_000).hint(&decision).await.unwrap();
+  assert!(hint.rollouts < 1_000_000);
+  assert!(hint.elapsed_ms < 5_000, "{}ms", hint.elapsed_ms);
+}
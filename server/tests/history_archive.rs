@@ -0,0 +1,76 @@
+// 历史记录二进制归档的读写
+
+use server::domain::PlayerIndex;
+use server::{HistoryArchiveReader, HistoryArchiveWriter, HistoryReqEvent};
+
+fn events() -> Vec<HistoryReqEvent> {
+  let mut events = vec![HistoryReqEvent::StartGame {
+    id: 0,
+    init_crown: PlayerIndex::from_usize(1),
+  }];
+  for i in 0..6 {
+    events.push(HistoryReqEvent::InitGold {
+      id: i + 1,
+      actor: PlayerIndex::from_usize(i as usize),
+      gold: 2,
+    });
+  }
+  events.push(HistoryReqEvent::FinishGame { id: 7 });
+  events
+}
+
+fn archive(events: &[HistoryReqEvent]) -> Vec<u8> {
+  let mut writer = HistoryArchiveWriter::with_level(Vec::new(), 1).unwrap();
+  for event in events {
+    writer.write_event(event).unwrap();
+  }
+  writer.finish().unwrap()
+}
+
+fn read_all(bytes: &[u8]) -> anyhow::Result<Vec<HistoryReqEvent>> {
+  let mut reader = HistoryArchiveReader::new(bytes)?;
+  let mut events = Vec::new();
+  while let Some(event) = reader.read_event()? {
+    events.push(event);
+  }
+  Ok(events)
+}
+
+fn to_json(events: &[HistoryReqEvent]) -> Vec<String> {
+  events
+    .iter()
+    .map(|event| serde_json::to_string(event).unwrap())
+    .collect()
+}
+
+#[test]
+fn archive_round_trips_events() {
+  let events = events();
+  let read = read_all(&archive(&events)).unwrap();
+  assert_eq!(to_json(&read), to_json(&events));
+}
+
+#[test]
+fn truncated_archive_is_an_error_not_a_shorter_history() {
+  let bytes = archive(&events());
+  // 5 字节的文件头之后至少还有一个字节的 zstd 流
+  for len in 6..bytes.len() {
+    assert!(
+      read_all(&bytes[..len]).is_err(),
+      "truncated to {} of {} bytes",
+      len,
+      bytes.len()
+    );
+  }
+}
+
+#[test]
+fn corrupt_frame_length_is_an_error_before_allocating() {
+  // 文件头照抄, zstd 流里是一个接近 4 GB 的长度前缀, 后面没有帧
+  let header = &archive(&[])[..5];
+  let mut stream = u32::MAX.to_le_bytes().to_vec();
+  stream.extend_from_slice(&[0; 16]);
+  let bytes = [header, &zstd::encode_all(stream.as_slice(), 1).unwrap()].concat();
+  let error = read_all(&bytes).err().unwrap();
+  assert!(error.to_string().contains("exceeds"), "{}", error);
+}
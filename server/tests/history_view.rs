@@ -0,0 +1,125 @@
+// 按座位, 阵营和观众视角投影的历史记录
+
+use server::domain::{Camp, Card, MagicianSkill, PlayerIndex, PlayerOffset, Role};
+use server::{HistoryProjector, HistoryReqEvent, HistoryViewEvent, HistoryViewer, infer_camps, project_history};
+
+mod common;
+
+use common::*;
+
+#[tokio::test]
+async fn projected_history_rebases_offsets_and_hides_private_decisions() {
+  let scenario = scenario(
+    vec![
+      seat(0, &[Card::酒馆], &[]),
+      seat(0, &[], &[]),
+      seat(3, &[Card::市场, Card::庄园], &[]),
+      seat(0, &[], &[Card::庄园]),
+    ],
+    &[Role::刺客, Role::小偷, Role::主教],
+    &[],
+  );
+  let played = play(
+    scenario,
+    vec![
+      Script::new()
+        .role(Role::魔术师)
+        .magic(MagicianSkill::Swap(PlayerOffset::from_usize(2))),
+      Script::new(),
+      Script::new().role(Role::军阀).destroy(3, Card::庄园),
+    ],
+  )
+  .await;
+
+  // 0 号和 2 号换牌, 2 号拆 3 号的庄园. 各视角下的 offset 都以观察者为 0
+  for (viewer, swap, destroy, sees_swap, sees_destroy) in [
+    (HistoryViewer::Seat(PlayerIndex::from_usize(1)), 1, 2, false, false),
+    (HistoryViewer::Seat(PlayerIndex::from_usize(2)), 0, 1, false, true),
+    (HistoryViewer::Team(Camp::汉), 2, 3, true, true),
+    (HistoryViewer::Spectator, 2, 3, false, false),
+  ] {
+    let view = project_history(&played.events, viewer);
+    let (magic_obs, chosen) = view
+      .iter()
+      .find_map(|event| match event {
+        HistoryViewEvent::MagicReq { obs, .. } => Some(obs.is_some()),
+        _ => None,
+      })
+      .zip(view.iter().find_map(|event| match event {
+        HistoryViewEvent::MagicResp { chosen, .. } => chosen.clone(),
+        _ => None,
+      }))
+      .unwrap();
+    assert_eq!(magic_obs, sees_swap, "{:?}", viewer);
+    assert!(
+      matches!(chosen, MagicianSkill::Swap(offset) if offset.value() == swap),
+      "{:?}: {:?}",
+      viewer,
+      chosen
+    );
+
+    let (destroy_obs, choices) = view
+      .iter()
+      .find_map(|event| match event {
+        HistoryViewEvent::DestroyReq { obs, choices, .. } => Some((obs.is_some(), choices)),
+        _ => None,
+      })
+      .unwrap();
+    assert_eq!(destroy_obs, sees_destroy, "{:?}", viewer);
+    assert!(
+      choices
+        .iter()
+        .any(|t| t.player_offset.value() == destroy && t.card == Card::庄园),
+      "{:?}: {:?}",
+      viewer,
+      choices
+    );
+    let chosen = view
+      .iter()
+      .find_map(|event| match event {
+        HistoryViewEvent::DestroyResp { chosen_offset, .. } => *chosen_offset,
+        _ => None,
+      })
+      .unwrap();
+    assert_eq!(chosen.value(), destroy, "{:?}", viewer);
+  }
+
+  // 去掉 LoadScenario 后从 obs 推断阵营
+  let events = played
+    .events
+    .into_iter()
+    .filter(|event| !matches!(event, HistoryReqEvent::LoadScenario { .. }))
+    .collect::<Vec<_>>();
+  assert_eq!(infer_camps(&events), vec![Camp::汉, Camp::楚, Camp::汉, Camp::楚]);
+}
+
+#[tokio::test]
+async fn team_view_without_camps_hides_every_decision() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  let played = play(scenario, Vec::new()).await;
+
+  let mut projector = HistoryProjector::new(HistoryViewer::Team(Camp::汉), Vec::new());
+  let view = played
+    .events
+    .iter()
+    .filter_map(|event| projector.project(event))
+    .collect::<Vec<_>>();
+  assert!(
+    view
+      .iter()
+      .any(|event| matches!(event, HistoryViewEvent::ChooseRoleReq { .. }))
+  );
+  assert!(view.iter().all(|event| match event {
+    HistoryViewEvent::ChooseRoleReq { obs, choices, .. } => obs.is_none() && choices.is_none(),
+    _ => true,
+  }));
+}
@@ -0,0 +1,182 @@
+// 引擎不变量检查: 随机对局中每次状态变化后和每次提问前都不能违反不变量, 发出的 obs 也不能泄露隐藏信息.
+// 见 src/bin/fuzz.rs.
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use server::domain::Camp;
+use server::{
+  AbstractFAAgent, AbstractFYIAgent, Game, History, NoopFYIAgent, Obs, ObsEncoder, ObsMessage, ObsMirror, Player,
+  PlayerIndexedVec, RandomFAAgent, Scenario,
+};
+use tokio::sync::mpsc;
+
+fn history() -> History {
+  let (history_req_bcast_sender, mut history_req_bcast_receiver) = mpsc::channel::<String>(1024);
+  let (_, history_resp_receiver) = mpsc::channel::<String>(1024);
+  tokio::spawn(async move { while history_req_bcast_receiver.recv().await.is_some() {} });
+  History::new(history_req_bcast_sender, history_resp_receiver)
+}
+
+fn agents(seed: u64, num_players: usize) -> PlayerIndexedVec<Box<dyn AbstractFAAgent>> {
+  let mut agents = PlayerIndexedVec::<Box<dyn AbstractFAAgent>>::new();
+  for i in 0..num_players {
+    agents.push(Box::new(RandomFAAgent::with_seed(seed * 10 + i as u64)));
+  }
+  agents
+}
+
+fn fyi_agents(num_players: usize) -> PlayerIndexedVec<Box<dyn AbstractFYIAgent>> {
+  let mut fyi_agents = PlayerIndexedVec::<Box<dyn AbstractFYIAgent>>::new();
+  for _ in 0..num_players {
+    fyi_agents.push(Box::new(NoopFYIAgent::new()));
+  }
+  fyi_agents
+}
+
+async fn new_game(seed: u64, num_players: usize, history: History) -> Game {
+  let mut players = PlayerIndexedVec::<Player>::new();
+  for i in 0..num_players {
+    let camp = if i % 2 == 0 { Camp::汉 } else { Camp::楚 };
+    players.push(Player::new(uuid::Uuid::new_v4(), format!("player{}", i), camp));
+  }
+  let rng = StdRng::seed_from_u64(seed);
+  Game::new(
+    num_players,
+    players,
+    agents(seed, num_players),
+    fyi_agents(num_players),
+    rng,
+    history,
+  )
+  .await
+}
+
+#[tokio::test]
+async fn random_games_keep_invariants() {
+  for seed in 0..20u64 {
+    let num_players = if seed.is_multiple_of(2) { 4 } else { 6 };
+    let mut game = new_game(seed, num_players, history()).await;
+    game.enable_invariant_checks();
+    game.enable_leak_checks();
+    game.enable_strict_agents();
+    game.run().await.unwrap();
+  }
+}
+
+#[tokio::test]
+async fn obs_deltas_rebuild_every_obs_and_resync_after_a_gap() {
+  for seed in 0..4u64 {
+    let num_players = if seed.is_multiple_of(2) { 4 } else { 6 };
+    let (sender, mut receiver) = mpsc::channel::<String>(1 << 16);
+    let (_, resp_receiver) = mpsc::channel::<String>(1);
+    let mut game = new_game(seed, num_players, History::new(sender, resp_receiver)).await;
+    game.run().await.unwrap();
+
+    // 每个玩家在历史记录中看到的 obs
+    let mut observed = vec![Vec::<Obs>::new(); num_players];
+    while let Ok(text) = receiver.try_recv() {
+      let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+      let Some(fields) = value.as_object().and_then(|event| event.values().next()) else {
+        continue;
+      };
+      if let (Some(actor), Some(obs)) = (fields.get("actor").and_then(|actor| actor.as_u64()), fields.get("obs")) {
+        observed[actor as usize].push(serde_json::from_value(obs.clone()).unwrap());
+      }
+    }
+
+    for stream in observed {
+      assert!(stream.len() > 10, "seed {}: {} obs", seed, stream.len());
+      let lost = stream.len() / 2;
+      let mut encoder = ObsEncoder::new();
+      let mut mirror = ObsMirror::new();
+      let (mut full_bytes, mut sync_bytes, mut resynced) = (0, 0, false);
+      for (i, obs) in stream.iter().enumerate() {
+        let json = serde_json::to_string(&encoder.encode(obs)).unwrap();
+        full_bytes += serde_json::to_string(obs).unwrap().len();
+        sync_bytes += json.len();
+        if i == lost {
+          continue;
+        }
+        let message: ObsMessage = serde_json::from_str(&json).unwrap();
+        match mirror.receive(&message) {
+          Some(rebuilt) => assert_eq!(&rebuilt, obs, "seed {}: obs {}", seed, i),
+          None => {
+            // 丢了一条之后的第一条对不上序号, 重发完整的
+            assert_eq!(i, lost + 1, "seed {}", seed);
+            encoder.resync();
+            let message = encoder.encode(obs);
+            assert!(matches!(&message, ObsMessage::Sync(sync) if sync.full.is_some()));
+            assert_eq!(mirror.receive(&message).as_ref(), Some(obs));
+            resynced = true;
+          },
+        }
+      }
+      assert!(resynced);
+      assert!(
+        sync_bytes * 4 < full_bytes,
+        "seed {}: {} vs {}",
+        seed,
+        sync_bytes,
+        full_bytes
+      );
+    }
+  }
+}
+
+#[tokio::test]
+async fn round_snapshot_replays_as_scenario() {
+  let mut game = new_game(7, 4, history()).await;
+  game.enable_invariant_checks();
+  game.run().await.unwrap();
+
+  let mut scenario = game.round_snapshot().unwrap().clone();
+  scenario.validate().unwrap();
+  scenario.rounds = Some(1);
+  scenario.seed = Some(0);
+  let round = scenario.round;
+
+  let mut replay = Game::from_scenario(
+    &scenario,
+    agents(0, 4),
+    fyi_agents(4),
+    StdRng::seed_from_u64(0),
+    history(),
+  )
+  .unwrap();
+  replay.enable_invariant_checks();
+  replay.run().await.unwrap();
+  assert_eq!(replay.round_snapshot().unwrap().round, round);
+}
+
+#[test]
+fn gold_cannot_go_negative() {
+  let mut player = Player::new(uuid::Uuid::new_v4(), "player0".to_string(), Camp::汉);
+  player.set_gold(1);
+  let error = player.sub_gold(2).unwrap_err();
+  assert_eq!(error.kind(), "non-negative gold");
+  assert_eq!(player.gold(), 1);
+}
+
+#[tokio::test]
+async fn scenario_snapshot_matches_loaded_position() {
+  let scenario = Scenario::load(concat!(env!("CARGO_MANIFEST_DIR"), "/scenarios/example.toml")).unwrap();
+  let num_players = scenario.players.len();
+  let game = Game::from_scenario(
+    &scenario,
+    agents(0, num_players),
+    fyi_agents(num_players),
+    StdRng::seed_from_u64(0),
+    history(),
+  )
+  .unwrap();
+
+  let snapshot = game.snapshot(scenario.round);
+  snapshot.validate().unwrap();
+  assert_eq!(snapshot.crown, scenario.crown);
+  for (player, expected) in snapshot.players.iter().zip(scenario.players.iter()) {
+    assert_eq!(player.gold, expected.gold);
+    assert_eq!(player.hand, expected.hand);
+    assert_eq!(player.buildings, expected.buildings);
+  }
+  assert_eq!(snapshot.deck[..scenario.deck.len()], scenario.deck[..]);
+}
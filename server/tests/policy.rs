@@ -0,0 +1,118 @@
+// 从权重文件加载的策略 agent: 屏蔽非法动作, 温度为 0 时取 argmax
+
+use std::sync::Arc;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use server::{
+  AbstractFAAgent, AbstractFYIAgent, Game, NUM_ACTIONS, NoopFYIAgent, Obs, PlayerIndexedVec, PolicyFAAgent,
+  PolicyModel, Sample, extract_samples, replay_outcome,
+};
+
+mod common;
+
+use common::*;
+
+// 权重全为 0 的线性模型, logits 就是 bias, 与局面无关. 打乱的 bias 让很多非法动作的 logit 比合法的大
+fn bias() -> Vec<f32> {
+  (0..NUM_ACTIONS).map(|a| ((a * 7919) % 97) as f32).collect()
+}
+
+// 动作 a 那一行的权重都是 weight(a)
+fn model(weight: impl Fn(usize) -> f32, bias: Vec<f32>) -> Arc<PolicyModel> {
+  let weights = (0..NUM_ACTIONS)
+    .map(|a| vec![weight(a); Obs::num_features()])
+    .collect::<Vec<_>>();
+  let json = serde_json::json!({ "layers": [{ "weights": weights, "bias": bias }] });
+  let path = std::env::temp_dir().join(format!(
+    "policy_model_{}_{}.json",
+    std::process::id(),
+    rand::random::<u32>()
+  ));
+  std::fs::write(&path, json.to_string()).unwrap();
+  let model = PolicyModel::load(&path).unwrap();
+  std::fs::remove_file(&path).unwrap();
+  Arc::new(model)
+}
+
+// 所有座位都是策略 agent, 打三轮. 非法动作会让严格模式的对局出错
+async fn play_policy(num_players: usize, temperature: f32) -> Vec<Sample> {
+  play_model(model(|_| 0.0, bias()), num_players, temperature).await
+}
+
+async fn play_model(model: Arc<PolicyModel>, num_players: usize, temperature: f32) -> Vec<Sample> {
+  let mut scenario = scenario((0..num_players).map(|_| seat(2, &[], &[])).collect(), &[], &[]);
+  scenario.rounds = Some(3);
+  let mut agents = PlayerIndexedVec::<Box<dyn AbstractFAAgent>>::new();
+  let mut fyi_agents = PlayerIndexedVec::<Box<dyn AbstractFYIAgent>>::new();
+  for i in 0..num_players {
+    agents.push(Box::new(PolicyFAAgent::with_seed(model.clone(), temperature, i as u64)));
+    fyi_agents.push(Box::new(NoopFYIAgent::new()));
+  }
+  let (history, receiver) = history();
+  let mut game = Game::from_scenario(&scenario, agents, fyi_agents, StdRng::seed_from_u64(0), history).unwrap();
+  game.enable_strict_agents();
+  game.run().await.unwrap();
+  let events = drain(receiver);
+  extract_samples(0, &events, replay_outcome(&events))
+}
+
+#[tokio::test]
+async fn masking_never_picks_an_illegal_action() {
+  let bias = bias();
+  let top = (0..NUM_ACTIONS).max_by(|&a, &b| bias[a].total_cmp(&bias[b])).unwrap();
+  for (num_players, temperature) in [(4, 0.0), (4, 1.0), (6, 5.0)] {
+    let samples = play_policy(num_players, temperature).await;
+    assert!(!samples.is_empty());
+    for sample in &samples {
+      assert!(
+        sample.mask[sample.action],
+        "{:?} took illegal action {}",
+        sample.kind, sample.action
+      );
+    }
+    // 至少有一次最大的 logit 是非法动作, 否则屏蔽没有被检验到
+    assert!(samples.iter().any(|sample| !sample.mask[top]));
+  }
+}
+
+#[tokio::test]
+async fn zero_temperature_takes_the_legal_argmax() {
+  let bias = bias();
+  for sample in play_policy(4, 0.0).await {
+    let best = (0..NUM_ACTIONS)
+      .filter(|&a| sample.mask[a])
+      .max_by(|&a, &b| bias[a].total_cmp(&bias[b]))
+      .unwrap();
+    assert_eq!(sample.action, best, "{:?}", sample.kind);
+  }
+}
+
+// logits 溢出成 ±inf 或 NaN, 或者温度极端时没法按 softmax 采样, 退回 argmax 而不是 panic
+#[tokio::test]
+async fn extreme_logits_and_temperatures_fall_back_to_the_argmax() {
+  let overflow = || {
+    model(
+      |a| if a % 2 == 0 { f32::MAX } else { -f32::MAX },
+      vec![0.0; NUM_ACTIONS],
+    )
+  };
+  for temperature in [1.0, f32::MIN_POSITIVE, f32::MAX] {
+    for sample in play_model(overflow(), 4, temperature).await {
+      assert!(
+        sample.mask[sample.action],
+        "{:?} took illegal action {}",
+        sample.kind, sample.action
+      );
+    }
+  }
+  for temperature in [f32::MIN_POSITIVE, f32::INFINITY, f32::NAN] {
+    for sample in play_policy(4, temperature).await {
+      assert!(
+        sample.mask[sample.action],
+        "{:?} took illegal action {}",
+        sample.kind, sample.action
+      );
+    }
+  }
+}
@@ -0,0 +1,63 @@
+// 选角色开局库的保存和加载
+
+use server::domain::{Oper, Role};
+use server::{HistoryReqEvent, RoleBook};
+
+mod common;
+
+use common::*;
+
+fn stats(book: &RoleBook, events: &[HistoryReqEvent]) -> Vec<Vec<(Role, u64, f64)>> {
+  events
+    .iter()
+    .filter_map(|event| match event {
+      HistoryReqEvent::ChooseRoleResp { obs, choices, .. } => Some(
+        book
+          .lookup(obs, *choices)
+          .iter()
+          .map(|s| (s.role, s.picks, s.wins))
+          .collect(),
+      ),
+      _ => None,
+    })
+    .collect()
+}
+
+#[tokio::test]
+async fn role_book_round_trips_through_its_file_and_rejects_other_versions() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  let played = play(scenario, vec![Script::new().role(Role::国王).opers(&[Oper::Gold(2)])]).await;
+  let mut book = RoleBook::new();
+  book.add_game(&played.events, [played.result.0, played.result.1]);
+  book.add_game(&played.events, [played.result.0, played.result.1]);
+  assert!(book.num_situations() > 0);
+
+  let path = std::env::temp_dir().join(format!("role_book_{}.json", std::process::id()));
+  let path = path.to_str().unwrap();
+  book.save(path).unwrap();
+  let loaded = RoleBook::load(path).unwrap();
+  assert_eq!(loaded.num_games(), 2);
+  assert_eq!(loaded.num_situations(), book.num_situations());
+  assert_eq!(stats(&loaded, &played.events), stats(&book, &played.events));
+
+  // 版本号不同的文件不能加载
+  let mut raw: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+  raw["version"] = serde_json::json!(2);
+  std::fs::write(path, raw.to_string()).unwrap();
+  let error = RoleBook::load(path).err().unwrap();
+  std::fs::remove_file(path).unwrap();
+  assert!(
+    error.to_string().contains("unsupported role book version: 2"),
+    "{}",
+    error
+  );
+}
@@ -0,0 +1,1011 @@
+// 规则一致性测试: 每条规则 (doc/rule.md) 用一个或几个自定义局面, 由按剧本决策的 agent 来打, 然后检查结果.
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use server::domain::{Camp, Card, MagicianSkill, Oper, PlayerOffset, Role, RoleSet};
+use server::{
+  AbstractFAAgent, AbstractFYIAgent, Game, HistoryReqEvent, NoopFYIAgent, Player, PlayerIndexedVec, RandomFAAgent,
+};
+
+mod common;
+
+use common::*;
+
+fn has_build(choices: &[Oper]) -> bool {
+  choices.iter().any(|oper| matches!(oper, Oper::Build(_)))
+}
+
+// 1. 游戏流程
+
+#[tokio::test]
+async fn standard_game_deals_two_gold_and_one_card_then_runs_until_eight_buildings() {
+  for num_players in [4, 6] {
+    let (history, receiver) = history();
+    let mut players = PlayerIndexedVec::<Player>::new();
+    let mut agents = PlayerIndexedVec::<Box<dyn AbstractFAAgent>>::new();
+    let mut fyi_agents = PlayerIndexedVec::<Box<dyn AbstractFYIAgent>>::new();
+    for i in 0..num_players {
+      let camp = if i % 2 == 0 { Camp::汉 } else { Camp::楚 };
+      players.push(Player::new(uuid::Uuid::new_v4(), format!("p{}", i), camp));
+      agents.push(Box::new(RandomFAAgent::new()));
+      fyi_agents.push(Box::new(NoopFYIAgent::new()));
+    }
+    let mut game = Game::new(
+      num_players,
+      players,
+      agents,
+      fyi_agents,
+      StdRng::seed_from_u64(7),
+      history,
+    )
+    .await;
+    game.enable_leak_checks();
+    game.run().await.unwrap();
+    let events = drain(receiver);
+
+    let init_gold = events
+      .iter()
+      .filter_map(|event| match event {
+        HistoryReqEvent::InitGold { gold, .. } => Some(*gold),
+        _ => None,
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(init_gold, vec![2; num_players]);
+    let init_cards = events
+      .iter()
+      .filter(|event| matches!(event, HistoryReqEvent::InitCardReq { .. }))
+      .count();
+    assert_eq!(init_cards, num_players);
+    assert!(matches!(events.last(), Some(HistoryReqEvent::FinishGame { .. })));
+    assert!(game.players().iter().any(|player| player.buildings_len() == 8));
+  }
+}
+
+#[tokio::test]
+async fn game_ends_after_the_round_in_which_someone_builds_eight() {
+  let seven = [
+    Card::酒馆,
+    Card::贸易站,
+    Card::庄园,
+    Card::神殿,
+    Card::教堂,
+    Card::瞭望台,
+    Card::监狱,
+  ];
+  let mut scenario = scenario(
+    vec![
+      seat(10, &[Card::市场], &seven),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::刺客, Role::小偷, Role::军阀],
+    &[],
+  );
+  scenario.rounds = None;
+  let played = play(
+    scenario,
+    vec![Script::new().role(Role::国王).opers(&[Oper::Build(Card::市场)])],
+  )
+  .await;
+
+  let rounds = played
+    .events
+    .iter()
+    .filter(|event| matches!(event, HistoryReqEvent::StartRound { .. }))
+    .count();
+  assert_eq!(rounds, 1);
+  assert!(matches!(played.events.last(), Some(HistoryReqEvent::FinishGame { .. })));
+}
+
+// 2. 角色卡
+
+#[tokio::test]
+async fn assassin_cannot_target_self_or_public_drops_and_killed_player_skips_turn() {
+  let scenario = scenario(
+    vec![
+      seat(0, &[], &[]),
+      seat(2, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::主教, Role::商人, Role::建筑师],
+    &[],
+  );
+  let played = play(
+    scenario,
+    vec![
+      Script::new().role(Role::刺客).kill(Role::国王),
+      Script::new().role(Role::国王).opers(&[Oper::Gold(2)]),
+      Script::new().role(Role::魔术师),
+      Script::new().role(Role::军阀),
+    ],
+  )
+  .await;
+
+  let kill_choices = played
+    .events
+    .iter()
+    .find_map(|event| match event {
+      HistoryReqEvent::KillReq { choices, .. } => Some(*choices),
+      _ => None,
+    })
+    .unwrap();
+  // 暗弃的建筑师仍然可以被选
+  let expected = RoleSet::empty() | Role::小偷 | Role::魔术师 | Role::国王 | Role::建筑师 | Role::军阀;
+  assert_eq!(kill_choices, expected);
+  assert!(
+    played
+      .events
+      .iter()
+      .any(|event| matches!(event, HistoryReqEvent::SkipKilledTurn { actor, .. } if actor.value() == 1))
+  );
+  assert!(played.oper_choices(1).is_empty());
+  assert_eq!(played.player(1).gold(), 2);
+}
+
+#[tokio::test]
+async fn thief_cannot_target_assassin_or_killed_role_and_takes_gold_before_victim_acts() {
+  let scenario = scenario(
+    vec![
+      seat(0, &[], &[]),
+      seat(1, &[], &[]),
+      seat(5, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::主教, Role::商人, Role::建筑师],
+    &[],
+  );
+  let played = play(
+    scenario,
+    vec![
+      Script::new().role(Role::刺客).kill(Role::军阀),
+      Script::new().role(Role::小偷).steal(Role::国王),
+      Script::new().role(Role::国王).opers(&[Oper::Gold(2)]),
+      Script::new().role(Role::军阀),
+    ],
+  )
+  .await;
+
+  let steal_choices = played
+    .events
+    .iter()
+    .find_map(|event| match event {
+      HistoryReqEvent::StealReq { choices, .. } => Some(*choices),
+      _ => None,
+    })
+    .unwrap();
+  assert_eq!(
+    steal_choices,
+    RoleSet::empty() | Role::魔术师 | Role::国王 | Role::建筑师
+  );
+  assert!(played.events.iter().any(|event| matches!(
+    event,
+    HistoryReqEvent::StealGold { from, to, amount: 5, .. } if from.value() == 2 && to.value() == 1
+  )));
+  assert_eq!(played.player(1).gold(), 6);
+  assert_eq!(played.player(2).gold(), 2);
+}
+
+#[tokio::test]
+async fn magician_swaps_whole_hand_with_another_player() {
+  let scenario = scenario(
+    vec![
+      seat(0, &[Card::酒馆], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[Card::市场, Card::庄园], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::刺客, Role::小偷, Role::军阀],
+    &[],
+  );
+  let played = play(
+    scenario,
+    vec![
+      Script::new()
+        .role(Role::魔术师)
+        .magic(MagicianSkill::Swap(PlayerOffset::from_usize(2))),
+    ],
+  )
+  .await;
+
+  assert_eq!(played.player(0).cards(), &vec![Card::市场, Card::庄园]);
+  assert_eq!(played.player(2).cards(), &vec![Card::酒馆]);
+}
+
+#[tokio::test]
+async fn magician_replaces_discarded_cards_from_the_deck() {
+  let scenario = scenario(
+    vec![
+      seat(0, &[Card::酒馆, Card::市场], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::刺客, Role::小偷, Role::军阀],
+    &[Card::皇宫],
+  );
+  let played = play(
+    scenario,
+    vec![
+      Script::new()
+        .role(Role::魔术师)
+        .magic(MagicianSkill::制衡(vec![Card::酒馆])),
+    ],
+  )
+  .await;
+
+  assert_eq!(played.player(0).cards(), &vec![Card::市场, Card::皇宫]);
+}
+
+#[tokio::test]
+async fn king_takes_the_crown_next_round_even_when_killed() {
+  let mut scenario = scenario(
+    vec![
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::主教, Role::商人, Role::建筑师],
+    &[],
+  );
+  scenario.rounds = Some(2);
+  let played = play(
+    scenario,
+    vec![
+      Script::new().role(Role::刺客).kill(Role::国王),
+      Script::new().role(Role::国王),
+      Script::new().role(Role::魔术师),
+      Script::new().role(Role::军阀),
+    ],
+  )
+  .await;
+
+  assert!(
+    played
+      .events
+      .iter()
+      .any(|event| matches!(event, HistoryReqEvent::StartRound { round: 2, crown, .. } if crown.value() == 1))
+  );
+}
+
+#[tokio::test]
+async fn crown_stays_when_king_is_not_chosen() {
+  let mut scenario = scenario(
+    vec![
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::主教, Role::商人, Role::国王],
+    &[],
+  );
+  scenario.rounds = Some(2);
+  let played = play(scenario, Vec::new()).await;
+
+  assert!(
+    !played
+      .events
+      .iter()
+      .any(|event| matches!(event, HistoryReqEvent::MoveCrown { round: 1, .. }))
+  );
+  assert!(
+    played
+      .events
+      .iter()
+      .any(|event| matches!(event, HistoryReqEvent::StartRound { round: 2, crown, .. } if crown.value() == 0))
+  );
+}
+
+#[tokio::test]
+async fn rent_roles_get_one_extra_gold_per_building_of_their_color() {
+  for (role, buildings, amount) in [
+    (Role::国王, [Card::庄园, Card::城堡, Card::神殿], 4),
+    (Role::主教, [Card::神殿, Card::教堂, Card::酒馆], 4),
+    (Role::商人, [Card::酒馆, Card::市场, Card::庄园], 4),
+    (Role::军阀, [Card::瞭望台, Card::监狱, Card::战场], 5),
+  ] {
+    let scenario = scenario(
+      vec![
+        seat(0, &[], &buildings),
+        seat(0, &[], &[]),
+        seat(0, &[], &[]),
+        seat(0, &[], &[]),
+      ],
+      &[Role::刺客, Role::小偷, Role::魔术师],
+      &[],
+    );
+    let played = play(scenario, vec![Script::new().role(role).opers(&[Oper::Gold(amount)])]).await;
+
+    // 商人回合开始时还有 1 金
+    let bonus = if role == Role::商人 { 1 } else { 0 };
+    assert_eq!(played.player(0).gold(), amount + bonus, "{:?}", role);
+  }
+}
+
+#[tokio::test]
+async fn bishop_buildings_cannot_be_destroyed_even_when_killed() {
+  let scenario = scenario(
+    vec![
+      seat(0, &[], &[]),
+      seat(0, &[], &[Card::神殿, Card::教堂]),
+      seat(0, &[], &[Card::酒馆]),
+      seat(10, &[], &[]),
+    ],
+    &[Role::小偷, Role::商人, Role::建筑师],
+    &[],
+  );
+  let played = play(
+    scenario,
+    vec![
+      Script::new().role(Role::刺客).kill(Role::主教),
+      Script::new().role(Role::主教),
+      Script::new().role(Role::国王),
+      Script::new().role(Role::军阀),
+    ],
+  )
+  .await;
+
+  assert_eq!(played.destroy_choices(), vec![(2, Card::酒馆)]);
+}
+
+#[tokio::test]
+async fn merchant_gets_one_gold_at_turn_start() {
+  let scenario = scenario(
+    vec![
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::刺客, Role::小偷, Role::军阀],
+    &[],
+  );
+  let played = play(scenario, vec![Script::new().role(Role::商人)]).await;
+
+  assert!(
+    played
+      .events
+      .iter()
+      .any(|event| matches!(event, HistoryReqEvent::Merchant { .. }))
+  );
+  assert_eq!(played.player(0).gold(), 1);
+}
+
+#[tokio::test]
+async fn architect_draws_two_cards_and_builds_up_to_three_times() {
+  let scenario = scenario(
+    vec![
+      seat(10, &[Card::酒馆, Card::神殿, Card::瞭望台, Card::市场], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::刺客, Role::小偷, Role::军阀],
+    &[Card::庄园, Card::城堡],
+  );
+  let played = play(
+    scenario,
+    vec![Script::new().role(Role::建筑师).opers(&[
+      Oper::Gold(2),
+      Oper::Build(Card::酒馆),
+      Oper::Build(Card::神殿),
+      Oper::Build(Card::瞭望台),
+    ])],
+  )
+  .await;
+
+  assert_eq!(played.player(0).cards(), &vec![Card::市场, Card::庄园, Card::城堡]);
+  assert_eq!(played.buildings(0), vec![Card::酒馆, Card::神殿, Card::瞭望台]);
+  assert_eq!(played.player(0).gold(), 9);
+  assert!(!has_build(played.oper_choices(0).last().unwrap()));
+}
+
+#[tokio::test]
+async fn warlord_destroys_for_one_gold_less_but_not_own_or_bishop_buildings() {
+  let scenario = scenario(
+    vec![
+      seat(3, &[], &[Card::酒馆]),
+      seat(0, &[], &[Card::庄园, Card::大教堂]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[Card::神殿]),
+    ],
+    &[Role::刺客, Role::小偷, Role::商人],
+    &[],
+  );
+  let played = play(
+    scenario,
+    vec![
+      Script::new().role(Role::军阀).destroy(1, Card::庄园),
+      Script::new().role(Role::魔术师),
+      Script::new().role(Role::国王),
+      Script::new().role(Role::主教),
+    ],
+  )
+  .await;
+
+  // 大教堂要 4 金, 军阀只有 3 金
+  assert_eq!(played.destroy_choices(), vec![(1, Card::庄园)]);
+  assert_eq!(played.player(0).gold(), 1);
+  assert_eq!(played.buildings(1), vec![Card::大教堂]);
+}
+
+#[tokio::test]
+async fn warlord_cannot_destroy_a_city_with_eight_buildings() {
+  let seven = [
+    Card::酒馆,
+    Card::贸易站,
+    Card::庄园,
+    Card::神殿,
+    Card::教堂,
+    Card::瞭望台,
+    Card::监狱,
+  ];
+  let scenario = scenario(
+    vec![
+      seat(10, &[Card::市场, Card::码头], &seven),
+      seat(10, &[], &[]),
+      seat(0, &[], &[Card::城堡]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::刺客, Role::小偷, Role::商人],
+    &[],
+  );
+  let played = play(
+    scenario,
+    vec![
+      Script::new().role(Role::建筑师).opers(&[Oper::Build(Card::市场)]),
+      Script::new().role(Role::军阀),
+      Script::new().role(Role::国王),
+      Script::new().role(Role::主教),
+    ],
+  )
+  .await;
+
+  assert_eq!(played.destroy_choices(), vec![(2, Card::城堡)]);
+  // 建满 8 个之后建筑师剩下的建设次数也不能再用
+  assert!(!has_build(played.oper_choices(0).last().unwrap()));
+}
+
+#[tokio::test]
+async fn regular_roles_build_once_per_turn_and_never_duplicate() {
+  let scenario = scenario(
+    vec![
+      seat(10, &[Card::酒馆, Card::市场, Card::市场, Card::神殿], &[Card::酒馆]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::刺客, Role::小偷, Role::军阀],
+    &[],
+  );
+  let played = play(
+    scenario,
+    vec![Script::new().role(Role::国王).opers(&[Oper::Build(Card::市场)])],
+  )
+  .await;
+
+  let choices = played.oper_choices(0);
+  let builds = choices[0]
+    .iter()
+    .filter(|oper| matches!(oper, Oper::Build(_)))
+    .copied()
+    .collect::<Vec<_>>();
+  assert_eq!(builds, vec![Oper::Build(Card::市场), Oper::Build(Card::神殿)]);
+  assert!(!has_build(choices[1]));
+}
+
+// 3. 资质卡
+
+#[tokio::test]
+async fn fortress_cannot_be_destroyed() {
+  let scenario = scenario(
+    vec![
+      seat(10, &[], &[]),
+      seat(0, &[], &[Card::要塞, Card::酒馆]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::刺客, Role::小偷, Role::商人],
+    &[],
+  );
+  let played = play(scenario, vec![Script::new().role(Role::军阀)]).await;
+
+  assert_eq!(played.destroy_choices(), vec![(1, Card::酒馆)]);
+}
+
+#[tokio::test]
+async fn great_wall_raises_destroy_fee_of_other_buildings_only() {
+  for (target, gold_left) in [(Card::城墙, 5), (Card::庄园, 7)] {
+    let scenario = scenario(
+      vec![
+        seat(10, &[], &[]),
+        seat(0, &[], &[Card::城墙, Card::庄园]),
+        seat(0, &[], &[]),
+        seat(0, &[], &[]),
+      ],
+      &[Role::刺客, Role::小偷, Role::商人],
+      &[],
+    );
+    let played = play(scenario, vec![Script::new().role(Role::军阀).destroy(1, target)]).await;
+
+    assert_eq!(played.player(0).gold(), gold_left, "{:?}", target);
+  }
+}
+
+#[tokio::test]
+async fn great_wall_fee_can_put_a_building_out_of_reach() {
+  // 庄园拆除费 2, 有城墙时 3, 军阀只有 2 金
+  for (wall, offered) in [(false, true), (true, false)] {
+    let buildings = if wall {
+      vec![Card::城墙, Card::庄园]
+    } else {
+      vec![Card::庄园]
+    };
+    let scenario = scenario(
+      vec![
+        seat(2, &[], &[]),
+        seat(0, &[], &buildings),
+        seat(0, &[], &[Card::酒馆]),
+        seat(0, &[], &[]),
+      ],
+      &[Role::刺客, Role::小偷, Role::商人],
+      &[],
+    );
+    let played = play(scenario, vec![Script::new().role(Role::军阀)]).await;
+
+    assert_eq!(
+      played.destroy_choices().contains(&(1, Card::庄园)),
+      offered,
+      "wall {}",
+      wall
+    );
+  }
+}
+
+#[tokio::test]
+async fn graveyard_owner_decides_whether_to_buy_back_destroyed_building() {
+  for buy in [true, false] {
+    let scenario = scenario(
+      vec![
+        seat(10, &[], &[]),
+        seat(0, &[], &[Card::庄园]),
+        seat(1, &[], &[Card::墓地]),
+        seat(0, &[], &[]),
+      ],
+      &[Role::刺客, Role::小偷, Role::商人],
+      &[],
+    );
+    let owner = if buy {
+      Script::new().role(Role::国王).tomb()
+    } else {
+      Script::new().role(Role::国王)
+    };
+    let played = play(
+      scenario,
+      vec![
+        // 军阀自己的剧本总是会买, 墓地的决策落到军阀身上时结果就不对了
+        Script::new().role(Role::军阀).destroy(1, Card::庄园).tomb(),
+        Script::new().role(Role::魔术师),
+        owner,
+        Script::new().role(Role::主教),
+      ],
+    )
+    .await;
+
+    assert_eq!(played.tomb_actors(), vec![2]);
+    let tomb_obs = played
+      .events
+      .iter()
+      .find_map(|event| match event {
+        HistoryReqEvent::TombReq { obs, .. } => Some(obs),
+        _ => None,
+      })
+      .unwrap();
+    assert_eq!(tomb_obs.hero_gold(), 1);
+    assert!(tomb_obs.hero_has_building(Card::墓地));
+    if buy {
+      assert_eq!(played.player(2).gold(), 0);
+      assert_eq!(played.player(2).cards(), &vec![Card::庄园]);
+    } else {
+      assert_eq!(played.player(2).gold(), 1);
+      assert!(played.player(2).cards().is_empty());
+    }
+  }
+}
+
+#[tokio::test]
+async fn graveyard_is_not_offered_to_warlord_or_owner_without_gold() {
+  for (warlord_buildings, owner_buildings, owner_gold) in
+    [(&[Card::墓地][..], &[][..], 1), (&[][..], &[Card::墓地][..], 0)]
+  {
+    let scenario = scenario(
+      vec![
+        seat(10, &[], warlord_buildings),
+        seat(0, &[], &[Card::庄园]),
+        seat(owner_gold, &[], owner_buildings),
+        seat(0, &[], &[]),
+      ],
+      &[Role::刺客, Role::小偷, Role::商人],
+      &[],
+    );
+    let played = play(
+      scenario,
+      vec![
+        Script::new().role(Role::军阀).destroy(1, Card::庄园).tomb(),
+        Script::new().role(Role::魔术师),
+        Script::new().role(Role::国王).tomb(),
+        Script::new().role(Role::主教),
+      ],
+    )
+    .await;
+
+    assert!(played.tomb_actors().is_empty());
+  }
+}
+
+#[tokio::test]
+async fn graveyard_owner_can_buy_back_own_destroyed_building() {
+  let scenario = scenario(
+    vec![
+      seat(10, &[], &[]),
+      seat(1, &[], &[Card::庄园, Card::墓地]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::刺客, Role::小偷, Role::商人],
+    &[],
+  );
+  let played = play(
+    scenario,
+    vec![
+      Script::new().role(Role::军阀).destroy(1, Card::庄园),
+      Script::new().role(Role::国王).tomb(),
+    ],
+  )
+  .await;
+
+  assert_eq!(played.tomb_actors(), vec![1]);
+  assert_eq!(played.player(1).cards(), &vec![Card::庄园]);
+  assert!(!played.player(1).has_building(Card::庄园));
+}
+
+#[tokio::test]
+async fn draw_offers_two_choose_one_by_default() {
+  let scenario = scenario(
+    vec![
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::刺客, Role::小偷, Role::军阀],
+    &[Card::皇宫, Card::城堡],
+  );
+  let played = play(
+    scenario,
+    vec![
+      Script::new()
+        .role(Role::国王)
+        .opers(&[Oper::Card2Choose1])
+        .keep(&[Card::城堡]),
+    ],
+  )
+  .await;
+
+  let choices = played.oper_choices(0)[0];
+  assert!(!choices.contains(&Oper::Card3Choose1));
+  assert!(!choices.contains(&Oper::Card2Choose2));
+  assert_eq!(played.player(0).cards(), &vec![Card::城堡]);
+}
+
+#[tokio::test]
+async fn observatory_chooses_one_of_three_cards() {
+  let scenario = scenario(
+    vec![
+      seat(0, &[], &[Card::天文台]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::刺客, Role::小偷, Role::军阀],
+    &[Card::皇宫, Card::城堡, Card::庄园],
+  );
+  let played = play(
+    scenario,
+    vec![
+      Script::new()
+        .role(Role::国王)
+        .opers(&[Oper::Card3Choose1])
+        .keep(&[Card::庄园]),
+    ],
+  )
+  .await;
+
+  assert!(!played.oper_choices(0)[0].contains(&Oper::Card2Choose1));
+  assert_eq!(played.player(0).cards(), &vec![Card::庄园]);
+}
+
+#[tokio::test]
+async fn library_keeps_both_drawn_cards() {
+  let scenario = scenario(
+    vec![
+      seat(0, &[], &[Card::图书馆]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::刺客, Role::小偷, Role::军阀],
+    &[Card::皇宫, Card::城堡],
+  );
+  let played = play(
+    scenario,
+    vec![Script::new().role(Role::国王).opers(&[Oper::Card2Choose2])],
+  )
+  .await;
+
+  assert!(!played.oper_choices(0)[0].contains(&Oper::Card2Choose1));
+  assert_eq!(played.player(0).cards(), &vec![Card::皇宫, Card::城堡]);
+}
+
+#[tokio::test]
+async fn smithy_buys_three_cards_for_two_gold_once_per_turn() {
+  let scenario = scenario(
+    vec![
+      seat(5, &[], &[Card::铁匠铺]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::刺客, Role::小偷, Role::军阀],
+    &[Card::皇宫, Card::城堡, Card::庄园],
+  );
+  let played = play(scenario, vec![Script::new().role(Role::国王).opers(&[Oper::BuyCard])]).await;
+
+  assert_eq!(played.player(0).gold(), 3);
+  assert_eq!(played.player(0).cards(), &vec![Card::皇宫, Card::城堡, Card::庄园]);
+  assert!(!played.oper_choices(0)[1].contains(&Oper::BuyCard));
+}
+
+#[tokio::test]
+async fn smithy_needs_two_gold() {
+  let scenario = scenario(
+    vec![
+      seat(1, &[], &[Card::铁匠铺]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::刺客, Role::小偷, Role::军阀],
+    &[],
+  );
+  let played = play(scenario, vec![Script::new().role(Role::国王)]).await;
+
+  assert!(!played.oper_choices(0)[0].contains(&Oper::BuyCard));
+}
+
+#[tokio::test]
+async fn smithy_with_exactly_two_gold_spends_both() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[Card::铁匠铺]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::刺客, Role::小偷, Role::军阀],
+    &[Card::皇宫, Card::城堡, Card::庄园],
+  );
+  let played = play(
+    scenario,
+    vec![Script::new().role(Role::主教).opers(&[Oper::BuyCard, Oper::EndRound])],
+  )
+  .await;
+
+  assert_eq!(played.player(0).gold(), 0);
+  assert_eq!(played.player(0).cards().len(), 3);
+}
+
+#[tokio::test]
+async fn laboratory_sells_one_card_for_one_gold_once_per_turn() {
+  let scenario = scenario(
+    vec![
+      seat(0, &[Card::酒馆, Card::市场], &[Card::实验室]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::刺客, Role::小偷, Role::军阀],
+    &[],
+  );
+  let played = play(
+    scenario,
+    vec![Script::new().role(Role::国王).opers(&[Oper::SellCard(Card::酒馆)])],
+  )
+  .await;
+
+  assert_eq!(played.player(0).gold(), 1);
+  assert_eq!(played.player(0).cards(), &vec![Card::市场]);
+  assert!(
+    !played.oper_choices(0)[1]
+      .iter()
+      .any(|oper| matches!(oper, Oper::SellCard(_)))
+  );
+}
+
+#[tokio::test]
+async fn magic_school_counts_as_rent_color() {
+  let scenario = scenario(
+    vec![
+      seat(0, &[], &[Card::庄园, Card::魔法学院]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::刺客, Role::小偷, Role::军阀],
+    &[],
+  );
+  let played = play(scenario, vec![Script::new().role(Role::国王).opers(&[Oper::Gold(4)])]).await;
+
+  assert_eq!(played.player(0).gold(), 4);
+}
+
+#[tokio::test]
+async fn ghost_town_counts_as_any_color_unless_built_in_final_round() {
+  let buildings = [Card::酒馆, Card::庄园, Card::神殿, Card::要塞];
+  // 酒馆 1 + 庄园 3 + 神殿 1 + 要塞 3 + 鬼城 2
+  for (rounds, score) in [(1, 10), (2, 13)] {
+    let mut scenario = scenario(
+      vec![
+        seat(10, &[Card::鬼城], &buildings),
+        seat(0, &[], &[]),
+        seat(0, &[], &[]),
+        seat(0, &[], &[]),
+      ],
+      &[Role::刺客, Role::小偷, Role::军阀],
+      &[],
+    );
+    scenario.rounds = Some(rounds);
+    let played = play(
+      scenario,
+      vec![Script::new().role(Role::国王).opers(&[Oper::Build(Card::鬼城)])],
+    )
+    .await;
+
+    assert_eq!(played.player(0).score(), score, "rounds {}", rounds);
+  }
+}
+
+#[tokio::test]
+async fn ghost_town_covers_only_one_missing_color() {
+  // 鬼城 2 + 酒馆 1 + 庄园 3 + 神殿 1, 另有要塞 3 时只差一色, 没有时差两色
+  for (fortress, score) in [(true, 13), (false, 7)] {
+    let mut buildings = vec![Card::鬼城, Card::酒馆, Card::庄园, Card::神殿];
+    if fortress {
+      buildings.push(Card::要塞);
+    }
+    let scenario = scenario(
+      vec![
+        seat(0, &[], &buildings),
+        seat(0, &[], &[]),
+        seat(0, &[], &[]),
+        seat(0, &[], &[]),
+      ],
+      &[Role::刺客, Role::小偷, Role::军阀],
+      &[],
+    );
+    let played = play(scenario, Vec::new()).await;
+
+    assert_eq!(played.player(0).score(), score, "fortress {}", fortress);
+  }
+}
+
+// 4. 计分规则
+
+#[tokio::test]
+async fn scoring_adds_color_and_dragon_gate_university_bonuses() {
+  let scenario = scenario(
+    vec![
+      seat(0, &[], &[Card::酒馆, Card::庄园, Card::神殿, Card::瞭望台, Card::要塞]),
+      seat(0, &[], &[Card::龙门, Card::大学]),
+      seat(0, &[], &[Card::鬼城, Card::酒馆, Card::庄园, Card::神殿, Card::要塞]),
+      seat(0, &[], &[Card::城堡, Card::教堂]),
+    ],
+    &[Role::刺客, Role::小偷, Role::军阀],
+    &[],
+  );
+  let played = play(scenario, Vec::new()).await;
+
+  // 2 号座位的鬼城视为红色
+  assert_eq!(played.game.scores(), vec![12, 16, 13, 6]);
+  // 汉 12 + 13, 楚 16 + 6
+  assert_eq!(played.result, (0.0, 1.0));
+}
+
+#[tokio::test]
+async fn only_the_first_to_eight_buildings_gets_the_first_bonus() {
+  let seven = [
+    Card::酒馆,
+    Card::贸易站,
+    Card::庄园,
+    Card::神殿,
+    Card::教堂,
+    Card::瞭望台,
+    Card::监狱,
+  ];
+  let scenario = scenario(
+    vec![
+      seat(10, &[Card::市场], &seven),
+      seat(10, &[Card::市场], &seven),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[Role::刺客, Role::小偷, Role::军阀],
+    &[],
+  );
+  let played = play(
+    scenario,
+    vec![
+      Script::new().role(Role::建筑师).opers(&[Oper::Build(Card::市场)]),
+      Script::new().role(Role::国王).opers(&[Oper::Build(Card::市场)]),
+    ],
+  )
+  .await;
+
+  // 国王先行动, 先建满 8 个. 建筑分 14, 满 8 个 +2, 第一个 +2
+  assert_eq!(played.player(1).score(), 18);
+  assert_eq!(played.player(0).score(), 16);
+}
+
+// 5. 选择角色规则
+
+#[tokio::test]
+async fn role_selection_offers_one_fewer_role_to_each_player() {
+  for (num_players, expected, public_drops) in [(4, vec![5, 4, 3, 2], 1), (6, vec![7, 6, 5, 4, 3, 2], 0)] {
+    let mut scenario = scenario((0..num_players).map(|_| seat(0, &[], &[])).collect(), &[], &[]);
+    scenario.crown = 1;
+    let played = play(scenario, Vec::new()).await;
+
+    let offered = played
+      .events
+      .iter()
+      .filter_map(|event| match event {
+        HistoryReqEvent::ChooseRoleReq { actor, choices, .. } => Some((actor.value(), choices.len())),
+        _ => None,
+      })
+      .collect::<Vec<_>>();
+    let order = (0..num_players).map(|i| (i + 1) % num_players);
+    assert_eq!(offered, order.zip(expected).collect::<Vec<_>>());
+    let public = played
+      .events
+      .iter()
+      .filter_map(|event| match event {
+        HistoryReqEvent::PublicDropRoles { roles, .. } => Some(roles.len()),
+        _ => None,
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(public, vec![2; public_drops]);
+  }
+}
+
+#[test]
+fn scenario_rejects_inconsistent_card_counts() {
+  let mut scenario = scenario(
+    vec![
+      seat(0, &[Card::市政厅, Card::市政厅], &[Card::市政厅]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+      seat(0, &[], &[]),
+    ],
+    &[],
+    &[],
+  );
+  assert!(scenario.validate().is_err());
+
+  scenario.players[0].hand.pop();
+  assert!(scenario.validate().is_ok());
+  scenario.shuffle_rest = false;
+  assert!(scenario.validate().is_err());
+}
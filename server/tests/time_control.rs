@@ -0,0 +1,109 @@
+// 决策时限: 时间池, 超时后由 fallback 决策并记入历史
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use server::agent_transports::WsTransport;
+use server::domain::{AgentReqEvent, Oper, PlayerIndex, Role};
+use server::{
+  AbstractFAAgent, AbstractFYIAgent, Game, HistoryReqEvent, IdGen, NoopFYIAgent, PlayerIndexedVec, RemoteFAAgent,
+  TimeControl,
+};
+use tokio::sync::mpsc;
+
+mod common;
+
+use common::*;
+
+#[tokio::test]
+async fn remote_agent_that_runs_out_of_time_is_replaced_by_fallback_and_timeout_is_recorded() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  // 远端一直不回复, 0 号的每次决策都由按剧本决策的 fallback 做出
+  let (req_sender, mut req_receiver) = mpsc::channel::<String>(1024);
+  let (_resp_sender, resp_receiver) = mpsc::channel::<String>(1);
+  let fallback = ScriptedAgent {
+    seat: PlayerIndex::from_usize(0),
+    script: Script::new().role(Role::国王).opers(&[Oper::Gold(2)]),
+  };
+  let time_control = TimeControl {
+    decision_ms: 30,
+    bank_ms: 40,
+    increment_ms: 5,
+  };
+  let remote = RemoteFAAgent::new(
+    IdGen::new(),
+    WsTransport::new(req_sender, resp_receiver),
+    Box::new(fallback),
+  )
+  .with_time_control(time_control);
+
+  let (history, receiver) = history();
+  let mut agents = PlayerIndexedVec::<Box<dyn AbstractFAAgent>>::new();
+  let mut fyi_agents = PlayerIndexedVec::<Box<dyn AbstractFYIAgent>>::new();
+  agents.push(Box::new(remote));
+  for i in 1..4 {
+    agents.push(Box::new(ScriptedAgent {
+      seat: PlayerIndex::from_usize(i),
+      script: Script::default(),
+    }));
+  }
+  for _ in 0..4 {
+    fyi_agents.push(Box::new(NoopFYIAgent::new()));
+  }
+  let mut game = Game::from_scenario(&scenario, agents, fyi_agents, StdRng::seed_from_u64(0), history).unwrap();
+  game.run().await.unwrap();
+  let events = drain(receiver);
+
+  assert_eq!(game.report().unwrap().players[0].stats.roles[0].role, Role::国王);
+
+  // 第一次用满 30ms, 时间池 40 - 30 + 5 = 15; 之后可用的时间被时间池限制
+  let timeouts = events
+    .iter()
+    .filter_map(|event| match event {
+      HistoryReqEvent::DecisionTimeout {
+        actor,
+        budget_ms,
+        bank_ms,
+        ..
+      } => Some((actor.value(), *budget_ms, *bank_ms)),
+      _ => None,
+    })
+    .collect::<Vec<_>>();
+  assert!(timeouts.len() >= 2);
+  assert_eq!(timeouts[0], (0, 30, Some(15)));
+  assert_eq!(timeouts[1], (0, 15, Some(5)));
+  assert!(timeouts[2..].iter().all(|&timeout| timeout == (0, 5, Some(5))));
+
+  // 每次超时都紧接在对应的回复之前
+  let resp_count = events
+    .iter()
+    .zip(events.iter().skip(1))
+    .filter(|(event, _)| matches!(event, HistoryReqEvent::DecisionTimeout { .. }))
+    .filter(|(_, next)| {
+      matches!(
+        next,
+        HistoryReqEvent::ChooseRoleResp { .. } | HistoryReqEvent::OperResp { .. }
+      )
+    })
+    .count();
+  assert_eq!(resp_count, timeouts.len());
+
+  // 发给远端的 obs 带着决策开始时的时钟
+  let first: AgentReqEvent = serde_json::from_str(&req_receiver.try_recv().unwrap()).unwrap();
+  let AgentReqEvent::ChooseRole { obs, .. } = first else {
+    panic!("expected ChooseRole, got {:?}", first);
+  };
+  let clock = obs.full().unwrap().clock().unwrap();
+  assert_eq!(
+    (clock.budget_ms, clock.bank_ms, clock.increment_ms),
+    (30, Some(40), Some(5))
+  );
+}
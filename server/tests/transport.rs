@@ -0,0 +1,400 @@
+// 远程 agent 的传输: TCP, 进程内通道, 子进程, obs 增量, 握手和 MessagePack
+
+use std::time::Duration;
+
+use server::agent_transports::{ChannelTransport, SubprocessTransport, TcpTransport};
+use server::domain::{AgentReqEvent, AgentRespEvent, Card, Oper, PlayerIndex, Role};
+use server::fa_agents::NoopFAAgent;
+use server::{
+  AbstractFAAgent, DecisionKind, HandshakeError, HeuristicFAAgent, HeuristicWeights, HistoryReqEvent, IdGen,
+  ObsMessage, ObsMirror, PROTOCOL_VERSION, RemoteFAAgent, TimeControl, WireFormat, respond,
+};
+use strum::EnumCount;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+mod common;
+
+use common::*;
+
+#[tokio::test]
+async fn remote_agent_over_tcp_json_lines_skips_bad_and_stale_replies() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let addr = listener.local_addr().unwrap();
+  tokio::spawn(async move {
+    let (stream, _) = listener.accept().await.unwrap();
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let mut agent = ScriptedAgent {
+      seat: PlayerIndex::from_usize(0),
+      script: Script::new().role(Role::国王).opers(&[Oper::Gold(2)]),
+    };
+    let mut mirror = ObsMirror::new();
+    while let Some(line) = lines.next_line().await.unwrap() {
+      let event: AgentReqEvent = serde_json::from_str(&line).unwrap();
+      // 每个正确的回复之前先有一行坏掉的和一条 id 不对的
+      let stale = AgentRespEvent::WaitForReady { id: event.id() + 1000 };
+      let resp = respond(&mut agent, &mut mirror, event).await.unwrap();
+      let text = format!(
+        "not json\n{}\n{}\n",
+        serde_json::to_string(&stale).unwrap(),
+        serde_json::to_string(&resp).unwrap()
+      );
+      if writer.write_all(text.as_bytes()).await.is_err() {
+        break;
+      }
+    }
+  });
+
+  let transport = TcpTransport::connect(addr).await.unwrap();
+  let mut remote = RemoteFAAgent::new(IdGen::new(), transport, Box::new(NoopFAAgent::new()));
+  remote.wait_for_ready().await;
+  let (history, receiver) = history();
+  let mut game = remote_game(&scenario, Box::new(remote), history);
+  game.enable_strict_agents();
+  game.run().await.unwrap();
+  let events = drain(receiver);
+
+  assert_eq!(game.report().unwrap().players[0].stats.roles[0].role, Role::国王);
+  assert!(!events.iter().any(|event| matches!(
+    event,
+    HistoryReqEvent::DecisionTimeout { .. } | HistoryReqEvent::AgentReplaced { .. }
+  )));
+}
+
+#[tokio::test]
+async fn remote_agent_falls_back_for_the_rest_of_the_game_once_disconnected() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  // 远端已经走了, 第一次发请求就失败
+  let (req_sender, req_receiver) = mpsc::channel::<AgentReqEvent>(1);
+  let (_resp_sender, resp_receiver) = mpsc::channel::<AgentRespEvent>(1);
+  drop(req_receiver);
+  let fallback = ScriptedAgent {
+    seat: PlayerIndex::from_usize(0),
+    script: Script::new().role(Role::国王).opers(&[Oper::Gold(2)]),
+  };
+  let remote = RemoteFAAgent::new(
+    IdGen::new(),
+    ChannelTransport::new(req_sender, resp_receiver),
+    Box::new(fallback),
+  )
+  .with_time_control(TimeControl {
+    decision_ms: 30,
+    bank_ms: 0,
+    increment_ms: 0,
+  });
+  let (history, receiver) = history();
+  let mut game = remote_game(&scenario, Box::new(remote), history);
+  game.run().await.unwrap();
+  let events = drain(receiver);
+
+  // 断开不算超时, 也不是 agent 出错
+  assert_eq!(game.report().unwrap().players[0].stats.roles[0].role, Role::国王);
+  assert!(!events.iter().any(|event| matches!(
+    event,
+    HistoryReqEvent::DecisionTimeout { .. } | HistoryReqEvent::AgentReplaced { .. }
+  )));
+}
+
+#[tokio::test]
+async fn remote_agent_over_channel_plays_like_the_local_agent() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  let script = Script::new().role(Role::国王).opers(&[Oper::Gold(2)]);
+  let local = play(scenario.clone(), vec![script.clone()]).await;
+
+  let agent = ScriptedAgent {
+    seat: PlayerIndex::from_usize(0),
+    script,
+  };
+  let remote = RemoteFAAgent::new(
+    IdGen::new(),
+    ChannelTransport::spawn(Box::new(agent)),
+    Box::new(NoopFAAgent::new()),
+  );
+  let (history, _receiver) = history();
+  let mut game = remote_game(&scenario, Box::new(remote), history);
+  game.enable_strict_agents();
+  let result = game.run().await.unwrap().result;
+
+  assert_eq!(result, local.result);
+  assert_eq!(game.report().unwrap().players, local.game.report().unwrap().players);
+}
+
+#[tokio::test]
+async fn remote_agent_forwards_the_remote_rationale_and_none_without_fallback() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  // 其他座位的 ScriptedAgent 不给解释, 历史里的解释都来自 0 号位
+  let explained = |remote: Box<dyn AbstractFAAgent>, fallback: Box<dyn AbstractFAAgent>| {
+    let scenario = scenario.clone();
+    async move {
+      let remote = RemoteFAAgent::new(IdGen::new(), ChannelTransport::spawn(remote), fallback);
+      let (history, receiver) = history();
+      let mut game = remote_game(&scenario, Box::new(remote), history);
+      game.enable_strict_agents();
+      game.run().await.unwrap();
+      drain(receiver)
+        .iter()
+        .filter(|event| event.rationale().is_some())
+        .count()
+    }
+  };
+
+  let heuristic = || Box::new(HeuristicFAAgent::new(HeuristicWeights::default()));
+  assert!(explained(heuristic(), Box::new(NoopFAAgent::new())).await > 0);
+  let scripted = ScriptedAgent {
+    seat: PlayerIndex::from_usize(0),
+    script: Script::new().role(Role::国王).opers(&[Oper::Gold(2)]),
+  };
+  assert_eq!(explained(Box::new(scripted), heuristic()).await, 0);
+}
+
+#[tokio::test]
+async fn remote_agent_with_obs_deltas_plays_like_the_local_agent_and_resyncs_a_lost_copy() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  let script = Script::new().role(Role::国王).opers(&[Oper::Gold(2)]);
+  let local = play(scenario.clone(), vec![script.clone()]).await;
+
+  let (req_sender, mut req_receiver) = mpsc::channel::<AgentReqEvent>(4);
+  let (resp_sender, resp_receiver) = mpsc::channel::<AgentRespEvent>(4);
+  let agent = tokio::spawn(async move {
+    let mut agent = ScriptedAgent {
+      seat: PlayerIndex::from_usize(0),
+      script,
+    };
+    let mut mirror = ObsMirror::new();
+    let (mut decisions, mut deltas, mut resyncs) = (0, 0, 0);
+    while let Some(event) = req_receiver.recv().await {
+      if let Some(ObsMessage::Sync(sync)) = event.obs() {
+        decisions += 1;
+        if sync.full.is_none() {
+          deltas += 1;
+        }
+        // 第三次决策之前丢掉副本, 像是 agent 重启过
+        if decisions == 3 {
+          mirror = ObsMirror::new();
+        }
+      }
+      let Some(resp) = respond(&mut agent, &mut mirror, event).await else {
+        break;
+      };
+      if matches!(resp, AgentRespEvent::Resync { .. }) {
+        resyncs += 1;
+      }
+      if resp_sender.send(resp).await.is_err() {
+        break;
+      }
+    }
+    (deltas, resyncs)
+  });
+
+  let mut remote = RemoteFAAgent::new(
+    IdGen::new(),
+    ChannelTransport::new(req_sender, resp_receiver),
+    Box::new(NoopFAAgent::new()),
+  );
+  assert!(remote.handshake().await.unwrap().obs_deltas);
+  let (history, _receiver) = history();
+  let mut game = remote_game(&scenario, Box::new(remote), history);
+  game.enable_strict_agents();
+  let result = game.run().await.unwrap().result;
+  assert_eq!(result, local.result);
+  assert_eq!(game.report().unwrap().players, local.game.report().unwrap().players);
+
+  drop(game);
+  let (deltas, resyncs) = agent.await.unwrap();
+  assert!(deltas > 0);
+  assert_eq!(resyncs, 1);
+}
+
+#[tokio::test]
+async fn subprocess_agent_plays_a_whole_game_over_stdio() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  let command = format!("{} --agent random", env!("CARGO_BIN_EXE_stdio_agent"));
+  let remote = RemoteFAAgent::spawn(&command, Box::new(NoopFAAgent::new()))
+    .await
+    .unwrap();
+  let (history, receiver) = history();
+  let mut game = remote_game(&scenario, Box::new(remote), history);
+  game.enable_strict_agents();
+  game.run().await.unwrap();
+  let events = drain(receiver);
+
+  assert!(!events.iter().any(|event| matches!(
+    event,
+    HistoryReqEvent::DecisionTimeout { .. } | HistoryReqEvent::AgentReplaced { .. }
+  )));
+  // 握手协商的协议紧接在 StartGame 之后
+  assert!(matches!(
+    &events[1],
+    HistoryReqEvent::AgentProtocol { actor, version, name, .. }
+      if actor.value() == 0 && *version == PROTOCOL_VERSION && name == "RandomAgent"
+  ));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn crashing_subprocess_agent_is_restarted_then_falls_back() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  // 握手之后读到第一个请求就退出
+  let script = std::env::temp_dir().join(format!("crashing_agent_{}.sh", std::process::id()));
+  std::fs::write(
+    &script,
+    "read line\necho '{\"WaitForReady\":{\"id\":4294967295}}'\nread line\necho crash >&2\nexit 1\n",
+  )
+  .unwrap();
+  let transport = SubprocessTransport::spawn(&format!("sh {}", script.display()), Duration::from_secs(10))
+    .await
+    .unwrap()
+    .with_max_restarts(2);
+  let fallback = ScriptedAgent {
+    seat: PlayerIndex::from_usize(0),
+    script: Script::new().role(Role::国王).opers(&[Oper::Gold(2)]),
+  };
+  let remote = RemoteFAAgent::new(IdGen::new(), transport, Box::new(fallback));
+  let (history, receiver) = history();
+  let mut game = remote_game(&scenario, Box::new(remote), history);
+  game.run().await.unwrap();
+  let events = drain(receiver);
+  std::fs::remove_file(&script).unwrap();
+
+  assert_eq!(game.report().unwrap().players[0].stats.roles[0].role, Role::国王);
+  assert!(!events.iter().any(|event| matches!(
+    event,
+    HistoryReqEvent::DecisionTimeout { .. } | HistoryReqEvent::AgentReplaced { .. }
+  )));
+}
+
+#[tokio::test]
+async fn agent_missing_decision_types_is_rejected_at_handshake() {
+  let (req_sender, mut req_receiver) = mpsc::channel::<AgentReqEvent>(4);
+  let (resp_sender, resp_receiver) = mpsc::channel::<AgentRespEvent>(4);
+  // 老版本的 agent 不会回答墓地
+  let decisions = DecisionKind::all()
+    .into_iter()
+    .filter(|kind| *kind != DecisionKind::Tomb)
+    .collect::<Vec<_>>();
+  let agent = tokio::spawn(async move {
+    let hello = req_receiver.recv().await.unwrap();
+    let AgentReqEvent::Hello { id, cards, .. } = hello else {
+      panic!("expected Hello, got {:?}", hello);
+    };
+    assert_eq!(cards.len(), Card::COUNT);
+    resp_sender
+      .send(AgentRespEvent::Hello {
+        id,
+        protocol_version: PROTOCOL_VERSION,
+        name: "old".to_string(),
+        decisions,
+        obs_deltas: false,
+      })
+      .await
+      .unwrap();
+    req_receiver.recv().await.unwrap()
+  });
+
+  let mut remote = RemoteFAAgent::new(
+    IdGen::new(),
+    ChannelTransport::new(req_sender, resp_receiver),
+    Box::new(NoopFAAgent::new()),
+  );
+  let error = remote.handshake().await.unwrap_err();
+  assert!(matches!(error, HandshakeError::Incompatible(_)), "{}", error);
+  assert!(remote.is_disconnected());
+  assert!(remote.protocol().is_none());
+  let AgentReqEvent::Reject { reason, .. } = agent.await.unwrap() else {
+    panic!("expected Reject");
+  };
+  assert!(reason.contains("Tomb"), "{}", reason);
+}
+
+#[tokio::test]
+async fn history_events_survive_the_msgpack_round_trip() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  let played = play(scenario, vec![]).await;
+  for event in &played.events {
+    let expected = serde_json::to_value(event).unwrap();
+    // 服务端转换内部的 JSON 文本, 客户端按类型解码
+    let json = serde_json::to_string(event).unwrap();
+    let frame = WireFormat::MessagePack.encode_json_text(&json).unwrap();
+    let decoded: HistoryReqEvent = WireFormat::MessagePack.decode(&frame).unwrap();
+    assert_eq!(serde_json::to_value(&decoded).unwrap(), expected);
+    // 反过来, 客户端按类型编码, 服务端转回 JSON 文本
+    let frame = WireFormat::MessagePack.encode(event).unwrap();
+    let json = WireFormat::MessagePack.decode_json_text(&frame).unwrap();
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&json).unwrap(), expected);
+  }
+}
@@ -0,0 +1,425 @@
+// WebSocket 上的 agent 连接: 帧格式, 断线续连和按座位分配端点的 dispatcher
+
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use server::agent_transports::WsTransport;
+use server::domain::{AgentReqEvent, Oper, PlayerIndex, Role};
+use server::fa_agents::NoopFAAgent;
+use server::{
+  AbstractFAAgent, AgentTransport, HistoryReqEvent, IdGen, ObsMirror, RemoteFAAgent, WireFormat, WsDispatcher,
+  respond,
+};
+use tokio::sync::mpsc;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+
+mod common;
+
+use common::*;
+
+#[tokio::test]
+async fn ws_agent_connection_can_choose_msgpack_binary_frames() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  let script = Script::new().role(Role::国王).opers(&[Oper::Gold(2)]);
+  let local = play(scenario.clone(), vec![script.clone()]).await;
+
+  let port = std::net::TcpListener::bind("127.0.0.1:0")
+    .unwrap()
+    .local_addr()
+    .unwrap()
+    .port();
+  let dispatcher = WsDispatcher::new(format!("127.0.0.1:{}", port));
+  let end_point = uuid::Uuid::new_v4();
+  let (req_sender, req_receiver) = mpsc::channel::<String>(16);
+  let (resp_sender, resp_receiver) = mpsc::channel::<String>(16);
+  dispatcher.add_end_point(end_point, req_receiver, resp_sender).await;
+
+  let client = tokio::spawn(async move {
+    let url = format!(
+      "ws://127.0.0.1:{}/{}{}",
+      port,
+      end_point,
+      WireFormat::MessagePack.query()
+    );
+    let (mut ws_stream, _) = loop {
+      match connect_async(&url).await {
+        Ok(connected) => break connected,
+        Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+      }
+    };
+    let mut agent = ScriptedAgent {
+      seat: PlayerIndex::from_usize(0),
+      script,
+    };
+    let mut mirror = ObsMirror::new();
+    let mut frames = 0;
+    while let Some(Ok(msg)) = ws_stream.next().await {
+      let frame = match msg {
+        Message::Binary(frame) => frame,
+        Message::Close(_) => break,
+        other => panic!("expected a binary frame, got {:?}", other),
+      };
+      frames += 1;
+      let event: AgentReqEvent = WireFormat::MessagePack.decode(&frame).unwrap();
+      let Some(resp) = respond(&mut agent, &mut mirror, event).await else {
+        break;
+      };
+      let frame = WireFormat::MessagePack.encode(&resp).unwrap();
+      ws_stream.send(Message::Binary(frame.into())).await.unwrap();
+    }
+    frames
+  });
+
+  let mut remote = RemoteFAAgent::new(
+    IdGen::new(),
+    WsTransport::new(req_sender, resp_receiver),
+    Box::new(NoopFAAgent::new()),
+  );
+  remote.wait_for_ready().await;
+  remote.handshake().await.unwrap();
+  let (history, receiver) = history();
+  let mut game = remote_game(&scenario, Box::new(remote), history);
+  game.enable_strict_agents();
+  let result = game.run().await.unwrap().result;
+  let events = drain(receiver);
+
+  assert_eq!(result, local.result);
+  assert!(!events.iter().any(|event| matches!(
+    event,
+    HistoryReqEvent::DecisionTimeout { .. } | HistoryReqEvent::AgentReplaced { .. }
+  )));
+  drop(game);
+  assert!(client.await.unwrap() > 2);
+}
+
+// 连上 agent endpoint, 回复到第一个决策请求为止, 不回复它就断开. 返回 resume token 和这个请求的 id
+async fn connect_and_drop_at_first_decision(url: &str, agent: &mut ScriptedAgent) -> (uuid::Uuid, u32) {
+  let (mut ws_stream, _) = loop {
+    match connect_async(url).await {
+      Ok(connected) => break connected,
+      Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+    }
+  };
+  let mut mirror = ObsMirror::new();
+  let mut token = None;
+  while let Some(Ok(Message::Text(text))) = ws_stream.next().await {
+    let event: AgentReqEvent = serde_json::from_str(&text).unwrap();
+    if event.obs().is_some() {
+      return (token.unwrap(), event.id());
+    }
+    if let AgentReqEvent::Hello { resume_token, .. } = &event {
+      token = *resume_token;
+    }
+    let resp = respond(agent, &mut mirror, event).await.unwrap();
+    let text = serde_json::to_string(&resp).unwrap();
+    ws_stream.send(Message::Text(text.into())).await.unwrap();
+  }
+  panic!("closed before the first decision");
+}
+
+#[tokio::test]
+async fn ws_agent_resumes_with_its_token_and_gets_the_outstanding_request() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  let script = Script::new().role(Role::国王).opers(&[Oper::Gold(2)]);
+  let local = play(scenario.clone(), vec![script.clone()]).await;
+
+  let port = std::net::TcpListener::bind("127.0.0.1:0")
+    .unwrap()
+    .local_addr()
+    .unwrap()
+    .port();
+  let dispatcher = WsDispatcher::new(format!("127.0.0.1:{}", port));
+  let end_point = uuid::Uuid::new_v4();
+  let transport = dispatcher.add_agent_end_point(end_point, Duration::from_secs(10)).await;
+
+  let client = tokio::spawn(async move {
+    let url = format!("ws://127.0.0.1:{}/{}", port, end_point);
+    let mut agent = ScriptedAgent {
+      seat: PlayerIndex::from_usize(0),
+      script,
+    };
+    let (token, dropped) = connect_and_drop_at_first_decision(&url, &mut agent).await;
+
+    // 没有 token 的连接被拒绝
+    let (mut intruder, _) = connect_async(&url).await.unwrap();
+    let Some(Ok(Message::Close(Some(close_frame)))) = intruder.next().await else {
+      panic!("expected a close frame");
+    };
+    assert_eq!(close_frame.code, CloseCode::Policy);
+
+    let (mut ws_stream, _) = connect_async(format!("{}?resume={}", url, token)).await.unwrap();
+    let mut mirror = ObsMirror::new();
+    let mut received = Vec::new();
+    while let Some(Ok(Message::Text(text))) = ws_stream.next().await {
+      let event: AgentReqEvent = serde_json::from_str(&text).unwrap();
+      received.push(event.clone());
+      let Some(resp) = respond(&mut agent, &mut mirror, event).await else {
+        continue;
+      };
+      let text = serde_json::to_string(&resp).unwrap();
+      ws_stream.send(Message::Text(text.into())).await.unwrap();
+    }
+    (dropped, received)
+  });
+
+  let mut remote = RemoteFAAgent::new(IdGen::new(), transport, Box::new(NoopFAAgent::new()));
+  remote.wait_for_ready().await;
+  remote.handshake().await.unwrap();
+  let (history, receiver) = history();
+  let mut game = remote_game(&scenario, Box::new(remote), history);
+  game.enable_strict_agents();
+  let result = game.run().await.unwrap().result;
+  let events = drain(receiver);
+
+  assert_eq!(result, local.result);
+  assert!(!events.iter().any(|event| matches!(
+    event,
+    HistoryReqEvent::DecisionTimeout { .. } | HistoryReqEvent::AgentReplaced { .. }
+  )));
+  drop(game);
+  let (dropped, received) = client.await.unwrap();
+  // 先收到当前的 obs, 再收到断开前没有回复的请求, 带完整的 obs
+  assert!(matches!(&received[0], AgentReqEvent::Resumed { obs: Some(obs), .. } if obs.full().is_some()));
+  assert_eq!(received[1].id(), dropped);
+  assert!(received[1].obs().unwrap().full().is_some());
+  assert!(received.len() > 2);
+}
+
+#[tokio::test]
+async fn ws_agent_away_past_the_grace_period_is_played_by_the_fallback() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  let script = Script::new().role(Role::国王).opers(&[Oper::Gold(2)]);
+  let local = play(scenario.clone(), vec![script.clone()]).await;
+
+  let port = std::net::TcpListener::bind("127.0.0.1:0")
+    .unwrap()
+    .local_addr()
+    .unwrap()
+    .port();
+  let dispatcher = WsDispatcher::new(format!("127.0.0.1:{}", port));
+  let end_point = uuid::Uuid::new_v4();
+  let transport = dispatcher
+    .add_agent_end_point(end_point, Duration::from_millis(100))
+    .await;
+
+  let fallback = ScriptedAgent {
+    seat: PlayerIndex::from_usize(0),
+    script: script.clone(),
+  };
+  let client = tokio::spawn(async move {
+    let url = format!("ws://127.0.0.1:{}/{}", port, end_point);
+    let mut agent = ScriptedAgent {
+      seat: PlayerIndex::from_usize(0),
+      script,
+    };
+    connect_and_drop_at_first_decision(&url, &mut agent).await
+  });
+
+  let mut remote = RemoteFAAgent::new(IdGen::new(), transport, Box::new(fallback));
+  remote.wait_for_ready().await;
+  remote.handshake().await.unwrap();
+  let (history, _receiver) = history();
+  let mut game = remote_game(&scenario, Box::new(remote), history);
+  game.enable_strict_agents();
+  let start = std::time::Instant::now();
+  let result = game.run().await.unwrap().result;
+
+  assert_eq!(result, local.result);
+  // 只等第一次的宽限期, 之后直接由 fallback 决策
+  assert!(start.elapsed() < Duration::from_secs(2));
+  client.await.unwrap();
+}
+
+// 按脚本回复 url 上的请求, 直到连接关闭. 返回收到的请求数
+async fn play_over_ws(url: String, mut agent: ScriptedAgent) -> usize {
+  let (mut ws_stream, _) = loop {
+    match connect_async(&url).await {
+      Ok(connected) => break connected,
+      Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+    }
+  };
+  let mut mirror = ObsMirror::new();
+  let mut received = 0;
+  while let Some(Ok(Message::Text(text))) = ws_stream.next().await {
+    received += 1;
+    let event: AgentReqEvent = serde_json::from_str(&text).unwrap();
+    let Some(resp) = respond(&mut agent, &mut mirror, event).await else {
+      continue;
+    };
+    let text = serde_json::to_string(&resp).unwrap();
+    ws_stream.send(Message::Text(text.into())).await.unwrap();
+  }
+  received
+}
+
+#[tokio::test]
+async fn ws_dispatcher_hosts_concurrent_games_on_per_seat_end_points() {
+  let scenario = scenario(
+    vec![
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+      seat(2, &[], &[]),
+    ],
+    &[Role::主教, Role::建筑师, Role::魔术师],
+    &[],
+  );
+  // 两局的 0 号座位按不同的脚本决策, 请求发错座位结果就对不上
+  let scripts = [
+    Script::new().role(Role::国王).opers(&[Oper::Gold(2)]),
+    Script::default(),
+  ];
+  let mut locals = Vec::new();
+  for script in &scripts {
+    locals.push(play(scenario.clone(), vec![script.clone()]).await.result);
+  }
+
+  let port = std::net::TcpListener::bind("127.0.0.1:0")
+    .unwrap()
+    .local_addr()
+    .unwrap()
+    .port();
+  let dispatcher = WsDispatcher::new(format!("127.0.0.1:{}", port));
+  let games = [uuid::Uuid::new_v4(), uuid::Uuid::new_v4()];
+
+  // 第一局的观众, 连上之后才开局
+  let history_a = dispatcher.add_history(games[0]).await;
+  let url = format!("ws://127.0.0.1:{}/{}/history", port, games[0]);
+  let (mut watcher, _) = loop {
+    match connect_async(&url).await {
+      Ok(connected) => break connected,
+      Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+    }
+  };
+  let watcher = tokio::spawn(async move {
+    let mut events = Vec::new();
+    let close_frame = loop {
+      match watcher.next().await {
+        Some(Ok(Message::Text(text))) => events.push(serde_json::from_str::<HistoryReqEvent>(&text).unwrap()),
+        Some(Ok(Message::Close(close_frame))) => break close_frame,
+        other => panic!("unexpected message {:?}", other),
+      }
+    };
+    (events, close_frame)
+  });
+
+  let (history_b, _receiver_b) = history();
+  let mut clients = Vec::new();
+  let mut runs = Vec::new();
+  for ((game, script), history) in games.iter().zip(scripts).zip([history_a, history_b]) {
+    let seat = PlayerIndex::from_usize(0);
+    let transport = dispatcher.add_seat(*game, seat, Duration::from_secs(10)).await;
+    let url = format!("ws://127.0.0.1:{}/{}/0", port, game);
+    clients.push(tokio::spawn(play_over_ws(url, ScriptedAgent { seat, script })));
+    let scenario = &scenario;
+    runs.push(async move {
+      let mut remote = RemoteFAAgent::new(IdGen::new(), transport, Box::new(NoopFAAgent::new()));
+      remote.wait_for_ready().await;
+      remote.handshake().await.unwrap();
+      let mut game = remote_game(scenario, Box::new(remote), history);
+      game.enable_strict_agents();
+      game.run().await.unwrap().result
+    });
+  }
+  let run_b = runs.pop().unwrap();
+  let run_a = runs.pop().unwrap();
+  let (result_a, result_b) = tokio::join!(run_a, run_b);
+
+  assert_eq!(result_a, locals[0]);
+  assert_eq!(result_b, locals[1]);
+  for client in clients {
+    assert!(client.await.unwrap() > 2);
+  }
+  // 对局结束后观众收完事件, 连接正常关闭
+  let (events, close_frame) = watcher.await.unwrap();
+  assert!(
+    events
+      .iter()
+      .any(|event| matches!(event, HistoryReqEvent::StartGame { .. }))
+  );
+  assert_eq!(close_frame.unwrap().code, CloseCode::Normal);
+}
+
+#[tokio::test]
+async fn ws_dispatcher_closes_unknown_and_removed_end_points_with_close_codes() {
+  let port = std::net::TcpListener::bind("127.0.0.1:0")
+    .unwrap()
+    .local_addr()
+    .unwrap()
+    .port();
+  let dispatcher = WsDispatcher::new(format!("127.0.0.1:{}", port));
+  let game = uuid::Uuid::new_v4();
+  let mut transport = dispatcher
+    .add_seat(game, PlayerIndex::from_usize(0), Duration::from_secs(10))
+    .await;
+
+  async fn close_code(url: String) -> CloseCode {
+    let (mut ws_stream, _) = loop {
+      match connect_async(&url).await {
+        Ok(connected) => break connected,
+        Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+      }
+    };
+    let Some(Ok(Message::Close(Some(close_frame)))) = ws_stream.next().await else {
+      panic!("expected a close frame from {}", url);
+    };
+    close_frame.code
+  }
+
+  let base = format!("ws://127.0.0.1:{}", port);
+  assert_eq!(close_code(format!("{}/nope", base)).await, CloseCode::Policy);
+  assert_eq!(
+    close_code(format!("{}/{}", base, uuid::Uuid::new_v4())).await,
+    CloseCode::Policy
+  );
+  assert_eq!(close_code(format!("{}/{}/1", base, game)).await, CloseCode::Policy);
+  assert_eq!(
+    close_code(format!("{}/{}/0/extra", base, game)).await,
+    CloseCode::Policy
+  );
+  assert_eq!(
+    close_code(format!("{}/{}/0?format=xml", base, game)).await,
+    CloseCode::Unsupported
+  );
+
+  // 连上的客户端收到请求后移除对局, 连接以 Away 关闭
+  let (mut ws_stream, _) = connect_async(format!("{}/{}/0", base, game)).await.unwrap();
+  transport.send(&AgentReqEvent::WaitForReady { id: 1 }).await.unwrap();
+  assert!(matches!(ws_stream.next().await, Some(Ok(Message::Text(_)))));
+  dispatcher.remove_game(game).await;
+  let Some(Ok(Message::Close(Some(close_frame)))) = ws_stream.next().await else {
+    panic!("expected a close frame");
+  };
+  assert_eq!(close_frame.code, CloseCode::Away);
+  assert_eq!(close_code(format!("{}/{}/0", base, game)).await, CloseCode::Policy);
+  assert!(transport.recv().await.is_err());
+}
@@ -1,5 +1,6 @@
 use common_context::domain::factories::DbConfigFactory;
 use common_context::domain::valueobjects::DbConfig;
+use game_server::HintConfig;
 use serde::Deserialize;
 use thiserror::Error;
 
@@ -11,6 +12,8 @@ const CONTEXT_DIR: &str = "session_context";
 pub struct SessionConfig {
   pub db: DbConfig,
   pub session_duration_hours: u64,
+  /// Hint service for the `game.hint` method; hints are unavailable when absent
+  pub hint: Option<HintConfig>,
 }
 
 #[derive(Debug, Error)]
@@ -47,6 +50,7 @@ impl SessionConfig {
     struct ConfigFile {
       session: SessionSection,
       db: DbConfig,
+      hint: Option<HintConfig>,
     }
 
     let config: ConfigFile = toml::from_str(&contents).map_err(|e| SessionConfigError::ParseError(e))?;
@@ -54,6 +58,7 @@ impl SessionConfig {
     Ok(SessionConfig {
       db: config.db,
       session_duration_hours: config.session.session_duration_hours,
+      hint: config.hint,
     })
   }
 }
@@ -1,3 +1,4 @@
+use room_context::domain::valueobjects::TimeControl;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
@@ -44,6 +45,10 @@ pub const ERR_SESSION_EXPIRED: i32 = -32007;
 pub const ERR_INVALID_OPERATION: i32 = -32003;
 pub const ERR_INVALID_MAX_PLAYERS: i32 = -32004;
 pub const ERR_ROOM_NAME_EXISTS: i32 = -32005;
+pub const ERR_ROOM_NOT_FOUND: i32 = -32008;
+pub const ERR_NOT_ROOM_CREATOR: i32 = -32009;
+pub const ERR_HINTS_DISABLED: i32 = -32010;
+pub const ERR_HINTS_UNAVAILABLE: i32 = -32011;
 
 // JSON-RPC 2.0 structures
 #[derive(Debug, Serialize, Deserialize)]
@@ -163,6 +168,42 @@ pub async fn handle_jsonrpc_request(text: &str, state: &crate::state::AppState)
   execute_jsonrpc_method(&request.method, request.params.clone(), state, request.id).await
 }
 
+fn session_service_error(e: crate::services::SessionServiceError) -> (i32, String) {
+  use crate::services::SessionServiceError;
+  match e {
+    SessionServiceError::SessionNotFound(_) => (ERR_SESSION_NOT_FOUND, "Session not found".to_string()),
+    SessionServiceError::SessionExpired => (ERR_SESSION_EXPIRED, "Session expired".to_string()),
+    SessionServiceError::InvalidOperation(msg) => (ERR_INVALID_OPERATION, format!("Invalid operation: {}", msg)),
+    SessionServiceError::Database(err) => (JSON_RPC_INTERNAL_ERROR, format!("Database error: {}", err)),
+    SessionServiceError::InvalidMaxPlayers => (
+      ERR_INVALID_MAX_PLAYERS,
+      "Invalid max players: must be 4 or 6".to_string(),
+    ),
+    SessionServiceError::RoomNameExists => (ERR_ROOM_NAME_EXISTS, "Room name already exists".to_string()),
+    SessionServiceError::RoomNotFound => (ERR_ROOM_NOT_FOUND, "Room not found".to_string()),
+    SessionServiceError::NotRoomCreator => (ERR_NOT_ROOM_CREATOR, "Only the room creator can do this".to_string()),
+    SessionServiceError::HintsDisabled => (ERR_HINTS_DISABLED, "Hints are disabled in this room".to_string()),
+    SessionServiceError::HintsUnavailable => (ERR_HINTS_UNAVAILABLE, "Hint service is not configured".to_string()),
+  }
+}
+
+fn missing_param(id: JsonRpcId, name: &str) -> Option<JsonRpcResponse> {
+  Some(JsonRpcResponse::error(
+    id,
+    JSON_RPC_INVALID_REQUEST,
+    format!("Missing or invalid '{}' parameter", name),
+    None,
+  ))
+}
+
+fn time_control_json(time_control: TimeControl) -> serde_json::Value {
+  json!({
+    "decision_ms": time_control.decision_ms,
+    "bank_ms": time_control.bank_ms,
+    "increment_ms": time_control.increment_ms,
+  })
+}
+
 async fn execute_jsonrpc_method(
   method: &str, params: Option<serde_json::Value>, state: &crate::state::AppState, id: JsonRpcId,
 ) -> Option<JsonRpcResponse> {
@@ -268,6 +309,8 @@ async fn execute_jsonrpc_method(
                 "creator_name": room.creator_name,
                 "max_players": room.max_players,
                 "seated_players": room.seated_players,
+                "hints_enabled": room.hints_enabled,
+                "time_control": time_control_json(room.time_control),
                 "created_at": room.created_at.to_rfc3339(),
                 "expires_at": room.expires_at.to_rfc3339(),
               })
@@ -344,32 +387,100 @@ async fn execute_jsonrpc_method(
             "name": room.name().as_str(),
             "creator_id": room.creator().to_string(),
             "max_players": room.max_players().value(),
+            "hints_enabled": room.hints_enabled(),
+            "time_control": time_control_json(room.time_control()),
             "created_at": room.created_at().to_rfc3339(),
             "expires_at": room.expires_at().to_rfc3339(),
           }),
         )),
         Err(e) => {
-          let (code, message) = match e {
-            crate::services::SessionServiceError::SessionNotFound(_) => {
-              (ERR_SESSION_NOT_FOUND, "Session not found".to_string())
-            },
-            crate::services::SessionServiceError::SessionExpired => {
-              (ERR_SESSION_EXPIRED, "Session expired".to_string())
-            },
-            crate::services::SessionServiceError::InvalidOperation(msg) => {
-              (ERR_INVALID_OPERATION, format!("Invalid operation: {}", msg))
-            },
-            crate::services::SessionServiceError::Database(err) => {
-              (JSON_RPC_INTERNAL_ERROR, format!("Database error: {}", err))
-            },
-            crate::services::SessionServiceError::InvalidMaxPlayers => (
-              ERR_INVALID_MAX_PLAYERS,
-              "Invalid max players: must be 4 or 6".to_string(),
-            ),
-            crate::services::SessionServiceError::RoomNameExists => {
-              (ERR_ROOM_NAME_EXISTS, "Room name already exists".to_string())
-            },
-          };
+          let (code, message) = session_service_error(e);
+          Some(JsonRpcResponse::error(id, code, message, None))
+        },
+      }
+    },
+    "room.set_hints" => {
+      let params = params.unwrap_or_default();
+      let Some(session_id) = params.get("session_id").and_then(|v| v.as_str()) else {
+        return missing_param(id, "session_id");
+      };
+      let Some(room_id) = params.get("room_id").and_then(|v| v.as_str()) else {
+        return missing_param(id, "room_id");
+      };
+      let Some(hints_enabled) = params.get("hints_enabled").and_then(|v| v.as_bool()) else {
+        return missing_param(id, "hints_enabled");
+      };
+
+      match state
+        .session_service
+        .set_room_hints_enabled(session_id, room_id, hints_enabled)
+        .await
+      {
+        Ok(()) => Some(JsonRpcResponse::success(
+          id,
+          json!({ "room_id": room_id, "hints_enabled": hints_enabled }),
+        )),
+        Err(e) => {
+          let (code, message) = session_service_error(e);
+          Some(JsonRpcResponse::error(id, code, message, None))
+        },
+      }
+    },
+    "room.set_time_control" => {
+      // All in milliseconds, omitted fields are 0 (no limit / no time bank)
+      let params = params.unwrap_or_default();
+      let Some(session_id) = params.get("session_id").and_then(|v| v.as_str()) else {
+        return missing_param(id, "session_id");
+      };
+      let Some(room_id) = params.get("room_id").and_then(|v| v.as_str()) else {
+        return missing_param(id, "room_id");
+      };
+      let ms = |name: &str| params.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+      let time_control = TimeControl::new(ms("decision_ms"), ms("bank_ms"), ms("increment_ms"));
+
+      match state
+        .session_service
+        .set_room_time_control(session_id, room_id, time_control)
+        .await
+      {
+        Ok(()) => Some(JsonRpcResponse::success(
+          id,
+          json!({ "room_id": room_id, "time_control": time_control_json(time_control) }),
+        )),
+        Err(e) => {
+          let (code, message) = session_service_error(e);
+          Some(JsonRpcResponse::error(id, code, message, None))
+        },
+      }
+    },
+    "game.hint" => {
+      // `decision` is the AgentReqEvent the game server sent to this seat, forwarded as is
+      let params = params.unwrap_or_default();
+      let Some(session_id) = params.get("session_id").and_then(|v| v.as_str()) else {
+        return missing_param(id, "session_id");
+      };
+      let Some(room_id) = params.get("room_id").and_then(|v| v.as_str()) else {
+        return missing_param(id, "room_id");
+      };
+      let Some(decision) = params
+        .get("decision")
+        .and_then(|v| serde_json::from_value::<game_server::domain::AgentReqEvent>(v.clone()).ok())
+      else {
+        return missing_param(id, "decision");
+      };
+
+      match state.session_service.hint(session_id, room_id, &decision).await {
+        Ok(hint) => match serde_json::to_value(&hint) {
+          Ok(result) => Some(JsonRpcResponse::success(id, result)),
+          Err(e) => Some(JsonRpcResponse::error(
+            id,
+            JSON_RPC_INTERNAL_ERROR,
+            format!("Failed to serialize hint: {}", e),
+            None,
+          )),
+        },
+        Err(e) => {
+          let (code, message) = session_service_error(e);
           Some(JsonRpcResponse::error(id, code, message, None))
         },
       }
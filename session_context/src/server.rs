@@ -159,7 +159,12 @@ pub async fn start_server(
 
   let user_service = Arc::new(user_service);
   let room_service = Arc::new(room_service);
-  let session_service = Arc::new(SessionService::new(session_manager, room_manager));
+  let mut session_service = SessionService::new(session_manager, room_manager);
+  if let Some(hint_config) = &session_config.hint {
+    let hint_service = game_server::HintService::new(hint_config).map_err(|e| e.to_string())?;
+    session_service = session_service.with_hint_service(Arc::new(hint_service));
+  }
+  let session_service = Arc::new(session_service);
   let state = AppState {
     user_service,
     room_service,
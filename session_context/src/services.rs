@@ -1,11 +1,13 @@
 use std::sync::Arc;
 
+use game_server::domain::AgentReqEvent;
+use game_server::{Hint, HintService};
 use room_context::domain::entities::Room;
-use room_context::domain::valueobjects::{MaxPlayers, RoomName};
+use room_context::domain::valueobjects::{MaxPlayers, RoomId, RoomName, TimeControl};
 use room_context::errors::RoomError;
 use room_context::managers::RoomManager;
 use user_context::domain::SessionManager;
-use user_context::domain::valueobjects::SessionId;
+use user_context::domain::valueobjects::{SessionId, UserId};
 use user_context::errors::UserError;
 
 /// Service for session-based operations
@@ -14,6 +16,7 @@ use user_context::errors::UserError;
 pub struct SessionService {
   session_manager: Arc<SessionManager>,
   room_manager: Arc<RoomManager>,
+  hint_service: Option<Arc<HintService>>,
 }
 
 impl SessionService {
@@ -21,17 +24,22 @@ impl SessionService {
     Self {
       session_manager,
       room_manager,
+      hint_service: None,
     }
   }
 
-  /// Verify that a session exists and is not expired
-  pub async fn verify_session(&self, session_id: &str) -> Result<(), SessionServiceError> {
-    // Parse session_id string to SessionId
+  /// Enable the `game.hint` method backed by the given hint service
+  pub fn with_hint_service(mut self, hint_service: Arc<HintService>) -> Self {
+    self.hint_service = Some(hint_service);
+    self
+  }
+
+  /// Resolve a live session to its user
+  async fn session_user(&self, session_id: &str) -> Result<UserId, SessionServiceError> {
     let session_id_parsed = session_id
       .parse::<SessionId>()
       .map_err(|_| SessionServiceError::InvalidOperation(format!("Invalid session_id format: {}", session_id)))?;
 
-    // Get session info from SessionManager
     let session_info = self
       .session_manager
       .get_session(session_id_parsed)
@@ -43,13 +51,29 @@ impl SessionService {
         _ => SessionServiceError::InvalidOperation(format!("Failed to get session: {}", e)),
       })?;
 
-    // Check if session is expired
     use user_context::domain::SessionStatus;
     if session_info.is_expired || session_info.status == SessionStatus::Expired {
       return Err(SessionServiceError::SessionExpired);
     }
 
-    Ok(())
+    Ok(session_info.user_id)
+  }
+
+  async fn find_room(&self, room_id: &str) -> Result<Room, SessionServiceError> {
+    let room_id = room_id
+      .parse::<RoomId>()
+      .map_err(|_| SessionServiceError::InvalidOperation(format!("Invalid room_id format: {}", room_id)))?;
+    self
+      .room_manager
+      .get_room_by_id(room_id)
+      .await
+      .map_err(|e| SessionServiceError::Database(e.to_string()))?
+      .ok_or(SessionServiceError::RoomNotFound)
+  }
+
+  /// Verify that a session exists and is not expired
+  pub async fn verify_session(&self, session_id: &str) -> Result<(), SessionServiceError> {
+    self.session_user(session_id).await.map(|_| ())
   }
 
   /// Create a room using session_id for authentication
@@ -57,28 +81,7 @@ impl SessionService {
   pub async fn create_room(
     &self, session_id: &str, name: &str, max_players: usize,
   ) -> Result<Room, SessionServiceError> {
-    // Parse session_id string to SessionId
-    let session_id_parsed = session_id
-      .parse::<SessionId>()
-      .map_err(|_| SessionServiceError::InvalidOperation(format!("Invalid session_id format: {}", session_id)))?;
-
-    // Get session info from SessionManager
-    let session_info = self
-      .session_manager
-      .get_session(session_id_parsed)
-      .await
-      .map_err(|e| match e {
-        UserError::NotFound => SessionServiceError::SessionNotFound(session_id.to_string()),
-        UserError::InvalidOperation(msg) => SessionServiceError::InvalidOperation(msg),
-        UserError::Database(err) => SessionServiceError::Database(err.to_string()),
-        _ => SessionServiceError::InvalidOperation(format!("Failed to get session: {}", e)),
-      })?;
-
-    // Check if session is expired
-    use user_context::domain::SessionStatus;
-    if session_info.is_expired || session_info.status == SessionStatus::Expired {
-      return Err(SessionServiceError::SessionExpired);
-    }
+    let user_id = self.session_user(session_id).await?;
 
     // Convert name to RoomName and max_players to MaxPlayers
     let room_name = RoomName::from(name);
@@ -87,7 +90,7 @@ impl SessionService {
     // Create the room using the user_id from the session
     self
       .room_manager
-      .create_room(&room_name, session_info.user_id, max_players_vo)
+      .create_room(&room_name, user_id, max_players_vo)
       .await
       .map_err(|e| match e {
         RoomError::Database(err) => SessionServiceError::Database(err.to_string()),
@@ -97,6 +100,79 @@ impl SessionService {
         _ => SessionServiceError::InvalidOperation(format!("Failed to create room: {}", e)),
       })
   }
+
+  /// Switch hints on or off for a room; only the room creator may do this
+  pub async fn set_room_hints_enabled(
+    &self, session_id: &str, room_id: &str, hints_enabled: bool,
+  ) -> Result<(), SessionServiceError> {
+    let user_id = self.session_user(session_id).await?;
+    let room = self.find_room(room_id).await?;
+    if room.creator() != user_id {
+      return Err(SessionServiceError::NotRoomCreator);
+    }
+
+    self
+      .room_manager
+      .update_room_hints_enabled(room.id(), hints_enabled)
+      .await
+      .map_err(|e| match e {
+        RoomError::NotFound => SessionServiceError::RoomNotFound,
+        RoomError::Database(err) => SessionServiceError::Database(err.to_string()),
+        _ => SessionServiceError::InvalidOperation(format!("Failed to update hints: {}", e)),
+      })
+  }
+
+  /// Change a room's time control, only its creator may do so
+  pub async fn set_room_time_control(
+    &self, session_id: &str, room_id: &str, time_control: TimeControl,
+  ) -> Result<(), SessionServiceError> {
+    let user_id = self.session_user(session_id).await?;
+    let room = self.find_room(room_id).await?;
+    if room.creator() != user_id {
+      return Err(SessionServiceError::NotRoomCreator);
+    }
+
+    self
+      .room_manager
+      .update_room_time_control(room.id(), time_control)
+      .await
+      .map_err(|e| match e {
+        RoomError::NotFound => SessionServiceError::RoomNotFound,
+        RoomError::Database(err) => SessionServiceError::Database(err.to_string()),
+        _ => SessionServiceError::InvalidOperation(format!("Failed to update time control: {}", e)),
+      })
+  }
+
+  /// Ask the hint service what it would do for a pending decision of a seated player
+  pub async fn hint(
+    &self, session_id: &str, room_id: &str, decision: &AgentReqEvent,
+  ) -> Result<Hint, SessionServiceError> {
+    let hint_service = self
+      .hint_service
+      .as_ref()
+      .ok_or(SessionServiceError::HintsUnavailable)?;
+    let user_id = self.session_user(session_id).await?;
+    let room = self.find_room(room_id).await?;
+    if !room.hints_enabled() {
+      return Err(SessionServiceError::HintsDisabled);
+    }
+
+    let participant = self
+      .room_manager
+      .get_participant(room.id(), user_id)
+      .await
+      .map_err(|e| SessionServiceError::Database(e.to_string()))?;
+    if !participant.is_some_and(|p| p.is_sitting()) {
+      return Err(SessionServiceError::InvalidOperation(
+        "Only seated players can ask for hints".to_string(),
+      ));
+    }
+
+    hint_service
+      .hint(decision)
+      .await
+      .map_err(|e| SessionServiceError::InvalidOperation(format!("Failed to compute hint: {}", e)))
+  }
 }
 
 /// Errors that can occur in SessionService operations
@@ -119,4 +195,16 @@ pub enum SessionServiceError {
 
   #[error("Room name already exists")]
   RoomNameExists,
+
+  #[error("Room not found")]
+  RoomNotFound,
+
+  #[error("Only the room creator can do this")]
+  NotRoomCreator,
+
+  #[error("Hints are disabled in this room")]
+  HintsDisabled,
+
+  #[error("Hint service is not configured")]
+  HintsUnavailable,
 }